@@ -0,0 +1,377 @@
+//! Persistent cache of per-file results for incremental rescans
+//!
+//! Hashes, formats and MIME types are expensive to compute because they
+//! require reading file contents. A [`ScanCache`] remembers them together
+//! with a [`FileKey`] (size, modification time, inode and device) so that a
+//! later scan can reuse them for files that have not changed and only read
+//! files that are new or modified.
+//!
+//! Entries are keyed by canonical path, so scans of the same directory
+//! through different paths share them, and saving drops the entries of files
+//! that a completed scan of their directory did not find and that no longer
+//! exist.
+//!
+//! The cache is stored on disk as JSON Lines, one file per line.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hashing::FileHashes;
+
+/// Identity of a file's contents as far as the filesystem reports it
+///
+/// If any of these values change the file is treated as modified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileKey {
+    pub size: u64,
+    pub mtime_secs: i64,
+    pub mtime_nanos: u32,
+    /// Inode number (always 0 on platforms without inodes)
+    pub inode: u64,
+    /// Device ID (always 0 on platforms without inodes)
+    pub device: u64,
+}
+
+impl FileKey {
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let (mtime_secs, mtime_nanos) = metadata.modified()
+            .ok()
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| (d.as_secs() as i64, d.subsec_nanos()))
+            .unwrap_or((0, 0));
+
+        #[cfg(unix)]
+        let (inode, device) = {
+            use std::os::unix::fs::MetadataExt;
+            (metadata.ino(), metadata.dev())
+        };
+        #[cfg(not(unix))]
+        let (inode, device) = (0, 0);
+
+        Self {
+            size: metadata.len(),
+            mtime_secs,
+            mtime_nanos,
+            inode,
+            device,
+        }
+    }
+}
+
+/// Cached results for a single file; a missing hash or `None` means the value was never calculated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: FileKey,
+    #[serde(default, deserialize_with = "crate::hashing::deserialize_hashes")]
+    pub hashes: FileHashes,
+    pub format: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+/// On-disk form of a cache entry
+#[derive(Serialize, Deserialize)]
+struct CacheLine {
+    path: PathBuf,
+    #[serde(flatten)]
+    entry: CacheEntry,
+}
+
+/// Thread-safe cache of file results, optionally backed by a file
+///
+/// Entries loaded from disk are read-only during a scan; results computed
+/// during the scan are collected separately and merged in by [`ScanCache::save`].
+#[derive(Debug, Default)]
+pub struct ScanCache {
+    location: Option<PathBuf>,
+    entries: HashMap<PathBuf, CacheEntry>,
+    updated: Mutex<HashMap<PathBuf, CacheEntry>>,
+    /// Paths looked up since the cache was loaded
+    seen: Mutex<HashSet<PathBuf>>,
+    /// Canonical roots of the scans completed with this cache
+    scanned_roots: Mutex<Vec<PathBuf>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl ScanCache {
+    /// Create an empty in-memory cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a cache from disk, starting empty if the file does not exist yet
+    ///
+    /// Lines that cannot be parsed (for example from an older version) are ignored.
+    pub fn load(location: &Path) -> io::Result<Self> {
+        let mut cache = Self {
+            location: Some(location.to_path_buf()),
+            ..Self::default()
+        };
+
+        let file = match File::open(location) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(cache),
+            Err(e) => return Err(e),
+        };
+
+        for line in BufReader::new(file).lines() {
+            if let Ok(record) = serde_json::from_str::<CacheLine>(&line?) {
+                cache.entries.insert(record.path, record.entry);
+            }
+        }
+
+        Ok(cache)
+    }
+
+    /// Default cache file location in the user's cache directory
+    pub fn default_location() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("splendir").join("scan-cache.jsonl"))
+    }
+
+    /// Write the cache back to the file it was loaded from
+    ///
+    /// The file is replaced atomically so an interrupted save never leaves a
+    /// truncated cache behind.
+    pub fn save(&self) -> io::Result<()> {
+        let location = self.location.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "cache has no file location")
+        })?;
+        self.save_to(location)
+    }
+
+    /// Write the cache to a given file
+    ///
+    /// Loaded entries below the root of a completed scan are left out when
+    /// that scan did not find their file and it no longer exists, so deleted
+    /// and moved files do not pile up. Files a filtered scan skipped keep
+    /// their entries.
+    pub fn save_to(&self, location: &Path) -> io::Result<()> {
+        if let Some(parent) = location.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let poisoned = || io::Error::other("cache lock poisoned");
+        let updated = self.updated.lock().map_err(|_| poisoned())?;
+        let seen = self.seen.lock().map_err(|_| poisoned())?;
+        let scanned_roots = self.scanned_roots.lock().map_err(|_| poisoned())?;
+        let still_present = |path: &Path| {
+            seen.contains(path)
+                || !scanned_roots.iter().any(|root| path.starts_with(root))
+                || fs::symlink_metadata(path).is_ok()
+        };
+
+        let temp_location = location.with_extension("jsonl.tmp");
+        let mut writer = BufWriter::new(File::create(&temp_location)?);
+
+        let unchanged = self.entries.iter()
+            .filter(|(path, _)| !updated.contains_key(*path) && still_present(path));
+        for (path, entry) in unchanged.chain(updated.iter()) {
+            let line = CacheLine { path: path.clone(), entry: entry.clone() };
+            serde_json::to_writer(&mut writer, &line).map_err(io::Error::from)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&temp_location, location)
+    }
+
+    /// Look up a file, returning the cached entry only if its key still matches
+    pub fn lookup(&self, path: &Path, key: &FileKey) -> Option<CacheEntry> {
+        if let Ok(mut seen) = self.seen.lock() {
+            seen.insert(path.to_path_buf());
+        }
+        let found = self.updated.lock()
+            .ok()
+            .and_then(|updated| updated.get(path).cloned())
+            .or_else(|| self.entries.get(path).cloned())
+            .filter(|entry| entry.key == *key);
+
+        if found.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        found
+    }
+
+    /// Record fresh results for a file
+    pub fn store(&self, path: &Path, entry: CacheEntry) {
+        if let Ok(mut updated) = self.updated.lock() {
+            updated.insert(path.to_path_buf(), entry);
+        }
+    }
+
+    /// Number of entries (loaded plus newly stored)
+    pub fn len(&self) -> usize {
+        let new_paths = self.updated.lock()
+            .map(|updated| updated.keys().filter(|path| !self.entries.contains_key(*path)).count())
+            .unwrap_or(0);
+        self.entries.len() + new_paths
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lookups that found an unchanged file
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of lookups for new or modified files
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// A cache in use by one scan
+///
+/// Walked paths are translated into keys below the canonical scan root, so
+/// `splendir .` and `splendir /abs/path` share their entries.
+pub(crate) struct CacheScope<'a> {
+    cache: &'a ScanCache,
+    root: &'a Path,
+    canonical_root: PathBuf,
+}
+
+impl<'a> CacheScope<'a> {
+    pub(crate) fn new(cache: &'a ScanCache, root: &'a Path) -> Self {
+        let canonical_root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        Self { cache, root, canonical_root }
+    }
+
+    fn key_path(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => self.canonical_root.clone(),
+            Ok(relative) => self.canonical_root.join(relative),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+
+    pub(crate) fn lookup(&self, path: &Path, key: &FileKey) -> Option<CacheEntry> {
+        self.cache.lookup(&self.key_path(path), key)
+    }
+
+    pub(crate) fn store(&self, path: &Path, entry: CacheEntry) {
+        self.cache.store(&self.key_path(path), entry);
+    }
+
+    /// Mark the scan as complete, so that entries below its root for files
+    /// that it did not find and that are gone are dropped on save
+    ///
+    /// Not called for scans that fail or are cancelled, which may not have
+    /// visited every file.
+    pub(crate) fn finish(self) {
+        if let Ok(mut roots) = self.cache.scanned_roots.lock() {
+            roots.push(self.canonical_root);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::hashing::{Digest, HashAlgorithm};
+    use crate::DirectoryScanner;
+
+    /// Scan `root` with a cache loaded from `cache_path`, saving it afterwards
+    fn scan_with_cache(root: &Path, cache_path: &Path) -> (Vec<crate::FileInfo>, Arc<ScanCache>) {
+        let cache = Arc::new(ScanCache::load(cache_path).unwrap());
+        let files = DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .calculate_md5(true)
+            .cache(cache.clone())
+            .scan_detailed(root)
+            .unwrap();
+        cache.save().unwrap();
+        (files, cache)
+    }
+
+    fn fixture() -> (tempfile::TempDir, tempfile::TempDir, PathBuf) {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        fs::write(dir.path().join("same.txt"), b"unchanged").unwrap();
+        fs::write(dir.path().join("edited.txt"), b"before").unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache_path = cache_dir.path().join("splendir/scan-cache.jsonl");
+        (dir, cache_dir, cache_path)
+    }
+
+    #[test]
+    fn test_cache_reuses_unchanged_files() {
+        let (dir, _cache_dir, cache_path) = fixture();
+
+        let (_, cache) = scan_with_cache(dir.path(), &cache_path);
+        assert_eq!(cache.hits(), 0);
+
+        fs::write(dir.path().join("edited.txt"), b"after, and longer").unwrap();
+        let (files, cache) = scan_with_cache(dir.path(), &cache_path);
+        assert_eq!(cache.hits(), 1);
+
+        let edited = files.iter().find(|f| f.name == "edited.txt").unwrap();
+        assert_eq!(edited.hash(HashAlgorithm::Md5), Some(&Digest::Md5(md5::compute(b"after, and longer").0)));
+    }
+
+    #[test]
+    fn test_cache_keys_are_canonical() {
+        let (dir, _cache_dir, cache_path) = fixture();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+
+        scan_with_cache(dir.path(), &cache_path);
+        let (_, cache) = scan_with_cache(&dir.path().join("sub/.."), &cache_path);
+        assert_eq!(cache.hits(), 2);
+        assert_eq!(cache.misses(), 0);
+    }
+
+    #[test]
+    fn test_cache_drops_missing_files() {
+        let (dir, _cache_dir, cache_path) = fixture();
+        let other = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        fs::write(other.path().join("elsewhere.txt"), b"kept").unwrap();
+
+        scan_with_cache(dir.path(), &cache_path);
+        scan_with_cache(other.path(), &cache_path);
+        assert_eq!(ScanCache::load(&cache_path).unwrap().len(), 3);
+
+        // Only entries below the scanned directory are dropped
+        fs::remove_file(dir.path().join("edited.txt")).unwrap();
+        scan_with_cache(dir.path(), &cache_path);
+        let cache = ScanCache::load(&cache_path).unwrap();
+        assert_eq!(cache.len(), 2);
+        let key = |path: &Path| FileKey::from_metadata(&fs::metadata(path).unwrap());
+        let same = fs::canonicalize(dir.path().join("same.txt")).unwrap();
+        let elsewhere = fs::canonicalize(other.path().join("elsewhere.txt")).unwrap();
+        assert!(cache.lookup(&same, &key(&same)).is_some());
+        assert!(cache.lookup(&elsewhere, &key(&elsewhere)).is_some());
+    }
+
+    #[test]
+    fn test_filtered_scan_keeps_skipped_files() {
+        let (dir, _cache_dir, cache_path) = fixture();
+        scan_with_cache(dir.path(), &cache_path);
+
+        let cache = Arc::new(ScanCache::load(&cache_path).unwrap());
+        DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .path_filter(crate::PathFilter::new().exclude_glob("edited.txt"))
+            .cache(cache.clone())
+            .scan_detailed(dir.path())
+            .unwrap();
+        cache.save().unwrap();
+
+        let cache = ScanCache::load(&cache_path).unwrap();
+        assert_eq!(cache.len(), 2);
+        let edited = fs::canonicalize(dir.path().join("edited.txt")).unwrap();
+        let key = FileKey::from_metadata(&fs::metadata(&edited).unwrap());
+        assert!(cache.lookup(&edited, &key).is_some());
+    }
+}
@@ -0,0 +1,405 @@
+//! Charts of file sizes and types
+//!
+//! Counts the files below a [`TreeNode`], and the bytes they take, by size
+//! range (the ranges of [`FileSizeDistribution`]) and by [`FileType`], and
+//! lays the counts out as a bar or pie chart. The layouts are plain geometry
+//! so any front end can draw them: the GUI paints them on a canvas, and
+//! [`Chart::to_svg`] writes a standalone SVG for reports.
+
+use std::f32::consts::TAU;
+use std::fmt;
+
+use crate::dfxml::escape_xml;
+use crate::scanner::{format_file_size, FileSizeDistribution};
+use crate::tree::{get_file_color, FileType};
+use crate::usage::Rect;
+use crate::TreeNode;
+
+/// Share of each bar chart row left blank between bars
+const BAR_GAP: f32 = 0.25;
+/// Colors of the smallest and largest size ranges; the ones between are blended
+const SMALL_COLOR: [u8; 3] = [166, 206, 227];
+const LARGE_COLOR: [u8; 3] = [8, 64, 129];
+
+const SVG_WIDTH: f32 = 640.0;
+const SVG_TITLE_HEIGHT: f32 = 48.0;
+const SVG_ROW_HEIGHT: f32 = 26.0;
+/// Room for the labels to the left of the bars, and the values to their right
+const SVG_LABEL_WIDTH: f32 = 150.0;
+const SVG_VALUE_WIDTH: f32 = 100.0;
+const SVG_PIE_RADIUS: f32 = 120.0;
+const SVG_FONTS: &str = "Segoe UI, Helvetica, Arial, DejaVu Sans, Liberation Sans, sans-serif";
+
+/// How a chart is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartKind {
+    #[default]
+    Bar,
+    Pie,
+}
+
+impl ChartKind {
+    pub const ALL: [ChartKind; 2] = [ChartKind::Bar, ChartKind::Pie];
+}
+
+impl fmt::Display for ChartKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChartKind::Bar => "Bar",
+            ChartKind::Pie => "Pie",
+        })
+    }
+}
+
+/// What the bars and slices of a chart measure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartValue {
+    #[default]
+    Files,
+    Bytes,
+}
+
+impl ChartValue {
+    pub const ALL: [ChartValue; 2] = [ChartValue::Files, ChartValue::Bytes];
+}
+
+impl fmt::Display for ChartValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChartValue::Files => "Files",
+            ChartValue::Bytes => "Bytes",
+        })
+    }
+}
+
+/// One bar or slice: the files in a size range or of a type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartEntry {
+    pub label: String,
+    pub count: usize,
+    pub bytes: u64,
+    pub color: [u8; 3],
+}
+
+impl ChartEntry {
+    pub fn value(&self, value: ChartValue) -> f64 {
+        match value {
+            ChartValue::Files => self.count as f64,
+            ChartValue::Bytes => self.bytes as f64,
+        }
+    }
+
+    /// The entry's file count or total size, as shown beside a bar
+    pub fn value_label(&self, value: ChartValue) -> String {
+        match value {
+            ChartValue::Files if self.count == 1 => "1 file".to_string(),
+            ChartValue::Files => format!("{} files", self.count),
+            ChartValue::Bytes => format_file_size(self.bytes),
+        }
+    }
+}
+
+/// A titled set of chart entries, with only the ranges or types that have files
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chart {
+    pub title: String,
+    pub entries: Vec<ChartEntry>,
+}
+
+impl Chart {
+    /// Files below `tree` by size range, smallest first
+    pub fn by_size(tree: &TreeNode) -> Chart {
+        let mut totals = [(0usize, 0u64); FileSizeDistribution::LABELS.len()];
+        for_each_file(tree, &mut |file| {
+            let total = &mut totals[FileSizeDistribution::bucket(file.size)];
+            total.0 += 1;
+            total.1 += file.size;
+        });
+        let last = (totals.len() - 1) as f32;
+        let entries = totals
+            .iter()
+            .enumerate()
+            .filter(|(_, (count, _))| *count > 0)
+            .map(|(bucket, &(count, bytes))| ChartEntry {
+                label: FileSizeDistribution::LABELS[bucket].to_string(),
+                count,
+                bytes,
+                color: blend(SMALL_COLOR, LARGE_COLOR, bucket as f32 / last),
+            })
+            .collect();
+        Chart { title: "File sizes".to_string(), entries }
+    }
+
+    /// Files below `tree` by type, most files first as in the analysis summary
+    pub fn by_type(tree: &TreeNode) -> Chart {
+        let mut totals = [(0usize, 0u64); FileType::ALL.len()];
+        for_each_file(tree, &mut |file| {
+            let (_, file_type) = get_file_color(&file.path, &file.name, false);
+            let index = FileType::ALL.iter().position(|candidate| *candidate == file_type).unwrap_or(0);
+            totals[index].0 += 1;
+            totals[index].1 += file.size;
+        });
+        let mut entries: Vec<ChartEntry> = FileType::ALL
+            .iter()
+            .zip(totals)
+            .filter(|(_, (count, _))| *count > 0)
+            .map(|(file_type, (count, bytes))| ChartEntry {
+                label: file_type.description().to_string(),
+                count,
+                bytes,
+                color: file_type.chart_color(),
+            })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.count));
+        Chart { title: "File types".to_string(), entries }
+    }
+
+    pub fn values(&self, value: ChartValue) -> Vec<f64> {
+        self.entries.iter().map(|entry| entry.value(value)).collect()
+    }
+
+    /// An entry's label, file count and size, with its share of all files
+    /// and bytes, for a tooltip
+    pub fn describe(&self, index: usize) -> String {
+        let entry = &self.entries[index];
+        let share = |value| {
+            let total: f64 = self.values(value).iter().sum();
+            if total > 0.0 { 100.0 * entry.value(value) / total } else { 0.0 }
+        };
+        format!(
+            "{}\n{}, {}\n{:.1}% of files, {:.1}% of bytes",
+            entry.label,
+            entry.value_label(ChartValue::Files),
+            format_file_size(entry.bytes),
+            share(ChartValue::Files),
+            share(ChartValue::Bytes)
+        )
+    }
+
+    /// The chart as a standalone SVG document on a white background
+    pub fn to_svg(&self, kind: ChartKind, value: ChartValue) -> String {
+        let rows = self.entries.len().max(1) as f32;
+        let height = match kind {
+            ChartKind::Bar => SVG_TITLE_HEIGHT + rows * SVG_ROW_HEIGHT + 16.0,
+            ChartKind::Pie => SVG_TITLE_HEIGHT + (2.0 * SVG_PIE_RADIUS + 24.0).max(rows * SVG_ROW_HEIGHT + 16.0),
+        };
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"{}\" font-size=\"13\">\n",
+            SVG_FONTS,
+            w = SVG_WIDTH,
+            h = height
+        );
+        svg.push_str("  <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n");
+        svg.push_str(&format!(
+            "  <text x=\"16\" y=\"30\" font-size=\"18\" font-weight=\"bold\" fill=\"#222222\">{} ({})</text>\n",
+            escape_xml(&self.title),
+            value.to_string().to_lowercase()
+        ));
+        if self.entries.is_empty() {
+            svg.push_str(&format!("  <text x=\"16\" y=\"{}\" fill=\"#666666\">No files</text>\n", SVG_TITLE_HEIGHT + 16.0));
+        } else {
+            match kind {
+                ChartKind::Bar => self.svg_bars(&mut svg, value),
+                ChartKind::Pie => self.svg_pie(&mut svg, value),
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn svg_bars(&self, svg: &mut String, value: ChartValue) {
+        let plot = Rect::new(
+            SVG_LABEL_WIDTH + 16.0,
+            SVG_TITLE_HEIGHT,
+            SVG_WIDTH - SVG_LABEL_WIDTH - SVG_VALUE_WIDTH - 16.0,
+            self.entries.len() as f32 * SVG_ROW_HEIGHT,
+        );
+        for (entry, bar) in self.entries.iter().zip(bar_layout(&self.values(value), plot)) {
+            let middle = bar.y + bar.height / 2.0;
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" text-anchor=\"end\" dominant-baseline=\"middle\" fill=\"#222222\">{}</text>\n",
+                SVG_LABEL_WIDTH + 8.0,
+                middle,
+                escape_xml(&entry.label)
+            ));
+            svg.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                bar.x,
+                bar.y,
+                bar.width,
+                bar.height,
+                hex(entry.color)
+            ));
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\" fill=\"#444444\">{}</text>\n",
+                bar.x + bar.width + 6.0,
+                middle,
+                entry.value_label(value)
+            ));
+        }
+    }
+
+    fn svg_pie(&self, svg: &mut String, value: ChartValue) {
+        let (cx, cy) = (16.0 + SVG_PIE_RADIUS, SVG_TITLE_HEIGHT + 8.0 + SVG_PIE_RADIUS);
+        let values = self.values(value);
+        let total: f64 = values.iter().sum();
+        for (entry, (start, end)) in self.entries.iter().zip(pie_layout(&values)) {
+            if end - start >= TAU - 1e-4 {
+                // A single slice is the whole circle, which an arc cannot draw
+                svg.push_str(&format!(
+                    "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+                    cx,
+                    cy,
+                    SVG_PIE_RADIUS,
+                    hex(entry.color)
+                ));
+            } else if end > start {
+                let (x0, y0) = point_on_circle(cx, cy, SVG_PIE_RADIUS, start);
+                let (x1, y1) = point_on_circle(cx, cy, SVG_PIE_RADIUS, end);
+                let large_arc = u8::from(end - start > TAU / 2.0);
+                svg.push_str(&format!(
+                    "  <path d=\"M {cx} {cy} L {x0} {y0} A {r} {r} 0 {large_arc} 1 {x1} {y1} Z\" fill=\"{}\" stroke=\"#ffffff\" stroke-width=\"1.5\"/>\n",
+                    hex(entry.color),
+                    r = SVG_PIE_RADIUS
+                ));
+            }
+        }
+
+        // Legend to the right of the pie
+        let x = 2.0 * SVG_PIE_RADIUS + 48.0;
+        for (row, entry) in self.entries.iter().enumerate() {
+            let y = SVG_TITLE_HEIGHT + 8.0 + row as f32 * SVG_ROW_HEIGHT;
+            let share = if total > 0.0 { 100.0 * entry.value(value) / total } else { 0.0 };
+            svg.push_str(&format!(
+                "  <rect x=\"{}\" y=\"{}\" width=\"14\" height=\"14\" fill=\"{}\"/>\n",
+                x,
+                y,
+                hex(entry.color)
+            ));
+            svg.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\" fill=\"#222222\">{}: {} ({:.1}%)</text>\n",
+                x + 22.0,
+                y + 7.0,
+                escape_xml(&entry.label),
+                entry.value_label(value),
+                share
+            ));
+        }
+    }
+}
+
+/// Horizontal bars filling `plot`, one row per value from the top, each as
+/// long as its value relative to the largest
+pub fn bar_layout(values: &[f64], plot: Rect) -> Vec<Rect> {
+    let largest = values.iter().cloned().fold(0.0, f64::max);
+    let row = plot.height / values.len().max(1) as f32;
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, &value)| {
+            let width = if largest > 0.0 { (plot.width as f64 * value / largest) as f32 } else { 0.0 };
+            Rect::new(plot.x, plot.y + row * (index as f32 + BAR_GAP / 2.0), width, row * (1.0 - BAR_GAP))
+        })
+        .collect()
+}
+
+/// Pie slices as start and end angles in radians, clockwise from the top,
+/// each spanning its share of the total
+pub fn pie_layout(values: &[f64]) -> Vec<(f32, f32)> {
+    let total: f64 = values.iter().sum();
+    let mut angle = 0.0;
+    values
+        .iter()
+        .map(|&value| {
+            let start = angle;
+            if total > 0.0 {
+                angle += (TAU as f64 * value / total) as f32;
+            }
+            (start, angle)
+        })
+        .collect()
+}
+
+/// Index of the pie slice at `angle` (radians clockwise from the top)
+pub fn slice_at(slices: &[(f32, f32)], angle: f32) -> Option<usize> {
+    let angle = angle.rem_euclid(TAU);
+    slices.iter().position(|&(start, end)| angle >= start && angle < end)
+}
+
+/// The point at `angle` (clockwise from the top) on a circle
+pub fn point_on_circle(cx: f32, cy: f32, radius: f32, angle: f32) -> (f32, f32) {
+    (cx + radius * angle.sin(), cy - radius * angle.cos())
+}
+
+fn for_each_file(node: &TreeNode, visit: &mut impl FnMut(&TreeNode)) {
+    if !node.is_directory {
+        visit(node);
+    }
+    for child in &node.children {
+        for_each_file(child, visit);
+    }
+}
+
+fn blend(from: [u8; 3], to: [u8; 3], amount: f32) -> [u8; 3] {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+    [mix(from[0], to[0]), mix(from[1], to[1]), mix(from[2], to[2])]
+}
+
+fn hex(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.into(),
+            is_directory: !children.is_empty(),
+            size,
+            file_count: 1,
+            modified: None,
+            unix: None,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_size_and_type_charts() {
+        let tree = node("/data", 2_000_000, vec![
+            node("/data/empty.txt", 0, vec![]),
+            node("/data/a.rs", 5, vec![]),
+            node("/data/b.rs", 7, vec![]),
+            node("/data/media", 1_999_988, vec![node("/data/media/clip.mp4", 1_999_988, vec![])]),
+        ]);
+
+        let sizes = Chart::by_size(&tree);
+        let labels: Vec<&str> = sizes.entries.iter().map(|entry| entry.label.as_str()).collect();
+        assert_eq!(labels, ["Empty (0 bytes)", "1 B – 9 B", "1 MB – 9.99 MB"]);
+        assert_eq!((sizes.entries[1].count, sizes.entries[1].bytes), (2, 12));
+        assert_eq!(sizes.entries[0].color, SMALL_COLOR);
+
+        let types = Chart::by_type(&tree);
+        let labels: Vec<&str> = types.entries.iter().map(|entry| entry.label.as_str()).collect();
+        assert_eq!(labels, ["Source Code", "Document", "Video"]);
+        assert_eq!(types.describe(0), "Source Code\n2 files, 12 B\n50.0% of files, 0.0% of bytes");
+
+        let bars = bar_layout(&[4.0, 2.0], Rect::new(10.0, 0.0, 100.0, 40.0));
+        assert_eq!(bars[0].width, 100.0);
+        assert_eq!(bars[1].width, 50.0);
+        assert!(bars[1].y > bars[0].y + bars[0].height);
+
+        let slices = pie_layout(&[3.0, 1.0]);
+        assert!((slices[0].1 - 0.75 * TAU).abs() < 1e-4);
+        assert_eq!(slice_at(&slices, 0.1), Some(0));
+        assert_eq!(slice_at(&slices, -0.1), Some(1));
+
+        let svg = Chart { title: "Q&A".to_string(), entries: types.entries.clone() }.to_svg(ChartKind::Pie, ChartValue::Files);
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains("Q&amp;A (files)"));
+        assert_eq!(svg.matches("<path").count(), 3);
+        assert!(types.to_svg(ChartKind::Bar, ChartValue::Files).contains(">2 files</text>"));
+    }
+}
@@ -0,0 +1,334 @@
+//! Comparison of two scans of the same directory
+//!
+//! Files are matched by full path. Files present in both scans are reported
+//! as modified when their size, modification time or any hash calculated in
+//! both scans differs. Files that disappeared from one path and appeared at
+//! another with the same size and hash are reported as moved rather than as a
+//! deletion plus an addition.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::table::FileList;
+use crate::{Digest, HashAlgorithm};
+
+/// How a file differs between two scans
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum ChangeKind {
+    Added,
+    Deleted,
+    Modified,
+    Moved,
+}
+
+impl ChangeKind {
+    /// Returns a human-readable name for the change
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "Added",
+            ChangeKind::Deleted => "Deleted",
+            ChangeKind::Modified => "Modified",
+            ChangeKind::Moved => "Moved",
+        }
+    }
+}
+
+impl std::fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A single difference between two scans
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    /// Path in the newer scan (the old path for deleted files)
+    pub path: String,
+    /// Path in the older scan, for moved files
+    pub previous_path: Option<String>,
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+    /// What changed for modified files, e.g. "size, sha256"
+    pub details: String,
+}
+
+/// Result of comparing two scans
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanComparison {
+    /// Changes sorted by path
+    pub changes: Vec<FileChange>,
+    /// Number of files found unchanged at the same path
+    pub unchanged: usize,
+}
+
+impl ScanComparison {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Number of changes of a given kind
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|change| change.kind == kind).count()
+    }
+
+    /// Get a one-line summary of the differences
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added, {} deleted, {} modified, {} moved, {} unchanged",
+            self.count(ChangeKind::Added),
+            self.count(ChangeKind::Deleted),
+            self.count(ChangeKind::Modified),
+            self.count(ChangeKind::Moved),
+            self.unchanged,
+        )
+    }
+
+    /// Write the changes as CSV with a header row
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "Change,Path,Previous Path,Old Size,New Size,Details")?;
+        for change in &self.changes {
+            writeln!(
+                writer,
+                "{},\"{}\",\"{}\",{},{},\"{}\"",
+                change.kind,
+                change.path.replace('"', "\"\""),
+                change.previous_path.as_deref().unwrap_or("").replace('"', "\"\""),
+                change.old_size.map(|s| s.to_string()).unwrap_or_default(),
+                change.new_size.map(|s| s.to_string()).unwrap_or_default(),
+                change.details,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the comparison as JSON
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self).map_err(io::Error::from)?;
+        writeln!(writer)
+    }
+}
+
+/// Compare an older scan with a newer one
+///
+/// Either side can be a list of `FileInfo`s or rows of a
+/// [`FileTable`](crate::FileTable).
+pub fn compare_scans<O, N>(old: &O, new: &N) -> ScanComparison
+where
+    O: FileList + ?Sized,
+    N: FileList + ?Sized,
+{
+    let old_by_path: HashMap<Cow<str>, usize> = (0..old.len())
+        .map(|index| (old.full_path(index), index))
+        .collect();
+    let new_paths: HashSet<Cow<str>> = (0..new.len())
+        .map(|index| new.full_path(index))
+        .collect();
+
+    let mut comparison = ScanComparison::default();
+    let mut added = Vec::new();
+
+    for index in 0..new.len() {
+        let path = new.full_path(index);
+        match old_by_path.get(path.as_ref()) {
+            Some(&previous) => {
+                let differences = differences(old, previous, new, index);
+                if differences.is_empty() {
+                    comparison.unchanged += 1;
+                } else {
+                    comparison.changes.push(FileChange {
+                        kind: ChangeKind::Modified,
+                        path: path.into_owned(),
+                        previous_path: None,
+                        old_size: Some(old.size(previous)),
+                        new_size: Some(new.size(index)),
+                        details: differences.join(", "),
+                    });
+                }
+            }
+            None => added.push(index),
+        }
+    }
+
+    // Deleted files with a content hash are candidates for the source of a move
+    let mut deleted_by_content: HashMap<(u64, HashAlgorithm, Cow<Digest>), Vec<usize>> = HashMap::new();
+    let mut deleted = Vec::new();
+    for index in 0..old.len() {
+        if new_paths.contains(old.full_path(index).as_ref()) {
+            continue;
+        }
+        match content_hash(old, index) {
+            Some((algorithm, hash)) => deleted_by_content.entry((old.size(index), algorithm, hash)).or_default().push(index),
+            None => deleted.push(index),
+        }
+    }
+
+    for index in added {
+        let size = new.size(index);
+        let source = content_hash(new, index)
+            .and_then(|(algorithm, hash)| deleted_by_content.get_mut(&(size, algorithm, hash)))
+            .and_then(|candidates| candidates.pop());
+
+        comparison.changes.push(match source {
+            Some(previous) => FileChange {
+                kind: ChangeKind::Moved,
+                path: new.full_path(index).into_owned(),
+                previous_path: Some(old.full_path(previous).into_owned()),
+                old_size: Some(old.size(previous)),
+                new_size: Some(size),
+                details: String::new(),
+            },
+            None => FileChange {
+                kind: ChangeKind::Added,
+                path: new.full_path(index).into_owned(),
+                previous_path: None,
+                old_size: None,
+                new_size: Some(size),
+                details: String::new(),
+            },
+        });
+    }
+
+    deleted.extend(deleted_by_content.into_values().flatten());
+    for index in deleted {
+        comparison.changes.push(FileChange {
+            kind: ChangeKind::Deleted,
+            path: old.full_path(index).into_owned(),
+            previous_path: None,
+            old_size: Some(old.size(index)),
+            new_size: None,
+            details: String::new(),
+        });
+    }
+
+    comparison.changes.sort_by(|a, b| a.path.cmp(&b.path).then(a.kind.cmp(&b.kind)));
+    comparison
+}
+
+/// List the attributes that differ between two versions of a file
+fn differences<O, N>(old: &O, old_index: usize, new: &N, new_index: usize) -> Vec<&'static str>
+where
+    O: FileList + ?Sized,
+    N: FileList + ?Sized,
+{
+    let mut differences = Vec::new();
+    if old.size(old_index) != new.size(new_index) {
+        differences.push("size");
+    }
+    if !same_time(old.modified(old_index), new.modified(new_index)) {
+        differences.push("modified time");
+    }
+    for algorithm in HashAlgorithm::ALL {
+        if let (Some(old_hash), Some(new_hash)) = (old.hash(old_index, algorithm), new.hash(new_index, algorithm)) {
+            if old_hash != new_hash {
+                differences.push(algorithm.name());
+            }
+        }
+    }
+    differences
+}
+
+/// Whether two timestamps agree. One with no fractional part, from a
+/// baseline exported without sub-second precision or a filesystem that only
+/// keeps seconds, matches any time within the same second.
+fn same_time(old: Option<DateTime<Utc>>, new: Option<DateTime<Utc>>) -> bool {
+    match (old, new) {
+        (Some(old), Some(new)) if old.timestamp_subsec_nanos() == 0 || new.timestamp_subsec_nanos() == 0 => {
+            old.timestamp() == new.timestamp()
+        }
+        _ => old == new,
+    }
+}
+
+/// The strongest hash available for a file, used to recognise moved files
+fn content_hash<F: FileList + ?Sized>(files: &F, index: usize) -> Option<(HashAlgorithm, Cow<'_, Digest>)> {
+    HashAlgorithm::BY_STRENGTH
+        .into_iter()
+        .find_map(|algorithm| files.hash(index, algorithm).map(|hash| (algorithm, hash)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileInfo, FileTable};
+    use chrono::NaiveDateTime;
+
+    fn file(path: &str, size: u64, modified: &str, sha256: u8) -> FileInfo {
+        let mut file = FileInfo::for_test(path, size);
+        file.last_modified = NaiveDateTime::parse_from_str(modified, "%Y-%m-%d %H:%M:%S%.f").ok().map(|time| time.and_utc());
+        file.hashes.insert(HashAlgorithm::Sha256, Digest::Sha256([sha256; 32]));
+        file
+    }
+
+    fn kinds(comparison: &ScanComparison) -> Vec<(&str, ChangeKind)> {
+        comparison.changes.iter()
+            .map(|change| (change.path.as_str(), change.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_lists_added_and_deleted_files() {
+        let old = vec![file("/d/gone.txt", 4, "2024-01-01 00:00:00", 0xdd)];
+        let new = vec![file("/d/fresh.txt", 6, "2024-02-01 00:00:00", 0xff)];
+
+        let comparison = compare_scans(&old, &new);
+        assert_eq!(kinds(&comparison), vec![
+            ("/d/fresh.txt", ChangeKind::Added),
+            ("/d/gone.txt", ChangeKind::Deleted),
+        ]);
+        assert_eq!(comparison.unchanged, 0);
+    }
+
+    #[test]
+    fn test_compare_describes_modifications() {
+        let old = vec![file("/d/edit.txt", 2, "2024-01-01 00:00:00", 0xbb)];
+        let new = vec![file("/d/edit.txt", 5, "2024-02-01 00:00:00", 0xee)];
+
+        let comparison = compare_scans(&old, &new);
+        assert_eq!(kinds(&comparison), vec![("/d/edit.txt", ChangeKind::Modified)]);
+        assert_eq!(comparison.changes[0].details, "size, modified time, sha256");
+    }
+
+    #[test]
+    fn test_compare_detects_moves() {
+        let old = vec![file("/d/old/report.pdf", 3, "2024-01-01 00:00:00", 0xcc)];
+        let new = vec![file("/d/new/report.pdf", 3, "2024-01-01 00:00:00", 0xcc)];
+
+        let comparison = compare_scans(&old, &new);
+        assert_eq!(kinds(&comparison), vec![("/d/new/report.pdf", ChangeKind::Moved)]);
+        assert_eq!(comparison.changes[0].previous_path.as_deref(), Some("/d/old/report.pdf"));
+    }
+
+    #[test]
+    fn test_compare_reads_table_rows() {
+        let old = vec![
+            file("/d/kept.txt", 1, "2024-01-01 00:00:00", 0xaa),
+            file("/d/edit.txt", 2, "2024-01-01 00:00:00", 0xbb),
+        ];
+        let table: FileTable = [
+            file("/d/edit.txt", 2, "2024-01-01 00:00:00", 0xee),
+            file("/d/unlisted.txt", 3, "2024-01-01 00:00:00", 0xcc),
+            file("/d/kept.txt", 1, "2024-01-01 00:00:00", 0xaa),
+        ].into_iter().collect();
+
+        let comparison = compare_scans(&old, &table.rows(&[0, 2]));
+        assert_eq!(kinds(&comparison), vec![("/d/edit.txt", ChangeKind::Modified)]);
+        assert_eq!(comparison.changes[0].details, "sha256");
+        assert_eq!(comparison.unchanged, 1);
+    }
+
+    #[test]
+    fn test_compare_ignores_sub_second_precision() {
+        let old = vec![file("/d/same.txt", 1, "2024-01-01 00:00:00", 0xaa)];
+        // Rescanned with sub-second precision
+        let new = vec![file("/d/same.txt", 1, "2024-01-01 00:00:00.25", 0xaa)];
+
+        let comparison = compare_scans(&old, &new);
+        assert!(comparison.changes.is_empty());
+        assert_eq!(comparison.unchanged, 1);
+    }
+}
@@ -0,0 +1,264 @@
+//! Metadata filters applied during traversal
+//!
+//! A [`MetadataFilter`] keeps files by size, by created/modified/accessed
+//! time and by type. It is checked as each file is discovered, before any
+//! hashing or format detection, so files that do not match cost one
+//! `stat` call. Directories are never filtered out by metadata.
+//!
+//! Dates are interpreted in UTC, like the timestamps shown in file listings.
+
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::tree::{get_file_color, FileType};
+
+/// Metadata conditions a file must meet to be included
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataFilter {
+    /// Smallest size kept, in bytes
+    pub min_size: Option<u64>,
+    /// Largest size kept, in bytes
+    pub max_size: Option<u64>,
+    pub created: DateRange,
+    pub modified: DateRange,
+    pub accessed: DateRange,
+    /// Keep only files classified as one of these types
+    pub file_types: Vec<FileType>,
+    /// Keep only files whose detected MIME type is in one of these top-level
+    /// categories (`image`, `video`, `text`, ...). Detection reads the start
+    /// of the file, so it is checked last.
+    pub mime_categories: Vec<String>,
+}
+
+/// A time range; either end may be open. `from` is inclusive and `until` exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+}
+
+impl DateRange {
+    pub fn new(from: Option<SystemTime>, until: Option<SystemTime>) -> Self {
+        Self { from, until }
+    }
+
+    /// Parse the two ends of a range as typed by the user; an empty string
+    /// leaves that end open. See [`parse_date`] for the accepted forms.
+    pub fn parse(from: &str, until: &str) -> Result<Self, String> {
+        let bound = |value: &str, end: bool| {
+            let value = value.trim();
+            if value.is_empty() { Ok(None) } else { parse_date(value, end).map(Some) }
+        };
+        Ok(Self::new(bound(from, false)?, bound(until, true)?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.from.is_none() && self.until.is_none()
+    }
+
+    pub fn contains(&self, time: SystemTime) -> bool {
+        self.from.is_none_or(|from| time >= from) && self.until.is_none_or(|until| time < until)
+    }
+
+    /// Whether a timestamp that may be unavailable on this platform is in
+    /// range. An unknown time never matches a bounded range.
+    fn matches(&self, time: io::Result<SystemTime>) -> bool {
+        self.is_empty() || time.map(|time| self.contains(time)).unwrap_or(false)
+    }
+}
+
+impl MetadataFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_size(mut self, size: u64) -> Self {
+        self.min_size = Some(size);
+        self
+    }
+
+    pub fn max_size(mut self, size: u64) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    pub fn created(mut self, range: DateRange) -> Self {
+        self.created = range;
+        self
+    }
+
+    pub fn modified(mut self, range: DateRange) -> Self {
+        self.modified = range;
+        self
+    }
+
+    pub fn accessed(mut self, range: DateRange) -> Self {
+        self.accessed = range;
+        self
+    }
+
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        if !self.file_types.contains(&file_type) {
+            self.file_types.push(file_type);
+        }
+        self
+    }
+
+    pub fn mime_category(mut self, category: impl Into<String>) -> Self {
+        let category = category.into().trim().trim_end_matches("/*").to_lowercase();
+        if !self.mime_categories.contains(&category) {
+            self.mime_categories.push(category);
+        }
+        self
+    }
+
+    /// Whether no conditions are set, so every file passes
+    pub fn is_empty(&self) -> bool {
+        self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.created.is_empty()
+            && self.modified.is_empty()
+            && self.accessed.is_empty()
+            && self.file_types.is_empty()
+            && self.mime_categories.is_empty()
+    }
+
+    /// Whether a file with this metadata passes, cheapest checks first
+    pub fn matches(&self, path: &Path, metadata: &Metadata) -> bool {
+        let size = metadata.len();
+        if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+            return false;
+        }
+        if !self.modified.matches(metadata.modified())
+            || !self.created.matches(metadata.created())
+            || !self.accessed.matches(metadata.accessed())
+        {
+            return false;
+        }
+        if !self.file_types.is_empty() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let (_, file_type) = get_file_color(path, &name, false);
+            if !self.file_types.contains(&file_type) {
+                return false;
+            }
+        }
+        if !self.mime_categories.is_empty() {
+            let mime = crate::scanner::identify_mime_type(path).unwrap_or_default();
+            let category = mime.split('/').next().unwrap_or_default();
+            if !self.mime_categories.iter().any(|wanted| wanted == category) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Parse a size such as `1048576`, `500K`, `1.5G` or `2 TB` (binary units,
+/// matching the sizes shown in listings)
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("invalid size '{}' (expected e.g. 500K, 1.5G)", value)),
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number >= 0.0 => Ok((number * multiplier as f64).round() as u64),
+        _ => Err(format!("invalid size '{}' (expected e.g. 500K, 1.5G)", value)),
+    }
+}
+
+/// Parse one end of a date range. Accepts `2023`, `2023-06`, `2023-06-30`,
+/// `2023-06-30 14:00[:00]`, or an age such as `36h`, `30d`, `8w` or `2y`
+/// counted back from now. A year, month or day given as the end of a range
+/// (`end` set) covers the whole period, so `2023` to `2023` is all of 2023.
+pub fn parse_date(value: &str, end: bool) -> Result<SystemTime, String> {
+    let value = value.trim();
+    let invalid = || format!("invalid date '{}' (expected YYYY-MM-DD, YYYY-MM-DD HH:MM or an age like 30d)", value);
+
+    if let Some(age) = parse_age(value) {
+        return SystemTime::now().checked_sub(age).ok_or_else(invalid);
+    }
+
+    let datetime = if let Ok(year) = value.parse::<i32>() {
+        let start = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(invalid)?;
+        if end { NaiveDate::from_ymd_opt(year + 1, 1, 1).ok_or_else(invalid)? } else { start }
+            .and_time(NaiveTime::MIN)
+    } else if let Ok(date) = NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d") {
+        let next_month = date.checked_add_months(chrono::Months::new(1)).ok_or_else(invalid)?;
+        if end { next_month } else { date }.and_time(NaiveTime::MIN)
+    } else if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let next_day = date.succ_opt().ok_or_else(invalid)?;
+        if end { next_day } else { date }.and_time(NaiveTime::MIN)
+    } else {
+        let normalized = value.replacen('T', " ", 1);
+        NaiveDateTime::parse_from_str(&normalized, "%Y-%m-%d %H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(&normalized, "%Y-%m-%d %H:%M"))
+            .map_err(|_| invalid())?
+    };
+
+    let seconds = datetime.and_utc().timestamp();
+    let since_epoch = Duration::from_secs(seconds.unsigned_abs());
+    if seconds >= 0 {
+        Ok(SystemTime::UNIX_EPOCH + since_epoch)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(since_epoch).ok_or_else(invalid)
+    }
+}
+
+/// An age such as `30d`: a whole number followed by h, d, w or y
+fn parse_age(value: &str) -> Option<Duration> {
+    const HOUR: u64 = 60 * 60;
+    let unit = match value.chars().last()?.to_ascii_lowercase() {
+        'h' => HOUR,
+        'd' => 24 * HOUR,
+        'w' => 7 * 24 * HOUR,
+        'y' => 365 * 24 * HOUR,
+        _ => return None,
+    };
+    let count: u64 = value[..value.len() - 1].parse().ok()?;
+    Some(Duration::from_secs(count.checked_mul(unit)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_metadata_filter() {
+        assert_eq!(parse_size("1.5K"), Ok(1536));
+        assert_eq!(parse_size("2 GB"), Ok(2 << 30));
+        assert!(parse_size("12 parsecs").is_err());
+
+        let year = DateRange::parse("2023", "2023").unwrap();
+        assert!(year.contains(parse_date("2023-12-31 23:59", false).unwrap()));
+        assert!(!year.contains(parse_date("2024-01-01", false).unwrap()));
+        assert!(DateRange::parse("2023-13-01", "").is_err());
+
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        let photo = dir.path().join("photo.jpg");
+        let notes = dir.path().join("notes.txt");
+        fs::write(&photo, vec![0u8; 4096]).unwrap();
+        fs::write(&notes, b"short").unwrap();
+        let metadata = |path: &Path| fs::metadata(path).unwrap();
+
+        let filter = MetadataFilter::new().min_size(1024).file_type(FileType::Image);
+        assert!(filter.matches(&photo, &metadata(&photo)));
+        assert!(!filter.matches(&notes, &metadata(&notes)));
+
+        let old = MetadataFilter::new().modified(DateRange::parse("", "2y").unwrap());
+        assert!(!old.matches(&notes, &metadata(&notes)));
+        let text = MetadataFilter::new().mime_category("text/*");
+        assert!(text.matches(&notes, &metadata(&notes)));
+    }
+}
@@ -0,0 +1,231 @@
+//! Digital Forensics XML (DFXML) output
+//!
+//! Writes a detailed file list as a DFXML document with one `fileobject` per
+//! file, preceded by `creator` and `source` metadata describing the scan. The
+//! detected volume (filesystem type and mount point) is recorded in the
+//! enclosing `volume` element.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::export::{calculated, time_to_iso};
+use crate::{FileInfo, VolumeInfo};
+
+/// DFXML schema version written in the root element
+const DFXML_VERSION: &str = "1.2.0";
+
+/// Write a detailed file list as a DFXML document
+///
+/// # Arguments
+/// * `files` - Files to write as `fileobject` elements
+/// * `source` - The directory that was scanned
+/// * `volume` - The detected volume containing `source`, if known
+/// * `writer` - Destination for the XML
+pub fn write_dfxml<W: Write>(
+    files: &[FileInfo],
+    source: &Path,
+    volume: Option<&VolumeInfo>,
+    writer: W,
+) -> io::Result<()> {
+    let mut dfxml = DfxmlWriter::new(writer, source, volume)?;
+    for file in files {
+        dfxml.write_file(file)?;
+    }
+    dfxml.finish().map(|_| ())
+}
+
+/// Writes a DFXML document one `fileobject` at a time, for files arriving
+/// from a streaming scan
+///
+/// The document header is written on creation; call
+/// [`finish`](DfxmlWriter::finish) to close it.
+pub struct DfxmlWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> DfxmlWriter<W> {
+    pub fn new(mut writer: W, source: &Path, volume: Option<&VolumeInfo>) -> io::Result<Self> {
+        write_header(&mut writer, source, volume)?;
+        Ok(Self { writer })
+    }
+
+    pub fn write_file(&mut self, file: &FileInfo) -> io::Result<()> {
+        write_fileobject(file, &mut self.writer)
+    }
+
+    /// Push the elements written so far through to the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Close the document and return the underlying writer, flushed
+    pub fn finish(mut self) -> io::Result<W> {
+        writeln!(self.writer, "  </volume>")?;
+        writeln!(self.writer, "</dfxml>")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Write everything up to the first `fileobject`
+fn write_header<W: Write>(writer: &mut W, source: &Path, volume: Option<&VolumeInfo>) -> io::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        writer,
+        "<dfxml xmlns=\"http://www.forensicswiki.org/wiki/Category:Digital_Forensics_XML\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" version=\"{}\">",
+        DFXML_VERSION
+    )?;
+
+    writeln!(writer, "  <metadata>")?;
+    writeln!(writer, "    <dc:type>File System Listing</dc:type>")?;
+    writeln!(writer, "  </metadata>")?;
+
+    let command_line: Vec<String> = std::env::args().collect();
+    let start_time = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    writeln!(writer, "  <creator version=\"1.0\">")?;
+    writeln!(writer, "    <program>splendir</program>")?;
+    writeln!(writer, "    <version>{}</version>", env!("CARGO_PKG_VERSION"))?;
+    writeln!(writer, "    <execution_environment>")?;
+    writeln!(writer, "      <os_sysname>{}</os_sysname>", std::env::consts::OS)?;
+    writeln!(writer, "      <command_line>{}</command_line>", escape_xml(&command_line.join(" ")))?;
+    writeln!(writer, "      <start_time>{}</start_time>", start_time)?;
+    writeln!(writer, "    </execution_environment>")?;
+    writeln!(writer, "  </creator>")?;
+
+    writeln!(writer, "  <source>")?;
+    writeln!(writer, "    <image_filename>{}</image_filename>", escape_xml(&source.to_string_lossy()))?;
+    writeln!(writer, "  </source>")?;
+
+    writeln!(writer, "  <volume>")?;
+    if let Some(info) = volume {
+        writeln!(writer, "    <ftype_str>{}</ftype_str>", escape_xml(info.filesystem_type.name()))?;
+        writeln!(writer, "    <mount_point>{}</mount_point>", escape_xml(&info.mount_point.to_string_lossy()))?;
+        if let Some(label) = info.label.as_deref().filter(|label| !label.is_empty()) {
+            writeln!(writer, "    <volume_label>{}</volume_label>", escape_xml(label))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a single `fileobject` element
+fn write_fileobject<W: Write>(file: &FileInfo, writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "    <fileobject>")?;
+    writeln!(writer, "      <filename>{}</filename>", escape_xml(&file.full_path))?;
+    writeln!(writer, "      <name_type>r</name_type>")?;
+    writeln!(writer, "      <filesize>{}</filesize>", file.size)?;
+
+    // In schema order; DFXML records the permission bits without the file type
+    if let Some(unix) = &file.unix {
+        writeln!(writer, "      <inode>{}</inode>", unix.inode)?;
+        writeln!(writer, "      <mode>{}</mode>", unix.mode & 0o7777)?;
+        writeln!(writer, "      <nlink>{}</nlink>", unix.links)?;
+        writeln!(writer, "      <uid>{}</uid>", unix.uid)?;
+        writeln!(writer, "      <gid>{}</gid>", unix.gid)?;
+    }
+
+    let changed = file.unix.and_then(|unix| unix.changed);
+    for (element, value) in [
+        ("mtime", &file.last_modified),
+        ("ctime", &changed),
+        ("atime", &file.last_accessed),
+        ("crtime", &file.created),
+    ] {
+        if let Some(time) = value {
+            writeln!(writer, "      <{}>{}</{}>", element, time_to_iso(*time), element)?;
+        }
+    }
+
+    if let Some(format) = calculated(file.format.clone()) {
+        writeln!(writer, "      <libmagic>{}</libmagic>", escape_xml(&format))?;
+    }
+
+    for (algorithm, digest) in &file.hashes {
+        writeln!(writer, "      <hashdigest type=\"{}\">{}</hashdigest>", algorithm.name(), escape_xml(&digest.to_string()))?;
+    }
+
+    writeln!(writer, "    </fileobject>")
+}
+
+/// Escape text for use in XML element content and attribute values
+pub(crate) fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab and newline are not valid XML 1.0
+            c if c.is_control() && c != '\t' && c != '\n' => {
+                escaped.push_str(&format!("\\u{{{:04x}}}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Digest, HashAlgorithm, UnixMetadata};
+    use chrono::{TimeZone, Utc};
+
+    fn fileobject_xml(file: &FileInfo) -> String {
+        let mut output = Vec::new();
+        write_fileobject(file, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_fileobject_escapes_names() {
+        let xml = fileobject_xml(&FileInfo::for_test("/data/a&b.txt", 12));
+        assert!(xml.contains("<filename>/data/a&amp;b.txt</filename>"));
+    }
+
+    #[test]
+    fn test_fileobject_skips_uncalculated_values() {
+        let file = FileInfo {
+            last_modified: Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 45).single(),
+            ..FileInfo::for_test("/data/a.txt", 12)
+        };
+        let xml = fileobject_xml(&file);
+
+        assert!(xml.contains("<mtime>2024-03-01T12:30:45Z</mtime>"));
+        assert!(!xml.contains("<crtime>"));
+        assert!(!xml.contains("<ctime>"));
+        assert!(!xml.contains("<hashdigest"));
+        assert!(!xml.contains("libmagic"));
+    }
+
+    #[test]
+    fn test_fileobject_writes_unix_metadata() {
+        let file = FileInfo {
+            unix: Some(UnixMetadata {
+                mode: 0o100644,
+                uid: 1000,
+                gid: 100,
+                inode: 4242,
+                links: 1,
+                changed: None,
+            }),
+            ..FileInfo::for_test("/data/a.txt", 12)
+        };
+        let xml = fileobject_xml(&file);
+        assert!(xml.contains("<inode>4242</inode>\n      <mode>420</mode>"));
+    }
+
+    #[test]
+    fn test_fileobject_writes_requested_digests() {
+        let mut file = FileInfo::for_test("/data/a.txt", 12);
+        file.hashes.insert(HashAlgorithm::Md5, Digest::Md5([
+            0xd4, 0x1d, 0x8c, 0xd9, 0x8f, 0x00, 0xb2, 0x04, 0xe9, 0x80, 0x09, 0x98, 0xec, 0xf8, 0x42, 0x7e,
+        ]));
+        let xml = fileobject_xml(&file);
+
+        assert!(xml.contains("<hashdigest type=\"md5\">d41d8cd98f00b204e9800998ecf8427e</hashdigest>"));
+        assert!(!xml.contains("sha256"));
+    }
+}
@@ -0,0 +1,369 @@
+//! Duplicate file detection
+//!
+//! Files are grouped by size first, since files of different sizes cannot be
+//! identical. Only groups with more than one member are read at all. An
+//! optional partial hash over the first and last blocks of each file splits
+//! those groups cheaply before the full SHA256 is calculated for the files
+//! that still collide.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+
+use crate::report::{IssueCollector, ScanIssue, ScanPhase, ScanReport};
+use crate::table::FileList;
+use crate::{Digest, HashAlgorithm, ProgressCallback, ScanError};
+
+/// Size of the blocks read from each end of a file for the partial hash
+const PARTIAL_BLOCK_SIZE: u64 = 4096;
+
+/// A group of files with identical contents
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateSet {
+    /// Size of each file in bytes
+    pub size: u64,
+    /// SHA256 of the shared contents
+    pub sha256: Digest,
+    /// Paths of all copies, sorted
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// Bytes that would be freed by keeping only one copy
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.paths.len() as u64).saturating_sub(1)
+    }
+}
+
+/// Result of a duplicate search
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DuplicateReport {
+    /// Duplicate sets, largest wasted space first
+    pub sets: Vec<DuplicateSet>,
+    /// Number of files considered
+    pub files_examined: usize,
+    /// Number of files whose full contents had to be hashed
+    pub files_hashed: usize,
+    /// Files that could not be read
+    #[serde(skip)]
+    pub report: ScanReport,
+}
+
+impl DuplicateReport {
+    /// Total bytes that would be freed by keeping one copy of each set
+    pub fn wasted_bytes(&self) -> u64 {
+        self.sets.iter().map(|set| set.wasted_bytes()).sum()
+    }
+
+    /// Number of files that are redundant copies
+    pub fn duplicate_count(&self) -> usize {
+        self.sets.iter().map(|set| set.paths.len() - 1).sum()
+    }
+
+    /// Get a one-line summary of the duplicates found
+    pub fn summary(&self) -> String {
+        format!(
+            "{} duplicate sets, {} redundant copies, {} wasted",
+            self.sets.len(),
+            self.duplicate_count(),
+            crate::format_file_size(self.wasted_bytes()),
+        )
+    }
+
+    /// Write duplicate sets as CSV, one row per file
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "Set,Size (bytes),SHA256,Path")?;
+        for (index, set) in self.sets.iter().enumerate() {
+            for path in &set.paths {
+                writeln!(
+                    writer,
+                    "{},{},{},\"{}\"",
+                    index + 1,
+                    set.size,
+                    set.sha256,
+                    path.to_string_lossy().replace('"', "\"\""),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the duplicate sets as JSON
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self).map_err(io::Error::from)?;
+        writeln!(writer)
+    }
+}
+
+/// Finds duplicate files among the results of a scan
+#[derive(Clone)]
+pub struct DuplicateFinder {
+    /// Compare a hash of the first and last blocks before hashing whole files
+    pub partial_hash: bool,
+    /// Ignore files smaller than this (empty files are ignored by default)
+    pub min_size: u64,
+    pub cancellation_flag: Option<Arc<AtomicBool>>,
+}
+
+impl Default for DuplicateFinder {
+    fn default() -> Self {
+        Self {
+            partial_hash: true,
+            min_size: 1,
+            cancellation_flag: None,
+        }
+    }
+}
+
+impl DuplicateFinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn partial_hash(mut self, enabled: bool) -> Self {
+        self.partial_hash = enabled;
+        self
+    }
+
+    pub fn min_size(mut self, size: u64) -> Self {
+        self.min_size = size;
+        self
+    }
+
+    pub fn cancellation_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancellation_flag = Some(flag);
+        self
+    }
+
+    /// Find duplicates among scanned files
+    ///
+    /// SHA256 values already calculated during the scan are reused instead of
+    /// reading the files again. `files` can be a list of `FileInfo`s or rows
+    /// of a [`FileTable`](crate::FileTable).
+    pub fn find<F: FileList + ?Sized>(&self, files: &F, progress_callback: Option<ProgressCallback>) -> Result<DuplicateReport, ScanError> {
+        let issues = IssueCollector::new();
+
+        // Group by size; only sizes shared by several files can hold duplicates
+        let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+        for index in (0..files.len()).filter(|&index| files.size(index) >= self.min_size) {
+            by_size.entry(files.size(index)).or_default().push(index);
+        }
+        let mut candidates: Vec<usize> = by_size.into_values()
+            .filter(|group| group.len() > 1)
+            .flatten()
+            .collect();
+
+        if self.partial_hash {
+            candidates = self.filter_by_partial_hash(files, candidates, &progress_callback, &issues)?;
+        }
+
+        let total = candidates.len();
+        let processed = AtomicUsize::new(0);
+        let hashed = AtomicUsize::new(0);
+        let full_hashes: Vec<(u64, Digest, PathBuf)> = candidates
+            .par_iter()
+            .filter_map(|&index| {
+                if self.is_cancelled() {
+                    return None;
+                }
+                let path = PathBuf::from(files.full_path(index).as_ref());
+                let sha256 = if let Some(sha256) = files.hash(index, HashAlgorithm::Sha256) {
+                    Some(sha256.into_owned())
+                } else {
+                    hashed.fetch_add(1, Ordering::Relaxed);
+                    match crate::calculate_file_hashes(&path, &[HashAlgorithm::Sha256]) {
+                        Ok(mut hashes) => hashes.remove(&HashAlgorithm::Sha256),
+                        Err(e) => {
+                            issues.record(ScanIssue::new(&path, ScanPhase::Hashing, &e));
+                            None
+                        }
+                    }
+                };
+
+                let count = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(ref callback) = progress_callback {
+                    if count.is_multiple_of(10) || count == total {
+                        let progress = 0.5 + 0.5 * count as f32 / total.max(1) as f32;
+                        callback(progress, format!("Hashing {}/{} candidate files", count, total));
+                    }
+                }
+
+                sha256.map(|hash| (files.size(index), hash, path))
+            })
+            .collect();
+
+        if self.is_cancelled() {
+            return Err(ScanError::Cancelled);
+        }
+
+        let mut by_content: HashMap<(u64, Digest), Vec<PathBuf>> = HashMap::new();
+        for (size, sha256, path) in full_hashes {
+            by_content.entry((size, sha256)).or_default().push(path);
+        }
+
+        let mut sets: Vec<DuplicateSet> = by_content.into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|((size, sha256), mut paths)| {
+                paths.sort();
+                DuplicateSet { size, sha256, paths }
+            })
+            .collect();
+        sets.sort_by(|a, b| b.wasted_bytes().cmp(&a.wasted_bytes()).then(a.paths.cmp(&b.paths)));
+
+        Ok(DuplicateReport {
+            sets,
+            files_examined: files.len(),
+            files_hashed: hashed.into_inner(),
+            report: issues.into_report(),
+        })
+    }
+
+    /// Drop candidates whose first and last blocks match no other file of the same size
+    fn filter_by_partial_hash<F: FileList + ?Sized>(
+        &self,
+        files: &F,
+        candidates: Vec<usize>,
+        progress_callback: &Option<ProgressCallback>,
+        issues: &IssueCollector,
+    ) -> Result<Vec<usize>, ScanError> {
+        let total = candidates.len();
+        let processed = AtomicUsize::new(0);
+        let partials: Vec<((u64, [u8; 32]), usize)> = candidates
+            .par_iter()
+            .filter_map(|&index| {
+                if self.is_cancelled() {
+                    return None;
+                }
+                let full_path = files.full_path(index);
+                let path = Path::new(full_path.as_ref());
+                let size = files.size(index);
+                let result = partial_hash(path, size);
+
+                let count = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(ref callback) = progress_callback {
+                    if count.is_multiple_of(10) || count == total {
+                        let progress = 0.5 * count as f32 / total.max(1) as f32;
+                        callback(progress, format!("Checking {}/{} files of matching size", count, total));
+                    }
+                }
+
+                match result {
+                    Ok(hash) => Some(((size, hash), index)),
+                    Err(e) => {
+                        issues.record(ScanIssue::new(path, ScanPhase::Hashing, &e));
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        if self.is_cancelled() {
+            return Err(ScanError::Cancelled);
+        }
+
+        let mut groups: HashMap<(u64, [u8; 32]), Vec<usize>> = HashMap::new();
+        for (key, index) in partials {
+            groups.entry(key).or_default().push(index);
+        }
+        Ok(groups.into_values().filter(|group| group.len() > 1).flatten().collect())
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation_flag
+            .as_ref()
+            .map(|flag| flag.load(Ordering::Relaxed))
+            .unwrap_or(false)
+    }
+}
+
+/// Hash the first and last blocks of a file
+fn partial_hash(path: &Path, size: u64) -> io::Result<[u8; 32]> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; PARTIAL_BLOCK_SIZE as usize];
+
+    let head = read_up_to(&mut file, &mut buffer)?;
+    hasher.update(&buffer[..head]);
+
+    if size > 2 * PARTIAL_BLOCK_SIZE {
+        file.seek(SeekFrom::End(-(PARTIAL_BLOCK_SIZE as i64)))?;
+        let tail = read_up_to(&mut file, &mut buffer)?;
+        hasher.update(&buffer[..tail]);
+    } else if size > PARTIAL_BLOCK_SIZE {
+        let tail = read_up_to(&mut file, &mut buffer)?;
+        hasher.update(&buffer[..tail]);
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+/// Fill as much of the buffer as the file allows
+fn read_up_to(file: &mut fs::File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DirectoryScanner, FileTable};
+
+    #[test]
+    fn test_finds_duplicates_with_partial_hash() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        let mut large = vec![7u8; 3 * PARTIAL_BLOCK_SIZE as usize];
+        let shared = Digest::Sha256(Sha256::digest(&large).into());
+        fs::write(dir.path().join("a.bin"), &large).unwrap();
+        fs::write(dir.path().join("b.bin"), &large).unwrap();
+        // Same size, same first and last blocks, different middle
+        large[PARTIAL_BLOCK_SIZE as usize + 1] = 8;
+        fs::write(dir.path().join("c.bin"), &large).unwrap();
+        fs::write(dir.path().join("small.txt"), b"unique").unwrap();
+
+        let files = DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .calculate_sha256(false)
+            .scan_detailed(dir.path())
+            .unwrap();
+
+        for partial in [true, false] {
+            let report = DuplicateFinder::new().partial_hash(partial).find(&files, None).unwrap();
+            assert_eq!(report.sets.len(), 1);
+            let names: Vec<_> = report.sets[0].paths.iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            assert_eq!(names, vec!["a.bin", "b.bin"]);
+            assert_eq!(report.sets[0].sha256, shared);
+            assert_eq!(report.wasted_bytes(), 3 * PARTIAL_BLOCK_SIZE);
+        }
+
+        // The digest is written out as hex
+        let report = DuplicateFinder::new().find(&files, None).unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["sets"][0]["sha256"], shared.to_string());
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv).unwrap();
+        assert!(String::from_utf8(csv).unwrap().lines().nth(1).unwrap().starts_with(&format!("1,{},{},", 3 * PARTIAL_BLOCK_SIZE, shared)));
+
+        // Rows of a table give the same sets without rebuilding the files
+        let table: FileTable = files.iter().cloned().collect();
+        let rows: Vec<u32> = (0..table.len() as u32).rev().collect();
+        let report = DuplicateFinder::new().find(&table.rows(&rows), None).unwrap();
+        assert_eq!(report.sets.len(), 1);
+        assert_eq!(report.sets[0].paths.len(), 2);
+        assert_eq!(report.files_examined, files.len());
+    }
+}
@@ -0,0 +1,418 @@
+//! Structured export of scan results
+//!
+//! Detailed file lists are written as a JSON array or as JSON Lines (one
+//! object per line, suitable for streaming into other tools), trees as
+//! nested JSON, and directory analyses as a single JSON document. Sizes are
+//! emitted as numbers, timestamps as ISO 8601 strings in UTC, and values that
+//! were not calculated during the scan as `null`.
+//!
+//! File lists can also be written incrementally with a [`FileListWriter`],
+//! one record or batch at a time as a streaming scan produces them.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::hashing::FileHashes;
+use crate::unix::UnixMetadata;
+use crate::{DirectoryAnalysis, FileInfo, FileSizeDistribution, FileType, Rankings, TreeNode};
+
+/// Placeholder stored in `FileInfo` for values that were not calculated
+const NOT_CALCULATED: &str = "Not calculated";
+
+/// Serialized form of a `FileInfo`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub name: String,
+    pub full_path: String,
+    pub directory_path: String,
+    pub size: u64,
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    pub accessed: Option<String>,
+    /// Digests keyed by algorithm name (`md5`, `sha256`, ...), stored alongside the other fields
+    #[serde(flatten, deserialize_with = "crate::hashing::deserialize_hashes")]
+    pub hashes: FileHashes,
+    pub format: Option<String>,
+    pub mime_type: Option<String>,
+    /// Unix `st_mode`, owner and group ids, inode, link count and status
+    /// change time; absent on other platforms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed: Option<String>,
+}
+
+impl From<FileInfo> for FileRecord {
+    fn from(info: FileInfo) -> Self {
+        Self {
+            created: info.created.map(time_to_iso),
+            modified: info.last_modified.map(time_to_iso),
+            accessed: info.last_accessed.map(time_to_iso),
+            hashes: info.hashes,
+            format: calculated(info.format),
+            mime_type: calculated(info.mime_type),
+            mode: info.unix.map(|unix| unix.mode),
+            uid: info.unix.map(|unix| unix.uid),
+            gid: info.unix.map(|unix| unix.gid),
+            inode: info.unix.map(|unix| unix.inode),
+            links: info.unix.map(|unix| unix.links),
+            changed: info.unix.and_then(|unix| unix.changed).map(time_to_iso),
+            name: info.name,
+            full_path: info.full_path,
+            directory_path: info.directory_path,
+            size: info.size,
+        }
+    }
+}
+
+impl From<FileRecord> for FileInfo {
+    fn from(record: FileRecord) -> Self {
+        Self {
+            created: record.created.as_deref().and_then(iso_to_time),
+            last_modified: record.modified.as_deref().and_then(iso_to_time),
+            last_accessed: record.accessed.as_deref().and_then(iso_to_time),
+            hashes: record.hashes,
+            format: record.format.unwrap_or_else(|| NOT_CALCULATED.to_string()),
+            mime_type: record.mime_type.unwrap_or_else(|| NOT_CALCULATED.to_string()),
+            unix: match (record.mode, record.uid, record.gid, record.inode, record.links) {
+                (Some(mode), Some(uid), Some(gid), Some(inode), Some(links)) => Some(UnixMetadata {
+                    mode,
+                    uid,
+                    gid,
+                    inode,
+                    links,
+                    changed: record.changed.as_deref().and_then(iso_to_time),
+                }),
+                _ => None,
+            },
+            name: record.name,
+            full_path: record.full_path,
+            directory_path: record.directory_path,
+            size: record.size,
+        }
+    }
+}
+
+/// Serialized form of a `DirectoryAnalysis` (the tree is exported separately)
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisRecord {
+    pub path: PathBuf,
+    pub filesystem: Option<VolumeRecord>,
+    pub file_count: usize,
+    pub directory_count: usize,
+    pub total_size: u64,
+    pub oldest_modified: Option<String>,
+    pub newest_modified: Option<String>,
+    pub size_distribution: FileSizeDistribution,
+    /// File counts keyed by file type description (directories excluded)
+    pub file_types: BTreeMap<String, usize>,
+    /// Entries skipped because of `.gitignore` or `.ignore` rules
+    pub ignored_count: usize,
+    pub skipped_virtual_filesystems: Vec<PathBuf>,
+    pub problems: Vec<ProblemRecord>,
+    /// Largest files and directories, as many of each as the summary lists
+    pub rankings: Rankings,
+}
+
+/// Serialized form of a `VolumeInfo`
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeRecord {
+    pub filesystem_type: String,
+    pub mount_point: PathBuf,
+    pub label: Option<String>,
+    pub is_remote: bool,
+}
+
+/// Serialized form of a `ScanIssue`
+#[derive(Debug, Clone, Serialize)]
+pub struct ProblemRecord {
+    pub path: PathBuf,
+    pub phase: String,
+    pub kind: String,
+    pub message: String,
+}
+
+impl From<&DirectoryAnalysis> for AnalysisRecord {
+    fn from(analysis: &DirectoryAnalysis) -> Self {
+        let file_types = analysis.file_type_counts
+            .iter()
+            .filter(|(file_type, _)| **file_type != FileType::Directory)
+            .map(|(file_type, count)| (file_type.description().to_string(), *count))
+            .collect();
+
+        let filesystem = analysis.volume_info.as_ref().map(|info| VolumeRecord {
+            filesystem_type: info.filesystem_type.name().to_string(),
+            mount_point: info.mount_point.clone(),
+            label: info.label.clone(),
+            is_remote: info.is_remote,
+        });
+
+        let problems = analysis.report.issues
+            .iter()
+            .map(|issue| ProblemRecord {
+                path: issue.path.clone(),
+                phase: issue.phase.name().to_string(),
+                kind: format!("{:?}", issue.kind),
+                message: issue.message.clone(),
+            })
+            .collect();
+
+        Self {
+            path: analysis.path.clone(),
+            filesystem,
+            file_count: analysis.stats.file_count,
+            directory_count: analysis.stats.directory_count,
+            total_size: analysis.stats.total_size,
+            oldest_modified: analysis.stats.oldest_modified.map(time_to_iso),
+            newest_modified: analysis.stats.newest_modified.map(time_to_iso),
+            size_distribution: analysis.stats.size_distribution.clone(),
+            file_types,
+            ignored_count: analysis.stats.ignored_count,
+            skipped_virtual_filesystems: analysis.skipped_virtual_filesystems.clone(),
+            problems,
+            rankings: analysis.rankings(analysis.top_count),
+        }
+    }
+}
+
+/// Write a detailed file list as a pretty-printed JSON array
+pub fn write_files_json<W: Write>(files: &[FileInfo], writer: W) -> io::Result<()> {
+    let mut list = FileListWriter::new(writer, FileListFormat::Json);
+    list.write_batch(files)?;
+    list.finish().map(|_| ())
+}
+
+/// Write a detailed file list as JSON Lines (one compact object per line)
+pub fn write_files_jsonl<W: Write>(files: &[FileInfo], writer: W) -> io::Result<()> {
+    let mut list = FileListWriter::new(writer, FileListFormat::JsonLines);
+    list.write_batch(files)?;
+    list.finish().map(|_| ())
+}
+
+/// Layout of a file list written by a [`FileListWriter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileListFormat {
+    /// A pretty-printed JSON array, as written by `write_files_json`
+    Json,
+    /// One compact JSON object per line
+    JsonLines,
+}
+
+/// Writes file records as they arrive, so a listing never has to be held
+/// in memory
+///
+/// Call [`finish`](FileListWriter::finish) after the last record to close
+/// the JSON array.
+pub struct FileListWriter<W: Write> {
+    writer: W,
+    format: FileListFormat,
+    written: usize,
+}
+
+impl<W: Write> FileListWriter<W> {
+    pub fn new(writer: W, format: FileListFormat) -> Self {
+        Self { writer, format, written: 0 }
+    }
+
+    /// Number of records written so far
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    pub fn write(&mut self, file: &FileInfo) -> io::Result<()> {
+        match self.format {
+            FileListFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, file).map_err(io::Error::from)?;
+                self.writer.write_all(b"\n")?;
+            }
+            FileListFormat::Json => {
+                // Indent each record one level, matching a pretty-printed array
+                let record = serde_json::to_string_pretty(file).map_err(io::Error::from)?;
+                self.writer.write_all(if self.written == 0 { b"[\n" } else { b",\n" })?;
+                for (i, line) in record.lines().enumerate() {
+                    if i > 0 {
+                        self.writer.write_all(b"\n")?;
+                    }
+                    write!(self.writer, "  {}", line)?;
+                }
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn write_batch(&mut self, files: &[FileInfo]) -> io::Result<()> {
+        files.iter().try_for_each(|file| self.write(file))
+    }
+
+    /// Push the records written so far through to the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Close the list and return the underlying writer, flushed
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.format == FileListFormat::Json {
+            self.writer.write_all(if self.written == 0 { b"[]\n" } else { b"\n]\n" })?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Write a tree as nested JSON
+pub fn write_tree_json<W: Write>(tree: &TreeNode, mut writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, tree).map_err(io::Error::from)?;
+    writeln!(writer)
+}
+
+/// Write a directory analysis as JSON
+pub fn write_analysis_json<W: Write>(analysis: &AnalysisRecord, mut writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, analysis).map_err(io::Error::from)?;
+    writeln!(writer)
+}
+
+/// Read a detailed file list saved by `write_files_json` or `write_files_jsonl`
+///
+/// The format is detected from the first non-whitespace character.
+pub fn read_files_json<R: BufRead>(mut reader: R) -> io::Result<Vec<FileInfo>> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents)?;
+
+    if contents.trim_start().starts_with('[') {
+        return serde_json::from_str(&contents).map_err(io::Error::from);
+    }
+
+    contents.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(io::Error::from))
+        .collect()
+}
+
+/// Format a timestamp as ISO 8601 in UTC, with as many fractional digits as
+/// it has (none for whole seconds)
+pub(crate) fn time_to_iso(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parse an ISO 8601 timestamp in any offset
+fn iso_to_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|time| time.with_timezone(&Utc))
+}
+
+/// Map the "Not calculated" placeholder to `None`
+pub(crate) fn calculated(value: String) -> Option<String> {
+    if is_calculated(&value) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Whether a `FileInfo` field holds a real value rather than the placeholder
+pub(crate) fn is_calculated(value: &str) -> bool {
+    value != NOT_CALCULATED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::{Digest, HashAlgorithm};
+
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn utc(value: &str) -> Option<DateTime<Utc>> {
+        iso_to_time(value)
+    }
+
+    fn sample_file() -> FileInfo {
+        FileInfo {
+            last_modified: utc("2024-03-01T12:30:45Z"),
+            last_accessed: utc("2024-03-02T08:00:00.125Z"),
+            hashes: FileHashes::from([(HashAlgorithm::Sha256, Digest::parse(HashAlgorithm::Sha256, EMPTY_SHA256).unwrap())]),
+            format: "Portable Document Format".to_string(),
+            unix: Some(UnixMetadata {
+                mode: 0o100640,
+                uid: 1000,
+                gid: 100,
+                inode: 4242,
+                links: 1,
+                changed: utc("2024-03-01T12:30:45.5Z"),
+            }),
+            ..FileInfo::for_test("/data/report.pdf", 2048)
+        }
+    }
+
+    #[test]
+    fn test_file_json_is_typed() {
+        let value = serde_json::to_value(sample_file()).unwrap();
+
+        assert_eq!(value["size"], 2048);
+        assert_eq!(value["modified"], "2024-03-01T12:30:45Z");
+        assert_eq!(value["accessed"], "2024-03-02T08:00:00.125Z");
+        assert!(value["created"].is_null());
+        assert!(value["md5"].is_null());
+        assert_eq!(value["sha256"], EMPTY_SHA256);
+        assert_eq!(value["mode"], 0o100640);
+        assert_eq!(value["changed"], "2024-03-01T12:30:45.500Z");
+    }
+
+    #[test]
+    fn test_file_json_round_trip() {
+        let mut output = Vec::new();
+        write_files_jsonl(&[sample_file(), sample_file()], &mut output).unwrap();
+
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text.lines().count(), 2);
+
+        let restored = read_files_json(text.as_bytes()).unwrap();
+        assert_eq!(restored.len(), 2);
+        let restored = &restored[0];
+        let original = sample_file();
+        assert_eq!(restored.last_modified, original.last_modified);
+        assert_eq!(restored.last_accessed, original.last_accessed);
+        assert_eq!(restored.created, original.created);
+        assert_eq!(restored.hashes, original.hashes);
+        assert_eq!(restored.unix, original.unix);
+    }
+
+    #[test]
+    fn test_file_json_reads_null_digests() {
+        // Exports from before the hash map wrote null for uncalculated digests
+        let legacy = r#"{"name":"a","full_path":"/a","directory_path":"/","size":1,"created":null,"modified":null,"accessed":null,"md5":null,"sha256":"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855","sha512":null,"format":null,"mime_type":null}"#;
+        let restored = read_files_json(legacy.as_bytes()).unwrap();
+        assert_eq!(restored[0].hashes, sample_file().hashes);
+        assert_eq!(restored[0].unix, None);
+    }
+
+    #[test]
+    fn test_incremental_json_matches_array() {
+        let files = [sample_file(), sample_file()];
+        let mut expected = serde_json::to_vec_pretty(&files).unwrap();
+        expected.push(b'\n');
+
+        let mut list = FileListWriter::new(Vec::new(), FileListFormat::Json);
+        list.write(&files[0]).unwrap();
+        list.write_batch(&files[1..]).unwrap();
+        assert_eq!(list.written(), 2);
+        assert_eq!(list.finish().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_incremental_json_without_files() {
+        let empty = FileListWriter::new(Vec::new(), FileListFormat::Json).finish().unwrap();
+        assert_eq!(empty, b"[]\n");
+    }
+}
@@ -0,0 +1,248 @@
+//! Include and exclude filters applied during traversal
+//!
+//! A [`PathFilter`] holds the patterns as entered by the user: gitignore-style
+//! globs, regular expressions over the full path, and lists of extensions.
+//! It is compiled against the scan root when a scan starts.
+//!
+//! Exclusions apply to directories as well as files, and an excluded
+//! directory is never descended into. Inclusions only apply to files, since
+//! any directory might contain a matching file. When include patterns are
+//! set, a file is kept if it matches at least one of them.
+
+use std::fmt;
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::Regex;
+
+/// Patterns deciding which entries a scan includes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathFilter {
+    /// Keep only files matching one of these globs (`*.rs`, `src/`, `docs/**/*.md`)
+    pub include_globs: Vec<String>,
+    /// Skip entries matching these globs (`node_modules/`, `*.tmp`, `/build`)
+    pub exclude_globs: Vec<String>,
+    /// Keep only files whose full path matches one of these regexes
+    pub include_regexes: Vec<String>,
+    /// Skip entries whose full path matches one of these regexes
+    pub exclude_regexes: Vec<String>,
+    /// Keep only files with one of these extensions (case-insensitive, no dot)
+    pub include_extensions: Vec<String>,
+    /// Skip files with one of these extensions (case-insensitive, no dot)
+    pub exclude_extensions: Vec<String>,
+}
+
+/// A pattern that could not be compiled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    pub pattern: String,
+    pub message: String,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern '{}': {}", self.pattern, self.message)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl PathFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.include_globs.push(pattern.into());
+        self
+    }
+
+    pub fn exclude_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude_globs.push(pattern.into());
+        self
+    }
+
+    pub fn include_regex(mut self, pattern: impl Into<String>) -> Self {
+        self.include_regexes.push(pattern.into());
+        self
+    }
+
+    pub fn exclude_regex(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude_regexes.push(pattern.into());
+        self
+    }
+
+    pub fn include_extension(mut self, extension: impl Into<String>) -> Self {
+        self.include_extensions.push(normalize_extension(&extension.into()));
+        self
+    }
+
+    pub fn exclude_extension(mut self, extension: impl Into<String>) -> Self {
+        self.exclude_extensions.push(normalize_extension(&extension.into()));
+        self
+    }
+
+    /// Whether no patterns are set, so every entry passes
+    pub fn is_empty(&self) -> bool {
+        self.include_globs.is_empty()
+            && self.exclude_globs.is_empty()
+            && self.include_regexes.is_empty()
+            && self.exclude_regexes.is_empty()
+            && self.include_extensions.is_empty()
+            && self.exclude_extensions.is_empty()
+    }
+
+    /// Check that every pattern compiles, without scanning anything
+    pub fn validate(&self) -> Result<(), FilterError> {
+        self.compile(Path::new("")).map(|_| ())
+    }
+
+    /// Compile the patterns for a scan rooted at `root`, which anchors globs
+    /// that contain a slash
+    pub fn compile(&self, root: &Path) -> Result<CompiledFilter, FilterError> {
+        Ok(CompiledFilter {
+            include_globs: build_globs(root, &self.include_globs)?,
+            exclude_globs: build_globs(root, &self.exclude_globs)?,
+            include_regexes: build_regexes(&self.include_regexes)?,
+            exclude_regexes: build_regexes(&self.exclude_regexes)?,
+            include_extensions: self.include_extensions.iter().map(|e| normalize_extension(e)).collect(),
+            exclude_extensions: self.exclude_extensions.iter().map(|e| normalize_extension(e)).collect(),
+        })
+    }
+}
+
+/// Split a comma or newline separated list, as typed in the GUI or on the command line
+pub fn parse_pattern_list(list: &str) -> Vec<String> {
+    list.split([',', '\n'])
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn normalize_extension(extension: &str) -> String {
+    extension.trim().trim_start_matches('.').to_lowercase()
+}
+
+fn build_globs(root: &Path, patterns: &[String]) -> Result<Option<Gitignore>, FilterError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder.add_line(None, pattern).map_err(|e| FilterError {
+            pattern: pattern.clone(),
+            message: e.to_string(),
+        })?;
+    }
+    builder.build().map(Some).map_err(|e| FilterError {
+        pattern: patterns.join(", "),
+        message: e.to_string(),
+    })
+}
+
+fn build_regexes(patterns: &[String]) -> Result<Vec<Regex>, FilterError> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| FilterError {
+                pattern: pattern.clone(),
+                message: e.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A [`PathFilter`] ready to match paths under one scan root
+#[derive(Debug, Clone)]
+pub struct CompiledFilter {
+    include_globs: Option<Gitignore>,
+    exclude_globs: Option<Gitignore>,
+    include_regexes: Vec<Regex>,
+    exclude_regexes: Vec<Regex>,
+    include_extensions: Vec<String>,
+    exclude_extensions: Vec<String>,
+}
+
+impl CompiledFilter {
+    /// Whether an entry below the scan root passes the filter
+    pub fn is_included(&self, path: &Path, is_dir: bool) -> bool {
+        if self.is_excluded(path, is_dir) {
+            return false;
+        }
+        is_dir || self.matches_includes(path)
+    }
+
+    fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        if let Some(ref globs) = self.exclude_globs {
+            if globs.matched(path, is_dir).is_ignore() {
+                return true;
+            }
+        }
+        if !self.exclude_regexes.is_empty() {
+            let full_path = path.to_string_lossy();
+            if self.exclude_regexes.iter().any(|regex| regex.is_match(&full_path)) {
+                return true;
+            }
+        }
+        !is_dir && !self.exclude_extensions.is_empty() && has_extension(path, &self.exclude_extensions)
+    }
+
+    fn matches_includes(&self, path: &Path) -> bool {
+        if self.include_globs.is_none() && self.include_regexes.is_empty() && self.include_extensions.is_empty() {
+            return true;
+        }
+        if let Some(ref globs) = self.include_globs {
+            // A file inside an included directory is included too
+            if globs.matched_path_or_any_parents(path, false).is_ignore() {
+                return true;
+            }
+        }
+        if !self.include_regexes.is_empty() {
+            let full_path = path.to_string_lossy();
+            if self.include_regexes.iter().any(|regex| regex.is_match(&full_path)) {
+                return true;
+            }
+        }
+        has_extension(path, &self.include_extensions)
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .map(|extension| extensions.contains(&extension.to_string_lossy().to_lowercase()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_patterns() {
+        let root = Path::new("/data/project");
+        let filter = PathFilter::new()
+            .exclude_glob("node_modules/")
+            .exclude_glob("/build")
+            .exclude_regex(r"\.bak$")
+            .exclude_extension(".TMP")
+            .include_glob("*.rs")
+            .include_glob("docs/")
+            .include_extension("toml")
+            .compile(root)
+            .unwrap();
+
+        assert!(!filter.is_included(&root.join("web/node_modules"), true));
+        assert!(!filter.is_included(&root.join("build"), true));
+        assert!(filter.is_included(&root.join("src/build"), true));
+        assert!(filter.is_included(&root.join("src/main.rs"), false));
+        assert!(!filter.is_included(&root.join("src/main.rs.bak"), false));
+        assert!(!filter.is_included(&root.join("src/scratch.tmp"), false));
+        assert!(filter.is_included(&root.join("docs/guide/intro.md"), false));
+        assert!(filter.is_included(&root.join("Cargo.toml"), false));
+        assert!(!filter.is_included(&root.join("README.md"), false));
+
+        assert!(PathFilter::new().exclude_regex("(").validate().is_err());
+        assert_eq!(parse_pattern_list("*.log, target/\n,"), vec!["*.log", "target/"]);
+    }
+}
@@ -0,0 +1,663 @@
+//! Fuzzy hashes for finding similar rather than identical files
+//!
+//! [`Ssdeep`] implements context-triggered piecewise hashing as computed by
+//! ssdeep 2.13 and later, and [`Tlsh`] the default 128-bucket, 1-byte
+//! checksum variant of the Trend Micro Locality Sensitive Hash. Both consume
+//! input incrementally, so they are fed from the same read loop as the
+//! cryptographic hashes.
+//!
+//! The two measure similarity in opposite directions: [`ssdeep_compare`]
+//! returns a match score from 0 (unrelated) to 100 (identical), while
+//! [`tlsh_distance`] returns a distance that is 0 for identical files and
+//! grows as they diverge.
+
+/// Width of the ssdeep rolling hash window
+pub(crate) const ROLLING_WINDOW: usize = 7;
+const MIN_BLOCKSIZE: u64 = 3;
+const HASH_PRIME: u32 = 0x0100_0193;
+const HASH_INIT: u32 = 0x2802_1967;
+const NUM_BLOCKHASHES: usize = 31;
+/// Maximum length of the first ssdeep signature part
+const SPAMSUM_LENGTH: usize = 64;
+const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn block_size(index: usize) -> u64 {
+    MIN_BLOCKSIZE << index
+}
+
+fn sum_hash(c: u8, h: u32) -> u32 {
+    h.wrapping_mul(HASH_PRIME) ^ c as u32
+}
+
+/// Rolling hash over the last seven bytes, used to pick chunk boundaries
+#[derive(Clone, Default)]
+struct RollingHash {
+    window: [u8; ROLLING_WINDOW],
+    h1: u32,
+    h2: u32,
+    h3: u32,
+    n: usize,
+}
+
+impl RollingHash {
+    fn update(&mut self, c: u8) {
+        let value = c as u32;
+        self.h2 = self.h2.wrapping_sub(self.h1).wrapping_add((ROLLING_WINDOW as u32).wrapping_mul(value));
+        self.h1 = self.h1.wrapping_add(value).wrapping_sub(self.window[self.n] as u32);
+        self.window[self.n] = c;
+        self.n = (self.n + 1) % ROLLING_WINDOW;
+        self.h3 = (self.h3 << 5) ^ value;
+    }
+
+    fn sum(&self) -> u32 {
+        self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+    }
+}
+
+/// Signature being built for one candidate block size
+#[derive(Clone, Copy)]
+struct BlockHash {
+    h: u32,
+    half_h: u32,
+    digest: [u8; SPAMSUM_LENGTH],
+    half_digest: u8,
+    len: usize,
+}
+
+impl BlockHash {
+    const EMPTY: BlockHash = BlockHash {
+        h: HASH_INIT,
+        half_h: HASH_INIT,
+        digest: [0; SPAMSUM_LENGTH],
+        half_digest: 0,
+        len: 0,
+    };
+}
+
+/// Streaming ssdeep (spamsum) hasher
+///
+/// Signatures for every plausible block size are built at once, and block
+/// sizes that can no longer be selected are dropped as input arrives, so the
+/// input only has to be read once.
+#[derive(Clone)]
+pub struct Ssdeep {
+    blocks: [BlockHash; NUM_BLOCKHASHES],
+    start: usize,
+    end: usize,
+    total_size: u64,
+    roll: RollingHash,
+}
+
+impl Default for Ssdeep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ssdeep {
+    pub fn new() -> Self {
+        Self {
+            blocks: [BlockHash::EMPTY; NUM_BLOCKHASHES],
+            start: 0,
+            end: 1,
+            total_size: 0,
+            roll: RollingHash::default(),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.total_size = self.total_size.saturating_add(data.len() as u64);
+        for &c in data {
+            self.step(c);
+        }
+    }
+
+    fn step(&mut self, c: u8) {
+        self.roll.update(c);
+        let h = self.roll.sum() as u64;
+
+        for block in &mut self.blocks[self.start..self.end] {
+            block.h = sum_hash(c, block.h);
+            block.half_h = sum_hash(c, block.half_h);
+        }
+
+        // A trigger point for a block size is also one for every smaller size
+        let mut i = self.start;
+        while i < self.end {
+            let size = block_size(i);
+            if h % size != size - 1 {
+                break;
+            }
+            if self.blocks[i].len == 0 {
+                self.try_fork();
+            }
+
+            let block = &mut self.blocks[i];
+            block.digest[block.len] = B64[(block.h % 64) as usize];
+            block.half_digest = B64[(block.half_h % 64) as usize];
+            if block.len < SPAMSUM_LENGTH - 1 {
+                block.len += 1;
+                block.digest[block.len] = 0;
+                block.h = HASH_INIT;
+                if block.len < SPAMSUM_LENGTH / 2 {
+                    block.half_h = HASH_INIT;
+                    block.half_digest = 0;
+                }
+            } else {
+                self.try_reduce();
+            }
+            i += 1;
+        }
+    }
+
+    /// Start a signature for the next larger block size
+    fn try_fork(&mut self) {
+        if self.end >= NUM_BLOCKHASHES {
+            return;
+        }
+        let previous = self.blocks[self.end - 1];
+        self.blocks[self.end] = BlockHash {
+            h: previous.h,
+            half_h: previous.half_h,
+            ..BlockHash::EMPTY
+        };
+        self.end += 1;
+    }
+
+    /// Drop the smallest block size once it can no longer be chosen
+    fn try_reduce(&mut self) {
+        if self.end - self.start < 2 {
+            return;
+        }
+        if block_size(self.start) * SPAMSUM_LENGTH as u64 >= self.total_size {
+            return;
+        }
+        if self.blocks[self.start + 1].len < SPAMSUM_LENGTH / 2 {
+            return;
+        }
+        self.start += 1;
+    }
+
+    /// The signature in ssdeep's `blocksize:hash:hash` form
+    pub fn digest(&self) -> String {
+        let h = self.roll.sum();
+
+        // Smallest block size that gives at most SPAMSUM_LENGTH chunks, adjusted
+        // down while the signature would be too short to be useful
+        let mut bi = self.start;
+        while block_size(bi) * (SPAMSUM_LENGTH as u64) < self.total_size && bi < NUM_BLOCKHASHES - 1 {
+            bi += 1;
+        }
+        while bi >= self.end {
+            bi -= 1;
+        }
+        while bi > self.start && self.blocks[bi].len < SPAMSUM_LENGTH / 2 {
+            bi -= 1;
+        }
+
+        let mut result = format!("{}:", block_size(bi)).into_bytes();
+        let block = &self.blocks[bi];
+        result.extend_from_slice(&block.digest[..block.len]);
+        if h != 0 {
+            result.push(B64[(block.h % 64) as usize]);
+        } else if block.digest[block.len] != 0 {
+            result.push(block.digest[block.len]);
+        }
+        result.push(b':');
+
+        if bi < self.end - 1 {
+            let block = &self.blocks[bi + 1];
+            let len = block.len.min(SPAMSUM_LENGTH / 2 - 1);
+            result.extend_from_slice(&block.digest[..len]);
+            if h != 0 {
+                result.push(B64[(block.half_h % 64) as usize]);
+            } else if block.half_digest != 0 {
+                result.push(block.half_digest);
+            }
+        } else if h != 0 {
+            let block = &self.blocks[bi];
+            let tail = if bi == 0 { block.h } else { block.half_h };
+            result.push(B64[(tail % 64) as usize]);
+        }
+
+        // Only base64 characters, digits and ':' are ever written
+        String::from_utf8(result).unwrap_or_default()
+    }
+}
+
+/// Split an ssdeep signature into its block size and two parts
+///
+/// Block sizes other than those ssdeep produces are rejected, so callers can
+/// double any block size that parsed without overflowing.
+pub(crate) fn parse_ssdeep(signature: &str) -> Option<(u64, &[u8], &[u8])> {
+    let mut parts = signature.splitn(3, ':');
+    let size = parts.next()?.parse().ok()?;
+    if !(0..NUM_BLOCKHASHES).any(|index| block_size(index) == size) {
+        return None;
+    }
+    let first = parts.next()?;
+    // ssdeep's file output appends ,"filename" to the signature
+    let second = parts.next()?.split(',').next().unwrap_or("");
+    Some((size, first.as_bytes(), second.as_bytes()))
+}
+
+/// Remove runs of more than three identical characters, which carry little information
+pub(crate) fn eliminate_sequences(part: &[u8]) -> Vec<u8> {
+    part.iter()
+        .enumerate()
+        .filter(|&(i, &c)| i < 3 || !(c == part[i - 1] && c == part[i - 2] && c == part[i - 3]))
+        .map(|(_, &c)| c)
+        .collect()
+}
+
+fn has_common_substring(a: &[u8], b: &[u8]) -> bool {
+    a.windows(ROLLING_WINDOW).any(|window| b.windows(ROLLING_WINDOW).any(|other| window == other))
+}
+
+/// Edit distance with insertions and deletions costing 1 and substitutions 2
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, &ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 2 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn score_strings(a: &[u8], b: &[u8], size: u64) -> u32 {
+    if a.len() > SPAMSUM_LENGTH || b.len() > SPAMSUM_LENGTH || !has_common_substring(a, b) {
+        return 0;
+    }
+
+    let distance = edit_distance(a, b) * SPAMSUM_LENGTH / (a.len() + b.len());
+    let score = 100u32.saturating_sub((100 * distance / SPAMSUM_LENGTH) as u32);
+
+    // Short signatures at small block sizes cannot justify a high score
+    if size >= (99 + ROLLING_WINDOW as u64) / ROLLING_WINDOW as u64 * MIN_BLOCKSIZE {
+        return score;
+    }
+    let cap = (size / MIN_BLOCKSIZE) as u32 * a.len().min(b.len()) as u32;
+    score.min(cap)
+}
+
+/// Match score between two ssdeep signatures, from 0 (no match) to 100
+///
+/// Returns `None` if either signature is malformed.
+pub fn ssdeep_compare(first: &str, second: &str) -> Option<u32> {
+    let (size1, a1, a2) = parse_ssdeep(first)?;
+    let (size2, b1, b2) = parse_ssdeep(second)?;
+
+    // Signatures can only be compared at the same or adjacent block sizes
+    if size1 != size2 && size1 != size2 * 2 && size2 != size1 * 2 {
+        return Some(0);
+    }
+
+    let (a1, a2) = (eliminate_sequences(a1), eliminate_sequences(a2));
+    let (b1, b2) = (eliminate_sequences(b1), eliminate_sequences(b2));
+
+    if size1 == size2 && a1 == b1 && a2 == b2 {
+        return Some(100);
+    }
+
+    Some(if size1 == size2 {
+        score_strings(&a1, &b1, size1).max(score_strings(&a2, &b2, size1 * 2))
+    } else if size1 == size2 * 2 {
+        score_strings(&a1, &b2, size1)
+    } else {
+        score_strings(&a2, &b1, size2)
+    })
+}
+
+/// The TLSH Pearson permutation table
+const PEARSON: [u8; 256] = [
+    1, 87, 49, 12, 176, 178, 102, 166, 121, 193, 6, 84, 249, 230, 44, 163,
+    14, 197, 213, 181, 161, 85, 218, 80, 64, 239, 24, 226, 236, 142, 38, 200,
+    110, 177, 104, 103, 141, 253, 255, 50, 77, 101, 81, 18, 45, 96, 31, 222,
+    25, 107, 190, 70, 86, 237, 240, 34, 72, 242, 20, 214, 244, 227, 149, 235,
+    97, 234, 57, 22, 60, 250, 82, 175, 208, 5, 127, 199, 111, 62, 135, 248,
+    174, 169, 211, 58, 66, 154, 106, 195, 245, 171, 17, 187, 182, 179, 0, 243,
+    132, 56, 148, 75, 128, 133, 158, 100, 130, 126, 91, 13, 153, 246, 216, 219,
+    119, 68, 223, 78, 83, 88, 201, 99, 122, 11, 92, 32, 136, 114, 52, 10,
+    138, 30, 48, 183, 156, 35, 61, 26, 143, 74, 251, 94, 129, 162, 63, 152,
+    170, 7, 115, 167, 241, 206, 3, 150, 55, 59, 151, 220, 90, 53, 23, 131,
+    125, 173, 15, 238, 79, 95, 89, 16, 105, 137, 225, 224, 217, 160, 37, 123,
+    118, 73, 2, 157, 46, 116, 9, 145, 134, 228, 207, 212, 202, 215, 69, 229,
+    27, 188, 67, 124, 168, 252, 42, 4, 29, 108, 21, 247, 19, 205, 39, 203,
+    233, 40, 186, 147, 198, 192, 155, 33, 164, 191, 98, 204, 165, 180, 117, 76,
+    140, 36, 210, 172, 41, 54, 159, 8, 185, 232, 113, 196, 231, 47, 146, 120,
+    51, 65, 28, 144, 254, 221, 93, 189, 194, 139, 112, 43, 71, 109, 184, 209,
+];
+
+/// Number of buckets that make up the TLSH body
+const TLSH_BUCKETS: usize = 128;
+/// Bytes in the TLSH body (two bits per bucket)
+const TLSH_CODE_SIZE: usize = TLSH_BUCKETS / 4;
+/// Inputs shorter than this do not produce a TLSH
+const TLSH_MIN_LENGTH: u64 = 50;
+const TLSH_WINDOW: usize = 5;
+
+fn pearson(salt: u8, i: u8, j: u8, k: u8) -> u8 {
+    let mut h = PEARSON[salt as usize];
+    h = PEARSON[(h ^ i) as usize];
+    h = PEARSON[(h ^ j) as usize];
+    PEARSON[(h ^ k) as usize]
+}
+
+fn swap_nibbles(byte: u8) -> u8 {
+    byte.rotate_left(4)
+}
+
+/// Streaming TLSH hasher
+#[derive(Clone)]
+pub struct Tlsh {
+    buckets: [u32; 256],
+    window: [u8; TLSH_WINDOW],
+    len: u64,
+    checksum: u8,
+}
+
+impl Default for Tlsh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tlsh {
+    pub fn new() -> Self {
+        Self {
+            buckets: [0; 256],
+            window: [0; TLSH_WINDOW],
+            len: 0,
+            checksum: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            let j = (self.len % TLSH_WINDOW as u64) as usize;
+            self.window[j] = byte;
+
+            if self.len >= TLSH_WINDOW as u64 - 1 {
+                let at = |back: usize| self.window[(j + TLSH_WINDOW - back) % TLSH_WINDOW];
+                let (b0, b1, b2, b3, b4) = (at(0), at(1), at(2), at(3), at(4));
+
+                self.checksum = pearson(0, b0, b1, self.checksum);
+                for (salt, x, y) in [(2, b1, b2), (3, b1, b3), (5, b2, b3), (7, b2, b4), (11, b1, b4), (13, b3, b4)] {
+                    self.buckets[pearson(salt, b0, x, y) as usize] += 1;
+                }
+            }
+            self.len += 1;
+        }
+    }
+
+    /// The hash as `T1` followed by 70 hex digits, or `None` if the input was
+    /// too short or too uniform to produce a meaningful hash
+    pub fn digest(&self) -> Option<String> {
+        if self.len < TLSH_MIN_LENGTH {
+            return None;
+        }
+
+        let buckets = &self.buckets[..TLSH_BUCKETS];
+        let mut sorted = buckets.to_vec();
+        sorted.sort_unstable();
+        let (q1, q2, q3) = (
+            sorted[TLSH_BUCKETS / 4 - 1],
+            sorted[TLSH_BUCKETS / 2 - 1],
+            sorted[TLSH_BUCKETS - TLSH_BUCKETS / 4 - 1],
+        );
+
+        let nonzero = buckets.iter().filter(|&&count| count > 0).count();
+        if q3 == 0 || nonzero <= TLSH_BUCKETS / 2 {
+            return None;
+        }
+
+        let mut code = [0u8; TLSH_CODE_SIZE];
+        for (i, byte) in code.iter_mut().enumerate() {
+            for j in 0..4 {
+                let count = buckets[4 * i + j];
+                let level = if count > q3 {
+                    3
+                } else if count > q2 {
+                    2
+                } else if count > q1 {
+                    1
+                } else {
+                    0
+                };
+                *byte |= level << (j * 2);
+            }
+        }
+
+        let q1_ratio = ((q1 as f32 * 100.0 / q3 as f32) as u32 % 16) as u8;
+        let q2_ratio = ((q2 as f32 * 100.0 / q3 as f32) as u32 % 16) as u8;
+        let quartiles = (q2_ratio << 4) | q1_ratio;
+
+        let mut hex = String::with_capacity(2 + 2 * (3 + TLSH_CODE_SIZE));
+        hex.push_str("T1");
+        for byte in [swap_nibbles(self.checksum), swap_nibbles(tlsh_length_code(self.len)), swap_nibbles(quartiles)]
+            .into_iter()
+            .chain(code.iter().rev().copied())
+        {
+            hex.push_str(&format!("{:02X}", byte));
+        }
+        Some(hex)
+    }
+}
+
+/// Logarithmic encoding of the input length used in the TLSH header
+fn tlsh_length_code(len: u64) -> u8 {
+    let len = len as f64;
+    let code = if len <= 656.0 {
+        (len.ln() / 1.5f64.ln()).floor()
+    } else if len <= 3199.0 {
+        (len.ln() / 1.3f64.ln() - 8.72777).floor()
+    } else {
+        (len.ln() / 1.1f64.ln() - 62.5472).floor()
+    };
+    (code as i64 & 0xFF) as u8
+}
+
+/// Decoded TLSH header and body
+struct TlshParts {
+    checksum: u8,
+    length: u8,
+    q1_ratio: u8,
+    q2_ratio: u8,
+    code: [u8; TLSH_CODE_SIZE],
+}
+
+fn parse_tlsh(hash: &str) -> Option<TlshParts> {
+    let hex = hash.strip_prefix("T1").unwrap_or(hash);
+    if hex.len() != 2 * (3 + TLSH_CODE_SIZE) || !hex.is_ascii() {
+        return None;
+    }
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    let quartiles = swap_nibbles(bytes[2]);
+    let mut code = [0u8; TLSH_CODE_SIZE];
+    for (i, byte) in bytes[3..].iter().rev().enumerate() {
+        code[i] = *byte;
+    }
+    Some(TlshParts {
+        checksum: swap_nibbles(bytes[0]),
+        length: swap_nibbles(bytes[1]),
+        q1_ratio: quartiles & 0x0F,
+        q2_ratio: quartiles >> 4,
+        code,
+    })
+}
+
+/// Distance around a circular range of values
+fn mod_diff(x: u8, y: u8, range: u32) -> u32 {
+    let (x, y) = (x as u32, y as u32);
+    let direct = x.abs_diff(y);
+    direct.min(range - direct)
+}
+
+/// Distance between two TLSH hashes; 0 means identical, and values below
+/// roughly 100 indicate related content
+///
+/// Returns `None` if either hash is malformed.
+pub fn tlsh_distance(first: &str, second: &str) -> Option<u32> {
+    let a = parse_tlsh(first)?;
+    let b = parse_tlsh(second)?;
+    let mut distance = 0;
+
+    distance += match mod_diff(a.length, b.length, 256) {
+        0 => 0,
+        1 => 1,
+        d => d * 12,
+    };
+    for (x, y) in [(a.q1_ratio, b.q1_ratio), (a.q2_ratio, b.q2_ratio)] {
+        distance += match mod_diff(x, y, 16) {
+            d if d <= 1 => d,
+            d => (d - 1) * 12,
+        };
+    }
+    if a.checksum != b.checksum {
+        distance += 1;
+    }
+
+    for (x, y) in a.code.iter().zip(b.code.iter()) {
+        for shift in (0..8).step_by(2) {
+            let d = ((x >> shift) & 3).abs_diff((y >> shift) & 3) as u32;
+            distance += if d == 3 { 6 } else { d };
+        }
+    }
+    Some(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random text with enough variety for both hashes
+    fn sample_text(seed: u32, len: usize) -> Vec<u8> {
+        let words = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet"];
+        let mut state = seed;
+        let mut text = Vec::new();
+        while text.len() < len {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            text.extend_from_slice(words[(state >> 16) as usize % words.len()].as_bytes());
+            text.push(if (state >> 8).is_multiple_of(7) { b'\n' } else { b' ' });
+        }
+        text.truncate(len);
+        text
+    }
+
+    #[test]
+    fn test_fuzzy_hashes_rank_similar_content() {
+        let original = sample_text(1, 20_000);
+        let mut edited = original.clone();
+        edited.splice(10_000..10_050, b"a short edit in the middle of the document".iter().copied());
+        let unrelated = sample_text(2, 20_000);
+
+        let ssdeep = |data: &[u8]| {
+            let mut hasher = Ssdeep::new();
+            // Feed in uneven chunks to check the result does not depend on them
+            for chunk in data.chunks(777) {
+                hasher.update(chunk);
+            }
+            hasher.digest()
+        };
+        let (a, b, c) = (ssdeep(&original), ssdeep(&edited), ssdeep(&unrelated));
+        assert!(a.starts_with("384:") || a.starts_with("192:") || a.starts_with("768:"), "{}", a);
+        assert_eq!(ssdeep_compare(&a, &a), Some(100));
+        assert!(ssdeep_compare(&a, &b).unwrap() > 50, "{} vs {}", a, b);
+        assert!(ssdeep_compare(&a, &c).unwrap() < ssdeep_compare(&a, &b).unwrap());
+
+        let tlsh = |data: &[u8]| {
+            let mut hasher = Tlsh::new();
+            hasher.update(data);
+            hasher.digest().unwrap()
+        };
+        let (a, b, c) = (tlsh(&original), tlsh(&edited), tlsh(&unrelated));
+        assert_eq!(a.len(), 72);
+        assert_eq!(tlsh_distance(&a, &a), Some(0));
+        assert!(tlsh_distance(&a, &b).unwrap() < tlsh_distance(&a, &c).unwrap());
+
+        let mut short = Tlsh::new();
+        short.update(b"too short");
+        assert_eq!(short.digest(), None);
+    }
+
+    fn ssdeep_of(data: &[u8]) -> String {
+        let mut hasher = Ssdeep::new();
+        hasher.update(data);
+        hasher.digest()
+    }
+
+    fn tlsh_of(data: &[u8]) -> String {
+        let mut hasher = Tlsh::new();
+        hasher.update(data);
+        hasher.digest().unwrap()
+    }
+
+    /// A 20,000 byte document, the same with a short edit, and an unrelated one
+    fn edited_documents() -> [Vec<u8>; 3] {
+        let original = sample_text(1, 20_000);
+        let mut edited = original.clone();
+        edited.splice(10_000..10_050, b"a short edit in the middle of the document".iter().copied());
+        [original, edited, sample_text(2, 20_000)]
+    }
+
+    #[test]
+    fn test_ssdeep_known_digests() {
+        // Examples published with python-ssdeep, ssdeep-rs and ppdeep, all
+        // computed by libfuzzy
+        let ctph = "3:AXGBicFlgVNhBGcL6wCrFQEv:AXGHsNhxLsr2C";
+        let upper = "3:AXGBicFlIHBGcL6wCrFQEv:AXGH6xLsr2C";
+        assert_eq!(ssdeep_of(b"Also called fuzzy hashes, Ctph can match inputs that have homologies."), ctph);
+        assert_eq!(ssdeep_of(b"Also called fuzzy hashes, CTPH can match inputs that have homologies."), upper);
+        assert_eq!(ssdeep_compare(ctph, upper), Some(22));
+        assert_eq!(ssdeep_of(b"Hello there!"), "3:aNRn:aNRn");
+        assert_eq!(
+            ssdeep_of("The equivalence of mass and energy translates into the well-known E = mc\u{b2}".as_bytes()),
+            "3:RC0qYX4LBFA0dxEq4z2LRK+oCKI9VnXn:RvqpLB60dx8ilK+owX"
+        );
+        assert_eq!(ssdeep_of(b""), "3::");
+
+        // Larger inputs select a larger block size; these digests were
+        // checked against a separate port of libfuzzy rather than ssdeep itself
+        let [original, edited, unrelated] = edited_documents();
+        assert_eq!(ssdeep_of(&original), "384:TH7dcoUA0/ULJIg49mQnFvGyaGPubxCzfjf42lWS1EuFN8J+uiFasiWTmS:U");
+        assert_eq!(ssdeep_of(&edited), "384:TH7dcoUA0/ULJIg49mQnFvGyaGPRbxCzfjf42lWS1EuFN8J+uiFasiWTmS:t");
+        assert_eq!(ssdeep_of(&unrelated), "384:iJ2sVpBFnIm3JfWIHXQOp3nFVMz75sNYrvwNJ4WIE2JSR7hEOk36hhPH4:r");
+    }
+
+    #[test]
+    fn test_tlsh_known_digests() {
+        // Checked against a separate port of the reference TLSH 4 code, not
+        // output of the tlsh tool
+        let ctph = tlsh_of(b"Also called fuzzy hashes, Ctph can match inputs that have homologies.");
+        let upper = tlsh_of(b"Also called fuzzy hashes, CTPH can match inputs that have homologies.");
+        assert_eq!(ctph, "T1A2A022A3CC0FB00C8C0222228B82082A8E02E0F2C28002A8CC0CAC0E022023E00C30F0");
+        assert_eq!(upper, "T1FFA022E38E0BA80A8C0032238382002A8E3AC0BAC28022A8CA0C2E0F020023F00C38F0");
+        assert_eq!(tlsh_distance(&ctph, &upper), Some(48));
+
+        let [original, edited, unrelated] = edited_documents().map(|document| tlsh_of(&document));
+        assert_eq!(original, "T12392C5182B4656F25DAC460B2B43A9A1634DC57FEF1F9BEF459B72308CCA0F91508F1A");
+        assert_eq!(edited, "T13092B5182B4656F25DAC460B2B43A9A1634DC57FEF1F9BEF459B72308CCA0F91508F1A");
+        assert_eq!(unrelated, "T18592E3182B4656B25DAC860B3B43A9A1634DC57FAF1F9BEF459B72308CCA0F5150CF1A");
+        assert_eq!(tlsh_distance(&original, &edited), Some(2));
+        assert_eq!(tlsh_distance(&original, &unrelated), Some(31));
+    }
+
+    #[test]
+    fn test_ssdeep_rejects_impossible_block_sizes() {
+        // Doubling this block size would overflow
+        assert_eq!(ssdeep_compare("18446744073709551615:a:b", "3:a:b"), None);
+        assert_eq!(ssdeep_compare("3:a:b", "18446744073709551615:a:b"), None);
+        assert_eq!(ssdeep_compare("4:abcdefgh:abc", "4:abcdefgh:abc"), None);
+        assert_eq!(ssdeep_compare("3221225472:abcdefgh:abc", "3221225472:abcdefgh:abc"), Some(100));
+    }
+}
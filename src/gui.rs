@@ -1,21 +1,32 @@
 use iced::{
-    widget::{button, checkbox, column, container, pick_list, progress_bar, row, scrollable, text, text_input, rule, Column, Space, Id},
-    widget::text::Wrapping,
-    Alignment, Element, Length, Theme, Task, Font, time,
+    widget::{button, canvas, checkbox, column, container, pick_list, progress_bar, rich_text, row, scrollable, span, text, text_input, rule, Column, Space, Id},
+    widget::text::{Rich, Span, Wrapping},
+    Alignment, Element, Length, Theme, Task, Font, time, mouse, Point, Rectangle, Renderer, Size,
 };
 use iced::widget::scrollable::AbsoluteOffset;
 use iced::widget::operation::scroll_to;
 use iced::window;
 use rfd::FileDialog;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::{Instant, Duration};
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use directory_scanner::{
-    analyze_directory_with_options, format_tree_output,
-    AnalysisOptions, DirectoryScanner, FileInfo, ProgressCallback, TreeNode,
+    scan_and_analyze_each,
+    write_tree_json, write_analysis_json,
+    read_files_json, compare_scans, write_manifest, read_manifest, verify_manifest,
+    AnalysisRecord, Chart, ChartKind, ChartValue, RankedEntry, Rankings, ScanCache, DEFAULT_TOP_COUNT, ScanComparison, ChangeKind, VolumeInfo,
+    DuplicateFinder, DuplicateReport, SimilarityFinder, SimilarityReport, ManifestFormat, VerificationReport, VerifyStatus,
+    DirectoryScanner, DfxmlWriter, Digest, FileInfo, FileListFormat, FileListWriter, FileTable, FileType, HashAlgorithm, PathFilter, MetadataFilter, DateRange, ProgressCallback, ScanReport, TreeNode,
+    DirectoryAnalysis, DisplayZone, Rect, SearchMode, SearchQuery, TimeFormat, TimestampStyle, TreeFormatOptions, TreeFormatter, UnixMetadata,
+    parse_pattern_list, parse_size, bar_layout, pie_layout, slice_at, find_node, get_file_color, segment_at, sunburst, tile_at, treemap,
 };
+use chrono::{DateTime, Utc};
+
+/// Shown in place of values that were not requested for a scan
+const NOT_CALCULATED: &str = "Not calculated";
 
 /// Version string read from Cargo.toml at compile time
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -40,15 +51,25 @@ fn theme(_state: &SplendirGui) -> Theme {
     Theme::Dark
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum ScanMode {
+    #[default]
     Detailed,
     Tree,
     Analysis,
+    Problems,
+    Compare,
+    Duplicates,
+    Similar,
+    Verify,
 }
 
 impl ScanMode {
-    const ALL: [ScanMode; 3] = [ScanMode::Detailed, ScanMode::Tree, ScanMode::Analysis];
+    const ALL: [ScanMode; 8] = [
+        ScanMode::Detailed, ScanMode::Tree, ScanMode::Analysis,
+        ScanMode::Problems, ScanMode::Compare, ScanMode::Duplicates,
+        ScanMode::Similar, ScanMode::Verify,
+    ];
 }
 
 impl std::fmt::Display for ScanMode {
@@ -57,12 +78,178 @@ impl std::fmt::Display for ScanMode {
             ScanMode::Detailed => write!(f, "Detailed File List"),
             ScanMode::Tree => write!(f, "Tree View"),
             ScanMode::Analysis => write!(f, "Directory Analysis"),
+            ScanMode::Problems => write!(f, "Problems"),
+            ScanMode::Compare => write!(f, "Compare with Baseline"),
+            ScanMode::Duplicates => write!(f, "Duplicate Files"),
+            ScanMode::Similar => write!(f, "Similar Files"),
+            ScanMode::Verify => write!(f, "Verify Manifest"),
+        }
+    }
+}
+
+/// How the Directory Analysis mode shows the scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum AnalysisView {
+    #[default]
+    Summary,
+    Charts,
+    Largest,
+    Treemap,
+    Sunburst,
+}
+
+impl AnalysisView {
+    const ALL: [AnalysisView; 5] = [
+        AnalysisView::Summary,
+        AnalysisView::Charts,
+        AnalysisView::Largest,
+        AnalysisView::Treemap,
+        AnalysisView::Sunburst,
+    ];
+}
+
+impl std::fmt::Display for AnalysisView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisView::Summary => write!(f, "Summary"),
+            AnalysisView::Charts => write!(f, "Charts"),
+            AnalysisView::Largest => write!(f, "Largest"),
+            AnalysisView::Treemap => write!(f, "Treemap"),
+            AnalysisView::Sunburst => write!(f, "Sunburst"),
+        }
+    }
+}
+
+/// The charts of the Charts view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChartSubject {
+    Sizes,
+    Types,
+}
+
+impl ChartSubject {
+    /// Default name offered when the chart is exported
+    fn file_stem(&self) -> &'static str {
+        match self {
+            ChartSubject::Sizes => "file-sizes",
+            ChartSubject::Types => "file-types",
+        }
+    }
+}
+
+/// Include/exclude pattern lists entered in the Filters section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterField {
+    IncludeGlobs,
+    ExcludeGlobs,
+    IncludeRegexes,
+    ExcludeRegexes,
+    IncludeExtensions,
+    ExcludeExtensions,
+}
+
+impl FilterField {
+    const ALL: [FilterField; 6] = [
+        FilterField::IncludeGlobs,
+        FilterField::ExcludeGlobs,
+        FilterField::IncludeRegexes,
+        FilterField::ExcludeRegexes,
+        FilterField::IncludeExtensions,
+        FilterField::ExcludeExtensions,
+    ];
+    
+    fn label(&self) -> &'static str {
+        match self {
+            FilterField::IncludeGlobs => "Include:",
+            FilterField::ExcludeGlobs => "Exclude:",
+            FilterField::IncludeRegexes => "Include regex:",
+            FilterField::ExcludeRegexes => "Exclude regex:",
+            FilterField::IncludeExtensions => "Extensions:",
+            FilterField::ExcludeExtensions => "Skip ext.:",
+        }
+    }
+    
+    fn placeholder(&self) -> &'static str {
+        match self {
+            FilterField::IncludeGlobs => "*.rs, docs/",
+            FilterField::ExcludeGlobs => "node_modules/, *.tmp",
+            FilterField::IncludeRegexes => r"/src/.*\.rs$",
+            FilterField::ExcludeRegexes => r"/\.cache/",
+            FilterField::IncludeExtensions => "jpg, png",
+            FilterField::ExcludeExtensions => "tmp, bak",
+        }
+    }
+}
+
+/// Unix ownership, permission and inode columns of the detailed list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum UnixColumn {
+    Permissions,
+    Owner,
+    Group,
+    Inode,
+    Links,
+    Changed,
+}
+
+impl UnixColumn {
+    const ALL: [UnixColumn; 6] = [
+        UnixColumn::Permissions,
+        UnixColumn::Owner,
+        UnixColumn::Group,
+        UnixColumn::Inode,
+        UnixColumn::Links,
+        UnixColumn::Changed,
+    ];
+    
+    /// Columns offered on this platform; other systems have no Unix metadata
+    fn available() -> &'static [UnixColumn] {
+        if cfg!(unix) { &Self::ALL } else { &[] }
+    }
+    
+    fn label(&self) -> &'static str {
+        match self {
+            UnixColumn::Permissions => "Permissions",
+            UnixColumn::Owner => "Owner",
+            UnixColumn::Group => "Group",
+            UnixColumn::Inode => "Inode",
+            UnixColumn::Links => "Links",
+            UnixColumn::Changed => "Changed",
+        }
+    }
+    
+    fn value(&self, unix: Option<&UnixMetadata>, time_format: &TimeFormat) -> String {
+        let Some(unix) = unix else {
+            return "N/A".to_string();
+        };
+        match self {
+            UnixColumn::Permissions => unix.permissions(),
+            UnixColumn::Owner => unix.owner(),
+            UnixColumn::Group => unix.group(),
+            UnixColumn::Inode => unix.inode.to_string(),
+            UnixColumn::Links => unix.links.to_string(),
+            UnixColumn::Changed => time_format.format(unix.changed),
         }
     }
 }
 
+/// Size, date and MIME inputs entered in the Metadata Filters section
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MetadataField {
+    MinSize,
+    MaxSize,
+    CreatedFrom,
+    CreatedUntil,
+    ModifiedFrom,
+    ModifiedUntil,
+    AccessedFrom,
+    AccessedUntil,
+    MimeCategories,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum ScanPreset {
+    #[default]
     Default,
     DefaultMD5,
     DefaultSHA256,
@@ -153,6 +340,15 @@ impl std::fmt::Display for SortOrder {
 // Shared progress state for communication between threads
 type ProgressState = Arc<Mutex<Option<(f32, String)>>>;
 
+// Files processed by a running scan and not yet shown
+type LiveFiles = Arc<Mutex<Vec<FileInfo>>>;
+
+// Names and digests beyond this many bytes are moved to a temporary file
+const SPILL_THRESHOLD: usize = 256 * 1024 * 1024;
+
+// Pause in typing before the results are searched
+const SEARCH_DELAY: Duration = Duration::from_millis(200);
+
 #[derive(Debug, Clone)]
 struct ColumnVisibility {
     show_filename: bool,
@@ -163,10 +359,9 @@ struct ColumnVisibility {
     show_modified: bool,
     show_accessed: bool,
     show_format: bool,
-    calculate_md5: bool,
-    calculate_sha256: bool,
-    calculate_sha512: bool,
+    hash_algorithms: BTreeSet<HashAlgorithm>,
     calculate_mime: bool,
+    unix_columns: BTreeSet<UnixColumn>,
 }
 
 struct SplendirGui {
@@ -178,11 +373,22 @@ struct SplendirGui {
     follow_symlinks: bool,
     skip_virtual_filesystems: bool,
     stay_on_filesystem: bool,
-    calculate_md5: bool,
-    calculate_sha256: bool,
-    calculate_sha512: bool,
+    respect_ignore_files: bool,
+    use_scan_cache: bool,
+    hash_algorithms: BTreeSet<HashAlgorithm>,
     colorize_output: bool,
     max_depth: String,
+    /// Comma-separated pattern lists, indexed by FilterField
+    filter_inputs: [String; 6],
+    /// Metadata filter values, indexed by MetadataField
+    metadata_inputs: [String; 9],
+    /// File types kept by the metadata filter; empty keeps every type
+    file_type_filter: Vec<FileType>,
+    /// Time zone as typed; `time_format` keeps the last zone that parsed
+    time_zone_input: String,
+    time_format: TimeFormat,
+    show_tree_dates: bool,
+    show_tree_sizes: bool,
     
     // Column visibility
     show_filename: bool,
@@ -195,6 +401,8 @@ struct SplendirGui {
     show_format: bool,
     calculate_format: bool,
     calculate_mime: bool,
+    /// Also shown in the tree, like `tree -pug`, for permissions, owner and group
+    unix_columns: BTreeSet<UnixColumn>,
     
     // Sort options
     sort_by: SortBy,
@@ -216,6 +424,7 @@ struct SplendirGui {
     
     // Progress tracking
     progress_state: Option<ProgressState>,
+    live_files: Option<LiveFiles>,
     
     // Cancellation flag
     cancellation_flag: Option<Arc<AtomicBool>>,
@@ -223,7 +432,46 @@ struct SplendirGui {
     // Virtual scrolling state
     tree_scroll_offset: f32,
     tree_flattened_cache: Vec<FlatTreeNode>,
+    /// Directories open in the tree view when there is no search; the root
+    /// is always open
+    tree_expanded: HashSet<PathBuf>,
+    /// Depth typed for "Expand to depth"; empty expands everything
+    tree_expand_depth: String,
+    
+    // Search over the detail and tree views
+    search_text: String,
+    search_mode: SearchMode,
+    /// Numbers each search asked for, so stale results can be dropped
+    search_requests: SearchRequests,
+    search_error: Option<String>,
+    
+    // Disk usage charts in the Analysis mode
+    analysis_view: AnalysisView,
+    /// Directory the charts are drilled down to; `None` for the scanned directory
+    usage_root: Option<PathBuf>,
+    /// How each chart of the Charts view is drawn, and what both measure
+    size_chart_kind: ChartKind,
+    type_chart_kind: ChartKind,
+    chart_value: ChartValue,
+    /// Entries in each list of largest files and directories
+    top_count: usize,
     detail_scroll_offset: f32,
+    problems_scroll_offset: f32,
+    compare_scroll_offset: f32,
+    duplicates_scroll_offset: f32,
+    /// Flattened duplicate rows: set index, and file index within the set (None for the set heading)
+    duplicate_rows: Vec<(usize, Option<usize>)>,
+    duplicate_partial_hash: bool,
+    similar_scroll_offset: f32,
+    /// Flattened similarity rows: cluster index, and file index within the cluster (None for the heading)
+    similar_rows: Vec<(usize, Option<usize>)>,
+    similar_algorithm: HashAlgorithm,
+    similar_threshold: String,
+    verify_scroll_offset: f32,
+    
+    // Earlier scan loaded for comparison
+    baseline_path: Option<PathBuf>,
+    baseline_files: Vec<FileInfo>,
     
     // Dialog state
     show_about: bool,
@@ -232,18 +480,6 @@ struct SplendirGui {
     columns_expanded: bool,
 }
 
-impl Default for ScanMode {
-    fn default() -> Self {
-        ScanMode::Detailed
-    }
-}
-
-impl Default for ScanPreset {
-    fn default() -> Self {
-        ScanPreset::Default
-    }
-}
-
 impl Default for SplendirGui {
     fn default() -> Self {
         Self {
@@ -254,11 +490,18 @@ impl Default for SplendirGui {
             follow_symlinks: false,
             skip_virtual_filesystems: true,  // Safe default
             stay_on_filesystem: false,
-            calculate_md5: false,
-            calculate_sha256: false,
-            calculate_sha512: false,
+            respect_ignore_files: false,
+            use_scan_cache: false,
+            hash_algorithms: BTreeSet::new(),
             colorize_output: false,
             max_depth: String::new(),
+            filter_inputs: Default::default(),
+            metadata_inputs: Default::default(),
+            file_type_filter: Vec::new(),
+            time_zone_input: String::new(),
+            time_format: TimeFormat::default(),
+            show_tree_dates: false,
+            show_tree_sizes: false,
             
             // Default column visibility: File Name, Path, Size, Modified
             show_filename: true,
@@ -271,6 +514,7 @@ impl Default for SplendirGui {
             show_format: false,
             calculate_format: false,
             calculate_mime: false,
+            unix_columns: BTreeSet::new(),
             
             // Sort options
             sort_by: SortBy::TreeDefault,
@@ -283,24 +527,199 @@ impl Default for SplendirGui {
             error_message: None,
             system_message: None,
             progress_state: None,
+            live_files: None,
             cancellation_flag: None,
             tree_scroll_offset: 0.0,
             tree_flattened_cache: Vec::new(),
+            tree_expanded: HashSet::new(),
+            tree_expand_depth: String::new(),
+            search_text: String::new(),
+            search_mode: SearchMode::Substring,
+            search_requests: SearchRequests::default(),
+            search_error: None,
+            analysis_view: AnalysisView::Summary,
+            usage_root: None,
+            size_chart_kind: ChartKind::Bar,
+            type_chart_kind: ChartKind::Pie,
+            chart_value: ChartValue::Files,
+            top_count: DEFAULT_TOP_COUNT,
             detail_scroll_offset: 0.0,
+            problems_scroll_offset: 0.0,
+            compare_scroll_offset: 0.0,
+            duplicates_scroll_offset: 0.0,
+            duplicate_rows: Vec::new(),
+            duplicate_partial_hash: true,
+            similar_scroll_offset: 0.0,
+            similar_rows: Vec::new(),
+            similar_algorithm: HashAlgorithm::Ssdeep,
+            similar_threshold: SimilarityFinder::default_threshold(HashAlgorithm::Ssdeep).to_string(),
+            verify_scroll_offset: 0.0,
+            baseline_path: None,
+            baseline_files: Vec::new(),
             show_about: false,
             columns_expanded: false,
         }
     }
 }
 
+/// Numbers the searches asked for: typing waits for a pause and then
+/// searches, and only the latest request's results are shown
+#[derive(Debug, Default)]
+struct SearchRequests {
+    latest: u64,
+}
+
+impl SearchRequests {
+    /// Number a new request, making every earlier one stale
+    fn start(&mut self) -> u64 {
+        self.cancel();
+        self.latest
+    }
+    
+    /// Make every request so far stale
+    fn cancel(&mut self) {
+        self.latest += 1;
+    }
+    
+    fn is_latest(&self, generation: u64) -> bool {
+        generation == self.latest
+    }
+}
+
+/// The outcome of a search over the scan results
+#[derive(Debug, Clone)]
+struct SearchResults {
+    query: SearchQuery,
+    /// Whether each row of the file table matches, kept so a re-sort can
+    /// be filtered without searching again
+    matches: Vec<bool>,
+    /// Matching rows in the order of `detail_rows`
+    rows: Vec<u32>,
+    /// The matching part of the tree; `None` when nothing in it matches
+    tree: Option<Arc<TreeNode>>,
+    /// Directories open in the tree view while the search is shown, at
+    /// first every one leading to a match; the view without a search keeps
+    /// its own set
+    expanded: HashSet<PathBuf>,
+    /// Tree view rows as first shown, moved into the tree cache when the
+    /// search completes
+    flattened: Vec<FlatTreeNode>,
+}
+
 #[derive(Debug, Clone, Default)]
 struct ScanResults {
-    detailed_files: Vec<FileInfo>,
-    original_order: Vec<FileInfo>, // Preserve original scan order
-    tree_node: Option<TreeNode>,
-    tree_output: String,
+    /// Every file found, stored compactly, in processing order
+    files: Arc<FileTable>,
+    /// Rows in depth-then-path order (the Default sort)
+    original_order: Vec<u32>,
+    /// Rows in the order shown in the detail view
+    detail_rows: Vec<u32>,
+    /// Kept so the summary can be redrawn when the timestamp settings
+    /// change and the tree text formatted on export; shared with searches
+    /// running in the background
+    analysis: Option<Arc<DirectoryAnalysis>>,
+    /// Rows and tree entries matching the search box, when it is not empty
+    search: Option<SearchResults>,
     analysis_output: String,
+    analysis_record: Option<AnalysisRecord>,
+    /// Files by size range and by type, for the Charts view
+    size_chart: Option<Chart>,
+    type_chart: Option<Chart>,
+    /// Largest files and directories, `top_count` of each
+    rankings: Option<Rankings>,
+    volume_info: Option<VolumeInfo>,
+    report: ScanReport,
+    comparison: Option<ScanComparison>,
+    duplicates: Option<DuplicateReport>,
+    similar: Option<SimilarityReport>,
+    /// Last manifest checked, kept across rescans
+    manifest_path: Option<PathBuf>,
+    verification: Option<VerificationReport>,
     scan_time: Option<f32>,
+    /// Number of files whose results were reused from the scan cache
+    cache_hits: Option<usize>,
+}
+
+impl ScanResults {
+    fn chart(&self, subject: ChartSubject) -> Option<&Chart> {
+        match subject {
+            ChartSubject::Sizes => self.size_chart.as_ref(),
+            ChartSubject::Types => self.type_chart.as_ref(),
+        }
+    }
+    
+    /// Rows shown in the detail view and exported: every row, or only
+    /// those matching the search
+    fn shown_rows(&self) -> &[u32] {
+        self.search.as_ref().map_or(&self.detail_rows, |search| &search.rows)
+    }
+    
+    fn is_shown(&self, row: u32) -> bool {
+        self.search.as_ref().is_none_or(|search| search.matches[row as usize])
+    }
+    
+    /// The tree shown and exported: the whole tree, or the part matching the search
+    fn shown_tree(&self) -> Option<&TreeNode> {
+        match self.search {
+            Some(ref search) => search.tree.as_deref(),
+            None => self.analysis.as_ref().map(|analysis| &analysis.tree),
+        }
+    }
+}
+
+/// What an export writes. The file table, analysis and search tree are
+/// shared with the scan results; rows and reports are copied only for the
+/// mode being exported.
+struct ExportSource {
+    files: Arc<FileTable>,
+    /// Rows shown in the detail view, in the order shown
+    rows: Vec<u32>,
+    /// The same rows in depth-then-path order, for manifests
+    rows_in_path_order: Vec<u32>,
+    analysis: Option<Arc<DirectoryAnalysis>>,
+    /// The matching part of the tree when a search is shown
+    search_tree: Option<Option<Arc<TreeNode>>>,
+    analysis_output: String,
+    analysis_record: Option<AnalysisRecord>,
+    volume_info: Option<VolumeInfo>,
+    report: ScanReport,
+    comparison: Option<ScanComparison>,
+    duplicates: Option<DuplicateReport>,
+    similar: Option<SimilarityReport>,
+    verification: Option<VerificationReport>,
+}
+
+impl ExportSource {
+    fn new(results: &ScanResults, mode: ScanMode) -> Self {
+        let detailed = mode == ScanMode::Detailed;
+        Self {
+            files: Arc::clone(&results.files),
+            rows: if detailed { results.shown_rows().to_vec() } else { Vec::new() },
+            rows_in_path_order: if detailed {
+                results.original_order.iter().copied().filter(|&row| results.is_shown(row)).collect()
+            } else {
+                Vec::new()
+            },
+            analysis: results.analysis.clone(),
+            search_tree: results.search.as_ref().map(|search| search.tree.clone()),
+            analysis_output: results.analysis_output.clone(),
+            analysis_record: results.analysis_record.clone(),
+            volume_info: results.volume_info.clone(),
+            report: if mode == ScanMode::Problems { results.report.clone() } else { ScanReport::default() },
+            comparison: results.comparison.as_ref().filter(|_| mode == ScanMode::Compare).cloned(),
+            duplicates: results.duplicates.as_ref().filter(|_| mode == ScanMode::Duplicates).cloned(),
+            similar: results.similar.as_ref().filter(|_| mode == ScanMode::Similar).cloned(),
+            verification: results.verification.as_ref().filter(|_| mode == ScanMode::Verify).cloned(),
+        }
+    }
+
+    /// The tree shown when the export was started
+    fn shown_tree(&self) -> Option<&TreeNode> {
+        match self.search_tree {
+            Some(ref tree) => tree.as_deref(),
+            None => self.analysis.as_ref().map(|analysis| &analysis.tree),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -315,11 +734,17 @@ enum Message {
     FollowSymlinksToggled(bool),
     SkipVirtualFilesystemsToggled(bool),
     StayOnFilesystemToggled(bool),
-    CalculateSHA256Toggled(bool),
-    CalculateSHA512Toggled(bool),
-    CalculateMD5Toggled(bool),
-    ColorizeOutputToggled(bool),
+    RespectIgnoreFilesToggled(bool),
+    UseScanCacheToggled(bool),
+    HashAlgorithmToggled(HashAlgorithm, bool),
     MaxDepthChanged(String),
+    FilterChanged(FilterField, String),
+    MetadataFilterChanged(MetadataField, String),
+    FileTypeFilterToggled(FileType, bool),
+    TimeZoneChanged(String),
+    TimeStyleSelected(TimestampStyle),
+    TreeDatesToggled(bool),
+    TreeSizesToggled(bool),
     
     // Column visibility toggles
     ShowFilenameToggled(bool),
@@ -331,6 +756,7 @@ enum Message {
     ShowAccessedToggled(bool),
     ShowFormatToggled(bool),
     CalculateMimeToggled(bool),
+    UnixColumnToggled(UnixColumn, bool),
     
     // Sort options
     SortBySelected(SortBy),
@@ -340,19 +766,62 @@ enum Message {
     StartScan,
     CancelScan,
     UpdateProgress,
-    ScanComplete(ScanResults),
+    ScanComplete(Box<ScanResults>),
     ScanError(String),
     
     // Export Events
     ExportResults,
     ExportComplete(Result<String, String>),
     
+    // Comparison Events
+    LoadBaselinePressed,
+    BaselineLoaded(Result<(PathBuf, Vec<FileInfo>), String>),
+    
+    // Duplicate Events
+    DuplicatePartialHashToggled(bool),
+    FindDuplicates,
+    DuplicatesComplete(Result<Box<DuplicateReport>, String>),
+    
+    // Similarity Events
+    SimilarAlgorithmSelected(HashAlgorithm),
+    SimilarThresholdChanged(String),
+    FindSimilar,
+    SimilarComplete(Result<Box<SimilarityReport>, String>),
+    
+    // Manifest Events
+    VerifyManifestPressed,
+    ManifestSelected(Option<PathBuf>),
+    VerifyComplete(Result<(PathBuf, Box<VerificationReport>), String>),
+    
     // Column expansion
     ToggleColumnExpansion,
     
     // Scrolling Events
     TreeScrolled(f32),
+    TreeNodeToggled(PathBuf),
+    TreeExpandDepthChanged(String),
+    TreeExpandToDepth,
+    TreeCollapseAll,
+    SearchChanged(String),
+    SearchModeSelected(SearchMode),
+    /// Sent a moment after the search box changes, to search once typing pauses
+    SearchDebounced(u64),
+    SearchComplete(u64, Result<Box<SearchResults>, String>),
+    AnalysisViewSelected(AnalysisView),
+    UsageDrillDown(PathBuf),
+    UsageUp,
+    ChartKindSelected(ChartSubject, ChartKind),
+    ChartValueSelected(ChartValue),
+    ExportChart(ChartSubject),
+    TopCountSelected(usize),
+    /// Show a ranked file or directory in the treemap
+    RankedEntrySelected(PathBuf),
     DetailScrolled(f32),
+    ProblemsScrolled(f32),
+    CompareScrolled(f32),
+    DuplicatesScrolled(f32),
+    SimilarScrolled(f32),
+    VerifyScrolled(f32),
     
     // Application Events
     ShowAbout,
@@ -387,21 +856,17 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
             
             // Update tree cache when switching to tree mode (if we have tree data)
             if mode == ScanMode::Tree && state.tree_flattened_cache.is_empty() {
-                if let Some(ref tree_node) = state.scan_results.tree_node {
-                    state.tree_flattened_cache = flatten_tree(tree_node, 0);
-                }
+                rebuild_tree_cache(state);
             }
         }
         Message::PresetSelected(preset) => {
-            state.scan_preset = preset.clone();
+            state.scan_preset = preset;
             // Update options based on preset
             match preset {
                 ScanPreset::Minimal => {
                     state.include_dotfiles = false;
                     state.follow_symlinks = false;
-                    state.calculate_md5 = false;
-                    state.calculate_sha256 = false;
-                    state.calculate_sha512 = false;
+                    state.hash_algorithms = BTreeSet::new();
                     state.max_depth = String::new();
                     state.colorize_output = false;
                     // Minimal: minimal columns
@@ -414,13 +879,12 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                     state.show_accessed = false;
                     state.show_format = false;
                     state.calculate_mime = false;
+                    state.unix_columns = BTreeSet::new();
                 }
                 ScanPreset::Complete => {
                     state.include_dotfiles = true;
                     state.follow_symlinks = true;
-                    state.calculate_md5 = true;
-                    state.calculate_sha256 = true;
-                    state.calculate_sha512 = true;
+                    state.hash_algorithms = HashAlgorithm::ALL.into();
                     state.max_depth = String::new();
                     state.colorize_output = false;
                     // Complete: all columns
@@ -434,13 +898,12 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                     state.show_format = true;
                     state.calculate_format = true;
                     state.calculate_mime = true;
+                    state.unix_columns = UnixColumn::available().iter().copied().collect();
                 }
                 ScanPreset::Default => {
                     state.include_dotfiles = false;
                     state.follow_symlinks = false;
-                    state.calculate_md5 = false;
-                    state.calculate_sha256 = false;
-                    state.calculate_sha512 = false;
+                    state.hash_algorithms = BTreeSet::new();
                     state.max_depth = String::new();
                     state.colorize_output = false;
                     // Default: File Name, Path, Size, Modified
@@ -454,13 +917,12 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                     state.show_format = false;
                     state.calculate_format = false;
                     state.calculate_mime = false;
+                    state.unix_columns = BTreeSet::new();
                 }
                 ScanPreset::DefaultMD5 => {
                     state.include_dotfiles = false;
                     state.follow_symlinks = false;
-                    state.calculate_md5 = true;
-                    state.calculate_sha256 = false;
-                    state.calculate_sha512 = false;
+                    state.hash_algorithms = BTreeSet::from([HashAlgorithm::Md5]);
                     state.max_depth = String::new();
                     state.colorize_output = false;
                     // Default: File Name, Path, Size, Modified
@@ -474,13 +936,12 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                     state.show_format = false;
                     state.calculate_format = false;
                     state.calculate_mime = false;
+                    state.unix_columns = BTreeSet::new();
                 }
                 ScanPreset::DefaultSHA256 => {
                     state.include_dotfiles = false;
                     state.follow_symlinks = false;
-                    state.calculate_md5 = false;
-                    state.calculate_sha256 = true;
-                    state.calculate_sha512 = false;
+                    state.hash_algorithms = BTreeSet::from([HashAlgorithm::Sha256]);
                     state.max_depth = String::new();
                     state.colorize_output = false;
                     // Default: File Name, Path, Size, Modified
@@ -494,13 +955,12 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                     state.show_format = false;
                     state.calculate_format = false;
                     state.calculate_mime = false;
+                    state.unix_columns = BTreeSet::new();
                 }
                 ScanPreset::DefaultSHA512 => {
                     state.include_dotfiles = false;
                     state.follow_symlinks = false;
-                    state.calculate_md5 = false;
-                    state.calculate_sha256 = false;
-                    state.calculate_sha512 = true;
+                    state.hash_algorithms = BTreeSet::from([HashAlgorithm::Sha512]);
                     state.max_depth = String::new();
                     state.colorize_output = false;
                     // Default: File Name, Path, Size, Modified
@@ -514,6 +974,7 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                     state.show_format = false;
                     state.calculate_format = false;
                     state.calculate_mime = false;
+                    state.unix_columns = BTreeSet::new();
                 }
             }
         }
@@ -529,21 +990,55 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
         Message::StayOnFilesystemToggled(value) => {
             state.stay_on_filesystem = value;
         }
-        Message::CalculateMD5Toggled(value) => {
-            state.calculate_md5 = value;
+        Message::RespectIgnoreFilesToggled(value) => {
+            state.respect_ignore_files = value;
         }
-        Message::CalculateSHA256Toggled(value) => {
-            state.calculate_sha256 = value;
+        Message::UseScanCacheToggled(value) => {
+            state.use_scan_cache = value;
         }
-        Message::CalculateSHA512Toggled(value) => {
-            state.calculate_sha512 = value;
-        }
-        Message::ColorizeOutputToggled(value) => {
-            state.colorize_output = value;
+        Message::HashAlgorithmToggled(algorithm, value) => {
+            if value {
+                state.hash_algorithms.insert(algorithm);
+            } else {
+                state.hash_algorithms.remove(&algorithm);
+            }
         }
         Message::MaxDepthChanged(value) => {
             state.max_depth = value;
         }
+        Message::FilterChanged(field, value) => {
+            state.filter_inputs[field as usize] = value;
+            state.error_message = None;
+        }
+        Message::MetadataFilterChanged(field, value) => {
+            state.metadata_inputs[field as usize] = value;
+            state.error_message = None;
+        }
+        Message::FileTypeFilterToggled(file_type, value) => {
+            if value {
+                state.file_type_filter.push(file_type);
+            } else {
+                state.file_type_filter.retain(|selected| *selected != file_type);
+            }
+        }
+        Message::TimeZoneChanged(value) => {
+            // Applied as soon as it names a zone; checked again before a scan
+            if let Ok(zone) = DisplayZone::parse(&value) {
+                state.time_format.zone = zone;
+                refresh_analysis_output(state);
+            }
+            state.time_zone_input = value;
+        }
+        Message::TimeStyleSelected(style) => {
+            state.time_format.style = style;
+            refresh_analysis_output(state);
+        }
+        Message::TreeDatesToggled(value) => {
+            state.show_tree_dates = value;
+        }
+        Message::TreeSizesToggled(value) => {
+            state.show_tree_sizes = value;
+        }
         Message::ShowFilenameToggled(value) => {
             state.show_filename = value;
         }
@@ -572,28 +1067,37 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
         Message::CalculateMimeToggled(value) => {
             state.calculate_mime = value;
         }
+        Message::UnixColumnToggled(column, value) => {
+            if value {
+                state.unix_columns.insert(column);
+            } else {
+                state.unix_columns.remove(&column);
+            }
+        }
         Message::SortBySelected(sort_by) => {
             state.sort_by = sort_by;
             // Re-sort existing results if we have them
-            if !state.scan_results.detailed_files.is_empty() {
-                sort_files(
-                    &mut state.scan_results.detailed_files, 
+            if !state.scan_results.detail_rows.is_empty() {
+                state.scan_results.detail_rows = sorted_rows(
+                    &state.scan_results.files,
                     &state.scan_results.original_order,
-                    state.sort_by, 
+                    state.sort_by,
                     state.sort_order
                 );
+                filter_search_rows(&mut state.scan_results);
             }
         }
         Message::SortOrderSelected(sort_order) => {
             state.sort_order = sort_order;
             // Re-sort existing results if we have them
-            if !state.scan_results.detailed_files.is_empty() {
-                sort_files(
-                    &mut state.scan_results.detailed_files, 
+            if !state.scan_results.detail_rows.is_empty() {
+                state.scan_results.detail_rows = sorted_rows(
+                    &state.scan_results.files,
                     &state.scan_results.original_order,
-                    state.sort_by, 
+                    state.sort_by,
                     state.sort_order
                 );
+                filter_search_rows(&mut state.scan_results);
             }
         }
         Message::StartScan => {
@@ -613,6 +1117,21 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                 return Task::none();
             }
             
+            if let Err(e) = create_path_filter(state).validate() {
+                state.error_message = Some(format!("Invalid filter: {}", e));
+                return Task::none();
+            }
+            
+            if let Err(e) = create_metadata_filter(state) {
+                state.error_message = Some(format!("Invalid filter: {}", e));
+                return Task::none();
+            }
+            
+            if let Err(e) = DisplayZone::parse(&state.time_zone_input) {
+                state.error_message = Some(format!("Invalid time zone: {}", e));
+                return Task::none();
+            }
+            
             state.is_scanning = true;
             state.scan_progress = 0.0;
             state.scan_status = "Starting scan...".to_string();
@@ -621,7 +1140,24 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
             // Reset scroll positions
             state.tree_scroll_offset = 0.0;
             state.detail_scroll_offset = 0.0;
+            state.problems_scroll_offset = 0.0;
+            state.compare_scroll_offset = 0.0;
+            state.duplicates_scroll_offset = 0.0;
+            state.duplicate_rows.clear();
+            state.similar_scroll_offset = 0.0;
+            state.similar_rows.clear();
             state.tree_flattened_cache.clear();
+            state.tree_expanded.clear();
+            state.scan_results.search = None;
+            state.search_requests.cancel();
+            state.usage_root = None;
+            
+            // The detail view fills in as files are processed
+            state.scan_results.files = Arc::new(FileTable::new().spill_threshold(SPILL_THRESHOLD));
+            state.scan_results.original_order.clear();
+            state.scan_results.detail_rows.clear();
+            let live_files: LiveFiles = Arc::new(Mutex::new(Vec::new()));
+            state.live_files = Some(live_files.clone());
             
             // Create progress state for communication
             let progress_state = Arc::new(Mutex::new(None));
@@ -632,13 +1168,14 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
             state.cancellation_flag = Some(cancellation_flag.clone());
             
             let scanner = create_scanner(state).cancellation_flag(cancellation_flag);
-            let colorize = state.colorize_output;
+            let use_cache = state.use_scan_cache;
+            let top_count = state.top_count;
             
-            // Note: we no longer pass scan_mode since we scan all modes
+            // One scan feeds every mode, so the current one is not passed
             return Task::perform(
-                perform_scan_with_progress(path, scanner, ScanMode::Detailed, colorize, progress_state),
+                perform_scan_with_progress(path, scanner, top_count, use_cache, progress_state, live_files),
                 |result| match result {
-                    Ok(results) => Message::ScanComplete(results),
+                    Ok(results) => Message::ScanComplete(Box::new(results)),
                     Err(err) => Message::ScanError(err),
                 },
             );
@@ -658,46 +1195,81 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                     }
                 }
             }
+            // Rows arrive in processing order; they are sorted when the scan completes
+            take_live_files(state);
         }
         Message::ScanComplete(mut results) => {
             state.is_scanning = false;
             state.cancellation_flag = None;
             
-            // Save original order before any sorting
-            results.original_order = results.detailed_files.clone();
+            // The files were stored as they arrived; add any still waiting
+            take_live_files(state);
+            state.live_files = None;
+            results.files = std::mem::take(&mut state.scan_results.files);
+            results.original_order = results.files.rows_in_path_order();
             
-            // Sort files if we're not using tree default
-            sort_files(&mut results.detailed_files, &results.original_order, state.sort_by, state.sort_order);
+            // Manifest verification does not depend on the scan
+            results.manifest_path = state.scan_results.manifest_path.take();
+            results.verification = state.scan_results.verification.take();
             
-            // Update tree cache regardless of current mode (since we now have tree data)
-            if let Some(ref tree_node) = results.tree_node {
-                state.tree_flattened_cache = flatten_tree(tree_node, 0);
+            if state.baseline_path.is_some() {
+                let scanned = results.files.rows(&results.original_order);
+                results.comparison = Some(compare_scans(&state.baseline_files, &scanned));
             }
             
-            state.scan_results = results;
+            // Sort files if we're not using tree default
+            results.detail_rows = sorted_rows(&results.files, &results.original_order, state.sort_by, state.sort_order);
+            
+            state.scan_results = *results;
+            
+            // Update tree cache regardless of current mode (since we now have tree data)
+            expand_tree_to_depth(state);
             state.scan_status = format!(
                 "All scans completed in {:.2}s",
                 state.scan_results.scan_time.unwrap_or(0.0)
             );
+            if let Some(hits) = state.scan_results.cache_hits {
+                state.scan_status.push_str(&format!(" ({} unchanged files reused from cache)", hits));
+            }
+            if !state.scan_results.report.is_empty() {
+                state.system_message = Some(format!(
+                    "{} (see Problems)",
+                    state.scan_results.report.summary()
+                ));
+            }
             state.progress_state = None;
+            // Search the new results for whatever is in the search box
+            return start_search(state);
         }
         Message::ScanError(error) => {
             state.is_scanning = false;
             state.cancellation_flag = None;
+            // Do not leave a partial listing behind
+            if state.live_files.take().is_some() {
+                state.scan_results.files = Arc::default();
+                state.scan_results.original_order.clear();
+                state.scan_results.detail_rows.clear();
+            }
             state.error_message = Some(error);
             state.scan_status = "Scan failed".to_string();
             state.progress_state = None;
         }
         Message::ExportResults => {
-            if state.scan_results.detailed_files.is_empty() 
-                && state.scan_results.tree_output.is_empty() 
+            // The table is still being filled
+            if state.is_scanning {
+                return Task::none();
+            }
+            if state.scan_results.detail_rows.is_empty() 
+                && state.scan_results.analysis.is_none() 
                 && state.scan_results.analysis_output.is_empty() {
                 state.error_message = Some("No results to export".to_string());
                 return Task::none();
             }
             
-            let results = state.scan_results.clone();
-            let mode = state.scan_mode.clone();
+            let mode = state.scan_mode;
+            let source = ExportSource::new(&state.scan_results, mode);
+            let time_format = state.time_format;
+            let tree_options = tree_format_options(state);
             let columns = ColumnVisibility {
                 show_filename: state.show_filename,
                 show_path: state.show_path,
@@ -707,10 +1279,9 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                 show_modified: state.show_modified,
                 show_accessed: state.show_accessed,
                 show_format: state.show_format,
-                calculate_md5: state.calculate_md5,
-                calculate_sha256: state.calculate_sha256,
-                calculate_sha512: state.calculate_sha512,
+                hash_algorithms: state.hash_algorithms.clone(),
                 calculate_mime: state.calculate_mime,
+                unix_columns: state.unix_columns.clone(),
             };
             
             return Task::perform(
@@ -719,10 +1290,14 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                         .set_title("Export Scan Results")
                         .add_filter("Text files", &["txt"])
                         .add_filter("CSV files", &["csv"])
+                        .add_filter("JSON files", &["json"])
+                        .add_filter("JSON Lines files", &["jsonl"])
+                        .add_filter("DFXML files", &["xml", "dfxml"])
+                        .add_filter("Checksum manifests", &["sha256", "md5", "sha512"])
                         .save_file();
                     
                     if let Some(path) = file_dialog {
-                        export_results(path, results, mode, columns).await
+                        export_results(path, source, mode, columns, time_format, tree_options).await
                     } else {
                         Err("Export cancelled".to_string())
                     }
@@ -743,85 +1318,442 @@ fn update(state: &mut SplendirGui, message: Message) -> Task<Message> {
                 }
             }
         }
-        Message::ToggleColumnExpansion => {
-            state.columns_expanded = !state.columns_expanded;
-            
-            // Restore scroll position after toggling using absolute offset
-            // We use scroll_to with AbsoluteOffset (pixel position) instead of
-            // snap_to with RelativeOffset (0.0-1.0 percentage) because we track
-            // the actual pixel offset in detail_scroll_offset
-            let offset = state.detail_scroll_offset;
-            return scroll_to(
-                Id::new("detailed_results_scroll"),
-                AbsoluteOffset { x: 0.0, y: offset }
+        Message::LoadBaselinePressed => {
+            return Task::perform(
+                async {
+                    let path = FileDialog::new()
+                        .set_title("Load Baseline Scan")
+                        .add_filter("JSON files", &["json", "jsonl"])
+                        .pick_file()
+                        .ok_or_else(|| "Load cancelled".to_string())?;
+                    load_baseline(path).await
+                },
+                Message::BaselineLoaded,
             );
         }
-        Message::TreeScrolled(offset) => {
-            state.tree_scroll_offset = offset;
+        Message::BaselineLoaded(result) => {
+            match result {
+                Ok((path, files)) => {
+                    let results = &mut state.scan_results;
+                    let comparison = compare_scans(&files, &results.files.rows(&results.original_order));
+                    results.comparison = Some(comparison);
+                    state.system_message = Some(format!(
+                        "Loaded {} files from baseline: {}",
+                        files.len(),
+                        path.display()
+                    ));
+                    state.baseline_path = Some(path);
+                    state.baseline_files = files;
+                    state.compare_scroll_offset = 0.0;
+                }
+                Err(error) => {
+                    if error != "Load cancelled" {
+                        state.error_message = Some(format!("Failed to load baseline: {}", error));
+                    }
+                }
+            }
         }
-        Message::DetailScrolled(offset) => {
-            state.detail_scroll_offset = offset;
+        Message::DuplicatePartialHashToggled(value) => {
+            state.duplicate_partial_hash = value;
         }
-        Message::ShowAbout => {
-            state.show_about = true;
+        Message::FindDuplicates => {
+            if state.is_scanning || state.scan_results.original_order.is_empty() {
+                return Task::none();
+            }
+            
+            state.is_scanning = true;
+            state.scan_progress = 0.0;
+            state.scan_status = "Finding duplicates...".to_string();
+            state.error_message = None;
+            state.duplicates_scroll_offset = 0.0;
+            
+            let progress_state = Arc::new(Mutex::new(None));
+            state.progress_state = Some(progress_state.clone());
+            
+            let cancellation_flag = Arc::new(AtomicBool::new(false));
+            state.cancellation_flag = Some(cancellation_flag.clone());
+            
+            let finder = DuplicateFinder::new()
+                .partial_hash(state.duplicate_partial_hash)
+                .cancellation_flag(cancellation_flag);
+            let files = Arc::clone(&state.scan_results.files);
+            let rows = state.scan_results.original_order.clone();
+            
+            return Task::perform(
+                find_duplicates_with_progress(files, rows, finder, progress_state),
+                |result| Message::DuplicatesComplete(result.map(Box::new)),
+            );
         }
-        Message::CloseAbout => {
-            state.show_about = false;
+        Message::DuplicatesComplete(result) => {
+            state.is_scanning = false;
+            state.cancellation_flag = None;
+            state.progress_state = None;
+            
+            match result {
+                Ok(report) => {
+                    state.duplicate_rows = report.sets.iter()
+                        .enumerate()
+                        .flat_map(|(set_index, set)| {
+                            std::iter::once((set_index, None))
+                                .chain((0..set.paths.len()).map(move |file_index| (set_index, Some(file_index))))
+                        })
+                        .collect();
+                    state.scan_status = format!(
+                        "Duplicate search completed: {} ({} files hashed)",
+                        report.summary(),
+                        report.files_hashed
+                    );
+                    
+                    // Unreadable files belong with the other scan problems
+                    state.scan_results.report.issues.extend(report.report.issues.iter().cloned());
+                    state.scan_results.report.sort();
+                    state.scan_results.duplicates = Some(*report);
+                }
+                Err(error) => {
+                    state.scan_status = "Duplicate search failed".to_string();
+                    state.error_message = Some(error);
+                }
+            }
         }
-        Message::Exit => {
-            // Cancel any running scan before exiting
-            if let Some(ref flag) = state.cancellation_flag {
-                flag.store(true, Ordering::Relaxed);
+        Message::SimilarAlgorithmSelected(algorithm) => {
+            state.similar_algorithm = algorithm;
+            state.similar_threshold = SimilarityFinder::default_threshold(algorithm).to_string();
+        }
+        Message::SimilarThresholdChanged(value) => {
+            if value.is_empty() || value.chars().all(|c| c.is_ascii_digit()) {
+                state.similar_threshold = value;
             }
-            return iced::exit();
         }
-    }
-    
-    Task::none()
-}
-
-fn view(state: &SplendirGui) -> Element<'_, Message> {
-    let header = view_header(state);
-    let options = view_options(state);
-    let results = view_results(state);
-    
-    // Options sidebar and main content area side by side
-    let main_row = row![
-        container(options).padding(20),
-        rule::vertical(1),
-        container(
-            if state.is_scanning {
-                container(view_progress(state)).padding(20)
-            } else if let Some(error) = &state.error_message {
-                container(
-                    text(error)
-                        .size(14)
-                        .color(iced::Color::from_rgb(0.8, 0.2, 0.2))
-                ).padding(20)
-            } else {
-                container(results).padding(20)
+        Message::FindSimilar => {
+            if state.is_scanning || state.scan_results.original_order.is_empty() {
+                return Task::none();
             }
-        )
-        .width(Length::Fill)
-    ]
-    .spacing(0);
-    
-    let content = column![
-        header,
-        main_row,
-    ]
-    .spacing(10);
-    
-    // Bottom row with system messages on left and version on right
-    let message_text = if let Some(ref msg) = state.system_message {
-        format!("Messages: {}", msg)
-    } else {
-        "Messages:".to_string()
-    };
-    
-    let bottom_row = row![
-        text(message_text)
-            .size(12)
+            let Ok(threshold) = state.similar_threshold.parse::<u32>() else {
+                state.error_message = Some("Enter a whole number for the similarity threshold".to_string());
+                return Task::none();
+            };
+            
+            state.is_scanning = true;
+            state.scan_progress = 0.0;
+            state.scan_status = "Finding similar files...".to_string();
+            state.error_message = None;
+            state.similar_scroll_offset = 0.0;
+            
+            let progress_state = Arc::new(Mutex::new(None));
+            state.progress_state = Some(progress_state.clone());
+            
+            let cancellation_flag = Arc::new(AtomicBool::new(false));
+            state.cancellation_flag = Some(cancellation_flag.clone());
+            
+            let finder = SimilarityFinder::new()
+                .algorithm(state.similar_algorithm)
+                .threshold(threshold)
+                .cancellation_flag(cancellation_flag);
+            let files = Arc::clone(&state.scan_results.files);
+            let rows = state.scan_results.original_order.clone();
+            
+            return Task::perform(
+                find_similar_with_progress(files, rows, finder, progress_state),
+                |result| Message::SimilarComplete(result.map(Box::new)),
+            );
+        }
+        Message::SimilarComplete(result) => {
+            state.is_scanning = false;
+            state.cancellation_flag = None;
+            state.progress_state = None;
+            
+            match result {
+                Ok(report) => {
+                    state.similar_rows = report.clusters.iter()
+                        .enumerate()
+                        .flat_map(|(cluster_index, cluster)| {
+                            std::iter::once((cluster_index, None))
+                                .chain((0..cluster.files.len()).map(move |file_index| (cluster_index, Some(file_index))))
+                        })
+                        .collect();
+                    state.scan_status = format!(
+                        "Similarity search completed: {} ({} files hashed)",
+                        report.summary(),
+                        report.files_hashed
+                    );
+                    
+                    state.scan_results.report.issues.extend(report.report.issues.iter().cloned());
+                    state.scan_results.report.sort();
+                    state.scan_results.similar = Some(*report);
+                }
+                Err(error) => {
+                    state.scan_status = "Similarity search failed".to_string();
+                    state.error_message = Some(error);
+                }
+            }
+        }
+        Message::VerifyManifestPressed => {
+            return Task::perform(
+                async {
+                    FileDialog::new()
+                        .set_title("Select Manifest to Verify")
+                        .add_filter("Checksum manifests", &["md5", "sha256", "sha512", "txt"])
+                        .add_filter("All files", &["*"])
+                        .pick_file()
+                },
+                Message::ManifestSelected,
+            );
+        }
+        Message::ManifestSelected(path) => {
+            let Some(path) = path else {
+                return Task::none();
+            };
+            if state.is_scanning {
+                return Task::none();
+            }
+            let Some((format, algorithm)) = ManifestFormat::detect(&path) else {
+                state.error_message = Some(format!(
+                    "Unrecognised manifest name: {} (expected e.g. SHA256SUMS, files.md5 or manifest-sha256.txt)",
+                    path.display()
+                ));
+                return Task::none();
+            };
+            
+            state.is_scanning = true;
+            state.scan_progress = 0.0;
+            state.scan_status = "Verifying manifest...".to_string();
+            state.error_message = None;
+            state.verify_scroll_offset = 0.0;
+            
+            let progress_state = Arc::new(Mutex::new(None));
+            state.progress_state = Some(progress_state.clone());
+            
+            let cancellation_flag = Arc::new(AtomicBool::new(false));
+            state.cancellation_flag = Some(cancellation_flag.clone());
+            
+            let scanner = create_scanner(state).cancellation_flag(cancellation_flag);
+            
+            return Task::perform(
+                verify_manifest_with_progress(path, format, algorithm, scanner, progress_state),
+                |result| Message::VerifyComplete(result.map(|(path, report)| (path, Box::new(report)))),
+            );
+        }
+        Message::VerifyComplete(result) => {
+            state.is_scanning = false;
+            state.cancellation_flag = None;
+            state.progress_state = None;
+            
+            match result {
+                Ok((path, report)) => {
+                    state.scan_status = format!("Manifest verification completed: {}", report.summary());
+                    state.scan_results.manifest_path = Some(path);
+                    state.scan_results.verification = Some(*report);
+                }
+                Err(error) => {
+                    state.scan_status = "Manifest verification failed".to_string();
+                    state.error_message = Some(error);
+                }
+            }
+        }
+        Message::ToggleColumnExpansion => {
+            state.columns_expanded = !state.columns_expanded;
+            
+            // Restore scroll position after toggling using absolute offset
+            // We use scroll_to with AbsoluteOffset (pixel position) instead of
+            // snap_to with RelativeOffset (0.0-1.0 percentage) because we track
+            // the actual pixel offset in detail_scroll_offset
+            let offset = state.detail_scroll_offset;
+            return scroll_to(
+                Id::new("detailed_results_scroll"),
+                AbsoluteOffset { x: 0.0, y: offset }
+            );
+        }
+        Message::TreeScrolled(offset) => {
+            state.tree_scroll_offset = offset;
+        }
+        Message::TreeNodeToggled(path) => {
+            toggle_directory(shown_tree_expanded(state), path);
+            rebuild_tree_cache(state);
+        }
+        Message::TreeExpandDepthChanged(value) => {
+            state.tree_expand_depth = value;
+        }
+        Message::TreeExpandToDepth => {
+            expand_tree_to_depth(state);
+        }
+        Message::TreeCollapseAll => {
+            shown_tree_expanded(state).clear();
+            rebuild_tree_cache(state);
+        }
+        Message::SearchChanged(value) => {
+            state.search_text = value;
+            return debounce_search(state);
+        }
+        Message::SearchModeSelected(mode) => {
+            state.search_mode = mode;
+            return debounce_search(state);
+        }
+        Message::SearchDebounced(generation) => {
+            if state.search_requests.is_latest(generation) {
+                return start_search(state);
+            }
+        }
+        Message::AnalysisViewSelected(view) => {
+            state.analysis_view = view;
+        }
+        Message::UsageDrillDown(path) => {
+            if let Some(ref analysis) = state.scan_results.analysis {
+                state.usage_root = usage_root_for(&analysis.tree, path);
+            }
+        }
+        Message::UsageUp => {
+            if let (Some(root), Some(analysis)) = (&state.usage_root, &state.scan_results.analysis) {
+                state.usage_root = usage_parent(&analysis.tree, root);
+            }
+        }
+        Message::ChartKindSelected(subject, kind) => {
+            *chart_kind_mut(state, subject) = kind;
+        }
+        Message::ChartValueSelected(value) => {
+            state.chart_value = value;
+        }
+        Message::ExportChart(subject) => {
+            let Some(chart) = state.scan_results.chart(subject).cloned() else {
+                state.error_message = Some("No chart to export".to_string());
+                return Task::none();
+            };
+            let kind = *chart_kind_mut(state, subject);
+            let value = state.chart_value;
+            
+            return Task::perform(
+                async move {
+                    let file_dialog = FileDialog::new()
+                        .set_title("Export Chart")
+                        .set_file_name(format!("{}.svg", subject.file_stem()))
+                        .add_filter("SVG images", &["svg"])
+                        .add_filter("PNG images", &["png"])
+                        .save_file();
+                    
+                    if let Some(path) = file_dialog {
+                        export_chart(path, chart, kind, value).await
+                    } else {
+                        Err("Export cancelled".to_string())
+                    }
+                },
+                Message::ExportComplete,
+            );
+        }
+        Message::TopCountSelected(count) => {
+            state.top_count = count;
+            let results = &mut state.scan_results;
+            if let Some(ref mut analysis) = results.analysis {
+                let analysis = Arc::make_mut(analysis);
+                analysis.top_count = count;
+                results.analysis_output = analysis.summary();
+                results.analysis_record = Some(AnalysisRecord::from(&*analysis));
+                results.rankings = Some(analysis.rankings(count));
+            }
+        }
+        Message::RankedEntrySelected(path) => {
+            if let Some(ref analysis) = state.scan_results.analysis {
+                state.usage_root = usage_root_for(&analysis.tree, path);
+                state.analysis_view = AnalysisView::Treemap;
+            }
+        }
+        Message::SearchComplete(generation, result) => {
+            // Results for text that has since changed are dropped
+            if state.search_requests.is_latest(generation) {
+                match result {
+                    Ok(search) => {
+                        state.scan_results.search = Some(*search);
+                        return show_search_results(state);
+                    }
+                    Err(e) => state.search_error = Some(e),
+                }
+            }
+        }
+        Message::DetailScrolled(offset) => {
+            state.detail_scroll_offset = offset;
+        }
+        Message::ProblemsScrolled(offset) => {
+            state.problems_scroll_offset = offset;
+        }
+        Message::CompareScrolled(offset) => {
+            state.compare_scroll_offset = offset;
+        }
+        Message::DuplicatesScrolled(offset) => {
+            state.duplicates_scroll_offset = offset;
+        }
+        Message::SimilarScrolled(offset) => {
+            state.similar_scroll_offset = offset;
+        }
+        Message::VerifyScrolled(offset) => {
+            state.verify_scroll_offset = offset;
+        }
+        Message::ShowAbout => {
+            state.show_about = true;
+        }
+        Message::CloseAbout => {
+            state.show_about = false;
+        }
+        Message::Exit => {
+            // Cancel any running scan before exiting
+            if let Some(ref flag) = state.cancellation_flag {
+                flag.store(true, Ordering::Relaxed);
+            }
+            return iced::exit();
+        }
+    }
+    
+    Task::none()
+}
+
+fn view(state: &SplendirGui) -> Element<'_, Message> {
+    let header = view_header(state);
+    let options = view_options(state);
+    let results = view_results(state);
+    
+    // Options sidebar and main content area side by side
+    let main_row = row![
+        container(options).padding(20),
+        rule::vertical(1),
+        container(
+            if state.is_scanning
+                && state.live_files.is_some()
+                && state.scan_mode == ScanMode::Detailed
+                && !state.scan_results.detail_rows.is_empty()
+            {
+                // Show files as they are processed, below the progress bar
+                container(
+                    column![view_progress(state), view_detailed_results_virtual(state)].spacing(10)
+                ).padding(20)
+            } else if state.is_scanning {
+                container(view_progress(state)).padding(20)
+            } else if let Some(error) = &state.error_message {
+                container(
+                    text(error)
+                        .size(14)
+                        .color(iced::Color::from_rgb(0.8, 0.2, 0.2))
+                ).padding(20)
+            } else {
+                container(results).padding(20)
+            }
+        )
+        .width(Length::Fill)
+    ]
+    .spacing(0);
+    
+    let content = column![
+        header,
+        main_row,
+    ]
+    .spacing(10);
+    
+    // Bottom row with system messages on left and version on right
+    let message_text = if let Some(ref msg) = state.system_message {
+        format!("Messages: {}", msg)
+    } else {
+        "Messages:".to_string()
+    };
+    
+    let bottom_row = row![
+        text(message_text)
+            .size(12)
             .color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
         Space::new().width(Length::Fill),
         text(format!("Splendir v{}", VERSION))
@@ -878,11 +1810,13 @@ fn create_scanner(state: &SplendirGui) -> DirectoryScanner {
         .follow_symlinks(state.follow_symlinks)
         .skip_virtual_filesystems(state.skip_virtual_filesystems)
         .stay_on_filesystem(state.stay_on_filesystem)
-        .calculate_sha256(state.calculate_sha256)
-        .calculate_sha512(state.calculate_sha512)
-        .calculate_md5(state.calculate_md5)
+        .respect_ignore_files(state.respect_ignore_files)
+        .hash_algorithms(state.hash_algorithms.iter().copied())
         .calculate_format(state.calculate_format)
-        .calculate_mime(state.calculate_mime);
+        .calculate_mime(state.calculate_mime)
+        .path_filter(create_path_filter(state))
+        .metadata_filter(create_metadata_filter(state).unwrap_or_default())
+        .time_format(state.time_format);
     
     if let Ok(depth) = state.max_depth.parse::<usize>() {
         scanner = scanner.max_depth(depth);
@@ -891,6 +1825,181 @@ fn create_scanner(state: &SplendirGui) -> DirectoryScanner {
     scanner
 }
 
+/// Build the metadata filter from the sidebar inputs; empty inputs leave
+/// that condition unset
+fn create_metadata_filter(state: &SplendirGui) -> Result<MetadataFilter, String> {
+    let input = |field: MetadataField| state.metadata_inputs[field as usize].trim();
+    let size = |field: MetadataField| match input(field) {
+        "" => Ok(None),
+        value => parse_size(value).map(Some),
+    };
+    let range = |from: MetadataField, until: MetadataField| DateRange::parse(input(from), input(until));
+    
+    let mut filter = MetadataFilter {
+        min_size: size(MetadataField::MinSize)?,
+        max_size: size(MetadataField::MaxSize)?,
+        created: range(MetadataField::CreatedFrom, MetadataField::CreatedUntil)?,
+        modified: range(MetadataField::ModifiedFrom, MetadataField::ModifiedUntil)?,
+        accessed: range(MetadataField::AccessedFrom, MetadataField::AccessedUntil)?,
+        file_types: state.file_type_filter.clone(),
+        ..MetadataFilter::default()
+    };
+    for category in parse_pattern_list(input(MetadataField::MimeCategories)) {
+        filter = filter.mime_category(category);
+    }
+    Ok(filter)
+}
+
+fn tree_format_options(state: &SplendirGui) -> TreeFormatOptions {
+    TreeFormatOptions::new()
+        .colorize(state.colorize_output)
+        .show_permissions(state.unix_columns.contains(&UnixColumn::Permissions))
+        .show_owner(state.unix_columns.contains(&UnixColumn::Owner))
+        .show_group(state.unix_columns.contains(&UnixColumn::Group))
+        .show_file_sizes(state.show_tree_sizes)
+        .show_modified(state.show_tree_dates)
+        .time_format(state.time_format)
+}
+
+/// How the chart of a subject is drawn
+fn chart_kind_mut(state: &mut SplendirGui, subject: ChartSubject) -> &mut ChartKind {
+    match subject {
+        ChartSubject::Sizes => &mut state.size_chart_kind,
+        ChartSubject::Types => &mut state.type_chart_kind,
+    }
+}
+
+/// Rebuild the visible tree rows after directories are opened or closed
+fn rebuild_tree_cache(state: &mut SplendirGui) {
+    let expanded = match state.scan_results.search {
+        Some(ref search) => &search.expanded,
+        None => &state.tree_expanded,
+    };
+    state.tree_flattened_cache = match state.scan_results.shown_tree() {
+        Some(tree) => flatten_tree(tree, expanded),
+        None => Vec::new(),
+    };
+}
+
+/// The open directories of the tree shown: the search's while there is one
+fn shown_tree_expanded(state: &mut SplendirGui) -> &mut HashSet<PathBuf> {
+    match state.scan_results.search {
+        Some(ref mut search) => &mut search.expanded,
+        None => &mut state.tree_expanded,
+    }
+}
+
+/// Open the tree down to the depth typed in the tree view, or fully when it is empty
+fn expand_tree_to_depth(state: &mut SplendirGui) {
+    let expanded = match state.scan_results.shown_tree() {
+        Some(tree) => directories_for_depth(tree, &state.tree_expand_depth),
+        None => HashSet::new(),
+    };
+    *shown_tree_expanded(state) = expanded;
+    rebuild_tree_cache(state);
+}
+
+/// Search again once typing pauses for `SEARCH_DELAY`
+fn debounce_search(state: &mut SplendirGui) -> Task<Message> {
+    let generation = state.search_requests.start();
+    Task::perform(tokio::time::sleep(SEARCH_DELAY), move |_| Message::SearchDebounced(generation))
+}
+
+/// Search the results in the background for the text in the search box,
+/// or show everything again when it is empty
+fn start_search(state: &mut SplendirGui) -> Task<Message> {
+    state.search_error = None;
+    let generation = state.search_requests.start();
+    let pattern = state.search_text.trim();
+    if pattern.is_empty() {
+        if state.scan_results.search.take().is_some() {
+            return show_search_results(state);
+        }
+        return Task::none();
+    }
+    let query = match SearchQuery::new(state.search_mode, pattern) {
+        Ok(query) => query,
+        Err(e) => {
+            state.search_error = Some(e.to_string());
+            return Task::none();
+        }
+    };
+    // Results still arriving are searched when the scan completes
+    if state.is_scanning || (state.scan_results.detail_rows.is_empty() && state.scan_results.analysis.is_none()) {
+        return Task::none();
+    }
+    
+    // Paths are searched below the scanned directory
+    let query = match state.scan_results.analysis {
+        Some(ref analysis) => query.relative_to(analysis.path.clone()),
+        None => query,
+    };
+    let files = state.scan_results.files.clone();
+    let rows = state.scan_results.detail_rows.clone();
+    let analysis = state.scan_results.analysis.clone();
+    Task::perform(
+        search_results(query, files, rows, analysis),
+        move |result| Message::SearchComplete(generation, result.map(Box::new)),
+    )
+}
+
+/// Show the latest search, with every directory leading to a match open in
+/// the tree, or when it was cleared everything as it was before the search
+fn show_search_results(state: &mut SplendirGui) -> Task<Message> {
+    match state.scan_results.search {
+        Some(ref mut search) => state.tree_flattened_cache = std::mem::take(&mut search.flattened),
+        None => rebuild_tree_cache(state),
+    }
+    
+    state.detail_scroll_offset = 0.0;
+    state.tree_scroll_offset = 0.0;
+    Task::batch([
+        scroll_to(Id::new("detailed_results_scroll"), AbsoluteOffset { x: 0.0, y: 0.0 }),
+        scroll_to(Id::new("tree_results_scroll"), AbsoluteOffset { x: 0.0, y: 0.0 }),
+    ])
+}
+
+/// Keep the search's matches in the new order after the detail view is re-sorted
+fn filter_search_rows(results: &mut ScanResults) {
+    if let Some(ref mut search) = results.search {
+        search.rows = results.detail_rows
+            .iter()
+            .copied()
+            .filter(|&row| search.matches[row as usize])
+            .collect();
+    }
+}
+
+/// Redraw the analysis summary after the timestamp settings change; the
+/// detail and tree views format their rows as they are drawn, and the tree
+/// text is formatted when it is exported
+fn refresh_analysis_output(state: &mut SplendirGui) {
+    let results = &mut state.scan_results;
+    if let Some(ref mut analysis) = results.analysis {
+        let analysis = Arc::make_mut(analysis);
+        analysis.time_format = state.time_format;
+        results.analysis_output = analysis.summary();
+    }
+}
+
+fn create_path_filter(state: &SplendirGui) -> PathFilter {
+    let patterns = |field: FilterField| parse_pattern_list(&state.filter_inputs[field as usize]);
+    let mut filter = PathFilter {
+        include_globs: patterns(FilterField::IncludeGlobs),
+        exclude_globs: patterns(FilterField::ExcludeGlobs),
+        include_regexes: patterns(FilterField::IncludeRegexes),
+        exclude_regexes: patterns(FilterField::ExcludeRegexes),
+        ..PathFilter::default()
+    };
+    for extension in patterns(FilterField::IncludeExtensions) {
+        filter = filter.include_extension(extension);
+    }
+    for extension in patterns(FilterField::ExcludeExtensions) {
+        filter = filter.exclude_extension(extension);
+    }
+    filter
+}
+
 fn view_header(state: &SplendirGui) -> Element<'_, Message> {
     let path_input = text_input("Select a directory to scan...", &state.selected_path)
         .on_input(Message::PathChanged)
@@ -927,12 +2036,12 @@ fn view_options(state: &SplendirGui) -> Element<'_, Message> {
         column![
             row![text("Mode:").width(80), pick_list(
                 &ScanMode::ALL[..],
-                Some(state.scan_mode.clone()),
+                Some(state.scan_mode),
                 Message::ScanModeSelected,
             )].spacing(10),
             row![text("Preset:").width(80), pick_list(
                 &ScanPreset::ALL[..],
-                Some(state.scan_preset.clone()),
+                Some(state.scan_preset),
                 Message::PresetSelected,
             )].spacing(10),
         ].spacing(10)
@@ -947,6 +2056,8 @@ fn view_options(state: &SplendirGui) -> Element<'_, Message> {
             checkbox(state.follow_symlinks).label("Follow symlinks").on_toggle(Message::FollowSymlinksToggled),
             checkbox(state.skip_virtual_filesystems).label("Skip virtual filesystems").on_toggle(Message::SkipVirtualFilesystemsToggled),
             checkbox(state.stay_on_filesystem).label("Stay on same filesystem").on_toggle(Message::StayOnFilesystemToggled),
+            checkbox(state.respect_ignore_files).label("Respect .gitignore and .ignore files").on_toggle(Message::RespectIgnoreFilesToggled),
+            checkbox(state.use_scan_cache).label("Reuse results for unchanged files").on_toggle(Message::UseScanCacheToggled),
             row![text("Max depth:"), text_input("", &state.max_depth)
                 .on_input(Message::MaxDepthChanged)
                 .width(Length::Fixed(75.0))
@@ -956,24 +2067,105 @@ fn view_options(state: &SplendirGui) -> Element<'_, Message> {
     ]
     .spacing(10);
     
-    // File Options section - two column layout
-    let file_options_col1 = column![
-        checkbox(state.show_filename).label("File Name").on_toggle(Message::ShowFilenameToggled),
-        checkbox(state.show_path).label("Path").on_toggle(Message::ShowPathToggled),
-        checkbox(state.show_path_name).label("Path + Name").on_toggle(Message::ShowPathNameToggled),
-        checkbox(state.show_size).label("Size").on_toggle(Message::ShowSizeToggled),
-        checkbox(state.show_created).label("Created").on_toggle(Message::ShowCreatedToggled),
-        checkbox(state.show_modified).label("Modified").on_toggle(Message::ShowModifiedToggled),
-        checkbox(state.show_accessed).label("Accessed").on_toggle(Message::ShowAccessedToggled),
-    ].spacing(8);
+    // Filters section - comma-separated pattern lists
+    let mut filter_rows = column![].spacing(8);
+    for field in FilterField::ALL {
+        filter_rows = filter_rows.push(
+            row![
+                text(field.label()).width(95),
+                text_input(field.placeholder(), &state.filter_inputs[field as usize])
+                    .on_input(move |value| Message::FilterChanged(field, value))
+                    .padding(6),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+        );
+    }
+    let filters_section = column![
+        text("Filters").size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
+        filter_rows,
+    ]
+    .spacing(10);
     
-    let file_options_col2 = column![
-        checkbox(state.show_format).label("Format").on_toggle(Message::ShowFormatToggled),
+    // Metadata Filters section - size and date ranges, then file types
+    let range_row = |label: &'static str, from: MetadataField, until: MetadataField, from_hint: &'static str, until_hint: &'static str| {
+        row![
+            text(label).width(70),
+            text_input(from_hint, &state.metadata_inputs[from as usize])
+                .on_input(move |value| Message::MetadataFilterChanged(from, value))
+                .padding(6),
+            text("to"),
+            text_input(until_hint, &state.metadata_inputs[until as usize])
+                .on_input(move |value| Message::MetadataFilterChanged(until, value))
+                .padding(6),
+        ]
+        .spacing(6)
+        .align_y(Alignment::Center)
+    };
+    let mut file_type_col1 = column![].spacing(8);
+    let mut file_type_col2 = column![].spacing(8);
+    let file_types = FileType::ALL.into_iter().filter(|file_type| *file_type != FileType::Directory);
+    for (i, file_type) in file_types.enumerate() {
+        let toggle = checkbox(state.file_type_filter.contains(&file_type))
+            .label(file_type.description())
+            .on_toggle(move |value| Message::FileTypeFilterToggled(file_type, value));
+        if i.is_multiple_of(2) {
+            file_type_col1 = file_type_col1.push(toggle);
+        } else {
+            file_type_col2 = file_type_col2.push(toggle);
+        }
+    }
+    let metadata_section = column![
+        text("Metadata Filters").size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
+        column![
+            range_row("Size:", MetadataField::MinSize, MetadataField::MaxSize, "100K", "1.5G"),
+            range_row("Modified:", MetadataField::ModifiedFrom, MetadataField::ModifiedUntil, "2023-01-01", "2y"),
+            range_row("Created:", MetadataField::CreatedFrom, MetadataField::CreatedUntil, "2023", "2023"),
+            range_row("Accessed:", MetadataField::AccessedFrom, MetadataField::AccessedUntil, "30d", ""),
+            row![
+                text("MIME:").width(70),
+                text_input("image, video", &state.metadata_inputs[MetadataField::MimeCategories as usize])
+                    .on_input(|value| Message::MetadataFilterChanged(MetadataField::MimeCategories, value))
+                    .padding(6),
+            ]
+            .spacing(6)
+            .align_y(Alignment::Center),
+            text("File types (none checked keeps all):").size(13),
+            row![file_type_col1, file_type_col2].spacing(15),
+        ].spacing(8)
+    ]
+    .spacing(10);
+    
+    // File Options section - two column layout
+    let mut file_options_col1 = column![
+        checkbox(state.show_filename).label("File Name").on_toggle(Message::ShowFilenameToggled),
+        checkbox(state.show_path).label("Path").on_toggle(Message::ShowPathToggled),
+        checkbox(state.show_path_name).label("Path + Name").on_toggle(Message::ShowPathNameToggled),
+        checkbox(state.show_size).label("Size").on_toggle(Message::ShowSizeToggled),
+        checkbox(state.show_created).label("Created").on_toggle(Message::ShowCreatedToggled),
+        checkbox(state.show_modified).label("Modified").on_toggle(Message::ShowModifiedToggled),
+        checkbox(state.show_accessed).label("Accessed").on_toggle(Message::ShowAccessedToggled),
+    ].spacing(8);
+    
+    for &column in UnixColumn::available() {
+        file_options_col1 = file_options_col1.push(
+            checkbox(state.unix_columns.contains(&column))
+                .label(column.label())
+                .on_toggle(move |value| Message::UnixColumnToggled(column, value))
+        );
+    }
+    
+    let mut file_options_col2 = column![
+        checkbox(state.show_format).label("Format").on_toggle(Message::ShowFormatToggled),
         checkbox(state.calculate_mime).label("Media Type").on_toggle(Message::CalculateMimeToggled),
-        checkbox(state.calculate_md5).label("MD5").on_toggle(Message::CalculateMD5Toggled),
-        checkbox(state.calculate_sha256).label("SHA256").on_toggle(Message::CalculateSHA256Toggled),
-        checkbox(state.calculate_sha512).label("SHA512").on_toggle(Message::CalculateSHA512Toggled),
     ].spacing(8);
+    for algorithm in HashAlgorithm::ALL {
+        file_options_col2 = file_options_col2.push(
+            checkbox(state.hash_algorithms.contains(&algorithm))
+                .label(algorithm.label())
+                .on_toggle(move |value| Message::HashAlgorithmToggled(algorithm, value))
+        );
+    }
     
     let file_options_section = column![
         text("File Options").size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
@@ -984,6 +2176,38 @@ fn view_options(state: &SplendirGui) -> Element<'_, Message> {
     ]
     .spacing(10);
     
+    // Timestamps section - zone and style for every displayed and exported time
+    let timestamps_section = column![
+        text("Timestamps").size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
+        row![
+            text("Zone:").width(70),
+            text_input("UTC, Local or Europe/Berlin", &state.time_zone_input)
+                .on_input(Message::TimeZoneChanged)
+                .padding(6),
+        ]
+        .spacing(6)
+        .align_y(Alignment::Center),
+        row![
+            text("Style:").width(70),
+            pick_list(
+                &TimestampStyle::ALL[..],
+                Some(state.time_format.style),
+                Message::TimeStyleSelected
+            ),
+        ]
+        .spacing(6)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(10);
+    
+    // Tree View section - details printed before each name, like tree -h --du -D
+    let tree_view_section = column![
+        text("Tree View").size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
+        checkbox(state.show_tree_sizes).label("Sizes and directory totals").on_toggle(Message::TreeSizesToggled),
+        checkbox(state.show_tree_dates).label("Modification times").on_toggle(Message::TreeDatesToggled),
+    ]
+    .spacing(10);
+    
     // Sort Options section
     let sort_options_section = column![
         text("Sort Options").size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
@@ -1018,8 +2242,16 @@ fn view_options(state: &SplendirGui) -> Element<'_, Message> {
             rule::horizontal(1),
             traversal_section,
             rule::horizontal(1),
+            filters_section,
+            rule::horizontal(1),
+            metadata_section,
+            rule::horizontal(1),
             file_options_section,
             rule::horizontal(1),
+            timestamps_section,
+            rule::horizontal(1),
+            tree_view_section,
+            rule::horizontal(1),
             sort_options_section,
         ]
         .spacing(10)
@@ -1038,8 +2270,10 @@ fn view_progress(state: &SplendirGui) -> Element<'_, Message> {
 }
 
 fn view_results(state: &SplendirGui) -> Element<'_, Message> {
-    if state.scan_results.detailed_files.is_empty() 
-        && state.scan_results.tree_node.is_none()
+    // Manifest verification does not need a scan first
+    if state.scan_mode != ScanMode::Verify
+        && state.scan_results.detail_rows.is_empty() 
+        && state.scan_results.analysis.is_none()
         && state.scan_results.analysis_output.is_empty() {
         return container(
             text("No scan results yet. Select a directory and click 'Start Scan'.")
@@ -1067,14 +2301,27 @@ fn view_results(state: &SplendirGui) -> Element<'_, Message> {
     
     status_row = status_row.push(
         button("Export Results")
-            .on_press(Message::ExportResults)
+            .on_press_maybe(if state.is_scanning { None } else { Some(Message::ExportResults) })
             .padding([8, 16])
     );
     
     let results_content = match state.scan_mode {
-        ScanMode::Detailed => view_detailed_results_virtual(state),
-        ScanMode::Tree => view_tree_results_virtual(state),
+        ScanMode::Detailed | ScanMode::Tree => column![
+            view_search_bar(state),
+            if state.scan_mode == ScanMode::Detailed {
+                view_detailed_results_virtual(state)
+            } else {
+                view_tree_results_virtual(state)
+            },
+        ]
+        .spacing(10)
+        .into(),
         ScanMode::Analysis => view_analysis_results(state),
+        ScanMode::Problems => view_problems_results(state),
+        ScanMode::Compare => view_compare_results(state),
+        ScanMode::Duplicates => view_duplicate_results(state),
+        ScanMode::Similar => view_similar_results(state),
+        ScanMode::Verify => view_verify_results(state),
     };
     
     column![
@@ -1087,9 +2334,54 @@ fn view_results(state: &SplendirGui) -> Element<'_, Message> {
     .into()
 }
 
+// Search box shared by the detail and tree views
+fn view_search_bar(state: &SplendirGui) -> Element<'_, Message> {
+    let status = if let Some(ref error) = state.search_error {
+        text(error).size(14).color(iced::Color::from_rgb(0.8, 0.2, 0.2))
+    } else if let Some(ref search) = state.scan_results.search {
+        let files = if search.rows.len() == 1 { "file" } else { "files" };
+        text(format!("{} {} match", search.rows.len(), files)).size(14)
+    } else {
+        text("")
+    };
+    
+    row![
+        text_input("Search names and paths", &state.search_text)
+            .on_input(Message::SearchChanged)
+            .padding(6)
+            .width(Length::FillPortion(3)),
+        pick_list(&SearchMode::ALL[..], Some(state.search_mode), Message::SearchModeSelected),
+        container(status).width(Length::FillPortion(2)),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+/// A name or path with the parts matching the search highlighted
+fn highlighted<'a>(value: &str, search: Option<&SearchResults>) -> Rich<'a, (), Message> {
+    let mut spans: Vec<Span<'a, (), Font>> = Vec::new();
+    let mut end = 0;
+    for found in search.map(|search| search.query.find_matches(value)).unwrap_or_default() {
+        if found.start > end {
+            spans.push(span(value[end..found.start].to_string()));
+        }
+        spans.push(
+            span(value[found.clone()].to_string())
+                .color(iced::Color::BLACK)
+                .background(iced::Color::from_rgb(0.95, 0.8, 0.3))
+        );
+        end = found.end;
+    }
+    if end < value.len() || spans.is_empty() {
+        spans.push(span(value[end..].to_string()));
+    }
+    rich_text(spans).wrapping(Wrapping::None)
+}
+
 // Virtual scrolling for detailed results with conditional sticky header
 fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
-    if state.scan_results.detailed_files.is_empty() {
+    if state.scan_results.detail_rows.is_empty() {
         return text("No files found").into();
     }
     
@@ -1097,22 +2389,29 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
     const VIEWPORT_HEIGHT: f32 = 2000.0; // Larger default to handle maximized windows
     const VISIBLE_ROWS: usize = (VIEWPORT_HEIGHT / ROW_HEIGHT) as usize + 2;
     
-    let total_files = state.scan_results.detailed_files.len();
+    let search = state.scan_results.search.as_ref();
+    let shown_rows = state.scan_results.shown_rows();
+    let total_files = shown_rows.len();
     
-    let file_count_text = text(format!("Total files: {}", total_files)).size(14);
+    let file_count_text = match search {
+        Some(_) => text(format!("Matching files: {} of {}", total_files, state.scan_results.detail_rows.len())).size(14),
+        None => text(format!("Total files: {}", total_files)).size(14),
+    };
     
     // Calculate actual widths based on content when expanded
-    let (filename_width, path_width, fullpath_width, standard_width, size_width, md5_width, sha256_width, sha512_width) = if state.columns_expanded {
-        let max_filename_len = state.scan_results.detailed_files.iter()
-            .map(|f| f.name.chars().count())
+    let (filename_width, path_width, fullpath_width, standard_width, size_width) = if state.columns_expanded {
+        let files = &state.scan_results.files;
+        let max_filename_len = (0..files.len())
+            .map(|row| files.name(row).chars().count())
             .max()
             .unwrap_or(10);
-        let max_path_len = state.scan_results.detailed_files.iter()
-            .map(|f| f.directory_path.chars().count())
+        let max_path_len = (0..files.len())
+            .map(|row| files.directory(row).chars().count())
             .max()
             .unwrap_or(20);
-        let max_fullpath_len = state.scan_results.detailed_files.iter()
-            .map(|f| f.full_path.chars().count())
+        // A full path is the directory, a separator and the name
+        let max_fullpath_len = (0..files.len())
+            .map(|row| files.directory(row).chars().count() + 1 + files.name(row).chars().count())
             .max()
             .unwrap_or(30);
         
@@ -1127,12 +2426,7 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
         let std_width = Length::Fixed(200.0);
         let sz_width = Length::Fixed(100.0);
         
-        // Hash widths: MD5=32 chars, SHA256=64 chars, SHA512=128 chars
-        let md5_w = Length::Fixed(32.0 * char_width + padding);
-        let sha256_w = Length::Fixed(64.0 * char_width + padding);
-        let sha512_w = Length::Fixed(128.0 * char_width + padding);
-        
-        (fn_width, p_width, fp_width, std_width, sz_width, md5_w, sha256_w, sha512_w)
+        (fn_width, p_width, fp_width, std_width, sz_width)
     } else {
         (
             Length::FillPortion(2),
@@ -1140,12 +2434,22 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
             Length::FillPortion(3),
             Length::FillPortion(2),
             Length::FillPortion(1),
-            Length::FillPortion(2),  // md5_width (collapsed)
-            Length::FillPortion(2),  // sha256_width (collapsed)
-            Length::FillPortion(2),  // sha512_width (collapsed)
         )
     };
     
+    // Hash columns are sized to the full digest when expanded (8px per hex character)
+    let hash_width = |algorithm: HashAlgorithm| if state.columns_expanded {
+        Length::Fixed(algorithm.digest_len() as f32 * 8.0 + 10.0)
+    } else {
+        Length::FillPortion(2)
+    };
+    
+    // Only the change time needs a date-sized column
+    let unix_width = |column: UnixColumn| match column {
+        UnixColumn::Changed => standard_width,
+        _ => size_width,
+    };
+    
     // Build header row
     let mut header_row = row![].spacing(10).padding([0, 10]).height(Length::Fixed(30.0)).align_y(Alignment::Center);
     
@@ -1203,22 +2507,16 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
                 .width(standard_width).clip(true)
         );
     }
-    if state.calculate_md5 {
-        header_row = header_row.push(
-            container(text("MD5").size(15).wrapping(Wrapping::None))
-                .width(md5_width).clip(true)
-        );
-    }
-    if state.calculate_sha256 {
+    for &column in &state.unix_columns {
         header_row = header_row.push(
-            container(text("SHA256").size(15).wrapping(Wrapping::None))
-                .width(sha256_width).clip(true)
+            container(text(column.label()).size(15).wrapping(Wrapping::None))
+                .width(unix_width(column)).clip(true)
         );
     }
-    if state.calculate_sha512 {
+    for &algorithm in &state.hash_algorithms {
         header_row = header_row.push(
-            container(text("SHA512").size(15).wrapping(Wrapping::None))
-                .width(sha512_width).clip(true)
+            container(text(algorithm.label()).size(15).wrapping(Wrapping::None))
+                .width(hash_width(algorithm)).clip(true)
         );
     }
     
@@ -1239,16 +2537,19 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
         if state.show_accessed { if let Length::Fixed(w) = standard_width { width += w; } }
         if state.show_format { if let Length::Fixed(w) = standard_width { width += w; } }
         if state.calculate_mime { if let Length::Fixed(w) = standard_width { width += w; } }
-        if state.calculate_md5 { if let Length::Fixed(w) = md5_width { width += w; } }
-        if state.calculate_sha256 { if let Length::Fixed(w) = sha256_width { width += w; } }
-        if state.calculate_sha512 { if let Length::Fixed(w) = sha512_width { width += w; } }
+        for &column in &state.unix_columns {
+            if let Length::Fixed(w) = unix_width(column) { width += w; }
+        }
+        for &algorithm in &state.hash_algorithms {
+            if let Length::Fixed(w) = hash_width(algorithm) { width += w; }
+        }
         
         // Add spacing between columns (10px per gap)
         let visible_columns = [
             state.show_filename, state.show_path, state.show_path_name, state.show_size,
             state.show_created, state.show_modified, state.show_accessed, state.show_format,
-            state.calculate_mime, state.calculate_md5, state.calculate_sha256, state.calculate_sha512
-        ].iter().filter(|&&x| x).count();
+            state.calculate_mime,
+        ].iter().filter(|&&x| x).count() + state.unix_columns.len() + state.hash_algorithms.len();
         width += (visible_columns.saturating_sub(1) * 10) as f32;
         
         // Add padding
@@ -1274,24 +2575,25 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
         
         // Add visible rows
         for i in start_index..end_index {
-            if let Some(file) = state.scan_results.detailed_files.get(i) {
+            if let Some(&row) = shown_rows.get(i) {
+                let file = state.scan_results.files.get(row as usize);
                 let mut data_row = row![].spacing(10).padding([0, 10]).height(ROW_HEIGHT).align_y(Alignment::Center);
                 
                 if state.show_filename {
                     data_row = data_row.push(
-                        container(text(&file.name).size(14).wrapping(Wrapping::None))
+                        container(highlighted(&file.name, search).size(14))
                             .width(filename_width).clip(true)
                     );
                 }
                 if state.show_path {
                     data_row = data_row.push(
-                        container(text(&file.directory_path).size(14).wrapping(Wrapping::None))
+                        container(highlighted(&file.directory_path, search).size(14))
                             .width(path_width).clip(true)
                     );
                 }
                 if state.show_path_name {
                     data_row = data_row.push(
-                        container(text(&file.full_path).size(14).wrapping(Wrapping::None))
+                        container(highlighted(&file.full_path, search).size(14))
                             .width(fullpath_width).clip(true)
                     );
                 }
@@ -1303,50 +2605,44 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
                 }
                 if state.show_created {
                     data_row = data_row.push(
-                        container(text(&file.created).size(14).wrapping(Wrapping::None))
+                        container(text(state.time_format.format(file.created)).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.show_modified {
                     data_row = data_row.push(
-                        container(text(&file.last_modified).size(14).wrapping(Wrapping::None))
+                        container(text(state.time_format.format(file.last_modified)).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.show_accessed {
                     data_row = data_row.push(
-                        container(text(&file.last_accessed).size(14).wrapping(Wrapping::None))
+                        container(text(state.time_format.format(file.last_accessed)).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.show_format {
                     data_row = data_row.push(
-                        container(text(&file.format).size(14).wrapping(Wrapping::None))
+                        container(text(file.format).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.calculate_mime {
                     data_row = data_row.push(
-                        container(text(&file.mime_type).size(14).wrapping(Wrapping::None))
+                        container(text(file.mime_type).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
-                if state.calculate_md5 {
-                    data_row = data_row.push(
-                        container(text(&file.md5).size(14).wrapping(Wrapping::None))
-                            .width(md5_width).clip(true)
-                    );
-                }
-                if state.calculate_sha256 {
+                for &column in &state.unix_columns {
                     data_row = data_row.push(
-                        container(text(&file.sha256).size(14).wrapping(Wrapping::None))
-                            .width(sha256_width).clip(true)
+                        container(text(column.value(file.unix.as_ref(), &state.time_format)).size(14).wrapping(Wrapping::None))
+                            .width(unix_width(column)).clip(true)
                     );
                 }
-                if state.calculate_sha512 {
+                for &algorithm in &state.hash_algorithms {
                     data_row = data_row.push(
-                        container(text(&file.sha512).size(14).wrapping(Wrapping::None))
-                            .width(sha512_width).clip(true)
+                        container(text(file.hashes.get(&algorithm).map_or_else(|| NOT_CALCULATED.to_string(), Digest::to_string)).size(14).wrapping(Wrapping::None))
+                            .width(hash_width(algorithm)).clip(true)
                     );
                 }
                 
@@ -1382,24 +2678,25 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
         
         // Add visible rows
         for i in start_index..end_index {
-            if let Some(file) = state.scan_results.detailed_files.get(i) {
+            if let Some(&row) = shown_rows.get(i) {
+                let file = state.scan_results.files.get(row as usize);
                 let mut data_row = row![].spacing(10).padding([0, 10]).height(ROW_HEIGHT).align_y(Alignment::Center);
                 
                 if state.show_filename {
                     data_row = data_row.push(
-                        container(text(&file.name).size(14).wrapping(Wrapping::None))
+                        container(highlighted(&file.name, search).size(14))
                             .width(filename_width).clip(true)
                     );
                 }
                 if state.show_path {
                     data_row = data_row.push(
-                        container(text(&file.directory_path).size(14).wrapping(Wrapping::None))
+                        container(highlighted(&file.directory_path, search).size(14))
                             .width(path_width).clip(true)
                     );
                 }
                 if state.show_path_name {
                     data_row = data_row.push(
-                        container(text(&file.full_path).size(14).wrapping(Wrapping::None))
+                        container(highlighted(&file.full_path, search).size(14))
                             .width(fullpath_width).clip(true)
                     );
                 }
@@ -1411,65 +2708,49 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
                 }
                 if state.show_created {
                     data_row = data_row.push(
-                        container(text(&file.created).size(14).wrapping(Wrapping::None))
+                        container(text(state.time_format.format(file.created)).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.show_modified {
                     data_row = data_row.push(
-                        container(text(&file.last_modified).size(14).wrapping(Wrapping::None))
+                        container(text(state.time_format.format(file.last_modified)).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.show_accessed {
                     data_row = data_row.push(
-                        container(text(&file.last_accessed).size(14).wrapping(Wrapping::None))
+                        container(text(state.time_format.format(file.last_accessed)).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.show_format {
                     data_row = data_row.push(
-                        container(text(&file.format).size(14).wrapping(Wrapping::None))
+                        container(text(file.format).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
                 if state.calculate_mime {
                     data_row = data_row.push(
-                        container(text(&file.mime_type).size(14).wrapping(Wrapping::None))
+                        container(text(file.mime_type).size(14).wrapping(Wrapping::None))
                             .width(standard_width).clip(true)
                     );
                 }
-                if state.calculate_md5 {
-                    let md5_text = if file.md5.len() > 12 {
-                        format!("{}...", &file.md5[..12])
-                    } else {
-                        file.md5.clone()
-                    };
-                    data_row = data_row.push(
-                        container(text(md5_text).size(14).wrapping(Wrapping::None))
-                            .width(md5_width).clip(true)
-                    );
-                }
-                if state.calculate_sha256 {
-                    let sha256_text = if file.sha256.len() > 12 {
-                        format!("{}...", &file.sha256[..12])
-                    } else {
-                        file.sha256.clone()
-                    };
+                for &column in &state.unix_columns {
                     data_row = data_row.push(
-                        container(text(sha256_text).size(14).wrapping(Wrapping::None))
-                            .width(sha256_width).clip(true)
+                        container(text(column.value(file.unix.as_ref(), &state.time_format)).size(14).wrapping(Wrapping::None))
+                            .width(unix_width(column)).clip(true)
                     );
                 }
-                if state.calculate_sha512 {
-                    let sha512_text = if file.sha512.len() > 12 {
-                        format!("{}...", &file.sha512[..12])
-                    } else {
-                        file.sha512.clone()
+                for &algorithm in &state.hash_algorithms {
+                    let hash_text = match file.hashes.get(&algorithm).map(Digest::to_string) {
+                        Some(digest) if digest.len() > 12 => format!("{}...", &digest[..12]),
+                        Some(digest) => digest,
+                        None => NOT_CALCULATED.to_string(),
                     };
                     data_row = data_row.push(
-                        container(text(sha512_text).size(14).wrapping(Wrapping::None))
-                            .width(sha512_width).clip(true)
+                        container(text(hash_text).size(14).wrapping(Wrapping::None))
+                            .width(hash_width(algorithm)).clip(true)
                     );
                 }
                 
@@ -1501,103 +2782,1280 @@ fn view_detailed_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
     };
     
     column![
-        file_count_text,
-        container(table_view)
+        file_count_text,
+        container(table_view)
+            .height(Length::Fill)
+    ]
+    .spacing(10)
+    .into()
+}
+
+// Virtual scrolling for tree view
+fn view_tree_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
+    if state.tree_flattened_cache.is_empty() {
+        if state.scan_results.analysis.is_none() {
+            return text("No tree data available").into();
+        }
+        if state.scan_results.search.is_some() {
+            return text("Nothing in the tree matches the search").into();
+        }
+        // Should have been populated during scan complete, but just in case
+        return text("Processing tree data...").into();
+    }
+    
+    const ROW_HEIGHT: f32 = 20.0;
+    const VIEWPORT_HEIGHT: f32 = 2000.0; // Larger default to handle maximized windows
+    const VISIBLE_ROWS: usize = (VIEWPORT_HEIGHT / ROW_HEIGHT) as usize + 2;
+    const TOGGLE_WIDTH: f32 = 16.0;
+    
+    let total_nodes = state.tree_flattened_cache.len();
+    
+    // Calculate visible range
+    // Don't constrain scroll_offset since we're using Length::Fill - let it scroll freely
+    let scroll_offset = state.tree_scroll_offset.max(0.0);
+    let start_index = (scroll_offset / ROW_HEIGHT) as usize;
+    let end_index = (start_index + VISIBLE_ROWS).min(total_nodes);
+    
+    // Create virtual viewport
+    let mut viewport = Column::new().spacing(0);
+    
+    // Add spacer for items above viewport
+    if start_index > 0 {
+        viewport = viewport.push(Space::new().width(Length::Fill).height(start_index as f32 * ROW_HEIGHT));
+    }
+    
+    // Add visible nodes
+    let tree_options = tree_format_options(state);
+    for i in start_index..end_index {
+        if let Some(node) = state.tree_flattened_cache.get(i) {
+            let indent = "  ".repeat(node.depth);
+            let prefix = if node.depth == 0 { "" } else if node.is_last { "└─ " } else { "├─ " };
+            
+            let details = tree_options.details(node.size, node.modified, node.unix.as_ref());
+            
+            let node_text = if state.colorize_output && node.is_directory {
+                format!("{}📁 ", details)
+            } else if state.colorize_output {
+                format!("{}📄 ", details)
+            } else {
+                details
+            };
+            
+            // Disclosure triangle for directories below the root that have entries
+            let toggle: Element<'_, Message> = if node.depth > 0 && node.has_children {
+                button(text(if node.expanded { "▼" } else { "▶" }).size(11).font(Font::MONOSPACE))
+                    .padding(0)
+                    .width(TOGGLE_WIDTH)
+                    .style(button::text)
+                    .on_press(Message::TreeNodeToggled(node.path.clone()))
+                    .into()
+            } else {
+                Space::new().width(TOGGLE_WIDTH).into()
+            };
+            
+            let mut node_row = row![
+                text(format!("{}{}", indent, prefix)).size(14).font(Font::MONOSPACE),
+                toggle,
+                text(node_text).size(14).font(Font::MONOSPACE),
+                highlighted(&node.name, state.scan_results.search.as_ref()).size(14).font(Font::MONOSPACE),
+            ]
+            .align_y(Alignment::Center);
+            if node.is_directory {
+                let files = if node.file_count == 1 { "file" } else { "files" };
+                node_row = node_row.push(
+                    text(format!("  {} {}, {}", node.file_count, files, format_size(node.size)))
+                        .size(13)
+                        .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+                );
+            }
+            
+            viewport = viewport.push(container(node_row).height(ROW_HEIGHT));
+        }
+    }
+    
+    // Add spacer for items below viewport
+    let remaining_items = total_nodes.saturating_sub(end_index);
+    if remaining_items > 0 {
+        viewport = viewport.push(Space::new().width(Length::Fill).height(remaining_items as f32 * ROW_HEIGHT));
+    }
+    
+    let controls = row![
+        text(format!("Tree view ({} nodes shown)", total_nodes)).size(14),
+        Space::new().width(Length::Fill),
+        text("Depth:").size(14),
+        text_input("all", &state.tree_expand_depth)
+            .on_input(Message::TreeExpandDepthChanged)
+            .on_submit(Message::TreeExpandToDepth)
+            .width(60)
+            .padding(4),
+        button("Expand to Depth").on_press(Message::TreeExpandToDepth).padding([4, 12]),
+        button("Collapse All").on_press(Message::TreeCollapseAll).padding([4, 12]),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+    
+    column![
+        controls,
+        container(
+            scrollable(viewport)
+                .id(Id::new("tree_results_scroll"))
+                .height(Length::Fill)
+                .on_scroll(|viewport| {
+                    Message::TreeScrolled(viewport.absolute_offset().y)
+                })
+        )
+        .height(Length::Fill)
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn view_analysis_results(state: &SplendirGui) -> Element<'_, Message> {
+    let mut view_buttons = row![].spacing(6);
+    for view in AnalysisView::ALL {
+        view_buttons = view_buttons.push(
+            button(text(view.to_string()).size(14))
+                .style(if view == state.analysis_view { button::primary } else { button::secondary })
+                .on_press(Message::AnalysisViewSelected(view))
+                .padding([4, 12])
+        );
+    }
+    
+    let content: Element<'_, Message> = match state.analysis_view {
+        AnalysisView::Summary if state.scan_results.analysis_output.is_empty() => {
+            text("No analysis data available").into()
+        }
+        AnalysisView::Summary => scrollable(
+            text(&state.scan_results.analysis_output)
+                .size(14)
+        )
+        .height(Length::Fill)
+        .into(),
+        AnalysisView::Charts => view_distribution_charts(state),
+        AnalysisView::Largest => view_rankings(state),
+        AnalysisView::Treemap | AnalysisView::Sunburst => view_usage_chart(state),
+    };
+    
+    column![view_buttons, content].spacing(10).into()
+}
+
+// Treemap or sunburst of the directory drilled down to, with a legend of file type colors
+/// The directory the disk usage charts show after opening `path` in `tree`:
+/// a file opens the directory it is in, and `None` is the scanned directory
+fn usage_root_for(tree: &TreeNode, path: PathBuf) -> Option<PathBuf> {
+    let directory = match find_node(tree, &path) {
+        Some(node) if !node.is_directory => path.parent().map(PathBuf::from),
+        _ => Some(path),
+    };
+    directory.filter(|directory| *directory != tree.path)
+}
+
+/// The directory above `root` in the disk usage charts
+fn usage_parent(tree: &TreeNode, root: &Path) -> Option<PathBuf> {
+    root.parent().filter(|parent| *parent != tree.path).map(PathBuf::from)
+}
+
+fn view_usage_chart(state: &SplendirGui) -> Element<'_, Message> {
+    let Some(ref analysis) = state.scan_results.analysis else {
+        return text("No tree data available").into();
+    };
+    let root = state.usage_root
+        .as_deref()
+        .and_then(|path| find_node(&analysis.tree, path))
+        .unwrap_or(&analysis.tree);
+    
+    let files = if root.file_count == 1 { "file" } else { "files" };
+    let heading = row![
+        button("Up")
+            .on_press_maybe(state.usage_root.is_some().then_some(Message::UsageUp))
+            .padding([4, 12]),
+        text(format!("{} ({} in {} {})", root.path.display(), format_size(root.size), root.file_count, files)).size(14),
+        Space::new().width(Length::Fill),
+        text("Click a directory to open it; right-click to go up")
+            .size(13)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+    
+    let mut legend = row![].spacing(14);
+    for file_type in FileType::ALL {
+        legend = legend.push(
+            row![
+                text("■").size(14).color(file_type_color(file_type)),
+                text(file_type.description()).size(13),
+            ]
+            .spacing(4)
+            .align_y(Alignment::Center)
+        );
+    }
+    
+    column![
+        heading,
+        canvas(UsageChart { root, view: state.analysis_view })
+            .width(Length::Fill)
+            .height(Length::Fill),
+        legend,
+    ]
+    .spacing(10)
+    .into()
+}
+
+// Charts of the files by size range and by type, side by side
+fn view_distribution_charts(state: &SplendirGui) -> Element<'_, Message> {
+    let (Some(sizes), Some(types)) = (&state.scan_results.size_chart, &state.scan_results.type_chart) else {
+        return text("No analysis data available").into();
+    };
+    
+    let mut heading = row![text("Measure:").size(14)].spacing(6).align_y(Alignment::Center);
+    for value in ChartValue::ALL {
+        heading = heading.push(
+            button(text(value.to_string()).size(13))
+                .style(if value == state.chart_value { button::primary } else { button::secondary })
+                .on_press(Message::ChartValueSelected(value))
+                .padding([2, 10])
+        );
+    }
+    heading = heading
+        .push(Space::new().width(Length::Fill))
+        .push(
+            text("Hover over a bar or slice for its file count and size")
+                .size(13)
+                .color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+        );
+    
+    column![
+        heading,
+        row![
+            view_chart_panel(sizes, ChartSubject::Sizes, state.size_chart_kind, state.chart_value),
+            view_chart_panel(types, ChartSubject::Types, state.type_chart_kind, state.chart_value),
+        ]
+        .spacing(20)
+        .height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn view_chart_panel(chart: &Chart, subject: ChartSubject, kind: ChartKind, value: ChartValue) -> Element<'_, Message> {
+    let mut header = row![
+        text(&chart.title).size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
+        Space::new().width(Length::Fill),
+    ]
+    .spacing(6)
+    .align_y(Alignment::Center);
+    for option in ChartKind::ALL {
+        header = header.push(
+            button(text(option.to_string()).size(13))
+                .style(if option == kind { button::primary } else { button::secondary })
+                .on_press(Message::ChartKindSelected(subject, option))
+                .padding([2, 10])
+        );
+    }
+    header = header.push(
+        button(text("Export Chart...").size(13))
+            .on_press(Message::ExportChart(subject))
+            .padding([2, 10])
+    );
+    
+    let body: Element<'_, Message> = if chart.entries.is_empty() {
+        text("No files").into()
+    } else {
+        canvas(DistributionChart { chart, kind, value })
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    };
+    
+    column![header, body]
+        .spacing(10)
+        .width(Length::FillPortion(1))
+        .into()
+}
+
+// Choices for the number of entries in each list of the Largest view
+const TOP_COUNTS: [usize; 4] = [10, 25, 50, 100];
+
+// Largest files and directories, each entry opening the treemap at it
+fn view_rankings(state: &SplendirGui) -> Element<'_, Message> {
+    let (Some(rankings), Some(analysis)) = (&state.scan_results.rankings, &state.scan_results.analysis) else {
+        return text("No analysis data available").into();
+    };
+    
+    let heading = row![
+        text("Show top").size(14),
+        pick_list(&TOP_COUNTS[..], Some(state.top_count), Message::TopCountSelected).text_size(14),
+        Space::new().width(Length::Fill),
+        text("Click an entry to show it in the treemap")
+            .size(13)
+            .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+    
+    let files = |count: usize| if count == 1 { "1 file".to_string() } else { format!("{} files", count) };
+    let lists = column![
+        view_ranking_list("Largest files", &rankings.largest_files, &analysis.path, |entry| {
+            (format_size(entry.size), String::new())
+        }),
+        view_ranking_list("Largest directories", &rankings.largest_directories, &analysis.path, |entry| {
+            (format_size(entry.size), files(entry.file_count))
+        }),
+        view_ranking_list("Most files", &rankings.most_files, &analysis.path, |entry| {
+            (files(entry.file_count), format_size(entry.size))
+        }),
+    ]
+    .spacing(16);
+    
+    column![heading, scrollable(lists).height(Length::Fill)]
+        .spacing(10)
+        .into()
+}
+
+/// A titled, numbered list of ranked entries, with `describe` giving each
+/// one's value and a note shown after its path
+fn view_ranking_list<'a>(
+    title: &'a str,
+    entries: &'a [RankedEntry],
+    root: &std::path::Path,
+    describe: impl Fn(&RankedEntry) -> (String, String),
+) -> Element<'a, Message> {
+    let mut list = column![
+        text(title).size(16).font(Font { weight: iced::font::Weight::Bold, ..Font::default() }).color(iced::Color::from_rgb(0.9, 0.9, 0.9)),
+    ]
+    .spacing(2);
+    if entries.is_empty() {
+        list = list.push(text("None").size(13).color(iced::Color::from_rgb(0.5, 0.5, 0.5)));
+    }
+    for (rank, entry) in entries.iter().enumerate() {
+        let (value, note) = describe(entry);
+        // Paths are shown from the scanned directory, which is shown in full
+        let path = match entry.path.strip_prefix(root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+            _ => entry.path.display().to_string(),
+        };
+        list = list.push(
+            button(
+                row![
+                    text(format!("{}.", rank + 1)).size(13).width(36),
+                    text(value).size(13).width(90),
+                    text(path).size(13).wrapping(Wrapping::None),
+                    text(note).size(13).color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                ]
+                .spacing(8)
+            )
+            .style(button::text)
+            .width(Length::Fill)
+            .padding([2, 6])
+            .on_press(Message::RankedEntrySelected(entry.path.clone()))
+        );
+    }
+    list.into()
+}
+
+// Room for the labels left of the bars and the values right of them, and
+// the tallest a bar chart row or legend line gets
+const CHART_LABEL_WIDTH: f32 = 140.0;
+const CHART_VALUE_WIDTH: f32 = 80.0;
+const CHART_ROW_HEIGHT: f32 = 24.0;
+
+/// Bar or pie chart of files by size range or type, drawn on a canvas
+struct DistributionChart<'a> {
+    chart: &'a Chart,
+    kind: ChartKind,
+    value: ChartValue,
+}
+
+impl DistributionChart<'_> {
+    /// Height of each bar chart row or legend line, shrunk to fit them all
+    fn row_height(&self, size: Size) -> f32 {
+        CHART_ROW_HEIGHT.min(size.height / self.chart.entries.len().max(1) as f32)
+    }
+    
+    fn bars(&self, size: Size) -> Vec<Rect> {
+        let plot = Rect::new(
+            CHART_LABEL_WIDTH + 8.0,
+            0.0,
+            (size.width - CHART_LABEL_WIDTH - CHART_VALUE_WIDTH - 8.0).max(0.0),
+            self.row_height(size) * self.chart.entries.len() as f32,
+        );
+        bar_layout(&self.chart.values(self.value), plot)
+    }
+    
+    /// Center and radius of the pie, which leaves the right half for its legend
+    fn pie(&self, size: Size) -> (Point, f32) {
+        let radius = ((size.width * 0.45).min(size.height) / 2.0 - 4.0).max(0.0);
+        (Point::new(radius + 4.0, radius + 4.0), radius)
+    }
+    
+    /// Index of the entry under the cursor: its bar's row, or its slice or legend line
+    fn entry_at(&self, size: Size, position: Point) -> Option<usize> {
+        let index = (position.y / self.row_height(size)) as usize;
+        match self.kind {
+            ChartKind::Bar => (index < self.chart.entries.len()).then_some(index),
+            ChartKind::Pie => {
+                let (center, radius) = self.pie(size);
+                let (dx, dy) = (position.x - center.x, position.y - center.y);
+                if dx * dx + dy * dy <= radius * radius {
+                    slice_at(&pie_layout(&self.chart.values(self.value)), dx.atan2(-dy))
+                } else {
+                    (position.x >= 2.0 * radius + 24.0 && index < self.chart.entries.len()).then_some(index)
+                }
+            }
+        }
+    }
+    
+    fn draw_bars(&self, frame: &mut canvas::Frame, hovered: Option<usize>) {
+        for (index, (entry, bar)) in self.chart.entries.iter().zip(self.bars(frame.size())).enumerate() {
+            let middle = bar.y + bar.height / 2.0;
+            frame.fill_text(canvas::Text {
+                content: fit_label(&entry.label, CHART_LABEL_WIDTH),
+                position: Point::new(CHART_LABEL_WIDTH, middle),
+                color: iced::Color::from_rgb(0.9, 0.9, 0.9),
+                size: 12.0.into(),
+                align_x: iced::widget::text::Alignment::Right,
+                align_y: iced::alignment::Vertical::Center,
+                ..canvas::Text::default()
+            });
+            let top_left = Point::new(bar.x, bar.y);
+            let size = Size::new(bar.width, bar.height);
+            frame.fill_rectangle(top_left, size, chart_color(entry.color));
+            if hovered == Some(index) {
+                frame.stroke_rectangle(top_left, size, canvas::Stroke::default().with_color(iced::Color::WHITE).with_width(2.0));
+            }
+            frame.fill_text(canvas::Text {
+                content: entry.value_label(self.value),
+                position: Point::new(bar.x + bar.width + 6.0, middle),
+                color: iced::Color::from_rgb(0.7, 0.7, 0.7),
+                size: 12.0.into(),
+                align_y: iced::alignment::Vertical::Center,
+                ..canvas::Text::default()
+            });
+        }
+    }
+    
+    fn draw_pie(&self, frame: &mut canvas::Frame, hovered: Option<usize>) {
+        let (center, radius) = self.pie(frame.size());
+        let values = self.chart.values(self.value);
+        let total: f64 = values.iter().sum();
+        for (index, (entry, (start, end))) in self.chart.entries.iter().zip(pie_layout(&values)).enumerate() {
+            if end <= start {
+                continue;
+            }
+            let path = annular_sector(center, 0.0, radius, start, end);
+            frame.fill(&path, chart_color(entry.color));
+            frame.stroke(&path, canvas::Stroke::default().with_color(iced::Color::from_rgb(0.08, 0.08, 0.1)).with_width(1.0));
+            if hovered == Some(index) {
+                frame.stroke(&path, canvas::Stroke::default().with_color(iced::Color::WHITE).with_width(2.0));
+            }
+        }
+        
+        let row_height = self.row_height(frame.size());
+        let x = 2.0 * radius + 24.0;
+        for (index, entry) in self.chart.entries.iter().enumerate() {
+            let middle = (index as f32 + 0.5) * row_height;
+            frame.fill_rectangle(Point::new(x, middle - 6.0), Size::new(12.0, 12.0), chart_color(entry.color));
+            let share = if total > 0.0 { 100.0 * entry.value(self.value) / total } else { 0.0 };
+            frame.fill_text(canvas::Text {
+                content: fit_label(&format!("{}: {} ({:.1}%)", entry.label, entry.value_label(self.value), share), frame.width() - x - 18.0),
+                position: Point::new(x + 18.0, middle),
+                color: if hovered == Some(index) { iced::Color::WHITE } else { iced::Color::from_rgb(0.8, 0.8, 0.8) },
+                size: 12.0.into(),
+                align_y: iced::alignment::Vertical::Center,
+                ..canvas::Text::default()
+            });
+        }
+    }
+}
+
+impl canvas::Program<Message> for DistributionChart<'_> {
+    type State = ();
+    
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        match event {
+            // Redraw so the entry under the cursor is outlined and described,
+            // and the tooltip goes once the cursor leaves
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) if cursor.is_over(bounds) => Some(canvas::Action::request_redraw()),
+            canvas::Event::Mouse(mouse::Event::CursorLeft) => Some(canvas::Action::request_redraw()),
+            _ => None,
+        }
+    }
+    
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let position = cursor.position_in(bounds);
+        let hovered = position.and_then(|position| self.entry_at(bounds.size(), position));
+        
+        match self.kind {
+            ChartKind::Bar => self.draw_bars(&mut frame, hovered),
+            ChartKind::Pie => self.draw_pie(&mut frame, hovered),
+        }
+        
+        if let (Some(index), Some(position)) = (hovered, position) {
+            draw_tooltip(&mut frame, self.chart.describe(index), position);
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+fn chart_color([red, green, blue]: [u8; 3]) -> iced::Color {
+    iced::Color::from_rgb8(red, green, blue)
+}
+
+// Directories below this many levels are not drawn in the treemap
+const TREEMAP_DEPTH: usize = 8;
+// Rings drawn in the sunburst, and the narrowest slice worth drawing (radians)
+const SUNBURST_RINGS: usize = 6;
+const SUNBURST_MIN_ANGLE: f32 = 0.005;
+
+/// Disk usage chart of one directory, drawn on a canvas: entries are sized
+/// by their total size and colored by file type
+struct UsageChart<'a> {
+    root: &'a TreeNode,
+    view: AnalysisView,
+}
+
+impl UsageChart<'_> {
+    /// The entry under the cursor, and the directory a click there opens
+    fn entry_at(&self, size: Size, position: Point) -> Option<(&TreeNode, Option<&TreeNode>)> {
+        match self.view {
+            AnalysisView::Treemap => {
+                let tiles = treemap(self.root, Rect::new(0.0, 0.0, size.width, size.height), TREEMAP_DEPTH);
+                let entry = tile_at(&tiles, position.x, position.y)?.node;
+                // A click on a file opens the directory around it
+                let directory = tiles.iter().rev()
+                    .find(|tile| tile.node.is_directory && tile.rect.contains(position.x, position.y))
+                    .map(|tile| tile.node);
+                Some((entry, directory))
+            }
+            AnalysisView::Sunburst => {
+                let (ring, angle) = sunburst_position(size, position)?;
+                if ring == 0 {
+                    return None;
+                }
+                let segments = sunburst(self.root, SUNBURST_RINGS, SUNBURST_MIN_ANGLE);
+                let entry = segment_at(&segments, ring, angle)?.node;
+                let directory = (1..=ring).rev()
+                    .filter_map(|depth| segment_at(&segments, depth, angle))
+                    .find(|segment| segment.node.is_directory)
+                    .map(|segment| segment.node);
+                Some((entry, directory))
+            }
+            AnalysisView::Summary | AnalysisView::Charts | AnalysisView::Largest => None,
+        }
+    }
+    
+    fn draw_treemap(&self, frame: &mut canvas::Frame, hovered: Option<&TreeNode>) {
+        let bounds = Rect::new(0.0, 0.0, frame.width(), frame.height());
+        for tile in treemap(self.root, bounds, TREEMAP_DEPTH) {
+            let top_left = Point::new(tile.rect.x, tile.rect.y);
+            let size = Size::new(tile.rect.width, tile.rect.height);
+            let color = if tile.node.is_directory {
+                // Nested directories get lighter, so their frames stand apart
+                let shade = 0.16 + 0.05 * tile.depth.min(8) as f32;
+                iced::Color::from_rgb(shade, shade + 0.02, shade + 0.08)
+            } else {
+                entry_color(tile.node)
+            };
+            frame.fill_rectangle(top_left, size, color);
+            if tile.rect.width >= 3.0 && tile.rect.height >= 3.0 {
+                frame.stroke_rectangle(top_left, size, canvas::Stroke::default().with_color(iced::Color::from_rgb(0.08, 0.08, 0.1)).with_width(1.0));
+            }
+            if hovered.is_some_and(|node| std::ptr::eq(node, tile.node)) {
+                frame.stroke_rectangle(top_left, size, canvas::Stroke::default().with_color(iced::Color::WHITE).with_width(2.0));
+            }
+            // Label tiles with room for a few characters
+            if tile.rect.width >= 40.0 && tile.rect.height >= 16.0 {
+                frame.fill_text(canvas::Text {
+                    content: fit_label(&tile.node.name, tile.rect.width - 6.0),
+                    position: Point::new(tile.rect.x + 3.0, tile.rect.y + 2.0),
+                    color: iced::Color::from_rgb(0.95, 0.95, 0.95),
+                    size: 12.0.into(),
+                    ..canvas::Text::default()
+                });
+            }
+        }
+    }
+    
+    fn draw_sunburst(&self, frame: &mut canvas::Frame, hovered: Option<&TreeNode>) {
+        let center = frame.center();
+        let ring_width = sunburst_ring_width(frame.size());
+        
+        // The directory being viewed sits in the middle
+        frame.fill(&canvas::Path::circle(center, ring_width), iced::Color::from_rgb(0.25, 0.27, 0.33));
+        frame.fill_text(canvas::Text {
+            content: fit_label(&self.root.name, ring_width * 1.8),
+            position: center,
+            color: iced::Color::from_rgb(0.95, 0.95, 0.95),
+            size: 12.0.into(),
+            align_x: iced::widget::text::Alignment::Center,
+            align_y: iced::alignment::Vertical::Center,
+            ..canvas::Text::default()
+        });
+        
+        for segment in sunburst(self.root, SUNBURST_RINGS, SUNBURST_MIN_ANGLE) {
+            let inner = ring_width * segment.depth as f32;
+            let outer = inner + ring_width;
+            let path = annular_sector(center, inner, outer, segment.start, segment.end);
+            let color = if segment.node.is_directory {
+                // Directories take the color of their ring, lightening outwards
+                let shade = 0.22 + 0.06 * segment.depth as f32;
+                iced::Color::from_rgb(shade * 0.8, shade * 0.9, shade + 0.15)
+            } else {
+                entry_color(segment.node)
+            };
+            frame.fill(&path, color);
+            frame.stroke(&path, canvas::Stroke::default().with_color(iced::Color::from_rgb(0.08, 0.08, 0.1)).with_width(1.0));
+            if hovered.is_some_and(|node| std::ptr::eq(node, segment.node)) {
+                frame.stroke(&path, canvas::Stroke::default().with_color(iced::Color::WHITE).with_width(2.0));
+            }
+        }
+    }
+}
+
+impl canvas::Program<Message> for UsageChart<'_> {
+    type State = ();
+    
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        let position = cursor.position_in(bounds)?;
+        match event {
+            // Redraw so the entry under the cursor is outlined and described
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => Some(canvas::Action::request_redraw()),
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                // The middle of the sunburst is the directory being viewed; clicking it goes up
+                if self.view == AnalysisView::Sunburst
+                    && sunburst_position(bounds.size(), position).is_some_and(|(ring, _)| ring == 0)
+                {
+                    return Some(canvas::Action::publish(Message::UsageUp).and_capture());
+                }
+                let (_, directory) = self.entry_at(bounds.size(), position)?;
+                Some(canvas::Action::publish(Message::UsageDrillDown(directory?.path.clone())).and_capture())
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                Some(canvas::Action::publish(Message::UsageUp).and_capture())
+            }
+            _ => None,
+        }
+    }
+    
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let position = cursor.position_in(bounds);
+        let hovered = position.and_then(|position| self.entry_at(bounds.size(), position)).map(|(entry, _)| entry);
+        
+        match self.view {
+            AnalysisView::Treemap => self.draw_treemap(&mut frame, hovered),
+            AnalysisView::Sunburst => self.draw_sunburst(&mut frame, hovered),
+            AnalysisView::Summary | AnalysisView::Charts | AnalysisView::Largest => {}
+        }
+        
+        if let (Some(node), Some(position)) = (hovered, position) {
+            draw_usage_tooltip(&mut frame, node, position);
+        }
+        vec![frame.into_geometry()]
+    }
+    
+    fn mouse_interaction(&self, _state: &Self::State, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+/// Name, size and file count of the entry under the cursor, in a box beside it
+fn draw_usage_tooltip(frame: &mut canvas::Frame, node: &TreeNode, position: Point) {
+    let mut label = format!("{}\n{}", node.name, format_size(node.size));
+    if node.is_directory {
+        let files = if node.file_count == 1 { "file" } else { "files" };
+        label.push_str(&format!(" in {} {}", node.file_count, files));
+    }
+    draw_tooltip(frame, label, position);
+}
+
+/// A few lines of text in a box beside the cursor
+fn draw_tooltip(frame: &mut canvas::Frame, label: String, position: Point) {
+    let width = label.lines().map(|line| line.chars().count()).max().unwrap_or(0) as f32 * 7.5 + 12.0;
+    let height = label.lines().count() as f32 * 17.0 + 4.0;
+    // Keep the box inside the chart
+    let x = if position.x + 14.0 + width > frame.width() { position.x - width - 8.0 } else { position.x + 14.0 };
+    let y = (position.y + 14.0).min(frame.height() - height).max(0.0);
+    frame.fill_rectangle(Point::new(x, y), Size::new(width, height), iced::Color::from_rgba(0.05, 0.05, 0.07, 0.92));
+    frame.fill_text(canvas::Text {
+        content: label,
+        position: Point::new(x + 6.0, y + 4.0),
+        color: iced::Color::WHITE,
+        size: 13.0.into(),
+        ..canvas::Text::default()
+    });
+}
+
+/// Width of each sunburst ring, leaving room for the center and every ring
+fn sunburst_ring_width(size: Size) -> f32 {
+    size.width.min(size.height) / 2.0 / (SUNBURST_RINGS as f32 + 1.0)
+}
+
+/// Ring (0 for the center) and angle clockwise from the top of a point in the sunburst
+fn sunburst_position(size: Size, position: Point) -> Option<(usize, f32)> {
+    let (dx, dy) = (position.x - size.width / 2.0, position.y - size.height / 2.0);
+    let ring = ((dx * dx + dy * dy).sqrt() / sunburst_ring_width(size)) as usize;
+    (ring <= SUNBURST_RINGS).then(|| (ring, dx.atan2(-dy)))
+}
+
+/// Outline of a slice of a ring, between two angles clockwise from the top
+fn annular_sector(center: Point, inner: f32, outer: f32, start: f32, end: f32) -> canvas::Path {
+    let point = |radius: f32, angle: f32| Point::new(center.x + radius * angle.sin(), center.y - radius * angle.cos());
+    // Enough straight pieces that the outer edge looks round
+    let steps = ((end - start) * outer / 4.0).ceil().max(1.0) as usize;
+    canvas::Path::new(|builder| {
+        builder.move_to(point(inner, start));
+        for step in 0..=steps {
+            builder.line_to(point(outer, start + (end - start) * step as f32 / steps as f32));
+        }
+        for step in (0..=steps).rev() {
+            builder.line_to(point(inner, start + (end - start) * step as f32 / steps as f32));
+        }
+        builder.close();
+    })
+}
+
+/// Shorten a name to fit a width, at about 7 pixels a character
+fn fit_label(name: &str, width: f32) -> String {
+    let room = (width / 7.0) as usize;
+    if name.chars().count() <= room {
+        name.to_string()
+    } else if room > 1 {
+        format!("{}…", name.chars().take(room - 1).collect::<String>())
+    } else {
+        String::new()
+    }
+}
+
+fn entry_color(node: &TreeNode) -> iced::Color {
+    let (_, file_type) = get_file_color(&node.path, &node.name, node.is_directory);
+    file_type_color(file_type)
+}
+
+/// Chart colors for each file type, following the colors of the terminal tree
+fn file_type_color(file_type: FileType) -> iced::Color {
+    chart_color(file_type.chart_color())
+}
+
+// Virtual scrolling for the list of paths that could not be scanned
+fn view_problems_results(state: &SplendirGui) -> Element<'_, Message> {
+    let issues = &state.scan_results.report.issues;
+    if issues.is_empty() {
+        return text("No problems encountered during the scan").into();
+    }
+    
+    const ROW_HEIGHT: f32 = 25.0;
+    const VIEWPORT_HEIGHT: f32 = 2000.0; // Larger default to handle maximized windows
+    const VISIBLE_ROWS: usize = (VIEWPORT_HEIGHT / ROW_HEIGHT) as usize + 2;
+    
+    let total_issues = issues.len();
+    
+    let header_row = row![
+        container(text("Path").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(5)).clip(true),
+        container(text("Phase").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(1)).clip(true),
+        container(text("Error").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(4)).clip(true),
+    ]
+    .spacing(10)
+    .padding([0, 10])
+    .height(Length::Fixed(30.0))
+    .align_y(Alignment::Center);
+    
+    let scroll_offset = state.problems_scroll_offset.max(0.0);
+    let start_index = (scroll_offset / ROW_HEIGHT) as usize;
+    let end_index = (start_index + VISIBLE_ROWS).min(total_issues);
+    
+    let mut body_rows = Column::new().spacing(0);
+    
+    // Add spacer for items above viewport
+    if start_index > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(start_index as f32 * ROW_HEIGHT));
+    }
+    
+    for issue in &issues[start_index..end_index] {
+        body_rows = body_rows.push(
+            row![
+                container(text(issue.path.to_string_lossy().to_string()).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(5)).clip(true),
+                container(text(issue.phase.name()).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(1)).clip(true),
+                container(text(&issue.message).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(4)).clip(true),
+            ]
+            .spacing(10)
+            .padding([0, 10])
+            .height(ROW_HEIGHT)
+            .align_y(Alignment::Center)
+        );
+    }
+    
+    // Add spacer for items below viewport
+    let remaining_items = total_issues.saturating_sub(end_index);
+    if remaining_items > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(remaining_items as f32 * ROW_HEIGHT));
+    }
+    
+    column![
+        text(state.scan_results.report.summary()).size(14),
+        header_row,
+        scrollable(body_rows)
+            .height(Length::Fill)
+            .on_scroll(|viewport| {
+                Message::ProblemsScrolled(viewport.absolute_offset().y)
+            }),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// Virtual scrolling for the differences between the baseline and the current scan
+fn view_compare_results(state: &SplendirGui) -> Element<'_, Message> {
+    let load_button = button("Load Baseline Scan...")
+        .on_press(Message::LoadBaselinePressed)
+        .padding([8, 16]);
+    
+    let (heading, comparison) = match (&state.baseline_path, &state.scan_results.comparison) {
+        (Some(baseline_path), Some(comparison)) => {
+            let heading = row![
+                text(format!("Baseline: {}", baseline_path.display())).size(14),
+                Space::new().width(Length::Fill),
+                load_button,
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center);
+            (heading, comparison)
+        }
+        _ => {
+            return column![
+                text("Load a scan exported as JSON or JSON Lines from the Detailed File List to compare it with the current results.")
+                    .size(14)
+                    .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                load_button,
+            ]
+            .spacing(10)
+            .into();
+        }
+    };
+    let changes = &comparison.changes;
+    
+    const ROW_HEIGHT: f32 = 25.0;
+    const VIEWPORT_HEIGHT: f32 = 2000.0; // Larger default to handle maximized windows
+    const VISIBLE_ROWS: usize = (VIEWPORT_HEIGHT / ROW_HEIGHT) as usize + 2;
+    
+    let total_changes = changes.len();
+    
+    let header_row = row![
+        container(text("Change").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(1)).clip(true),
+        container(text("Path").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(5)).clip(true),
+        container(text("Previous Path").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(4)).clip(true),
+        container(text("Details").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(2)).clip(true),
+    ]
+    .spacing(10)
+    .padding([0, 10])
+    .height(Length::Fixed(30.0))
+    .align_y(Alignment::Center);
+    
+    let scroll_offset = state.compare_scroll_offset.max(0.0);
+    let start_index = (scroll_offset / ROW_HEIGHT) as usize;
+    let end_index = (start_index + VISIBLE_ROWS).min(total_changes);
+    
+    let mut body_rows = Column::new().spacing(0);
+    
+    // Add spacer for items above viewport
+    if start_index > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(start_index as f32 * ROW_HEIGHT));
+    }
+    
+    for change in &changes[start_index..end_index] {
+        let color = match change.kind {
+            ChangeKind::Added => iced::Color::from_rgb(0.4, 0.8, 0.4),
+            ChangeKind::Deleted => iced::Color::from_rgb(0.9, 0.4, 0.4),
+            ChangeKind::Modified => iced::Color::from_rgb(0.9, 0.8, 0.4),
+            ChangeKind::Moved => iced::Color::from_rgb(0.5, 0.7, 1.0),
+        };
+        body_rows = body_rows.push(
+            row![
+                container(text(change.kind.name()).size(14).color(color).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(1)).clip(true),
+                container(text(&change.path).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(5)).clip(true),
+                container(text(change.previous_path.as_deref().unwrap_or("")).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(4)).clip(true),
+                container(text(&change.details).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(2)).clip(true),
+            ]
+            .spacing(10)
+            .padding([0, 10])
+            .height(ROW_HEIGHT)
+            .align_y(Alignment::Center)
+        );
+    }
+    
+    // Add spacer for items below viewport
+    let remaining_items = total_changes.saturating_sub(end_index);
+    if remaining_items > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(remaining_items as f32 * ROW_HEIGHT));
+    }
+    
+    column![
+        heading,
+        text(comparison.summary()).size(14),
+        header_row,
+        scrollable(body_rows)
+            .height(Length::Fill)
+            .on_scroll(|viewport| {
+                Message::CompareScrolled(viewport.absolute_offset().y)
+            }),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// Virtual scrolling for duplicate sets, each followed by the paths of its copies
+fn view_duplicate_results(state: &SplendirGui) -> Element<'_, Message> {
+    let find_button = button("Find Duplicates")
+        .on_press_maybe(if state.is_scanning { None } else { Some(Message::FindDuplicates) })
+        .padding([8, 16]);
+    let controls = row![
+        checkbox(state.duplicate_partial_hash)
+            .label("Compare first and last blocks before full hash")
+            .on_toggle(Message::DuplicatePartialHashToggled),
+        Space::new().width(Length::Fill),
+        find_button,
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+    
+    let duplicates = match state.scan_results.duplicates {
+        Some(ref duplicates) => duplicates,
+        None => {
+            return column![
+                controls,
+                text("Click 'Find Duplicates' to group files with identical contents. Only files that share a size are read.")
+                    .size(14)
+                    .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+            ]
+            .spacing(10)
+            .into();
+        }
+    };
+    
+    if duplicates.sets.is_empty() {
+        return column![controls, text("No duplicate files found").size(14)].spacing(10).into();
+    }
+    
+    const ROW_HEIGHT: f32 = 25.0;
+    const VIEWPORT_HEIGHT: f32 = 2000.0; // Larger default to handle maximized windows
+    const VISIBLE_ROWS: usize = (VIEWPORT_HEIGHT / ROW_HEIGHT) as usize + 2;
+    
+    let total_rows = state.duplicate_rows.len();
+    
+    let scroll_offset = state.duplicates_scroll_offset.max(0.0);
+    let start_index = (scroll_offset / ROW_HEIGHT) as usize;
+    let end_index = (start_index + VISIBLE_ROWS).min(total_rows);
+    
+    let mut body_rows = Column::new().spacing(0);
+    
+    // Add spacer for items above viewport
+    if start_index > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(start_index as f32 * ROW_HEIGHT));
+    }
+    
+    for &(set_index, file_index) in &state.duplicate_rows[start_index..end_index] {
+        let set = &duplicates.sets[set_index];
+        let content: Element<'_, Message> = match file_index {
+            None => text(format!(
+                "Set {}: {} copies of {} ({} wasted)",
+                set_index + 1,
+                set.paths.len(),
+                format_size(set.size),
+                format_size(set.wasted_bytes()),
+            ))
+            .size(14)
+            .font(Font { weight: iced::font::Weight::Bold, ..Font::default() })
+            .color(iced::Color::from_rgb(0.9, 0.8, 0.4))
+            .wrapping(Wrapping::None)
+            .into(),
+            Some(file_index) => text(format!("    {}", set.paths[file_index].display()))
+                .size(14)
+                .wrapping(Wrapping::None)
+                .into(),
+        };
+        body_rows = body_rows.push(
+            container(content)
+                .width(Length::Fill)
+                .height(ROW_HEIGHT)
+                .padding([0, 10])
+                .align_y(Alignment::Center)
+                .clip(true)
+        );
+    }
+    
+    // Add spacer for items below viewport
+    let remaining_items = total_rows.saturating_sub(end_index);
+    if remaining_items > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(remaining_items as f32 * ROW_HEIGHT));
+    }
+    
+    column![
+        controls,
+        text(duplicates.summary()).size(14),
+        scrollable(body_rows)
+            .height(Length::Fill)
+            .on_scroll(|viewport| {
+                Message::DuplicatesScrolled(viewport.absolute_offset().y)
+            }),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// Virtual scrolling for clusters of similar files, each followed by its members
+fn view_similar_results(state: &SplendirGui) -> Element<'_, Message> {
+    let find_button = button("Find Similar Files")
+        .on_press_maybe(if state.is_scanning { None } else { Some(Message::FindSimilar) })
+        .padding([8, 16]);
+    let threshold_label = if state.similar_algorithm == HashAlgorithm::Tlsh { "Max distance:" } else { "Min score:" };
+    let controls = row![
+        text("Hash:"),
+        pick_list(&HashAlgorithm::FUZZY[..], Some(state.similar_algorithm), Message::SimilarAlgorithmSelected),
+        text(threshold_label),
+        text_input("", &state.similar_threshold)
+            .on_input(Message::SimilarThresholdChanged)
+            .width(Length::Fixed(75.0))
+            .padding(8),
+        Space::new().width(Length::Fill),
+        find_button,
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+    
+    let similar = match state.scan_results.similar {
+        Some(ref similar) => similar,
+        None => {
+            return column![
+                controls,
+                text("Click 'Find Similar Files' to cluster files by fuzzy hash. ssdeep scores run from 0 to 100; TLSH distances start at 0 for identical files.")
+                    .size(14)
+                    .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+            ]
+            .spacing(10)
+            .into();
+        }
+    };
+    
+    if similar.clusters.is_empty() {
+        return column![controls, text("No similar files found").size(14)].spacing(10).into();
+    }
+    
+    const ROW_HEIGHT: f32 = 25.0;
+    const VIEWPORT_HEIGHT: f32 = 2000.0; // Larger default to handle maximized windows
+    const VISIBLE_ROWS: usize = (VIEWPORT_HEIGHT / ROW_HEIGHT) as usize + 2;
+    
+    let total_rows = state.similar_rows.len();
+    
+    let scroll_offset = state.similar_scroll_offset.max(0.0);
+    let start_index = (scroll_offset / ROW_HEIGHT) as usize;
+    let end_index = (start_index + VISIBLE_ROWS).min(total_rows);
+    
+    let mut body_rows = Column::new().spacing(0);
+    
+    // Add spacer for items above viewport
+    if start_index > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(start_index as f32 * ROW_HEIGHT));
+    }
+    
+    let measure = if similar.algorithm == HashAlgorithm::Tlsh { "distance" } else { "score" };
+    for &(cluster_index, file_index) in &state.similar_rows[start_index..end_index] {
+        let cluster = &similar.clusters[cluster_index];
+        let content: Element<'_, Message> = match file_index {
+            None => text(format!("Cluster {}: {} similar files", cluster_index + 1, cluster.files.len()))
+                .size(14)
+                .font(Font { weight: iced::font::Weight::Bold, ..Font::default() })
+                .color(iced::Color::from_rgb(0.9, 0.8, 0.4))
+                .wrapping(Wrapping::None)
+                .into(),
+            Some(file_index) => {
+                let file = &cluster.files[file_index];
+                text(format!(
+                    "    {} {:>3}   {:>10}   {}",
+                    measure,
+                    file.best_match,
+                    format_size(file.size),
+                    file.path.display(),
+                ))
+                .size(14)
+                .wrapping(Wrapping::None)
+                .into()
+            }
+        };
+        body_rows = body_rows.push(
+            container(content)
+                .width(Length::Fill)
+                .height(ROW_HEIGHT)
+                .padding([0, 10])
+                .align_y(Alignment::Center)
+                .clip(true)
+        );
+    }
+    
+    // Add spacer for items below viewport
+    let remaining_items = total_rows.saturating_sub(end_index);
+    if remaining_items > 0 {
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(remaining_items as f32 * ROW_HEIGHT));
+    }
+    
+    column![
+        controls,
+        text(similar.summary()).size(14),
+        scrollable(body_rows)
             .height(Length::Fill)
+            .on_scroll(|viewport| {
+                Message::SimilarScrolled(viewport.absolute_offset().y)
+            }),
     ]
     .spacing(10)
     .into()
 }
 
-// Virtual scrolling for tree view
-fn view_tree_results_virtual(state: &SplendirGui) -> Element<'_, Message> {
-    if state.tree_flattened_cache.is_empty() {
-        if state.scan_results.tree_node.is_none() {
-            return text("No tree data available").into();
+// Virtual scrolling for manifest verification results
+fn view_verify_results(state: &SplendirGui) -> Element<'_, Message> {
+    let verify_button = button("Verify Manifest...")
+        .on_press_maybe(if state.is_scanning { None } else { Some(Message::VerifyManifestPressed) })
+        .padding([8, 16]);
+    let manifest_label = match state.scan_results.manifest_path {
+        Some(ref path) => format!("Manifest: {}", path.display()),
+        None => "No manifest loaded".to_string(),
+    };
+    let controls = row![
+        text(manifest_label).size(14).wrapping(Wrapping::None),
+        Space::new().width(Length::Fill),
+        verify_button,
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+    
+    let verification = match state.scan_results.verification {
+        Some(ref verification) => verification,
+        None => {
+            return column![
+                controls,
+                text("Select a sha256sum/md5sum file or a BagIt manifest-<alg>.txt to rehash the files it lists. \
+                    Paths are resolved relative to the manifest's directory.")
+                    .size(14)
+                    .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+            ]
+            .spacing(10)
+            .into();
         }
-        // Should have been populated during scan complete, but just in case
-        return text("Processing tree data...").into();
-    }
+    };
+    let results = &verification.results;
     
-    const ROW_HEIGHT: f32 = 20.0;
+    const ROW_HEIGHT: f32 = 25.0;
     const VIEWPORT_HEIGHT: f32 = 2000.0; // Larger default to handle maximized windows
     const VISIBLE_ROWS: usize = (VIEWPORT_HEIGHT / ROW_HEIGHT) as usize + 2;
     
-    let total_nodes = state.tree_flattened_cache.len();
+    let total_results = results.len();
     
-    // Calculate visible range
-    // Don't constrain scroll_offset since we're using Length::Fill - let it scroll freely
-    let scroll_offset = state.tree_scroll_offset.max(0.0);
+    let header_row = row![
+        container(text("Status").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(1)).clip(true),
+        container(text("Path").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(5)).clip(true),
+        container(text("Details").size(15).wrapping(Wrapping::None)).width(Length::FillPortion(4)).clip(true),
+    ]
+    .spacing(10)
+    .padding([0, 10])
+    .height(Length::Fixed(30.0))
+    .align_y(Alignment::Center);
+    
+    let scroll_offset = state.verify_scroll_offset.max(0.0);
     let start_index = (scroll_offset / ROW_HEIGHT) as usize;
-    let end_index = (start_index + VISIBLE_ROWS).min(total_nodes);
+    let end_index = (start_index + VISIBLE_ROWS).min(total_results);
     
-    // Create virtual viewport
-    let mut viewport = Column::new().spacing(0);
+    let mut body_rows = Column::new().spacing(0);
     
     // Add spacer for items above viewport
     if start_index > 0 {
-        viewport = viewport.push(Space::new().width(Length::Fill).height(start_index as f32 * ROW_HEIGHT));
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(start_index as f32 * ROW_HEIGHT));
     }
     
-    // Add visible nodes
-    for i in start_index..end_index {
-        if let Some(node) = state.tree_flattened_cache.get(i) {
-            let indent = "  ".repeat(node.depth);
-            let prefix = if node.is_last { "└─ " } else { "├─ " };
-            
-            let node_text = if state.colorize_output && node.is_directory {
-                format!("{}{}📁 {}", indent, prefix, node.name)
-            } else if state.colorize_output {
-                format!("{}{}📄 {}", indent, prefix, node.name)
-            } else {
-                format!("{}{}{}", indent, prefix, node.name)
-            };
-            
-            viewport = viewport.push(
-                container(
-                    text(node_text)
-                        .size(14)
-                        .font(Font::MONOSPACE)
-                )
-                .height(ROW_HEIGHT)
-            );
-        }
+    for result in &results[start_index..end_index] {
+        let color = match result.status {
+            VerifyStatus::Ok => iced::Color::from_rgb(0.4, 0.8, 0.4),
+            VerifyStatus::Failed => iced::Color::from_rgb(0.9, 0.4, 0.4),
+            VerifyStatus::Missing => iced::Color::from_rgb(0.9, 0.8, 0.4),
+            VerifyStatus::Extra => iced::Color::from_rgb(0.5, 0.7, 1.0),
+        };
+        let details = match (&result.error, &result.actual) {
+            (Some(error), _) => error.clone(),
+            (None, Some(actual)) if result.status == VerifyStatus::Failed => format!("computed {}", actual),
+            _ => String::new(),
+        };
+        body_rows = body_rows.push(
+            row![
+                container(text(result.status.name()).size(14).color(color).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(1)).clip(true),
+                container(text(result.path.to_string_lossy()).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(5)).clip(true),
+                container(text(details).size(14).wrapping(Wrapping::None))
+                    .width(Length::FillPortion(4)).clip(true),
+            ]
+            .spacing(10)
+            .padding([0, 10])
+            .height(ROW_HEIGHT)
+            .align_y(Alignment::Center)
+        );
     }
     
     // Add spacer for items below viewport
-    let remaining_items = total_nodes.saturating_sub(end_index);
+    let remaining_items = total_results.saturating_sub(end_index);
     if remaining_items > 0 {
-        viewport = viewport.push(Space::new().width(Length::Fill).height(remaining_items as f32 * ROW_HEIGHT));
+        body_rows = body_rows.push(Space::new().width(Length::Fill).height(remaining_items as f32 * ROW_HEIGHT));
     }
     
     column![
-        text(format!("Tree view ({} nodes)", total_nodes)).size(14),
-        container(
-            scrollable(viewport)
-                .height(Length::Fill)
-                .on_scroll(|viewport| {
-                    Message::TreeScrolled(viewport.absolute_offset().y)
-                })
-        )
-        .height(Length::Fill)
+        controls,
+        text(verification.summary()).size(14),
+        header_row,
+        scrollable(body_rows)
+            .height(Length::Fill)
+            .on_scroll(|viewport| {
+                Message::VerifyScrolled(viewport.absolute_offset().y)
+            }),
     ]
     .spacing(10)
     .into()
 }
 
-fn view_analysis_results(state: &SplendirGui) -> Element<'_, Message> {
-    if state.scan_results.analysis_output.is_empty() {
-        return text("No analysis data available").into();
-    }
-    
-    scrollable(
-        text(&state.scan_results.analysis_output)
-            .size(14)
-    )
-    .height(Length::Fill)
-    .into()
-}
-
 fn view_about_dialog() -> Element<'static, Message> {
     let about_text = format!(
         "Splendir v{}\n\n\
@@ -1666,124 +4124,159 @@ fn view_about_dialog() -> Element<'static, Message> {
 }
 
 // Flatten tree structure for efficient rendering
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 struct FlatTreeNode {
     name: String,
+    path: PathBuf,
     depth: usize,
     is_directory: bool,
     is_last: bool,
+    /// Whether the directory has entries to show or hide
+    has_children: bool,
+    expanded: bool,
+    size: u64,
+    file_count: usize,
+    modified: Option<DateTime<Utc>>,
+    unix: Option<UnixMetadata>,
 }
 
-fn flatten_tree(node: &TreeNode, depth: usize) -> Vec<FlatTreeNode> {
-    let mut result = Vec::new();
-    
-    // Add current node
-    result.push(FlatTreeNode {
-        name: node.name.clone(),
-        depth,
-        is_directory: node.is_directory,
-        is_last: false,
-    });
+impl FlatTreeNode {
+    fn new(node: &TreeNode, depth: usize, is_last: bool, expanded: bool) -> Self {
+        FlatTreeNode {
+            name: node.name.clone(),
+            path: node.path.clone(),
+            depth,
+            is_directory: node.is_directory,
+            is_last,
+            has_children: !node.children.is_empty(),
+            expanded,
+            size: node.size,
+            file_count: node.file_count,
+            modified: node.modified,
+            unix: node.unix,
+        }
+    }
+}
+
+/// Flatten the rows currently visible in the tree view: the root and its
+/// entries, and the entries of every expanded directory below it
+fn flatten_tree(node: &TreeNode, expanded: &HashSet<PathBuf>) -> Vec<FlatTreeNode> {
+    let mut result = vec![FlatTreeNode::new(node, 0, false, true)];
     
     // Add children
     for (i, child) in node.children.iter().enumerate() {
         let is_last = i == node.children.len() - 1;
-        let mut child_nodes = flatten_tree_recursive(child, depth + 1, is_last);
-        result.append(&mut child_nodes);
+        flatten_tree_recursive(child, 1, is_last, expanded, &mut result);
     }
     
     result
 }
 
-fn flatten_tree_recursive(node: &TreeNode, depth: usize, is_last: bool) -> Vec<FlatTreeNode> {
-    let mut result = Vec::new();
-    
-    // Add current node
-    result.push(FlatTreeNode {
-        name: node.name.clone(),
-        depth,
-        is_directory: node.is_directory,
-        is_last,
-    });
+fn flatten_tree_recursive(node: &TreeNode, depth: usize, is_last: bool, expanded: &HashSet<PathBuf>, result: &mut Vec<FlatTreeNode>) {
+    let is_expanded = node.is_directory && expanded.contains(&node.path);
+    result.push(FlatTreeNode::new(node, depth, is_last, is_expanded));
     
-    // Add children
-    for (i, child) in node.children.iter().enumerate() {
-        let child_is_last = i == node.children.len() - 1;
-        let mut child_nodes = flatten_tree_recursive(child, depth + 1, child_is_last);
-        result.append(&mut child_nodes);
+    // Add children of open directories only
+    if is_expanded {
+        for (i, child) in node.children.iter().enumerate() {
+            let child_is_last = i == node.children.len() - 1;
+            flatten_tree_recursive(child, depth + 1, child_is_last, expanded, result);
+        }
+    }
+}
+
+/// Open a closed directory of the tree view, or close an open one
+fn toggle_directory(expanded: &mut HashSet<PathBuf>, path: PathBuf) {
+    if !expanded.remove(&path) {
+        expanded.insert(path);
+    }
+}
+
+/// The directories to open so the tree shows entries down to the depth typed
+/// in the tree view, or every directory when it is not a number
+fn directories_for_depth(tree: &TreeNode, depth: &str) -> HashSet<PathBuf> {
+    let mut expanded = HashSet::new();
+    // The root's entries are always shown, so depth 1 opens nothing below them
+    directories_to_depth(tree, 1, depth.trim().parse().ok(), &mut expanded);
+    expanded
+}
+
+/// Collect the directories to open so the tree shows entries down to `max_depth`
+/// levels below the root, or every entry when there is no limit
+fn directories_to_depth(node: &TreeNode, depth: usize, max_depth: Option<usize>, expanded: &mut HashSet<PathBuf>) {
+    if max_depth.is_some_and(|max| depth >= max) {
+        return;
+    }
+    for child in &node.children {
+        if child.is_directory && !child.children.is_empty() {
+            expanded.insert(child.path.clone());
+            directories_to_depth(child, depth + 1, max_depth, expanded);
+        }
     }
-    
-    result
 }
 
 async fn perform_scan_with_progress(
     path: PathBuf,
     scanner: DirectoryScanner,
-    _mode: ScanMode,  // No longer needed - we scan all modes
-    colorize: bool,
+    top_count: usize,
+    use_cache: bool,
     progress_state: ProgressState,
+    live_files: LiveFiles,
 ) -> Result<ScanResults, String> {
     let start_time = Instant::now();
     
     let result = tokio::task::spawn_blocking(move || {
         let mut results = ScanResults::default();
         
-        // Phase 1: Detailed file scan (slowest, with hashes)
-        // This is the most comprehensive scan and will populate the OS file cache
-        {
-            let progress_state_clone = progress_state.clone();
-            let progress_callback: ProgressCallback = Arc::new(move |progress, status| {
-                if let Ok(mut guard) = progress_state_clone.lock() {
-                    *guard = Some((progress * 0.4, format!("Phase 1/3: {}", status)));
-                }
-            });
-            
-            match scanner.scan_detailed_with_progress(&path, Some(progress_callback)) {
-                Ok(files) => results.detailed_files = files,
-                Err(e) => return Err(format!("Detailed scan failed: {}", e)),
+        // Load results from earlier scans so unchanged files are not reread
+        let cache = if use_cache {
+            let location = ScanCache::default_location()
+                .ok_or_else(|| "No cache directory available".to_string())?;
+            if let Ok(mut guard) = progress_state.lock() {
+                *guard = Some((0.0, "Loading scan cache...".to_string()));
             }
-        }
+            let cache = ScanCache::load(&location)
+                .map_err(|e| format!("Failed to load scan cache: {}", e))?;
+            Some(Arc::new(cache))
+        } else {
+            None
+        };
+        let scanner = match cache {
+            Some(ref cache) => scanner.cache(cache.clone()),
+            None => scanner,
+        };
         
-        // Phase 2: Tree structure scan (fast, uses cached file system data)
-        {
-            let progress_state_clone = progress_state.clone();
-            let progress_callback: ProgressCallback = Arc::new(move |progress, status| {
-                if let Ok(mut guard) = progress_state_clone.lock() {
-                    *guard = Some((0.4 + progress * 0.3, format!("Phase 2/3: {}", status)));
-                }
-            });
-            
-            match scanner.scan_tree_with_progress(&path, Some(progress_callback)) {
-                Ok(tree) => {
-                    results.tree_output = format_tree_output(&tree, colorize);
-                    results.tree_node = Some(tree);
-                }
-                Err(e) => return Err(format!("Tree scan failed: {}", e)),
+        // Single traversal feeds the detailed list, tree view and analysis
+        let progress_state_clone = progress_state.clone();
+        let progress_callback: ProgressCallback = Arc::new(move |progress, status| {
+            if let Ok(mut guard) = progress_state_clone.lock() {
+                *guard = Some((progress, status));
             }
-        }
+        });
         
-        // Phase 3: Directory analysis (fast, uses cached data)
-        {
-            let progress_state_clone = progress_state.clone();
-            let progress_callback: ProgressCallback = Arc::new(move |progress, status| {
-                if let Ok(mut guard) = progress_state_clone.lock() {
-                    *guard = Some((0.7 + progress * 0.3, format!("Phase 3/3: {}", status)));
-                }
-            });
-            
-            let analysis_options = AnalysisOptions {
-                include_dotfiles: scanner.include_dotfiles,
-                max_depth: scanner.max_depth,
-                skip_virtual_filesystems: scanner.skip_virtual_filesystems,
-                stay_on_filesystem: scanner.stay_on_filesystem,
-            };
-            
-            match analyze_directory_with_options(&path, analysis_options, progress_callback) {
-                Ok(analysis) => {
-                    results.analysis_output = analysis.summary();
-                }
-                Err(e) => return Err(format!("Analysis failed: {}", e)),
+        // Files are handed to the view as they are processed and stored there
+        let on_file = |info: FileInfo| {
+            if let Ok(mut guard) = live_files.lock() {
+                guard.push(info);
             }
+        };
+        match scan_and_analyze_each(&path, &scanner, top_count, progress_callback, on_file) {
+            Ok(analysis) => {
+                results.analysis_output = analysis.summary();
+                results.rankings = Some(analysis.rankings(top_count));
+                results.analysis_record = Some(AnalysisRecord::from(&analysis));
+                results.size_chart = Some(Chart::by_size(&analysis.tree));
+                results.type_chart = Some(Chart::by_type(&analysis.tree));
+                results.volume_info = analysis.volume_info.clone();
+                results.report = analysis.report.clone();
+                results.analysis = Some(Arc::new(analysis));
+            }
+            Err(e) => return Err(format!("Scan failed: {}", e)),
+        }
+        
+        if let Some(cache) = cache {
+            cache.save().map_err(|e| format!("Failed to save scan cache: {}", e))?;
+            results.cache_hits = Some(cache.hits());
         }
         
         // Final progress update
@@ -1805,6 +4298,113 @@ async fn perform_scan_with_progress(
     }
 }
 
+/// Find the rows and tree entries matching a search, in parallel
+async fn search_results(
+    query: SearchQuery,
+    files: Arc<FileTable>,
+    rows: Vec<u32>,
+    analysis: Option<Arc<DirectoryAnalysis>>,
+) -> Result<SearchResults, String> {
+    tokio::task::spawn_blocking(move || {
+        let rows = query.matching_rows(&files, &rows);
+        let mut matches = vec![false; files.len()];
+        for &row in &rows {
+            matches[row as usize] = true;
+        }
+        let tree = analysis.and_then(|analysis| query.filter_tree(&analysis.tree)).map(Arc::new);
+        let mut expanded = HashSet::new();
+        let flattened = match tree {
+            Some(ref tree) => {
+                directories_to_depth(tree, 1, None, &mut expanded);
+                flatten_tree(tree, &expanded)
+            }
+            None => Vec::new(),
+        };
+        SearchResults { query, matches, rows, tree, expanded, flattened }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+async fn find_duplicates_with_progress(
+    files: Arc<FileTable>,
+    rows: Vec<u32>,
+    finder: DuplicateFinder,
+    progress_state: ProgressState,
+) -> Result<DuplicateReport, String> {
+    tokio::task::spawn_blocking(move || {
+        let progress_callback: ProgressCallback = Arc::new(move |progress, status| {
+            if let Ok(mut guard) = progress_state.lock() {
+                *guard = Some((progress, status));
+            }
+        });
+        finder.find(&files.rows(&rows), Some(progress_callback))
+            .map_err(|e| format!("Duplicate search failed: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+async fn find_similar_with_progress(
+    files: Arc<FileTable>,
+    rows: Vec<u32>,
+    finder: SimilarityFinder,
+    progress_state: ProgressState,
+) -> Result<SimilarityReport, String> {
+    tokio::task::spawn_blocking(move || {
+        let progress_callback: ProgressCallback = Arc::new(move |progress, status| {
+            if let Ok(mut guard) = progress_state.lock() {
+                *guard = Some((progress, status));
+            }
+        });
+        finder.find(&files.rows(&rows), Some(progress_callback))
+            .map_err(|e| format!("Similarity search failed: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Check the files listed in a manifest against its directory
+async fn verify_manifest_with_progress(
+    path: PathBuf,
+    format: ManifestFormat,
+    algorithm: HashAlgorithm,
+    scanner: DirectoryScanner,
+    progress_state: ProgressState,
+) -> Result<(PathBuf, VerificationReport), String> {
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&path)
+            .map_err(|e| format!("Failed to open manifest: {}", e))?;
+        let entries = read_manifest(std::io::BufReader::new(file), format)
+            .map_err(|e| format!("Failed to read manifest: {}", e))?;
+        let base = path.parent().map(PathBuf::from).unwrap_or_default();
+        
+        let progress_callback: ProgressCallback = Arc::new(move |progress, status| {
+            if let Ok(mut guard) = progress_state.lock() {
+                *guard = Some((progress, status));
+            }
+        });
+        let report = verify_manifest(&entries, &base, algorithm, format, &scanner, Some(progress_callback))
+            .map_err(|e| format!("Verification failed: {}", e))?;
+        Ok((path, report))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Read a saved detailed file list to use as a comparison baseline
+async fn load_baseline(path: PathBuf) -> Result<(PathBuf, Vec<FileInfo>), String> {
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::open(&path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+        let files = read_files_json(std::io::BufReader::new(file))
+            .map_err(|e| format!("Failed to read scan: {}", e))?;
+        Ok((path, files))
+    })
+    .await
+    .map_err(|e| format!("Load task failed: {}", e))?
+}
+
 fn format_size(size: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     const THRESHOLD: u64 = 1024;
@@ -1824,7 +4424,7 @@ fn format_size(size: u64) -> String {
     format!("{:.1} {}", size_f, UNITS[unit_index])
 }
 
-async fn export_results(path: PathBuf, results: ScanResults, mode: ScanMode, columns: ColumnVisibility) -> Result<String, String> {
+async fn export_results(path: PathBuf, source: ExportSource, mode: ScanMode, columns: ColumnVisibility, time_format: TimeFormat, tree_options: TreeFormatOptions) -> Result<String, String> {
     use std::fs::File;
     use std::io::Write;
     
@@ -1832,6 +4432,91 @@ async fn export_results(path: PathBuf, results: ScanResults, mode: ScanMode, col
         let mut file = File::create(&path)
             .map_err(|e| format!("Failed to create file: {}", e))?;
         
+        // Manifest, JSON and DFXML output are chosen by the file name picked in the save dialog
+        if let (ScanMode::Detailed, Some((format, algorithm))) = (mode, ManifestFormat::detect(&path)) {
+            let root = source.analysis_record
+                .as_ref()
+                .map(|record| record.path.clone())
+                .unwrap_or_default();
+            let mut writer = std::io::BufWriter::new(file);
+            write_manifest(&source.files.rows(&source.rows_in_path_order), &root, algorithm, format, &mut writer)
+                .and_then(|_| writer.flush())
+                .map_err(|e| format!("Failed to write manifest: {}", e))?;
+            return Ok(path.to_string_lossy().to_string());
+        }
+        let extension = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if extension == "xml" || extension == "dfxml" {
+            if mode != ScanMode::Detailed {
+                return Err("DFXML export is only available for the Detailed File List".to_string());
+            }
+            let scanned = source.analysis_record
+                .as_ref()
+                .map(|record| record.path.clone())
+                .unwrap_or_default();
+            let writer = std::io::BufWriter::new(file);
+            // Rows are rebuilt one at a time rather than as a whole list
+            DfxmlWriter::new(writer, &scanned, source.volume_info.as_ref())
+                .and_then(|mut dfxml| {
+                    for &row in &source.rows {
+                        dfxml.write_file(&source.files.get(row as usize))?;
+                    }
+                    dfxml.finish()
+                })
+                .map_err(|e| format!("Failed to write DFXML: {}", e))?;
+            return Ok(path.to_string_lossy().to_string());
+        }
+        if extension == "json" || extension == "jsonl" {
+            let mut writer = std::io::BufWriter::new(file);
+            let written = match mode {
+                ScanMode::Detailed => {
+                    let format = if extension == "jsonl" { FileListFormat::JsonLines } else { FileListFormat::Json };
+                    let mut list = FileListWriter::new(&mut writer, format);
+                    source.rows
+                        .iter()
+                        .try_for_each(|&row| list.write(&source.files.get(row as usize)))
+                        .and_then(|_| list.finish().map(|_| ()))
+                }
+                ScanMode::Tree => match source.shown_tree() {
+                    Some(tree) => write_tree_json(tree, &mut writer),
+                    None => return Err("No tree data available".to_string()),
+                },
+                ScanMode::Analysis => match source.analysis_record {
+                    Some(ref record) => write_analysis_json(record, &mut writer),
+                    None => return Err("No analysis data available".to_string()),
+                },
+                ScanMode::Problems => match source.analysis_record {
+                    Some(ref record) => serde_json::to_writer_pretty(&mut writer, &record.problems)
+                        .map_err(std::io::Error::from)
+                        .and_then(|_| writeln!(writer)),
+                    None => return Err("No problem data available".to_string()),
+                },
+                ScanMode::Compare => match source.comparison {
+                    Some(ref comparison) => comparison.write_json(&mut writer),
+                    None => return Err("No comparison available; load a baseline scan first".to_string()),
+                },
+                ScanMode::Duplicates => match source.duplicates {
+                    Some(ref duplicates) => duplicates.write_json(&mut writer),
+                    None => return Err("No duplicate search has been run".to_string()),
+                },
+                ScanMode::Similar => match source.similar {
+                    Some(ref similar) => similar.write_json(&mut writer),
+                    None => return Err("No similarity search has been run".to_string()),
+                },
+                ScanMode::Verify => match source.verification {
+                    Some(ref verification) => serde_json::to_writer_pretty(&mut writer, verification)
+                        .map_err(std::io::Error::from)
+                        .and_then(|_| writeln!(writer)),
+                    None => return Err("No manifest has been verified".to_string()),
+                },
+            };
+            written
+                .and_then(|_| writer.flush())
+                .map_err(|e| format!("Failed to write JSON: {}", e))?;
+            return Ok(path.to_string_lossy().to_string());
+        }
+        
         match mode {
             ScanMode::Detailed => {
                 // Build CSV header dynamically based on selected columns
@@ -1845,15 +4530,14 @@ async fn export_results(path: PathBuf, results: ScanResults, mode: ScanMode, col
                 if columns.show_accessed { headers.push("Accessed"); }
                 if columns.show_format { headers.push("Format"); }
                 if columns.calculate_mime { headers.push("Media Type"); }
-                if columns.calculate_md5 { headers.push("MD5"); }
-                if columns.calculate_sha256 { headers.push("SHA256"); }
-                if columns.calculate_sha512 { headers.push("SHA512"); }
+                headers.extend(columns.unix_columns.iter().map(UnixColumn::label));
+                headers.extend(columns.hash_algorithms.iter().map(HashAlgorithm::label));
                 
                 writeln!(file, "{}", headers.join(","))
                     .map_err(|e| format!("Failed to write header: {}", e))?;
                 
                 // Write data rows with only selected columns
-                for file_info in &results.detailed_files {
+                for file_info in source.rows.iter().map(|&row| source.files.get(row as usize)) {
                     let mut values = Vec::new();
                     if columns.show_filename {
                         values.push(format!("\"{}\"", file_info.name.replace("\"", "\"\"")));
@@ -1868,28 +4552,25 @@ async fn export_results(path: PathBuf, results: ScanResults, mode: ScanMode, col
                         values.push(file_info.size.to_string());
                     }
                     if columns.show_created {
-                        values.push(format!("\"{}\"", file_info.created));
+                        values.push(format!("\"{}\"", time_format.format(file_info.created)));
                     }
                     if columns.show_modified {
-                        values.push(format!("\"{}\"", file_info.last_modified));
+                        values.push(format!("\"{}\"", time_format.format(file_info.last_modified)));
                     }
                     if columns.show_accessed {
-                        values.push(format!("\"{}\"", file_info.last_accessed));
+                        values.push(format!("\"{}\"", time_format.format(file_info.last_accessed)));
                     }
                     if columns.show_format {
-                        values.push(format!("\"{}\"", file_info.format));
+                        values.push(format!("\"{}\"", file_info.format.replace("\"", "\"\"")));
                     }
                     if columns.calculate_mime {
-                        values.push(format!("\"{}\"", file_info.mime_type));
-                    }
-                    if columns.calculate_md5 {
-                        values.push(format!("\"{}\"", file_info.md5));
+                        values.push(format!("\"{}\"", file_info.mime_type.replace("\"", "\"\"")));
                     }
-                    if columns.calculate_sha256 {
-                        values.push(format!("\"{}\"", file_info.sha256));
+                    for column in &columns.unix_columns {
+                        values.push(format!("\"{}\"", column.value(file_info.unix.as_ref(), &time_format).replace("\"", "\"\"")));
                     }
-                    if columns.calculate_sha512 {
-                        values.push(format!("\"{}\"", file_info.sha512));
+                    for &algorithm in &columns.hash_algorithms {
+                        values.push(format!("\"{}\"", file_info.hash(algorithm).map_or_else(|| NOT_CALCULATED.to_string(), Digest::to_string)));
                     }
                     
                     writeln!(file, "{}", values.join(","))
@@ -1897,11 +4578,9 @@ async fn export_results(path: PathBuf, results: ScanResults, mode: ScanMode, col
                 }
             }
             ScanMode::Tree => {
-                // Export tree as text
-                let tree_text = if !results.tree_output.is_empty() {
-                    results.tree_output
-                } else if let Some(ref tree_node) = results.tree_node {
-                    format_tree_output(tree_node, false)
+                // Export tree as text, formatted as the tree view shows it
+                let tree_text = if let Some(tree) = source.shown_tree() {
+                    TreeFormatter::new(tree_options).format_tree(tree)
                 } else {
                     String::from("No tree data available")
                 };
@@ -1911,9 +4590,42 @@ async fn export_results(path: PathBuf, results: ScanResults, mode: ScanMode, col
             }
             ScanMode::Analysis => {
                 // Export analysis as text
-                write!(file, "{}", results.analysis_output)
+                write!(file, "{}", source.analysis_output)
                     .map_err(|e| format!("Failed to write analysis: {}", e))?;
             }
+            ScanMode::Problems => {
+                // Export skipped paths as CSV
+                source.report.write_csv(&mut file)
+                    .map_err(|e| format!("Failed to write problems: {}", e))?;
+            }
+            ScanMode::Compare => {
+                // Export differences from the baseline as CSV
+                let comparison = source.comparison
+                    .ok_or_else(|| "No comparison available; load a baseline scan first".to_string())?;
+                comparison.write_csv(&mut file)
+                    .map_err(|e| format!("Failed to write comparison: {}", e))?;
+            }
+            ScanMode::Duplicates => {
+                // Export duplicate sets as CSV, one row per copy
+                let duplicates = source.duplicates
+                    .ok_or_else(|| "No duplicate search has been run".to_string())?;
+                duplicates.write_csv(&mut file)
+                    .map_err(|e| format!("Failed to write duplicates: {}", e))?;
+            }
+            ScanMode::Similar => {
+                // Export clusters as CSV, one row per member
+                let similar = source.similar
+                    .ok_or_else(|| "No similarity search has been run".to_string())?;
+                similar.write_csv(&mut file)
+                    .map_err(|e| format!("Failed to write similar files: {}", e))?;
+            }
+            ScanMode::Verify => {
+                // Export verification results as CSV, one row per file
+                let verification = source.verification
+                    .ok_or_else(|| "No manifest has been verified".to_string())?;
+                verification.write_csv(&mut file)
+                    .map_err(|e| format!("Failed to write verification results: {}", e))?;
+            }
         }
         
         Ok(path.to_string_lossy().to_string())
@@ -1922,28 +4634,58 @@ async fn export_results(path: PathBuf, results: ScanResults, mode: ScanMode, col
     .map_err(|e| format!("Export task failed: {}", e))?
 }
 
-/// Sort files based on sort criteria
-fn sort_files(files: &mut Vec<FileInfo>, original_order: &[FileInfo], sort_by: SortBy, sort_order: SortOrder) {
-    // Restore original order if using tree default
+/// Write a chart as SVG, or as PNG when the file name ends in `.png`
+async fn export_chart(path: PathBuf, chart: Chart, kind: ChartKind, value: ChartValue) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let svg = chart.to_svg(kind, value);
+        let is_png = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        let data = if is_png { svg_to_png(&svg)? } else { svg.into_bytes() };
+        std::fs::write(&path, data).map_err(|e| format!("Failed to write chart: {}", e))?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Render an SVG to PNG at twice its size, so it stays sharp in printed reports
+fn svg_to_png(svg: &str) -> Result<Vec<u8>, String> {
+    use resvg::{tiny_skia, usvg};
+    
+    const SCALE: f32 = 2.0;
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| format!("Failed to read chart: {}", e))?;
+    let size = tree.size();
+    let mut pixmap = tiny_skia::Pixmap::new((size.width() * SCALE).ceil() as u32, (size.height() * SCALE).ceil() as u32)
+        .ok_or_else(|| "Chart is too large to render".to_string())?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(SCALE, SCALE), &mut pixmap.as_mut());
+    pixmap.encode_png().map_err(|e| format!("Failed to encode PNG: {}", e))
+}
+
+/// Rows in the order selected for the detail view
+fn sorted_rows(files: &FileTable, original_order: &[u32], sort_by: SortBy, sort_order: SortOrder) -> Vec<u32> {
+    let mut rows = original_order.to_vec();
+    
+    // Keep the original order if using tree default
     if sort_by == SortBy::TreeDefault {
-        *files = original_order.to_vec();
         // Reverse if descending
         if sort_order == SortOrder::Descending {
-            files.reverse();
+            rows.reverse();
         }
-        return;
+        return rows;
     }
     
-    files.sort_by(|a, b| {
+    rows.sort_by(|&a, &b| {
+        let (a, b) = (a as usize, b as usize);
         let cmp = match sort_by {
             SortBy::TreeDefault => std::cmp::Ordering::Equal, // Already handled above
-            SortBy::FileName => a.name.cmp(&b.name),
-            SortBy::Size => a.size.cmp(&b.size),
-            SortBy::Created => a.created.cmp(&b.created),
-            SortBy::Modified => a.last_modified.cmp(&b.last_modified),
-            SortBy::Accessed => a.last_accessed.cmp(&b.last_accessed),
-            SortBy::Format => a.format.cmp(&b.format),
-            SortBy::MimeType => a.mime_type.cmp(&b.mime_type),
+            SortBy::FileName => files.name(a).cmp(files.name(b)),
+            SortBy::Size => files.size(a).cmp(&files.size(b)),
+            SortBy::Created => files.created(a).cmp(&files.created(b)),
+            SortBy::Modified => files.modified(a).cmp(&files.modified(b)),
+            SortBy::Accessed => files.accessed(a).cmp(&files.accessed(b)),
+            SortBy::Format => files.format(a).cmp(files.format(b)),
+            SortBy::MimeType => files.mime_type(a).cmp(files.mime_type(b)),
         };
         
         match sort_order {
@@ -1951,4 +4693,179 @@ fn sort_files(files: &mut Vec<FileInfo>, original_order: &[FileInfo], sort_by: S
             SortOrder::Descending => cmp.reverse(),
         }
     });
+    rows
+}
+
+/// Store the files processed by the running scan since the last call
+fn take_live_files(state: &mut SplendirGui) {
+    let Some(ref live_files) = state.live_files else {
+        return;
+    };
+    let Ok(mut waiting) = live_files.lock() else {
+        return;
+    };
+    let results = &mut state.scan_results;
+    // Searches and exports wait for the scan to finish and earlier ones hold
+    // the previous table, so nothing else shares this one
+    let Some(files) = Arc::get_mut(&mut results.files) else {
+        return;
+    };
+    for info in waiting.drain(..) {
+        let row = files.len() as u32;
+        files.push(&info);
+        results.original_order.push(row);
+        results.detail_rows.push(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use directory_scanner::ChartEntry;
+
+    fn node(path: &str, size: u64, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.into(),
+            is_directory: !children.is_empty() || !path.contains('.'),
+            size,
+            file_count: children.iter().map(|child| child.file_count).sum::<usize>().max(1),
+            modified: None,
+            unix: None,
+            children,
+        }
+    }
+
+    fn sample_tree() -> TreeNode {
+        node("/data", 100, vec![
+            node("/data/a.bin", 25, vec![]),
+            node("/data/docs", 75, vec![
+                node("/data/docs/big.pdf", 50, vec![]),
+                node("/data/docs/old", 25, vec![node("/data/docs/old/small.txt", 25, vec![])]),
+            ]),
+            node("/data/empty", 0, vec![]),
+        ])
+    }
+
+    fn shown(rows: &[FlatTreeNode]) -> Vec<(usize, &str)> {
+        rows.iter().map(|row| (row.depth, row.name.as_str())).collect()
+    }
+
+    #[test]
+    fn test_stale_searches_are_dropped() {
+        let mut requests = SearchRequests::default();
+        let first = requests.start();
+        let second = requests.start();
+        assert!(!requests.is_latest(first) && requests.is_latest(second));
+        requests.cancel();
+        assert!(!requests.is_latest(second));
+
+        let mut state = SplendirGui::default();
+        let stale = state.search_requests.start();
+        let current = state.search_requests.start();
+        let _ = update(&mut state, Message::SearchComplete(stale, Err("stale".to_string())));
+        assert_eq!(state.search_error, None);
+        let _ = update(&mut state, Message::SearchComplete(current, Err("unmatched (".to_string())));
+        assert_eq!(state.search_error.as_deref(), Some("unmatched ("));
+
+        // A pause in typing only searches for the latest text
+        let _ = update(&mut state, Message::SearchDebounced(stale));
+        assert!(state.search_requests.is_latest(current));
+        let _ = update(&mut state, Message::SearchDebounced(current));
+        assert!(!state.search_requests.is_latest(current));
+        assert_eq!(state.search_error, None);
+    }
+
+    #[test]
+    fn test_usage_drill_down() {
+        let tree = sample_tree();
+        let docs = PathBuf::from("/data/docs");
+        let old = PathBuf::from("/data/docs/old");
+
+        // Directories open themselves, files the directory they are in, and
+        // the scanned directory is the top
+        assert_eq!(usage_root_for(&tree, docs.clone()), Some(docs.clone()));
+        assert_eq!(usage_root_for(&tree, PathBuf::from("/data/docs/old/small.txt")), Some(old.clone()));
+        assert_eq!(usage_root_for(&tree, PathBuf::from("/data/a.bin")), None);
+        assert_eq!(usage_root_for(&tree, PathBuf::from("/data")), None);
+        assert_eq!(usage_parent(&tree, &old), Some(docs.clone()));
+        assert_eq!(usage_parent(&tree, &docs), None);
+
+        // A click in the treemap opens the innermost directory under it
+        let chart = UsageChart { root: &tree, view: AnalysisView::Treemap };
+        let size = Size::new(400.0, 100.0);
+        let (entry, directory) = chart.entry_at(size, Point::new(10.0, 50.0)).unwrap();
+        assert!(!entry.is_directory);
+        assert!(entry.path.starts_with(&docs));
+        assert!(directory.unwrap().path.starts_with(&docs));
+        let (entry, directory) = chart.entry_at(size, Point::new(390.0, 50.0)).unwrap();
+        assert_eq!(entry.name, "a.bin");
+        assert!(directory.is_none());
+
+        // The sunburst's center is the directory already shown
+        let chart = UsageChart { root: &tree, view: AnalysisView::Sunburst };
+        let square = Size::new(200.0, 200.0);
+        assert!(chart.entry_at(square, Point::new(100.0, 100.0)).is_none());
+        let (entry, directory) = chart.entry_at(square, Point::new(105.0, 100.0 - sunburst_ring_width(square) * 1.5)).unwrap();
+        assert_eq!((entry.name.as_str(), directory.map(|node| node.name.as_str())), ("docs", Some("docs")));
+        assert!(UsageChart { root: &tree, view: AnalysisView::Summary }.entry_at(square, Point::ORIGIN).is_none());
+    }
+
+    #[test]
+    fn test_chart_entry_under_cursor() {
+        let entry = |label: &str, count| ChartEntry { label: label.to_string(), count, bytes: 10 * count as u64, color: [0, 0, 0] };
+        let chart = Chart { title: "Files".to_string(), entries: vec![entry("a", 1), entry("b", 1), entry("c", 2)] };
+        let size = Size::new(400.0, 72.0);
+
+        // Each bar takes a row, whatever its length
+        let bars = DistributionChart { chart: &chart, kind: ChartKind::Bar, value: ChartValue::Files };
+        assert_eq!(bars.entry_at(size, Point::new(390.0, 30.0)), Some(1));
+        assert_eq!(bars.entry_at(size, Point::new(10.0, 70.0)), Some(2));
+        assert_eq!(bars.entry_at(size, Point::new(10.0, 80.0)), None);
+
+        // Slices run clockwise from the top, and the legend is to the right
+        let pie = DistributionChart { chart: &chart, kind: ChartKind::Pie, value: ChartValue::Files };
+        assert_eq!(pie.pie(size), (Point::new(36.0, 36.0), 32.0));
+        assert_eq!(pie.entry_at(size, Point::new(36.0, 10.0)), Some(0));
+        assert_eq!(pie.entry_at(size, Point::new(56.0, 46.0)), Some(1));
+        assert_eq!(pie.entry_at(size, Point::new(20.0, 50.0)), Some(2));
+        assert_eq!(pie.entry_at(size, Point::new(70.0, 70.0)), None);
+        assert_eq!(pie.entry_at(size, Point::new(200.0, 60.0)), Some(2));
+
+        let mut state = SplendirGui::default();
+        let _ = update(&mut state, Message::ChartKindSelected(ChartSubject::Types, ChartKind::Bar));
+        assert_eq!(*chart_kind_mut(&mut state, ChartSubject::Types), ChartKind::Bar);
+        assert_eq!(*chart_kind_mut(&mut state, ChartSubject::Sizes), ChartKind::Bar);
+        let _ = update(&mut state, Message::ChartKindSelected(ChartSubject::Sizes, ChartKind::Pie));
+        assert_eq!(state.size_chart_kind, ChartKind::Pie);
+    }
+
+    #[test]
+    fn test_tree_expansion() {
+        let tree = sample_tree();
+
+        // The root's entries are always shown; directories open one at a time
+        let mut expanded = HashSet::new();
+        let rows = flatten_tree(&tree, &expanded);
+        assert_eq!(shown(&rows), [(0, "data"), (1, "a.bin"), (1, "docs"), (1, "empty")]);
+        assert!(rows[2].has_children && !rows[2].expanded);
+        assert!(!rows[3].has_children);
+        assert!(rows[3].is_last && !rows[2].is_last);
+
+        toggle_directory(&mut expanded, PathBuf::from("/data/docs"));
+        let rows = flatten_tree(&tree, &expanded);
+        assert_eq!(shown(&rows)[2..5], [(1, "docs"), (2, "big.pdf"), (2, "old")]);
+        assert!(rows[2].expanded && rows[4].is_last);
+        toggle_directory(&mut expanded, PathBuf::from("/data/docs"));
+        assert_eq!(flatten_tree(&tree, &expanded).len(), 4);
+
+        // Expanding to a depth opens directories with entries above it
+        assert!(directories_for_depth(&tree, "1").is_empty());
+        assert_eq!(directories_for_depth(&tree, " 2 "), HashSet::from([PathBuf::from("/data/docs")]));
+        let all = directories_for_depth(&tree, "");
+        assert_eq!(all, HashSet::from([PathBuf::from("/data/docs"), PathBuf::from("/data/docs/old")]));
+        assert_eq!(directories_for_depth(&tree, "9"), all);
+        assert_eq!(shown(&flatten_tree(&tree, &all)).last(), Some(&(1, "empty")));
+        assert_eq!(flatten_tree(&tree, &all).len(), 7);
+    }
 }
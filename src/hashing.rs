@@ -0,0 +1,431 @@
+//! Pluggable file hashing
+//!
+//! Each supported algorithm is a [`HashAlgorithm`] variant. A [`MultiHasher`]
+//! feeds every selected algorithm from the same buffer, so asking for several
+//! digests still reads a file only once. Results are kept in a [`FileHashes`]
+//! map keyed by algorithm, each as a typed [`Digest`]: raw bytes for the exact
+//! hashes, and the tools' native notation for the fuzzy hashes from
+//! [`crate::fuzzy`]. Digests are written as lowercase hex only when displayed
+//! or exported.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha1::Sha1;
+use sha2::{Digest as _, Sha256, Sha512};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::fuzzy::{Ssdeep, Tlsh};
+
+/// Digests calculated for a file
+pub type FileHashes = BTreeMap<HashAlgorithm, Digest>;
+
+/// Size of the read buffer shared by all hashers
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// A supported hash algorithm
+///
+/// Serialized by its lowercase name (`"sha256"`, `"blake3"`, ...), which is
+/// also the key used in JSON exports and the cache file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Blake3,
+    /// 64-bit XXH3 (not cryptographic)
+    Xxh3,
+    /// CRC-32 as used by zip and gzip (not cryptographic)
+    Crc32,
+    /// Context-triggered piecewise hash, for similarity rather than identity
+    Ssdeep,
+    /// Trend Micro Locality Sensitive Hash, for similarity rather than identity
+    Tlsh,
+}
+
+/// Value stored for TLSH when a file is too short or uniform to hash, as
+/// written by the reference implementation
+pub const TLSH_NULL: &str = "TNULL";
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 9] = [
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Blake3,
+        HashAlgorithm::Xxh3,
+        HashAlgorithm::Crc32,
+        HashAlgorithm::Ssdeep,
+        HashAlgorithm::Tlsh,
+    ];
+
+    /// Algorithms whose digests can be compared for similarity
+    pub const FUZZY: [HashAlgorithm; 2] = [HashAlgorithm::Ssdeep, HashAlgorithm::Tlsh];
+
+    /// Algorithms in order of preference when one digest has to stand for a file's contents
+    pub const BY_STRENGTH: [HashAlgorithm; 7] = [
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Blake3,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Md5,
+        HashAlgorithm::Xxh3,
+        HashAlgorithm::Crc32,
+    ];
+
+    /// Lowercase name, as used for file names and serialized keys
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::Ssdeep => "ssdeep",
+            HashAlgorithm::Tlsh => "tlsh",
+        }
+    }
+
+    /// Name for column headers and labels
+    pub fn label(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha1 => "SHA1",
+            HashAlgorithm::Sha256 => "SHA256",
+            HashAlgorithm::Sha512 => "SHA512",
+            HashAlgorithm::Blake3 => "BLAKE3",
+            HashAlgorithm::Xxh3 => "XXH3",
+            HashAlgorithm::Crc32 => "CRC32",
+            HashAlgorithm::Ssdeep => "SSDEEP",
+            HashAlgorithm::Tlsh => "TLSH",
+        }
+    }
+
+    /// Length of a digest for this algorithm; ssdeep signatures vary, so this
+    /// is their maximum
+    pub fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Md5 => 32,
+            HashAlgorithm::Sha1 => 40,
+            HashAlgorithm::Sha256 => 64,
+            HashAlgorithm::Sha512 => 128,
+            HashAlgorithm::Blake3 => 64,
+            HashAlgorithm::Xxh3 => 16,
+            HashAlgorithm::Crc32 => 8,
+            HashAlgorithm::Ssdeep => 108,
+            HashAlgorithm::Tlsh => 72,
+        }
+    }
+
+    /// Look up an algorithm by name, ignoring case and dashes (`SHA-256`, `sha256`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.replace('-', "");
+        Self::ALL.into_iter().find(|algorithm| algorithm.name().eq_ignore_ascii_case(&name))
+    }
+
+    /// Whether collisions are computationally hard to produce
+    pub fn is_cryptographic(&self) -> bool {
+        !matches!(self, HashAlgorithm::Xxh3 | HashAlgorithm::Crc32) && !self.is_fuzzy()
+    }
+
+    /// Whether digests measure similarity, so equal content does not imply equal digests
+    /// across versions and unequal digests can still be close
+    pub fn is_fuzzy(&self) -> bool {
+        matches!(self, HashAlgorithm::Ssdeep | HashAlgorithm::Tlsh)
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// A calculated digest
+///
+/// Exact hashes hold their raw bytes; ssdeep and TLSH hold the signature in
+/// their tools' own notation, which is what their comparison functions take.
+/// [`Display`](std::fmt::Display) gives lowercase hex for the exact hashes
+/// and the signature for the fuzzy ones, as shown in listings and exports.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Digest {
+    Md5([u8; 16]),
+    Sha1([u8; 20]),
+    Sha256([u8; 32]),
+    Sha512([u8; 64]),
+    Blake3([u8; 32]),
+    Xxh3([u8; 8]),
+    Crc32([u8; 4]),
+    Ssdeep(String),
+    Tlsh(String),
+}
+
+impl Digest {
+    /// The algorithm that produced this digest
+    pub fn algorithm(&self) -> HashAlgorithm {
+        match self {
+            Digest::Md5(_) => HashAlgorithm::Md5,
+            Digest::Sha1(_) => HashAlgorithm::Sha1,
+            Digest::Sha256(_) => HashAlgorithm::Sha256,
+            Digest::Sha512(_) => HashAlgorithm::Sha512,
+            Digest::Blake3(_) => HashAlgorithm::Blake3,
+            Digest::Xxh3(_) => HashAlgorithm::Xxh3,
+            Digest::Crc32(_) => HashAlgorithm::Crc32,
+            Digest::Ssdeep(_) => HashAlgorithm::Ssdeep,
+            Digest::Tlsh(_) => HashAlgorithm::Tlsh,
+        }
+    }
+
+    /// The raw digest, or the signature's text for the fuzzy hashes
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Digest::Md5(bytes) => bytes,
+            Digest::Sha1(bytes) => bytes,
+            Digest::Sha256(bytes) | Digest::Blake3(bytes) => bytes,
+            Digest::Sha512(bytes) => bytes,
+            Digest::Xxh3(bytes) => bytes,
+            Digest::Crc32(bytes) => bytes,
+            Digest::Ssdeep(signature) | Digest::Tlsh(signature) => signature.as_bytes(),
+        }
+    }
+
+    /// The signature of a fuzzy hash, `None` for the exact ones
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Digest::Ssdeep(signature) | Digest::Tlsh(signature) => Some(signature),
+            _ => None,
+        }
+    }
+
+    /// Rebuild a digest from what [`as_bytes`](Digest::as_bytes) returned;
+    /// `None` when the length is wrong for the algorithm
+    pub fn from_bytes(algorithm: HashAlgorithm, bytes: &[u8]) -> Option<Self> {
+        Some(match algorithm {
+            HashAlgorithm::Md5 => Digest::Md5(bytes.try_into().ok()?),
+            HashAlgorithm::Sha1 => Digest::Sha1(bytes.try_into().ok()?),
+            HashAlgorithm::Sha256 => Digest::Sha256(bytes.try_into().ok()?),
+            HashAlgorithm::Sha512 => Digest::Sha512(bytes.try_into().ok()?),
+            HashAlgorithm::Blake3 => Digest::Blake3(bytes.try_into().ok()?),
+            HashAlgorithm::Xxh3 => Digest::Xxh3(bytes.try_into().ok()?),
+            HashAlgorithm::Crc32 => Digest::Crc32(bytes.try_into().ok()?),
+            HashAlgorithm::Ssdeep => Digest::Ssdeep(String::from_utf8(bytes.to_vec()).ok()?),
+            HashAlgorithm::Tlsh => Digest::Tlsh(String::from_utf8(bytes.to_vec()).ok()?),
+        })
+    }
+
+    /// Parse a digest as displayed: hex (either case) for the exact hashes,
+    /// the signature for the fuzzy ones. `None` when it is not valid for the
+    /// algorithm.
+    pub fn parse(algorithm: HashAlgorithm, value: &str) -> Option<Self> {
+        let value = value.trim();
+        if algorithm.is_fuzzy() {
+            return (!value.is_empty()).then(|| Self::from_bytes(algorithm, value.as_bytes()))?;
+        }
+        if !value.len().is_multiple_of(2) {
+            return None;
+        }
+        let bytes: Option<Vec<u8>> = (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+            .collect();
+        Self::from_bytes(algorithm, &bytes?)
+    }
+}
+
+impl std::fmt::Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.as_text() {
+            Some(signature) => f.write_str(signature),
+            None => self.as_bytes().iter().try_for_each(|byte| write!(f, "{:02x}", byte)),
+        }
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Read a [`FileHashes`] map written as algorithm names and displayed digests
+///
+/// Keys that are not algorithm names, `null` values (written for digests
+/// that were not calculated by older versions) and values that are not valid
+/// digests are skipped.
+pub(crate) fn deserialize_hashes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FileHashes, D::Error> {
+    let fields = BTreeMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    Ok(fields.into_iter()
+        .filter_map(|(name, value)| {
+            let algorithm = HashAlgorithm::from_name(&name)?;
+            Digest::parse(algorithm, value.as_str()?).map(|digest| (algorithm, digest))
+        })
+        .collect())
+}
+
+/// State of a single running digest
+enum Hasher {
+    Md5(md5::Context),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+    Xxh3(Box<Xxh3>),
+    Crc32(crc32fast::Hasher),
+    Ssdeep(Box<Ssdeep>),
+    Tlsh(Box<Tlsh>),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Context::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Xxh3 => Hasher::Xxh3(Box::new(Xxh3::new())),
+            HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Ssdeep => Hasher::Ssdeep(Box::default()),
+            HashAlgorithm::Tlsh => Hasher::Tlsh(Box::default()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(context) => context.consume(data),
+            Hasher::Sha1(hasher) => sha2::Digest::update(hasher, data),
+            Hasher::Sha256(hasher) => sha2::Digest::update(hasher, data),
+            Hasher::Sha512(hasher) => sha2::Digest::update(hasher, data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Hasher::Xxh3(hasher) => hasher.update(data),
+            Hasher::Crc32(hasher) => hasher.update(data),
+            Hasher::Ssdeep(hasher) => hasher.update(data),
+            Hasher::Tlsh(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> Digest {
+        match self {
+            Hasher::Md5(context) => Digest::Md5(context.compute().0),
+            Hasher::Sha1(hasher) => Digest::Sha1(sha2::Digest::finalize(hasher).into()),
+            Hasher::Sha256(hasher) => Digest::Sha256(sha2::Digest::finalize(hasher).into()),
+            Hasher::Sha512(hasher) => Digest::Sha512(sha2::Digest::finalize(hasher).into()),
+            Hasher::Blake3(hasher) => Digest::Blake3(*hasher.finalize().as_bytes()),
+            // Big-endian, so the hex form reads like the number
+            Hasher::Xxh3(hasher) => Digest::Xxh3(hasher.digest().to_be_bytes()),
+            Hasher::Crc32(hasher) => Digest::Crc32(hasher.finalize().to_be_bytes()),
+            Hasher::Ssdeep(hasher) => Digest::Ssdeep(hasher.digest()),
+            Hasher::Tlsh(hasher) => Digest::Tlsh(hasher.digest().unwrap_or_else(|| TLSH_NULL.to_string())),
+        }
+    }
+}
+
+/// Calculates several digests over the same stream of bytes
+pub struct MultiHasher {
+    hashers: Vec<(HashAlgorithm, Hasher)>,
+}
+
+impl MultiHasher {
+    pub fn new<I: IntoIterator<Item = HashAlgorithm>>(algorithms: I) -> Self {
+        let mut selected: Vec<HashAlgorithm> = algorithms.into_iter().collect();
+        selected.sort();
+        selected.dedup();
+        Self {
+            hashers: selected.into_iter().map(|algorithm| (algorithm, Hasher::new(algorithm))).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashers.is_empty()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for (_, hasher) in &mut self.hashers {
+            hasher.update(data);
+        }
+    }
+
+    pub fn finalize(self) -> FileHashes {
+        self.hashers.into_iter()
+            .map(|(algorithm, hasher)| (algorithm, hasher.finalize()))
+            .collect()
+    }
+}
+
+/// Hash everything a reader produces with each of the given algorithms
+pub fn hash_reader<R: Read>(mut reader: R, algorithms: &[HashAlgorithm]) -> io::Result<FileHashes> {
+    let mut hasher = MultiHasher::new(algorithms.iter().copied());
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    loop {
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Hash a file with each of the given algorithms in a single pass
+///
+/// The file is not opened at all when no algorithms are requested.
+pub fn calculate_file_hashes(path: &Path, algorithms: &[HashAlgorithm]) -> io::Result<FileHashes> {
+    if algorithms.is_empty() {
+        return Ok(FileHashes::new());
+    }
+    hash_reader(fs::File::open(path)?, algorithms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests() {
+        let hashes = hash_reader(&b"abc"[..], &HashAlgorithm::ALL).unwrap();
+        let expected = [
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgorithm::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (HashAlgorithm::Sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (
+                HashAlgorithm::Sha512,
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (HashAlgorithm::Blake3, "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"),
+            (HashAlgorithm::Xxh3, "78af5f94892f3950"),
+            (HashAlgorithm::Crc32, "352441c2"),
+            (HashAlgorithm::Tlsh, TLSH_NULL),
+        ];
+        for (algorithm, digest) in expected {
+            assert_eq!(hashes[&algorithm].to_string(), digest, "{}", algorithm);
+            assert_eq!(Digest::parse(algorithm, &digest.to_uppercase()).as_ref(), Some(&hashes[&algorithm]));
+        }
+        for algorithm in HashAlgorithm::ALL.into_iter().filter(|algorithm| !algorithm.is_fuzzy()) {
+            assert_eq!(hashes[&algorithm].to_string().len(), algorithm.digest_len());
+            assert_eq!(hashes[&algorithm].algorithm(), algorithm);
+        }
+        assert_eq!(Digest::parse(HashAlgorithm::Md5, "abc123"), None);
+        assert_eq!(HashAlgorithm::from_name("SHA-1"), Some(HashAlgorithm::Sha1));
+
+        // ssdeep's well-known signature for empty input, and one published
+        // with the ssdeep-rs bindings to libfuzzy
+        let empty = hash_reader(&b""[..], &[HashAlgorithm::Ssdeep]).unwrap();
+        assert_eq!(empty[&HashAlgorithm::Ssdeep].as_text(), Some("3::"));
+        let greeting = hash_reader(&b"Hello there!"[..], &[HashAlgorithm::Ssdeep]).unwrap();
+        assert_eq!(greeting[&HashAlgorithm::Ssdeep].as_text(), Some("3:aNRn:aNRn"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Hierarchical `.gitignore` and `.ignore` support
+//!
+//! Ignore files are matched the way ripgrep does it. A path is checked
+//! against the ignore files of every directory above it, including those
+//! above the scan root, and the deepest file with an opinion wins. `.ignore`
+//! files take precedence over `.gitignore` files, which in turn take
+//! precedence over `.git/info/exclude` and the global excludes file
+//! (`core.excludesFile`, or `~/.config/git/ignore`).
+//!
+//! The git sources only apply inside a git repository, and only up to the
+//! repository root; `.ignore` files apply everywhere. Each directory's files
+//! are read once, the first time a path below it is checked.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Ignore rules for one scan, loaded lazily as directories are visited
+#[derive(Debug)]
+pub struct IgnoreFiles {
+    root: PathBuf,
+    absolute_root: PathBuf,
+    dirs: Mutex<HashMap<PathBuf, Arc<DirIgnores>>>,
+    ignored: AtomicUsize,
+}
+
+/// The ignore files found in a single directory
+#[derive(Debug, Default)]
+struct DirIgnores {
+    ignore: Option<Gitignore>,
+    gitignore: Option<Gitignore>,
+    /// Set only for repository roots: `.git/info/exclude`, then the global excludes
+    repo_excludes: Option<Vec<Gitignore>>,
+}
+
+impl IgnoreFiles {
+    /// Prepare ignore matching for a scan rooted at `root`
+    pub fn new(root: &Path) -> Self {
+        let absolute_root = root
+            .canonicalize()
+            .or_else(|_| std::path::absolute(root))
+            .unwrap_or_else(|_| root.to_path_buf());
+        Self {
+            root: root.to_path_buf(),
+            absolute_root,
+            dirs: Mutex::new(HashMap::new()),
+            ignored: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether an entry below the scan root is ignored. Every ignored entry
+    /// is counted; an ignored directory counts once, as its contents are
+    /// never visited.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative,
+            _ => return false,
+        };
+        let path = self.absolute_root.join(relative);
+
+        // Directories containing the entry, deepest first
+        let chain: Vec<(&Path, Arc<DirIgnores>)> = path
+            .ancestors()
+            .skip(1)
+            .map(|dir| (dir, self.dir_ignores(dir)))
+            .collect();
+        let repo_root = chain.iter().position(|(_, dir)| dir.repo_excludes.is_some());
+
+        let git_ignores = chain[..repo_root.map_or(0, |index| index + 1)]
+            .iter()
+            .filter_map(|(_, dir)| dir.gitignore.as_ref());
+        let repo_excludes = repo_root
+            .and_then(|index| chain[index].1.repo_excludes.as_ref())
+            .into_iter()
+            .flatten();
+        let decision = chain
+            .iter()
+            .filter_map(|(_, dir)| dir.ignore.as_ref())
+            .chain(git_ignores)
+            .chain(repo_excludes)
+            .map(|matcher| matcher.matched(&path, is_dir))
+            .find(|decision| !decision.is_none());
+
+        let ignored = matches!(decision, Some(Match::Ignore(_)));
+        if ignored {
+            self.ignored.fetch_add(1, Ordering::Relaxed);
+        }
+        ignored
+    }
+
+    /// Number of entries skipped because of ignore rules so far
+    pub fn ignored_count(&self) -> usize {
+        self.ignored.load(Ordering::Relaxed)
+    }
+
+    fn dir_ignores(&self, dir: &Path) -> Arc<DirIgnores> {
+        if let Some(loaded) = self.dirs.lock().unwrap().get(dir) {
+            return Arc::clone(loaded);
+        }
+        let loaded = Arc::new(DirIgnores::load(dir));
+        self.dirs.lock().unwrap().insert(dir.to_path_buf(), Arc::clone(&loaded));
+        loaded
+    }
+}
+
+impl DirIgnores {
+    fn load(dir: &Path) -> Self {
+        let repo_excludes = dir.join(".git").exists().then(|| {
+            let exclude = load_file(dir, &dir.join(".git/info/exclude"));
+            let (global, _) = GitignoreBuilder::new(dir).build_global();
+            exclude.into_iter().chain(Some(global).filter(|global| !global.is_empty())).collect()
+        });
+        Self {
+            ignore: load_file(dir, &dir.join(".ignore")),
+            gitignore: load_file(dir, &dir.join(".gitignore")),
+            repo_excludes,
+        }
+    }
+}
+
+/// Parse an ignore file rooted at `dir`. Lines that fail to parse are
+/// skipped, as git does.
+fn load_file(dir: &Path, file: &Path) -> Option<Gitignore> {
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    builder.add(file);
+    builder.build().ok().filter(|matcher| !matcher.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_ignore_files_are_hierarchical() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        fs::create_dir_all(repo.join(".git/info")).unwrap();
+        fs::create_dir_all(repo.join("src/generated")).unwrap();
+        fs::write(repo.join(".gitignore"), "*.log\ntarget/\n").unwrap();
+        fs::write(repo.join(".git/info/exclude"), "scratch.txt\n").unwrap();
+        fs::write(repo.join("src/.gitignore"), "!keep.log\n").unwrap();
+        fs::write(repo.join("src/.ignore"), "generated/\n").unwrap();
+        // Outside the repository only .ignore files apply
+        fs::write(dir.path().join(".gitignore"), "repo/\n").unwrap();
+
+        let ignores = IgnoreFiles::new(&repo.join("src"));
+        assert!(ignores.is_ignored(&repo.join("src/debug.log"), false));
+        assert!(!ignores.is_ignored(&repo.join("src/keep.log"), false));
+        assert!(ignores.is_ignored(&repo.join("src/generated"), true));
+        assert!(ignores.is_ignored(&repo.join("src/scratch.txt"), false));
+        assert!(ignores.is_ignored(&repo.join("src/target"), true));
+        assert!(!ignores.is_ignored(&repo.join("src/target"), false));
+        assert!(!ignores.is_ignored(&repo.join("src/main.rs"), false));
+        assert!(!ignores.is_ignored(&repo.join("src"), true));
+        assert_eq!(ignores.ignored_count(), 4);
+    }
+}
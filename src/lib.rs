@@ -1,39 +1,129 @@
 use std::path::{Path, PathBuf};
 use std::io;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 // Re-export modules for external use
 pub mod scanner;
+pub mod hashing;
 pub mod tree;
 pub mod filesystem;
+pub mod report;
+pub mod export;
+pub mod dfxml;
+pub mod cache;
+pub mod compare;
+pub mod duplicates;
+pub mod manifest;
+pub mod fuzzy;
+pub mod similarity;
+pub mod filter;
+pub mod search;
+pub mod ignores;
+pub mod criteria;
+mod walk;
+pub mod stream;
+pub mod table;
+pub mod timestamps;
+pub mod unix;
+pub mod usage;
+pub mod charts;
+pub mod ranking;
 
 // Re-export commonly used types and functions for convenience
-pub use scanner::{DirectoryScanner, DirectoryStats, FileSizeDistribution, MountInfo, validate_path, process_file, calculate_sha256, calculate_md5, format_file_size, ProgressCallback};
+pub use scanner::{DirectoryScanner, CompleteScan, DirectoryStats, FileSizeDistribution, MountInfo, validate_path, process_file, calculate_sha256, calculate_md5, format_file_size, ProgressCallback, FileCallback};
+pub use stream::FileStream;
+pub use table::{FileTable, FileList, TableRows};
+pub use timestamps::{TimeFormat, DisplayZone, TimestampStyle};
+pub use unix::UnixMetadata;
+pub use usage::{Rect, TreemapTile, SunburstSegment, treemap, sunburst, squarify, tile_at, segment_at, find_node};
+pub use ranking::{RankedEntry, Rankings, DEFAULT_TOP_COUNT};
+pub use charts::{Chart, ChartEntry, ChartKind, ChartValue, bar_layout, pie_layout, slice_at};
+pub use hashing::{HashAlgorithm, Digest, FileHashes, MultiHasher, calculate_file_hashes};
+pub use fuzzy::{ssdeep_compare, tlsh_distance};
 pub use tree::{TreeFormatter, TreeFormatOptions, TreeLine, FileType, get_file_color, filter_tree_by_type, count_files_by_type};
 pub use filesystem::{FilesystemType, VolumeInfo, detect_filesystem};
+pub use report::{ScanIssue, ScanPhase, ScanReport};
+pub use export::{FileRecord, AnalysisRecord, FileListFormat, FileListWriter, write_files_json, write_files_jsonl, read_files_json, write_tree_json, write_analysis_json};
+pub use dfxml::{write_dfxml, DfxmlWriter};
+pub use cache::{ScanCache, CacheEntry, FileKey};
+pub use compare::{compare_scans, ChangeKind, FileChange, ScanComparison};
+pub use duplicates::{DuplicateFinder, DuplicateReport, DuplicateSet};
+pub use manifest::{ManifestFormat, ManifestEntry, VerifyStatus, VerifyResult, VerificationReport, write_manifest, read_manifest, verify_manifest};
+pub use filter::{PathFilter, CompiledFilter, FilterError, parse_pattern_list};
+pub use search::{SearchMode, SearchQuery};
+pub use ignores::IgnoreFiles;
+pub use criteria::{MetadataFilter, DateRange, parse_size, parse_date};
+pub use similarity::{SimilarityFinder, SimilarityReport, SimilarityCluster, SimilarFile, SimilarPair};
 
 // Core data structures
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "FileRecord", from = "FileRecord")]
 pub struct FileInfo {
     pub name: String,
     pub full_path: String,
     pub directory_path: String,
     pub size: u64,
-    pub created: String,
-    pub last_modified: String,
-    pub last_accessed: String,
-    pub md5: String,
-    pub sha256: String,
-    pub sha512: String,
+    /// Timestamps as reported by the filesystem, with whatever sub-second
+    /// precision it keeps; `None` where the platform does not provide one
+    pub created: Option<DateTime<Utc>>,
+    pub last_modified: Option<DateTime<Utc>>,
+    pub last_accessed: Option<DateTime<Utc>>,
+    /// Digests calculated during the scan; algorithms that were not requested are absent
+    pub hashes: FileHashes,
     pub format: String,
     pub mime_type: String,
+    /// Ownership, permissions and inode details; `None` on platforms other than Unix
+    pub unix: Option<UnixMetadata>,
 }
 
-#[derive(Debug, Clone)]
+impl FileInfo {
+    /// The digest calculated with an algorithm, if it was requested
+    pub fn hash(&self, algorithm: HashAlgorithm) -> Option<&Digest> {
+        self.hashes.get(&algorithm)
+    }
+}
+
+#[cfg(test)]
+impl FileInfo {
+    /// A file at `path` with the given size and nothing else recorded, for
+    /// tests to fill in the fields they look at
+    pub(crate) fn for_test(path: &str, size: u64) -> Self {
+        let (directory_path, name) = path.rsplit_once('/').unwrap_or(("", path));
+        Self {
+            name: name.to_string(),
+            full_path: path.to_string(),
+            directory_path: directory_path.to_string(),
+            size,
+            created: None,
+            last_modified: None,
+            last_accessed: None,
+            hashes: FileHashes::new(),
+            format: "Not calculated".to_string(),
+            mime_type: "Not calculated".to_string(),
+            unix: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TreeNode {
     pub name: String,
     pub path: PathBuf,
     pub is_directory: bool,
+    /// Size of a file, or for a directory the total size of the files below it
+    #[serde(default)]
+    pub size: u64,
+    /// 1 for a file, or for a directory the number of files below it
+    #[serde(default)]
+    pub file_count: usize,
+    /// Modification time, when it could be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+    /// Ownership and permissions, when they could be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix: Option<UnixMetadata>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub children: Vec<TreeNode>,
 }
 
@@ -45,6 +135,8 @@ pub enum ScanError {
     NotADirectory,
     PermissionDenied,
     Cancelled,
+    /// An include or exclude pattern could not be compiled
+    InvalidFilter(String),
 }
 
 impl From<io::Error> for ScanError {
@@ -65,6 +157,7 @@ impl std::fmt::Display for ScanError {
             ScanError::NotADirectory => write!(f, "Path is not a directory"),
             ScanError::PermissionDenied => write!(f, "Permission denied"),
             ScanError::Cancelled => write!(f, "Operation cancelled"),
+            ScanError::InvalidFilter(e) => write!(f, "Invalid filter: {}", e),
         }
     }
 }
@@ -167,9 +260,7 @@ pub fn analyze_directory(path: &Path, include_dotfiles: bool, max_depth: Option<
         .include_dotfiles(include_dotfiles)
         .max_depth(max_depth.unwrap_or(50)); // Reasonable default max depth
     
-    let stats = scanner.scan_stats(path)?;
-    let tree = scanner.scan_tree(path)?;
-    let file_type_counts = count_files_by_type(&tree);
+    let (tree, stats, file_type_counts, report) = scanner.scan_structure_with_progress(path, None)?;
     let volume_info = filesystem::detect_filesystem(path);
     
     Ok(DirectoryAnalysis {
@@ -179,6 +270,9 @@ pub fn analyze_directory(path: &Path, include_dotfiles: bool, max_depth: Option<
         path: path.to_path_buf(),
         volume_info,
         skipped_virtual_filesystems: Vec::new(),
+        report,
+        time_format: scanner.time_format,
+        top_count: DEFAULT_TOP_COUNT,
     })
 }
 
@@ -189,6 +283,12 @@ pub struct AnalysisOptions {
     pub max_depth: Option<usize>,
     pub skip_virtual_filesystems: bool,
     pub stay_on_filesystem: bool,
+    pub path_filter: PathFilter,
+    pub respect_ignore_files: bool,
+    pub metadata_filter: MetadataFilter,
+    pub time_format: TimeFormat,
+    /// Entries listed in each ranking of the summary
+    pub top_count: usize,
 }
 
 impl Default for AnalysisOptions {
@@ -198,6 +298,11 @@ impl Default for AnalysisOptions {
             max_depth: Some(50),
             skip_virtual_filesystems: true,
             stay_on_filesystem: false,
+            path_filter: PathFilter::default(),
+            respect_ignore_files: false,
+            metadata_filter: MetadataFilter::default(),
+            time_format: TimeFormat::default(),
+            top_count: DEFAULT_TOP_COUNT,
         }
     }
 }
@@ -224,51 +329,24 @@ pub fn analyze_directory_with_options(
     options: AnalysisOptions,
     progress_callback: ProgressCallback
 ) -> Result<DirectoryAnalysis, ScanError> {
-    use scanner::MountInfo;
-    
     let scanner = DirectoryScanner::new()
         .include_dotfiles(options.include_dotfiles)
         .skip_virtual_filesystems(options.skip_virtual_filesystems)
         .stay_on_filesystem(options.stay_on_filesystem)
+        .path_filter(options.path_filter)
+        .respect_ignore_files(options.respect_ignore_files)
+        .metadata_filter(options.metadata_filter)
+        .time_format(options.time_format)
         .max_depth(options.max_depth.unwrap_or(50));
     
-    // For analysis, we'll divide progress into three phases
-    let stats_callback = {
-        let callback = progress_callback.clone();
-        Arc::new(move |p: f32, s: String| {
-            callback(p * 0.33, format!("Phase 1/3: {}", s));
-        })
-    };
-    
-    let tree_callback = {
-        let callback = progress_callback.clone();
-        Arc::new(move |p: f32, s: String| {
-            callback(0.33 + p * 0.33, format!("Phase 2/3: {}", s));
-        })
-    };
-    
     progress_callback(0.0, "Starting analysis...".to_string());
     
     // Detect filesystem (fast, do it first)
     let volume_info = filesystem::detect_filesystem(path);
+    let skipped_virtual_filesystems = skipped_virtual_filesystems(path, options.skip_virtual_filesystems);
     
-    // Create mount info to determine which virtual filesystems would be skipped
-    let skipped_virtual_filesystems = if options.skip_virtual_filesystems {
-        MountInfo::new(path)
-            .ok()
-            .map(|info| info.get_virtual_mounts_under(path))
-            .unwrap_or_default()
-    } else {
-        Vec::new()
-    };
-    
-    let stats = scanner.scan_stats_with_progress(path, Some(stats_callback))?;
-    let tree = scanner.scan_tree_with_progress(path, Some(tree_callback))?;
-    
-    progress_callback(0.66, "Phase 3/3: Counting file types...".to_string());
-    let file_type_counts = count_files_by_type(&tree);
-    
-    progress_callback(1.0, "Analysis completed".to_string());
+    // Tree, statistics and file type counts all come from one traversal
+    let (tree, stats, file_type_counts, report) = scanner.scan_structure_with_progress(path, Some(progress_callback))?;
     
     Ok(DirectoryAnalysis {
         stats,
@@ -277,11 +355,86 @@ pub fn analyze_directory_with_options(
         path: path.to_path_buf(),
         volume_info,
         skipped_virtual_filesystems,
+        report,
+        time_format: scanner.time_format,
+        top_count: options.top_count,
+    })
+}
+
+/// Scan once and return the detailed file list together with a full
+/// directory analysis, using the scanner's own settings for both and listing
+/// `top_count` entries in each ranking of the summary
+pub fn scan_and_analyze_with_progress(
+    path: &Path,
+    scanner: &DirectoryScanner,
+    top_count: usize,
+    progress_callback: ProgressCallback
+) -> Result<(Vec<FileInfo>, DirectoryAnalysis), ScanError> {
+    let volume_info = filesystem::detect_filesystem(path);
+    let skipped_virtual_filesystems = skipped_virtual_filesystems(path, scanner.skip_virtual_filesystems);
+    
+    let scan = scanner.scan_all_with_progress(path, Some(progress_callback))?;
+    
+    let analysis = DirectoryAnalysis {
+        stats: scan.stats,
+        tree: scan.tree,
+        file_type_counts: scan.file_type_counts,
+        path: path.to_path_buf(),
+        volume_info,
+        skipped_virtual_filesystems,
+        report: scan.report,
+        time_format: scanner.time_format,
+        top_count,
+    };
+    
+    Ok((scan.files, analysis))
+}
+
+/// Like `scan_and_analyze_with_progress`, but each file is handed to
+/// `on_file` as soon as it has been processed instead of being collected, so
+/// very large scans can be stored compactly (see [`FileTable`])
+pub fn scan_and_analyze_each<F>(
+    path: &Path,
+    scanner: &DirectoryScanner,
+    top_count: usize,
+    progress_callback: ProgressCallback,
+    on_file: F,
+) -> Result<DirectoryAnalysis, ScanError>
+where
+    F: Fn(FileInfo) + Sync,
+{
+    let volume_info = filesystem::detect_filesystem(path);
+    let skipped_virtual_filesystems = skipped_virtual_filesystems(path, scanner.skip_virtual_filesystems);
+    
+    let scan = scanner.scan_all_each(path, Some(progress_callback), on_file)?;
+    
+    Ok(DirectoryAnalysis {
+        stats: scan.stats,
+        tree: scan.tree,
+        file_type_counts: scan.file_type_counts,
+        path: path.to_path_buf(),
+        volume_info,
+        skipped_virtual_filesystems,
+        report: scan.report,
+        time_format: scanner.time_format,
+        top_count,
     })
 }
 
+/// Determine which virtual filesystems would be skipped when scanning from a path
+fn skipped_virtual_filesystems(path: &Path, skip_virtual_filesystems: bool) -> Vec<PathBuf> {
+    if skip_virtual_filesystems {
+        MountInfo::new(path)
+            .ok()
+            .map(|info| info.get_virtual_mounts_under(path))
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
 /// Comprehensive analysis result
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DirectoryAnalysis {
     pub stats: DirectoryStats,
     pub tree: TreeNode,
@@ -289,6 +442,12 @@ pub struct DirectoryAnalysis {
     pub path: PathBuf,
     pub volume_info: Option<filesystem::VolumeInfo>,
     pub skipped_virtual_filesystems: Vec<PathBuf>,
+    /// Paths that could not be scanned
+    pub report: ScanReport,
+    /// How the summary shows timestamps
+    pub time_format: TimeFormat,
+    /// Entries listed in each ranking of the summary; 0 leaves the rankings out
+    pub top_count: usize,
 }
 
 impl DirectoryAnalysis {
@@ -321,10 +480,26 @@ impl DirectoryAnalysis {
         ));
         
         summary.push_str(&format!(
-            "Total size: {}\n\n",
+            "Total size: {}\n",
             self.stats.format_size()
         ));
         
+        if let (Some(oldest), Some(newest)) = (self.stats.oldest_modified, self.stats.newest_modified) {
+            summary.push_str(&format!(
+                "Modified: {} to {}\n",
+                self.time_format.format_time(oldest),
+                self.time_format.format_time(newest)
+            ));
+        }
+        
+        if self.stats.ignored_count > 0 {
+            summary.push_str(&format!(
+                "Ignored: {} entries matched by .gitignore/.ignore rules\n",
+                self.stats.ignored_count
+            ));
+        }
+        summary.push('\n');
+        
         // File size distribution
         summary.push_str("File size distribution:\n");
         summary.push_str(&self.stats.size_distribution.summary());
@@ -340,6 +515,13 @@ impl DirectoryAnalysis {
             }
         }
         
+        // Largest files and directories (only if asked for)
+        let rankings = self.rankings(self.top_count);
+        if !rankings.is_empty() {
+            summary.push('\n');
+            summary.push_str(&rankings.summary());
+        }
+        
         // Skipped virtual filesystems (only if non-empty)
         if !self.skipped_virtual_filesystems.is_empty() {
             let paths: Vec<String> = self.skipped_virtual_filesystems
@@ -349,9 +531,19 @@ impl DirectoryAnalysis {
             summary.push_str(&format!("\nSkipped virtual file systems at: {}\n", paths.join(", ")));
         }
         
+        // Problems encountered (only if non-empty)
+        if !self.report.is_empty() {
+            summary.push_str(&format!("\nProblems: {}\n", self.report.summary()));
+        }
+        
         summary
     }
     
+    /// The `count` largest files and directories, and the directories with the most files
+    pub fn rankings(&self, count: usize) -> Rankings {
+        Rankings::from_tree(&self.tree, count)
+    }
+    
     /// Export tree as formatted string
     pub fn export_tree(&self, colorize: bool) -> String {
         format_tree_output(&self.tree, colorize)
@@ -385,9 +577,7 @@ impl ScannerPresets {
     /// Minimal scan for large directories (no hashes, minimal file info)
     pub fn minimal() -> DirectoryScanner {
         DirectoryScanner::new()
-            .calculate_md5(false)
-            .calculate_sha256(false)
-            .calculate_sha512(false)
+            .hash_algorithms([])
             // .max_depth(3)
     }
     
@@ -396,9 +586,7 @@ impl ScannerPresets {
         DirectoryScanner::new()
             .include_dotfiles(true)
             .follow_symlinks(true)
-            .calculate_md5(true)
-            .calculate_sha256(true)
-            .calculate_sha512(true)
+            .hash_algorithms(HashAlgorithm::ALL)
     }
    
     /// Default scan with MD5 enabled
@@ -406,9 +594,7 @@ impl ScannerPresets {
         DirectoryScanner::new()
             .include_dotfiles(false)
             .follow_symlinks(false)
-            .calculate_md5(true)
-            .calculate_sha256(false)
-            .calculate_sha512(false)
+            .hash_algorithms([HashAlgorithm::Md5])
     }
 
     /// Default scan with SHA256 enabled
@@ -416,9 +602,7 @@ impl ScannerPresets {
         DirectoryScanner::new()
             .include_dotfiles(false)
             .follow_symlinks(false)
-            .calculate_md5(false)
-            .calculate_sha256(true)
-            .calculate_sha512(false)
+            .hash_algorithms([HashAlgorithm::Sha256])
     }
     
     /// Default scan with SHA512 enabled
@@ -426,8 +610,6 @@ impl ScannerPresets {
         DirectoryScanner::new()
             .include_dotfiles(false)
             .follow_symlinks(false)
-            .calculate_md5(false)
-            .calculate_sha256(false)
-            .calculate_sha512(true)
+            .hash_algorithms([HashAlgorithm::Sha512])
     }
 }
@@ -6,18 +6,57 @@ use std::sync::Arc;
 
 // Import from our library - now much simpler!
 use directory_scanner::{
-    scan_directory_detailed, 
-    scan_directory_detailed_with_progress,
-    scan_directory_tree,
-    scan_directory_tree_with_progress, 
-    format_tree_output,
-    analyze_directory,
-    analyze_directory_with_progress,
+    analyze_directory_with_options,
+    AnalysisOptions,
+    DEFAULT_TOP_COUNT,
+    DirectoryScanner,
     ScannerPresets,
-    scan_with_options,
-    scan_with_options_and_progress,
+    ScanReport,
+    AnalysisRecord,
+    write_files_json,
+    write_files_jsonl,
+    write_tree_json,
+    write_analysis_json,
+    write_dfxml,
+    DfxmlWriter,
+    FileListFormat,
+    FileListWriter,
+    FileInfo,
+    TimeFormat,
+    DisplayZone,
+    TimestampStyle,
+    TreeFormatter,
+    TreeFormatOptions,
+    detect_filesystem,
+    ScanCache,
+    compare_scans,
+    read_files_json,
+    DuplicateFinder,
+    SimilarityFinder,
+    HashAlgorithm,
+    PathFilter,
+    parse_pattern_list,
+    MetadataFilter,
+    DateRange,
+    FileType,
+    parse_size,
+    ManifestFormat,
+    VerifyStatus,
+    write_manifest,
+    read_manifest,
+    verify_manifest,
+    format_file_size,
 };
 
+/// How scan results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+    JsonLines,
+    Dfxml,
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     
@@ -29,8 +68,31 @@ fn main() {
     // Parse arguments - options first, then directory path
     let mut tree_mode = false;
     let mut colorize = false;
+    let mut tree_sizes = false;
+    let mut tree_dates = false;
+    let mut tree_permissions = false;
+    let mut tree_owner = false;
+    let mut tree_group = false;
+    let mut time_format = TimeFormat::new();
     let mut fast_mode = false;
+    let mut stream = false;
     let mut analysis_mode = false;
+    let mut top_count: Option<usize> = None;
+    let mut duplicates_mode = false;
+    let mut similar_algorithm: Option<HashAlgorithm> = None;
+    let mut similar_threshold: Option<u32> = None;
+    let mut problems_path: Option<String> = None;
+    let mut output_format = OutputFormat::Text;
+    let mut cache_path: Option<String> = None;
+    let mut hash_algorithms: Option<Vec<HashAlgorithm>> = None;
+    let mut baseline_path: Option<String> = None;
+    let mut manifest_path: Option<String> = None;
+    let mut verify_path: Option<String> = None;
+    let mut path_filter = PathFilter::new();
+    let mut respect_ignore_files = false;
+    let mut metadata_filter = MetadataFilter::new();
+    // Created, modified and accessed range ends as typed, parsed once all flags are read
+    let mut date_bounds: [[String; 2]; 3] = Default::default();
     let mut directory_path: Option<String> = None;
     
     // Skip program name (args[0]) and parse remaining arguments
@@ -39,8 +101,154 @@ fn main() {
         match args[i].as_str() {
             "--tree" => tree_mode = true,
             "-C" => colorize = true,
+            "--du" => tree_sizes = true,
+            "--dates" => tree_dates = true,
+            "-p" => tree_permissions = true,
+            "-u" => tree_owner = true,
+            "-g" => tree_group = true,
+            "--time-zone" => {
+                i += 1;
+                let zone = DisplayZone::parse(required_value(&args, i, "--time-zone requires UTC, local or a zone name such as Europe/Berlin"))
+                    .unwrap_or_else(|e| exit_with_error(&e));
+                time_format = time_format.zone(zone);
+            }
+            "--time-format" => {
+                i += 1;
+                let name = required_value(&args, i, "--time-format requires one of datetime, iso, epoch, epoch-ns");
+                match TimestampStyle::from_name(name) {
+                    Some(style) => time_format = time_format.style(style),
+                    None => exit_with_error(&format!("unknown time format '{}' (expected datetime, iso, epoch or epoch-ns)", name)),
+                }
+            }
             "--fast" => fast_mode = true,
+            "--stream" => stream = true,
             "--analyze" => analysis_mode = true,
+            "--duplicates" => duplicates_mode = true,
+            "--json" => output_format = OutputFormat::Json,
+            "--jsonl" => output_format = OutputFormat::JsonLines,
+            "--dfxml" => output_format = OutputFormat::Dfxml,
+            "--gitignore" => respect_ignore_files = true,
+            "--problems" => {
+                i += 1;
+                problems_path = Some(required_value(&args, i, "--problems requires a file path").to_string());
+            }
+            "--cache" => {
+                i += 1;
+                cache_path = Some(required_value(&args, i, "--cache requires a file path").to_string());
+            }
+            "--hash" => {
+                i += 1;
+                let list = required_value(&args, i, "--hash requires a comma-separated list of algorithms");
+                let mut algorithms = Vec::new();
+                for name in list.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                    match HashAlgorithm::from_name(name) {
+                        Some(algorithm) => algorithms.push(algorithm),
+                        None => {
+                            let known: Vec<&str> = HashAlgorithm::ALL.iter().map(|a| a.name()).collect();
+                            exit_with_error(&format!("unknown hash algorithm '{}' (expected one of: {})", name, known.join(", ")));
+                        }
+                    }
+                }
+                hash_algorithms = Some(algorithms);
+            }
+            "--similar" => {
+                i += 1;
+                let message = "--similar requires a fuzzy hash algorithm (ssdeep or tlsh)";
+                match HashAlgorithm::from_name(required_value(&args, i, message)) {
+                    Some(algorithm) if algorithm.is_fuzzy() => similar_algorithm = Some(algorithm),
+                    _ => exit_with_error(message),
+                }
+            }
+            "--top" => {
+                i += 1;
+                top_count = Some(required_number(&args, i, "--top requires a whole number"));
+            }
+            "--threshold" => {
+                i += 1;
+                similar_threshold = Some(required_number(&args, i, "--threshold requires a whole number"));
+            }
+            "--compare" => {
+                i += 1;
+                baseline_path = Some(required_value(&args, i, "--compare requires a saved scan file").to_string());
+            }
+            "--manifest" => {
+                i += 1;
+                manifest_path = Some(required_value(&args, i, "--manifest requires a file path").to_string());
+            }
+            "--verify" => {
+                i += 1;
+                verify_path = Some(required_value(&args, i, "--verify requires a manifest file").to_string());
+            }
+            "--include" => {
+                i += 1;
+                path_filter = path_filter.include_glob(required_value(&args, i, "--include requires a glob pattern"));
+            }
+            "--exclude" => {
+                i += 1;
+                path_filter = path_filter.exclude_glob(required_value(&args, i, "--exclude requires a glob pattern"));
+            }
+            "--include-regex" => {
+                i += 1;
+                path_filter = path_filter.include_regex(required_value(&args, i, "--include-regex requires a regular expression"));
+            }
+            "--exclude-regex" => {
+                i += 1;
+                path_filter = path_filter.exclude_regex(required_value(&args, i, "--exclude-regex requires a regular expression"));
+            }
+            "--ext" => {
+                i += 1;
+                for extension in parse_pattern_list(required_value(&args, i, "--ext requires a comma-separated list of extensions")) {
+                    path_filter = path_filter.include_extension(extension);
+                }
+            }
+            "--exclude-ext" => {
+                i += 1;
+                for extension in parse_pattern_list(required_value(&args, i, "--exclude-ext requires a comma-separated list of extensions")) {
+                    path_filter = path_filter.exclude_extension(extension);
+                }
+            }
+            flag @ ("--min-size" | "--max-size") => {
+                i += 1;
+                let size = parse_size(required_value(&args, i, &format!("{} requires a size such as 500K or 1.5G", flag)))
+                    .unwrap_or_else(|e| exit_with_error(&e));
+                if flag == "--min-size" {
+                    metadata_filter.min_size = Some(size);
+                } else {
+                    metadata_filter.max_size = Some(size);
+                }
+            }
+            flag @ ("--created-from" | "--created-until" | "--modified-from" | "--modified-until" | "--accessed-from" | "--accessed-until") => {
+                i += 1;
+                let value = required_value(&args, i, &format!("{} requires a date (YYYY-MM-DD) or an age such as 30d", flag));
+                let timestamp = match &flag[2..5] {
+                    "cre" => 0,
+                    "mod" => 1,
+                    _ => 2,
+                };
+                let end = usize::from(flag.ends_with("until"));
+                date_bounds[timestamp][end] = value.to_string();
+            }
+            "--type" => {
+                i += 1;
+                for name in parse_pattern_list(required_value(&args, i, "--type requires a comma-separated list of file types")) {
+                    match FileType::from_name(&name) {
+                        Some(file_type) if file_type != FileType::Directory => metadata_filter = metadata_filter.file_type(file_type),
+                        _ => {
+                            let known: Vec<String> = FileType::ALL.iter()
+                                .filter(|file_type| **file_type != FileType::Directory)
+                                .map(|file_type| file_type.description().to_lowercase())
+                                .collect();
+                            exit_with_error(&format!("unknown file type '{}' (expected one of: {})", name, known.join(", ")));
+                        }
+                    }
+                }
+            }
+            "--mime" => {
+                i += 1;
+                for category in parse_pattern_list(required_value(&args, i, "--mime requires a comma-separated list of MIME categories")) {
+                    metadata_filter = metadata_filter.mime_category(category);
+                }
+            }
             "--help" | "-h" => {
                 print_help(&args[0]);
                 process::exit(0);
@@ -65,6 +273,26 @@ fn main() {
         i += 1;
     }
     
+    if let Err(e) = path_filter.validate() {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+    let [created, modified, accessed] = date_bounds
+        .map(|[from, until]| DateRange::parse(&from, &until).unwrap_or_else(|e| exit_with_error(&e)));
+    metadata_filter = metadata_filter.created(created).modified(modified).accessed(accessed);
+    
+    // Verification works from the manifest alone; a directory overrides its location
+    if let Some(manifest) = verify_path {
+        if tree_mode || analysis_mode || duplicates_mode || similar_algorithm.is_some() || baseline_path.is_some() || manifest_path.is_some() || problems_path.is_some()
+            || stream || !path_filter.is_empty() || respect_ignore_files || !metadata_filter.is_empty()
+            || matches!(output_format, OutputFormat::JsonLines | OutputFormat::Dfxml) {
+            eprintln!("Error: --verify can only be combined with --json");
+            process::exit(1);
+        }
+        print_verify_mode(Path::new(&manifest), directory_path.as_deref().map(Path::new), output_format);
+        return;
+    }
+    
     // Ensure we have a directory path
     let path_str = match directory_path {
         Some(path) => path,
@@ -82,32 +310,127 @@ fn main() {
         eprintln!("Error: Cannot use --tree and --analyze together");
         process::exit(1);
     }
+    if output_format == OutputFormat::JsonLines && (tree_mode || analysis_mode) {
+        eprintln!("Error: --jsonl is only available for the detailed file listing");
+        process::exit(1);
+    }
+    if output_format == OutputFormat::Dfxml && (tree_mode || analysis_mode) {
+        eprintln!("Error: --dfxml is only available for the detailed file listing");
+        process::exit(1);
+    }
+    if stream && (tree_mode || analysis_mode || duplicates_mode || similar_algorithm.is_some() || baseline_path.is_some() || manifest_path.is_some()) {
+        eprintln!("Error: --stream is only available for the detailed file listing");
+        process::exit(1);
+    }
+    if (tree_sizes || tree_dates || tree_permissions || tree_owner || tree_group) && !tree_mode {
+        eprintln!("Error: --du, --dates, -p, -u and -g are only available with --tree");
+        process::exit(1);
+    }
+    if top_count.is_some() && !analysis_mode {
+        eprintln!("Error: --top is only available with --analyze");
+        process::exit(1);
+    }
+    if hash_algorithms.is_some() && (tree_mode || analysis_mode || duplicates_mode || similar_algorithm.is_some() || manifest_path.is_some()) {
+        eprintln!("Error: --hash is only available for the detailed file listing and --compare");
+        process::exit(1);
+    }
+    
+    let filtered = !path_filter.is_empty() || respect_ignore_files || !metadata_filter.is_empty();
+    
+    // Every mode walks the directory with the same traversal settings
+    let mut scanner = if fast_mode {
+        ScannerPresets::minimal()
+    } else {
+        DirectoryScanner::new()
+    };
+    if let Some(ref algorithms) = hash_algorithms {
+        scanner = scanner.hash_algorithms(algorithms.iter().copied());
+    }
+    scanner = scanner
+        .path_filter(path_filter)
+        .respect_ignore_files(respect_ignore_files)
+        .metadata_filter(metadata_filter)
+        .time_format(time_format);
+    
+    if let Some(manifest) = manifest_path {
+        // A manifest lists every file, so verifying it later can report extras
+        if tree_mode || analysis_mode || duplicates_mode || similar_algorithm.is_some() || similar_threshold.is_some() || baseline_path.is_some()
+            || fast_mode || filtered || output_format != OutputFormat::Text {
+            eprintln!("Error: --manifest can only be combined with --cache and --problems");
+            process::exit(1);
+        }
+        print_manifest_mode(path_obj, Path::new(&manifest), scanner, cache_path.as_deref(), problems_path.as_deref());
+        return;
+    }
+    
+    if similar_threshold.is_some() && similar_algorithm.is_none() {
+        eprintln!("Error: --threshold is only used with --similar");
+        process::exit(1);
+    }
+    
+    if duplicates_mode {
+        if tree_mode || analysis_mode || similar_algorithm.is_some() || baseline_path.is_some() || matches!(output_format, OutputFormat::JsonLines | OutputFormat::Dfxml) {
+            eprintln!("Error: --duplicates can only be combined with --json and --problems");
+            process::exit(1);
+        }
+        print_duplicates_mode(path_obj, scanner, output_format, problems_path.as_deref());
+        return;
+    }
+    
+    if let Some(algorithm) = similar_algorithm {
+        if tree_mode || analysis_mode || baseline_path.is_some() || matches!(output_format, OutputFormat::JsonLines | OutputFormat::Dfxml) {
+            eprintln!("Error: --similar can only be combined with --threshold, --json, --cache and --problems");
+            process::exit(1);
+        }
+        print_similar_mode(path_obj, scanner, algorithm, similar_threshold, output_format, cache_path.as_deref(), problems_path.as_deref());
+        return;
+    }
+    
+    if let Some(baseline) = baseline_path {
+        if tree_mode || analysis_mode || matches!(output_format, OutputFormat::JsonLines | OutputFormat::Dfxml) {
+            eprintln!("Error: --compare can only be combined with --fast, --json, --cache and --problems");
+            process::exit(1);
+        }
+        print_compare_mode(path_obj, Path::new(&baseline), scanner, output_format, cache_path.as_deref(), problems_path.as_deref());
+        return;
+    }
     
     // Execute based on mode
     match (tree_mode, analysis_mode) {
-        (true, false) => print_tree_mode(path_obj, colorize, fast_mode),
-        (false, true) => print_analysis_mode(path_obj),
-        (false, false) => print_detailed_mode(path_obj, fast_mode),
+        (true, false) => {
+            let options = TreeFormatOptions::new()
+                .colorize(colorize)
+                .show_permissions(tree_permissions)
+                .show_owner(tree_owner)
+                .show_group(tree_group)
+                .show_file_sizes(tree_sizes)
+                .show_modified(tree_dates)
+                .time_format(scanner.time_format);
+            print_tree_mode(path_obj, scanner, options, output_format, problems_path.as_deref())
+        }
+        (false, true) => print_analysis_mode(path_obj, &scanner, top_count.unwrap_or(DEFAULT_TOP_COUNT), output_format, problems_path.as_deref()),
+        (false, false) => print_detailed_mode(path_obj, scanner, fast_mode, stream, output_format, cache_path.as_deref(), problems_path.as_deref()),
         (true, true) => unreachable!(), // Already handled above
     }
 }
 
-fn print_tree_mode(path: &Path, colorize: bool, fast_mode: bool) {
-    println!("Directory tree for: {}", path.display());
-    
-    let progress_callback = create_progress_callback();
-    
-    let tree_result = if fast_mode {
-        let scanner = ScannerPresets::minimal();
-        scanner.scan_tree_with_progress(path, Some(progress_callback))
+fn print_tree_mode(path: &Path, scanner: DirectoryScanner, options: TreeFormatOptions, output_format: OutputFormat, problems_path: Option<&str>) {
+    let progress_callback = if output_format == OutputFormat::Text {
+        println!("Directory tree for: {}", path.display());
+        create_progress_callback()
     } else {
-        scan_directory_tree_with_progress(path, progress_callback)
+        create_silent_progress_callback()
     };
     
-    match tree_result {
-        Ok(tree) => {
-            let output = format_tree_output(&tree, colorize);
-            print!("{}", output);
+    match scanner.scan_tree_with_report(path, Some(progress_callback)) {
+        Ok((tree, report)) => {
+            if output_format == OutputFormat::Text {
+                let output = TreeFormatter::new(options).format_tree(&tree);
+                print!("{}", output);
+            } else {
+                write_structured_output(|out| write_tree_json(&tree, out));
+            }
+            report_problems(&report, problems_path);
         }
         Err(e) => {
             eprintln!("Error scanning directory: {}", e);
@@ -116,54 +439,101 @@ fn print_tree_mode(path: &Path, colorize: bool, fast_mode: bool) {
     }
 }
 
-fn print_detailed_mode(path: &Path, fast_mode: bool) {
-    println!("Directory scan results for: {}", path.display());
-    println!("{:-<100}", "");
+fn print_detailed_mode(path: &Path, mut scanner: DirectoryScanner, fast_mode: bool, stream: bool, output_format: OutputFormat, cache_path: Option<&str>, problems_path: Option<&str>) {
+    let cache = cache_path.map(open_cache);
+    if let Some(ref cache) = cache {
+        scanner = scanner.cache(cache.clone());
+    }
     
-    if fast_mode {
-        println!("{:<30} {:<50} {:<12} {:<25} {:<20}", 
-                 "File Name", "Full Path", "Size (bytes)", "Last Modified", "SHA256");
-    } else {
-        println!("{:<30} {:<50} {:<12} {:<25} {:<64}", 
-                 "File Name", "Full Path", "Size (bytes)", "Last Modified", "SHA256");
+    if stream {
+        let report = print_streamed_files(path, &scanner, fast_mode, output_format);
+        save_cache(cache.as_deref());
+        report_problems(&report, problems_path);
+        return;
+    }
+    
+    if output_format != OutputFormat::Text {
+        match scanner.scan_detailed_with_report(path, Some(create_silent_progress_callback())) {
+            Ok((files, report)) => {
+                match output_format {
+                    OutputFormat::JsonLines => write_structured_output(|out| write_files_jsonl(&files, out)),
+                    OutputFormat::Dfxml => {
+                        let volume = detect_filesystem(path);
+                        write_structured_output(|out| write_dfxml(&files, path, volume.as_ref(), out));
+                    }
+                    _ => write_structured_output(|out| write_files_json(&files, out)),
+                }
+                save_cache(cache.as_deref());
+                report_problems(&report, problems_path);
+            }
+            Err(e) => {
+                eprintln!("Error scanning directory: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
     }
-    println!("{:-<100}", "");
+    
+    let algorithms: Vec<HashAlgorithm> = scanner.hash_algorithms.iter().copied().collect();
+    print_listing_header(path, &algorithms, fast_mode);
     
     let progress_callback = create_progress_callback();
     
-    let files_result = if fast_mode {
-        let scanner = ScannerPresets::minimal();
-        scan_with_options_and_progress(path, scanner, progress_callback)
-    } else {
-        scan_directory_detailed_with_progress(path, progress_callback)
-    };
+    match scanner.scan_detailed_with_report(path, Some(progress_callback)) {
+        Ok((files, report)) => {
+            for file_info in &files {
+                print_file_row(file_info, &algorithms, fast_mode, &scanner.time_format);
+            }
+            save_cache(cache.as_deref());
+            report_problems(&report, problems_path);
+        }
+        Err(e) => {
+            eprintln!("Error scanning directory: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Write each file as soon as it has been processed, in the order processing
+/// finished, instead of after the whole scan. Returns the scan's problem report.
+fn print_streamed_files(path: &Path, scanner: &DirectoryScanner, fast_mode: bool, output_format: OutputFormat) -> ScanReport {
+    // A progress bar would be interleaved with the rows
+    let mut files = scanner.scan_detailed_streaming(path, Some(create_silent_progress_callback()));
     
-    match files_result {
-        Ok(files) => {
-            for file_info in files {
-                let sha256_display = if fast_mode && file_info.sha256 == "Not calculated" {
-                    "Not calculated".to_string()
-                } else {
-                    file_info.sha256
-                };
-                
-                if fast_mode {
-                    println!("{:<30} {:<50} {:<12} {:<25} {:<20}", 
-                             truncate_string(&file_info.name, 29),
-                             truncate_string(&file_info.full_path, 49),
-                             file_info.size, 
-                             file_info.last_modified,
-                             truncate_string(&sha256_display, 19));
-                } else {
-                    println!("{:<30} {:<50} {:<12} {:<25} {:<64}", 
-                             truncate_string(&file_info.name, 29),
-                             truncate_string(&file_info.full_path, 49),
-                             file_info.size, 
-                             file_info.last_modified,
-                             sha256_display);
+    match output_format {
+        OutputFormat::Text => {
+            let algorithms: Vec<HashAlgorithm> = scanner.hash_algorithms.iter().copied().collect();
+            print_listing_header(path, &algorithms, fast_mode);
+            for batch in &mut files {
+                for file_info in &batch {
+                    print_file_row(file_info, &algorithms, fast_mode, &scanner.time_format);
                 }
             }
         }
+        OutputFormat::Dfxml => write_structured_output(|out| {
+            let volume = detect_filesystem(path);
+            let mut dfxml = DfxmlWriter::new(out, path, volume.as_ref())?;
+            for batch in &mut files {
+                for file_info in &batch {
+                    dfxml.write_file(file_info)?;
+                }
+                dfxml.flush()?;
+            }
+            dfxml.finish().map(|_| ())
+        }),
+        OutputFormat::Json | OutputFormat::JsonLines => write_structured_output(|out| {
+            let format = if output_format == OutputFormat::JsonLines { FileListFormat::JsonLines } else { FileListFormat::Json };
+            let mut list = FileListWriter::new(out, format);
+            for batch in &mut files {
+                list.write_batch(&batch)?;
+                list.flush()?;
+            }
+            list.finish().map(|_| ())
+        }),
+    }
+    
+    match files.finish() {
+        Ok(report) => report,
         Err(e) => {
             eprintln!("Error scanning directory: {}", e);
             process::exit(1);
@@ -171,12 +541,63 @@ fn print_detailed_mode(path: &Path, fast_mode: bool) {
     }
 }
 
-fn print_analysis_mode(path: &Path) {
-    println!("Analyzing directory: {}", path.display());
+/// Width of a digest column; fast mode shortens them to keep rows on one line
+fn hash_column_width(algorithm: HashAlgorithm, fast_mode: bool) -> usize {
+    if fast_mode { algorithm.digest_len().min(20) } else { algorithm.digest_len() }
+}
+
+/// Print the title and column headings of the detailed text listing, with
+/// one column per requested digest
+fn print_listing_header(path: &Path, algorithms: &[HashAlgorithm], fast_mode: bool) {
+    println!("Directory scan results for: {}", path.display());
+    println!("{:-<100}", "");
     
-    let progress_callback = create_progress_callback();
+    print!("{:<30} {:<50} {:<12} {:<25}", "File Name", "Full Path", "Size (bytes)", "Last Modified");
+    for algorithm in algorithms {
+        print!(" {:<width$}", algorithm.label(), width = hash_column_width(*algorithm, fast_mode));
+    }
+    println!();
+    println!("{:-<100}", "");
+}
+
+/// Print one row of the detailed text listing
+fn print_file_row(file_info: &FileInfo, algorithms: &[HashAlgorithm], fast_mode: bool, time_format: &TimeFormat) {
+    print!("{:<30} {:<50} {:<12} {:<25}", 
+           truncate_string(&file_info.name, 29),
+           truncate_string(&file_info.full_path, 49),
+           file_info.size, 
+           time_format.format(file_info.last_modified));
+    for algorithm in algorithms {
+        let digest = file_info.hash(*algorithm).map_or_else(|| "Not calculated".to_string(), |digest| digest.to_string());
+        let width = hash_column_width(*algorithm, fast_mode);
+        print!(" {:<width$}", truncate_string(&digest, width.max(15)), width = width);
+    }
+    println!();
+}
+
+fn print_analysis_mode(path: &Path, scanner: &DirectoryScanner, top_count: usize, output_format: OutputFormat, problems_path: Option<&str>) {
+    let progress_callback = if output_format == OutputFormat::Text {
+        println!("Analyzing directory: {}", path.display());
+        create_progress_callback()
+    } else {
+        create_silent_progress_callback()
+    };
     
-    match analyze_directory_with_progress(path, false, Some(20), progress_callback) {
+    let options = AnalysisOptions {
+        max_depth: Some(20),
+        path_filter: scanner.path_filter.clone(),
+        respect_ignore_files: scanner.respect_ignore_files,
+        metadata_filter: scanner.metadata_filter.clone(),
+        time_format: scanner.time_format,
+        top_count,
+        ..Default::default()
+    };
+    match analyze_directory_with_options(path, options, progress_callback) {
+        Ok(analysis) if output_format != OutputFormat::Text => {
+            let record = AnalysisRecord::from(&analysis);
+            write_structured_output(|out| write_analysis_json(&record, out));
+            report_problems(&analysis.report, problems_path);
+        }
         Ok(analysis) => {
             println!("{}", analysis.summary());
             
@@ -194,6 +615,7 @@ fn print_analysis_mode(path: &Path) {
                     println!("  ... and {} more types", sorted_types.len() - 10);
                 }
             }
+            report_problems(&analysis.report, problems_path);
         }
         Err(e) => {
             eprintln!("Error analyzing directory: {}", e);
@@ -202,6 +624,336 @@ fn print_analysis_mode(path: &Path) {
     }
 }
 
+fn print_compare_mode(path: &Path, baseline: &Path, mut scanner: DirectoryScanner, output_format: OutputFormat, cache_path: Option<&str>, problems_path: Option<&str>) {
+    let baseline_files = match std::fs::File::open(baseline)
+        .and_then(|file| read_files_json(io::BufReader::new(file)))
+    {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Error reading baseline scan: {}", e);
+            process::exit(1);
+        }
+    };
+    
+    let cache = cache_path.map(open_cache);
+    if let Some(ref cache) = cache {
+        scanner = scanner.cache(cache.clone());
+    }
+    
+    match scanner.scan_detailed_with_report(path, Some(create_silent_progress_callback())) {
+        Ok((files, report)) => {
+            let comparison = compare_scans(&baseline_files, &files);
+            eprintln!("Compared with {}: {}", baseline.display(), comparison.summary());
+            if output_format == OutputFormat::Json {
+                write_structured_output(|out| comparison.write_json(out));
+            } else {
+                write_structured_output(|out| comparison.write_csv(out));
+            }
+            save_cache(cache.as_deref());
+            report_problems(&report, problems_path);
+        }
+        Err(e) => {
+            eprintln!("Error scanning directory: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn print_duplicates_mode(path: &Path, scanner: DirectoryScanner, output_format: OutputFormat, problems_path: Option<&str>) {
+    let progress_callback = if output_format == OutputFormat::Text {
+        println!("Finding duplicate files in: {}", path.display());
+        create_progress_callback()
+    } else {
+        create_silent_progress_callback()
+    };
+    
+    // Hashing is left to the duplicate finder, which only reads files of matching size
+    let scanner = scanner.hash_algorithms([]);
+    let (files, mut report) = match scanner.scan_detailed_with_report(path, Some(progress_callback.clone())) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error scanning directory: {}", e);
+            process::exit(1);
+        }
+    };
+    
+    let duplicates = match DuplicateFinder::new().find(&files, Some(progress_callback)) {
+        Ok(duplicates) => duplicates,
+        Err(e) => {
+            eprintln!("Error finding duplicates: {}", e);
+            process::exit(1);
+        }
+    };
+    
+    if output_format == OutputFormat::Json {
+        write_structured_output(|out| duplicates.write_json(out));
+    } else {
+        for (index, set) in duplicates.sets.iter().enumerate() {
+            println!(
+                "Set {}: {} copies of {} ({} wasted)",
+                index + 1,
+                set.paths.len(),
+                format_file_size(set.size),
+                format_file_size(set.wasted_bytes())
+            );
+            for file_path in &set.paths {
+                println!("    {}", file_path.display());
+            }
+        }
+        println!("{}", duplicates.summary());
+    }
+    
+    report.issues.extend(duplicates.report.issues);
+    report.sort();
+    report_problems(&report, problems_path);
+}
+
+fn print_similar_mode(
+    path: &Path,
+    scanner: DirectoryScanner,
+    algorithm: HashAlgorithm,
+    threshold: Option<u32>,
+    output_format: OutputFormat,
+    cache_path: Option<&str>,
+    problems_path: Option<&str>,
+) {
+    let progress_callback = if output_format == OutputFormat::Text {
+        println!("Finding similar files in: {}", path.display());
+        create_progress_callback()
+    } else {
+        create_silent_progress_callback()
+    };
+    
+    // The fuzzy hash is calculated during the scan, so the cache can supply it
+    let cache = cache_path.map(open_cache);
+    let mut scanner = scanner.hash_algorithms([algorithm]);
+    if let Some(ref cache) = cache {
+        scanner = scanner.cache(cache.clone());
+    }
+    let (files, mut report) = match scanner.scan_detailed_with_report(path, Some(progress_callback.clone())) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error scanning directory: {}", e);
+            process::exit(1);
+        }
+    };
+    save_cache(cache.as_deref());
+    
+    let mut finder = SimilarityFinder::new().algorithm(algorithm);
+    if let Some(threshold) = threshold {
+        finder = finder.threshold(threshold);
+    }
+    let similar = match finder.find(&files, Some(progress_callback)) {
+        Ok(similar) => similar,
+        Err(e) => {
+            eprintln!("Error finding similar files: {}", e);
+            process::exit(1);
+        }
+    };
+    
+    if output_format == OutputFormat::Json {
+        write_structured_output(|out| similar.write_json(out));
+    } else {
+        let measure = if algorithm == HashAlgorithm::Tlsh { "distance" } else { "score" };
+        for (index, cluster) in similar.clusters.iter().enumerate() {
+            println!("Cluster {}: {} similar files", index + 1, cluster.files.len());
+            for file in &cluster.files {
+                println!("    {} {:>4}  {:>10}  {}", measure, file.best_match, format_file_size(file.size), file.path.display());
+            }
+        }
+        println!("{}", similar.summary());
+    }
+    
+    report.issues.extend(similar.report.issues);
+    report.sort();
+    report_problems(&report, problems_path);
+}
+
+fn print_manifest_mode(path: &Path, manifest: &Path, scanner: DirectoryScanner, cache_path: Option<&str>, problems_path: Option<&str>) {
+    let Some((format, algorithm)) = ManifestFormat::detect(manifest) else {
+        eprintln!("Error: cannot tell the manifest type from '{}'", manifest.display());
+        eprintln!("Use a name such as SHA256SUMS, files.md5 or manifest-sha512.txt");
+        process::exit(1);
+    };
+    
+    println!("Writing {} manifest for: {}", algorithm, path.display());
+    let progress_callback = create_progress_callback();
+    
+    // Only the manifest's algorithm is needed
+    let mut scanner = scanner
+        .hash_algorithms([algorithm])
+        .calculate_format(false)
+        .calculate_mime(false);
+    let cache = cache_path.map(open_cache);
+    if let Some(ref cache) = cache {
+        scanner = scanner.cache(cache.clone());
+    }
+    
+    let (files, report) = match scanner.scan_detailed_with_report(path, Some(progress_callback)) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error scanning directory: {}", e);
+            process::exit(1);
+        }
+    };
+    save_cache(cache.as_deref());
+    
+    let result = std::fs::File::create(manifest).and_then(|file| {
+        let mut writer = io::BufWriter::new(file);
+        write_manifest(&files, path, algorithm, format, &mut writer)?;
+        writer.flush()
+    });
+    match result {
+        Ok(()) => println!("Manifest written to: {}", manifest.display()),
+        Err(e) => {
+            eprintln!("Error writing manifest: {}", e);
+            process::exit(1);
+        }
+    }
+    
+    report_problems(&report, problems_path);
+}
+
+fn print_verify_mode(manifest: &Path, base: Option<&Path>, output_format: OutputFormat) {
+    let Some((format, algorithm)) = ManifestFormat::detect(manifest) else {
+        eprintln!("Error: cannot tell the manifest type from '{}'", manifest.display());
+        eprintln!("Use a name such as SHA256SUMS, files.md5 or manifest-sha512.txt");
+        process::exit(1);
+    };
+    
+    let entries = match std::fs::File::open(manifest).and_then(|file| read_manifest(io::BufReader::new(file), format)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error reading manifest: {}", e);
+            process::exit(1);
+        }
+    };
+    let base = base
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| manifest.parent().map(Path::to_path_buf).unwrap_or_default());
+    
+    let progress_callback = if output_format == OutputFormat::Text {
+        println!("Verifying {} {} entries against: {}", entries.len(), algorithm, base.display());
+        create_progress_callback()
+    } else {
+        create_silent_progress_callback()
+    };
+    
+    let scanner = DirectoryScanner::new();
+    let verification = match verify_manifest(&entries, &base, algorithm, format, &scanner, Some(progress_callback)) {
+        Ok(verification) => verification,
+        Err(e) => {
+            eprintln!("Error verifying manifest: {}", e);
+            process::exit(1);
+        }
+    };
+    
+    if output_format == OutputFormat::Json {
+        write_structured_output(|out| {
+            serde_json::to_writer_pretty(&mut *out, &verification).map_err(io::Error::from)?;
+            writeln!(out)
+        });
+    } else {
+        // Same layout as `sha256sum -c`, leaving out files that passed
+        for result in verification.results.iter().filter(|result| result.status != VerifyStatus::Ok) {
+            match result.error {
+                Some(ref error) => println!("{}: {} ({})", result.path.display(), result.status, error),
+                None => println!("{}: {}", result.path.display(), result.status),
+            }
+        }
+        println!("{}", verification.summary());
+    }
+    
+    if !verification.is_valid() {
+        process::exit(1);
+    }
+}
+
+/// Value following a flag, exiting with the given message when it is missing
+fn required_value<'a>(args: &'a [String], index: usize, message: &str) -> &'a str {
+    match args.get(index) {
+        Some(value) => value,
+        None => {
+            eprintln!("Error: {}", message);
+            print_usage(&args[0]);
+            process::exit(1);
+        }
+    }
+}
+
+/// Value following a flag read as a number, exiting with the given message
+/// when it is missing or not a number
+fn required_number<T: std::str::FromStr>(args: &[String], index: usize, message: &str) -> T {
+    required_value(args, index, message)
+        .parse()
+        .unwrap_or_else(|_| exit_with_error(message))
+}
+
+/// Print an error message and exit
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+}
+
+/// Load a scan cache file, exiting on failure
+fn open_cache(cache_path: &str) -> Arc<ScanCache> {
+    match ScanCache::load(Path::new(cache_path)) {
+        Ok(cache) => Arc::new(cache),
+        Err(e) => {
+            eprintln!("Error loading cache file: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Write an updated scan cache back to disk
+fn save_cache(cache: Option<&ScanCache>) {
+    if let Some(cache) = cache {
+        if let Err(e) = cache.save() {
+            eprintln!("Error saving cache file: {}", e);
+            process::exit(1);
+        }
+        eprintln!("Reused cached results for {} unchanged files", cache.hits());
+    }
+}
+
+/// Print a summary of skipped paths and optionally write them to a CSV file
+fn report_problems(report: &ScanReport, problems_path: Option<&str>) {
+    if !report.is_empty() {
+        eprintln!("Warning: {}", report.summary());
+    }
+    
+    if let Some(csv_path) = problems_path {
+        let result = std::fs::File::create(csv_path)
+            .and_then(|mut file| report.write_csv(&mut file));
+        match result {
+            Ok(()) => eprintln!("Problems written to: {}", csv_path),
+            Err(e) => {
+                eprintln!("Error writing problems file: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Write structured output (JSON or DFXML) to stdout, exiting on failure
+fn write_structured_output<F>(write: F)
+where
+    F: FnOnce(&mut io::BufWriter<io::StdoutLock<'static>>) -> io::Result<()>,
+{
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let result = write(&mut out).and_then(|_| out.flush());
+    if let Err(e) = result {
+        eprintln!("Error writing output: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Create a progress callback that prints nothing, keeping stdout clean for structured output
+fn create_silent_progress_callback() -> Arc<dyn Fn(f32, String) + Send + Sync> {
+    Arc::new(|_progress: f32, _status: String| {})
+}
+
 /// Create a CLI progress callback that shows a progress bar
 fn create_progress_callback() -> Arc<dyn Fn(f32, String) + Send + Sync> {
     Arc::new(|progress: f32, status: String| {
@@ -252,16 +1004,69 @@ fn print_help(program_name: &str) {
     println!("OPTIONS:");
     println!("    --tree              Display results as a tree structure");
     println!("    -C                  Colorize the tree output (only works with --tree)");
+    println!("    --du                Show sizes and directory totals in the tree, like tree -h --du");
+    println!("    --dates             Show modification times in the tree (only works with --tree)");
+    println!("    -p, -u, -g          Show permissions, owners and groups in the tree, like tree -pug (Unix only)");
+    println!("    --time-zone <ZONE>  Show times in UTC (default), local time or a zone such as Europe/Berlin");
+    println!("    --time-format <FMT> Show times as datetime (default), iso, epoch or epoch-ns;");
+    println!("                        JSON and DFXML output always use ISO 8601 in UTC");
     println!("    --fast              Fast mode - skip SHA256 calculation and limit depth");
     println!("    --analyze           Comprehensive directory analysis with statistics");
+    println!("    --top <N>           Largest files and directories listed by --analyze (default 10, 0 for none)");
+    println!("    --duplicates        List sets of files with identical contents and the space they waste");
+    println!("    --similar <ALG>     Cluster files whose ssdeep or tlsh hashes are similar");
+    println!("    --threshold <N>     Minimum ssdeep score (default 50) or maximum tlsh distance (default 70)");
+    println!("    --json              Write results to stdout as JSON instead of text");
+    println!("    --jsonl             Write the detailed file listing as JSON Lines (one file per line)");
+    println!("    --dfxml             Write the detailed file listing as Digital Forensics XML");
+    println!("    --stream            Write each file of the detailed listing as soon as it is processed (unsorted)");
+    println!("    --hash <LIST>       Digests to calculate, e.g. sha1,blake3 (md5, sha1, sha256, sha512, blake3, xxh3, crc32, ssdeep, tlsh)");
+    println!("    --cache <FILE>      Reuse hashes from FILE for unchanged files and update it");
+    println!("    --compare <FILE>    Compare with a scan saved by --json/--jsonl and list the changes as CSV");
+    println!("    --manifest <FILE>   Write a checksum manifest (SHA256SUMS, *.md5, manifest-sha256.txt, ...)");
+    println!("    --verify <FILE>     Rehash the files listed in a manifest; the directory defaults to the manifest's");
+    println!("    --include <GLOB>    Only list files matching a gitignore-style glob (repeatable)");
+    println!("    --exclude <GLOB>    Skip entries matching a glob; excluded directories are not entered (repeatable)");
+    println!("    --include-regex <RE> Only list files whose full path matches a regex (repeatable)");
+    println!("    --exclude-regex <RE> Skip entries whose full path matches a regex (repeatable)");
+    println!("    --ext <LIST>        Only list files with these extensions, e.g. jpg,png");
+    println!("    --exclude-ext <LIST> Skip files with these extensions");
+    println!("    --gitignore         Skip entries matched by .gitignore, .ignore and global git excludes");
+    println!("    --min-size <SIZE>   Only list files of at least SIZE, e.g. 500K, 1.5G");
+    println!("    --max-size <SIZE>   Only list files of at most SIZE");
+    println!("    --modified-from <DATE>, --modified-until <DATE>");
+    println!("                        Only list files modified in a range; DATE is YYYY[-MM[-DD[ HH:MM]]] (UTC)");
+    println!("                        or an age such as 36h, 30d, 8w, 2y. --created-* and --accessed-* work alike");
+    println!("    --type <LIST>       Only list files of these types, e.g. image,video (see Analysis for the types)");
+    println!("    --mime <LIST>       Only list files whose detected MIME type is in these categories, e.g. image,text");
+    println!("    --problems <FILE>   Write paths that could not be scanned to a CSV file");
     println!("    -h, --help          Print this help information");
     println!();
     println!("EXAMPLES:");
     println!("    {} /home/user                    # Detailed file listing", program_name);
     println!("    {} --tree /home/user             # Tree view", program_name);
     println!("    {} --tree -C /home/user          # Colorized tree view", program_name);
+    println!("    {} --tree --dates --time-zone local ~/src # Tree with local modification times", program_name);
+    println!("    {} --time-format epoch-ns /data  # Nanosecond epoch timestamps", program_name);
+    println!("    {} --tree -p -u -g /srv/www      # Tree with permissions, owners and groups", program_name);
+    println!("    {} --tree --du /var/log          # Tree with sizes and directory totals", program_name);
     println!("    {} --fast /home/user             # Fast scan without SHA256", program_name);
     println!("    {} --analyze /home/user          # Comprehensive analysis", program_name);
+    println!("    {} --analyze --top 25 /data      # Analysis with the 25 largest files and directories", program_name);
+    println!("    {} --jsonl /home/user > files.jsonl  # Stream file records as JSON Lines", program_name);
+    println!("    {} --dfxml /home/user > scan.xml # DFXML for forensic tools", program_name);
+    println!("    {} --stream --jsonl /data | jq .name # Records while the scan is still running", program_name);
+    println!("    {} --hash sha1,blake3 --json /data # SHA-1 and BLAKE3 for every file", program_name);
+    println!("    {} --cache scan.jsonl /home/user # Only rehash new or modified files", program_name);
+    println!("    {} --compare before.jsonl /home/user # Added, deleted, modified and moved files", program_name);
+    println!("    {} --duplicates /home/user       # Duplicate file sets", program_name);
+    println!("    {} --exclude target/ --ext rs,toml ~/src # Rust sources outside target/", program_name);
+    println!("    {} --gitignore --analyze ~/src/monorepo # Only what git would track", program_name);
+    println!("    {} --min-size 1G --modified-until 2y /data # Large files untouched for two years", program_name);
+    println!("    {} --type image --created-from 2023 --created-until 2023 ~/Pictures # Images created in 2023", program_name);
+    println!("    {} --similar ssdeep /home/user   # Clusters of near-identical files", program_name);
+    println!("    {} --manifest SHA256SUMS /data   # sha256sum-compatible manifest", program_name);
+    println!("    {} --verify bag/manifest-md5.txt # Check a BagIt payload", program_name);
     println!("    {} --help                        # Show this help message", program_name);
     println!();
     println!("MODES:");
@@ -269,4 +1074,6 @@ fn print_help(program_name: &str) {
     println!("    Tree       : Shows directory structure as a visual tree");
     println!("    Fast       : Quick scan without SHA256 calculation (faster for large dirs)");
     println!("    Analysis   : Comprehensive statistics and file type breakdown");
+    println!("    Duplicates : Groups files by size, then hashes only size collisions");
+    println!("    Similar    : Fuzzy hashes every file and links pairs that pass the threshold");
 }
@@ -0,0 +1,477 @@
+//! Checksum manifests and fixity verification
+//!
+//! Two manifest layouts are supported:
+//!
+//! * the `md5sum`/`sha256sum`/`b3sum` format (`<digest>  <path>`), with
+//!   paths relative to the directory containing the manifest, and
+//! * BagIt `manifest-<algorithm>.txt` files (`<digest> <path>`), with paths
+//!   relative to the bag root and payload files under `data/`.
+//!
+//! Verification rehashes every listed file in parallel and reports each as
+//! OK, FAILED or MISSING, along with files present on disk but absent from
+//! the manifest.
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::hashing::{calculate_file_hashes, Digest, HashAlgorithm};
+use crate::table::FileList;
+use crate::{DirectoryScanner, ProgressCallback, ScanError};
+
+/// Layout of a manifest file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// `sha256sum`-style `<digest>  <path>` lines
+    Coreutils,
+    /// BagIt `manifest-<algorithm>.txt`
+    BagIt,
+}
+
+impl ManifestFormat {
+    /// Work out the format and algorithm from a manifest file name
+    ///
+    /// `manifest-sha256.txt` is BagIt; a file named after any
+    /// [`HashAlgorithm`] as its extension (`*.sha256`, `*.blake3`) or with a
+    /// `SUMS` suffix (`SHA1SUMS`, `MD5SUMS`) is a coreutils-style manifest.
+    /// Fuzzy hashes cannot verify content, so they never name a manifest.
+    pub fn detect(path: &Path) -> Option<(ManifestFormat, HashAlgorithm)> {
+        Self::detect_any(path).filter(|(_, algorithm)| !algorithm.is_fuzzy())
+    }
+
+    fn detect_any(path: &Path) -> Option<(ManifestFormat, HashAlgorithm)> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+
+        if let Some(name) = file_name.strip_prefix("manifest-").and_then(|n| n.strip_suffix(".txt")) {
+            return HashAlgorithm::from_name(name).map(|algorithm| (ManifestFormat::BagIt, algorithm));
+        }
+        if let Some(name) = file_name.strip_suffix("sums") {
+            return HashAlgorithm::from_name(name).map(|algorithm| (ManifestFormat::Coreutils, algorithm));
+        }
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        HashAlgorithm::from_name(&extension).map(|algorithm| (ManifestFormat::Coreutils, algorithm))
+    }
+}
+
+/// One line of a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub digest: String,
+    /// Path relative to the manifest base directory
+    pub path: PathBuf,
+}
+
+/// Write a manifest for scanned files
+///
+/// Paths are written relative to `root`. For BagIt, a `root` containing a
+/// `bagit.txt` is treated as the bag root and only its `data/` payload is
+/// listed; any other `root` is treated as the payload directory itself and
+/// its paths are prefixed with `data/`. Manifests already in `root` are
+/// left out, and digests missing from the scan are calculated as the
+/// manifest is written. `files` can be a list of `FileInfo`s or rows of a
+/// [`FileTable`](crate::FileTable).
+pub fn write_manifest<F: FileList + ?Sized, W: Write>(
+    files: &F,
+    root: &Path,
+    algorithm: HashAlgorithm,
+    format: ManifestFormat,
+    mut writer: W,
+) -> io::Result<()> {
+    let is_bag_root = root.join("bagit.txt").is_file();
+
+    for index in 0..files.len() {
+        let full_path = files.full_path(index);
+        let full_path = Path::new(full_path.as_ref());
+        if is_manifest_file(&normalize(full_path), root) {
+            continue;
+        }
+        let relative = full_path.strip_prefix(root).unwrap_or(full_path);
+
+        let relative = match format {
+            ManifestFormat::BagIt if is_bag_root => {
+                if !relative.starts_with("data") {
+                    continue;
+                }
+                relative.to_path_buf()
+            }
+            ManifestFormat::BagIt => Path::new("data").join(relative),
+            ManifestFormat::Coreutils => relative.to_path_buf(),
+        };
+
+        let digest = match files.hash(index, algorithm) {
+            Some(digest) => digest.to_string(),
+            None => hash_file(full_path, algorithm)?.to_string(),
+        };
+
+        let path = manifest_path_string(&relative);
+        match format {
+            ManifestFormat::Coreutils if path.contains(['\\', '\n', '\r']) => {
+                // GNU coreutils escapes the line with a leading backslash
+                let escaped = path.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+                writeln!(writer, "\\{}  {}", digest, escaped)?;
+            }
+            ManifestFormat::Coreutils => writeln!(writer, "{}  {}", digest, path)?,
+            ManifestFormat::BagIt => {
+                let encoded = path.replace('%', "%25").replace('\n', "%0A").replace('\r', "%0D");
+                writeln!(writer, "{} {}", digest, encoded)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read the entries of a manifest
+pub fn read_manifest<R: BufRead>(reader: R, format: ManifestFormat) -> io::Result<Vec<ManifestEntry>> {
+    let mut entries = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (escaped, line) = match line.strip_prefix('\\') {
+            Some(rest) if format == ManifestFormat::Coreutils => (true, rest),
+            _ => (false, line),
+        };
+
+        let invalid = || io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: expected '<digest> <path>'", index + 1),
+        );
+        let (digest, rest) = line.split_once([' ', '\t']).ok_or_else(invalid)?;
+        if digest.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let path = match format {
+            // One separator space remains, or '*' for binary mode
+            ManifestFormat::Coreutils => {
+                let path = rest.strip_prefix([' ', '*']).unwrap_or(rest);
+                if escaped {
+                    unescape_coreutils(path)
+                } else {
+                    path.to_string()
+                }
+            }
+            ManifestFormat::BagIt => rest.trim_start()
+                .replace("%0A", "\n")
+                .replace("%0D", "\r")
+                .replace("%25", "%"),
+        };
+
+        entries.push(ManifestEntry {
+            digest: digest.to_lowercase(),
+            path: PathBuf::from(path),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Outcome of checking one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum VerifyStatus {
+    Ok,
+    Failed,
+    Missing,
+    /// Present on disk but not listed in the manifest
+    Extra,
+}
+
+impl VerifyStatus {
+    /// Returns the status as printed by `sha256sum -c`
+    pub fn name(&self) -> &'static str {
+        match self {
+            VerifyStatus::Ok => "OK",
+            VerifyStatus::Failed => "FAILED",
+            VerifyStatus::Missing => "MISSING",
+            VerifyStatus::Extra => "EXTRA",
+        }
+    }
+}
+
+impl std::fmt::Display for VerifyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Result of checking one file against a manifest
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyResult {
+    pub path: PathBuf,
+    pub status: VerifyStatus,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    /// Read error for files that could not be hashed
+    pub error: Option<String>,
+}
+
+/// Results of verifying a manifest
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerificationReport {
+    /// Results sorted by path
+    pub results: Vec<VerifyResult>,
+}
+
+impl VerificationReport {
+    pub fn count(&self, status: VerifyStatus) -> usize {
+        self.results.iter().filter(|result| result.status == status).count()
+    }
+
+    /// True if every listed file matched and nothing extra was found
+    pub fn is_valid(&self) -> bool {
+        self.results.iter().all(|result| result.status == VerifyStatus::Ok)
+    }
+
+    /// Get a one-line summary of the verification
+    pub fn summary(&self) -> String {
+        format!(
+            "{} OK, {} FAILED, {} MISSING, {} extra",
+            self.count(VerifyStatus::Ok),
+            self.count(VerifyStatus::Failed),
+            self.count(VerifyStatus::Missing),
+            self.count(VerifyStatus::Extra),
+        )
+    }
+
+    /// Write the results as CSV with a header row
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "Status,Path,Expected,Actual,Error")?;
+        for result in &self.results {
+            writeln!(
+                writer,
+                "{},\"{}\",{},{},\"{}\"",
+                result.status,
+                result.path.to_string_lossy().replace('"', "\"\""),
+                result.expected.as_deref().unwrap_or(""),
+                result.actual.as_deref().unwrap_or(""),
+                result.error.as_deref().unwrap_or("").replace('"', "\"\""),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Verify the files listed in a manifest
+///
+/// Listed paths are resolved against `base`. Files under `base` that are not
+/// listed are reported as extra; for BagIt only the `data/` payload is
+/// checked for extras. The scanner controls traversal (dotfiles, symlinks,
+/// cancellation) when looking for extra files.
+pub fn verify_manifest(
+    entries: &[ManifestEntry],
+    base: &Path,
+    algorithm: HashAlgorithm,
+    format: ManifestFormat,
+    scanner: &DirectoryScanner,
+    progress_callback: Option<ProgressCallback>,
+) -> Result<VerificationReport, ScanError> {
+    let is_cancelled = || scanner.cancellation_flag
+        .as_ref()
+        .map(|flag| flag.load(Ordering::Relaxed))
+        .unwrap_or(false);
+
+    let total = entries.len();
+    let processed = AtomicUsize::new(0);
+    let mut results: Vec<VerifyResult> = entries
+        .par_iter()
+        .filter_map(|entry| {
+            if is_cancelled() {
+                return None;
+            }
+            let result = verify_entry(entry, base, algorithm);
+
+            let count = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(ref callback) = progress_callback {
+                if count.is_multiple_of(10) || count == total {
+                    let progress = 0.9 * count as f32 / total.max(1) as f32;
+                    callback(progress, format!("Verifying {} of {} files", count, total));
+                }
+            }
+            Some(result)
+        })
+        .collect();
+
+    if is_cancelled() {
+        return Err(ScanError::Cancelled);
+    }
+
+    // Anything on disk that the manifest does not mention
+    let extra_root = match format {
+        ManifestFormat::BagIt => base.join("data"),
+        ManifestFormat::Coreutils => base.to_path_buf(),
+    };
+    if extra_root.is_dir() {
+        if let Some(ref callback) = progress_callback {
+            callback(0.9, "Looking for files not in the manifest...".to_string());
+        }
+        let listing_scanner = scanner.clone()
+            .hash_algorithms([])
+            .calculate_format(false)
+            .calculate_mime(false);
+        let present = listing_scanner.scan_detailed(&extra_root)?;
+
+        let listed: HashSet<PathBuf> = entries.iter()
+            .map(|entry| normalize(&base.join(&entry.path)))
+            .collect();
+        for file in present {
+            let path = normalize(Path::new(&file.full_path));
+            if listed.contains(&path) || is_manifest_file(&path, base) {
+                continue;
+            }
+            results.push(VerifyResult {
+                path: path.strip_prefix(normalize(base)).map(Path::to_path_buf).unwrap_or(path),
+                status: VerifyStatus::Extra,
+                expected: None,
+                actual: None,
+                error: None,
+            });
+        }
+    }
+
+    results.sort_by(|a, b| a.path.cmp(&b.path).then(a.status.cmp(&b.status)));
+    if let Some(ref callback) = progress_callback {
+        callback(1.0, format!("Verified {} files", total));
+    }
+    Ok(VerificationReport { results })
+}
+
+/// Rehash a single listed file
+fn verify_entry(entry: &ManifestEntry, base: &Path, algorithm: HashAlgorithm) -> VerifyResult {
+    let path = base.join(&entry.path);
+    let mut result = VerifyResult {
+        path: entry.path.clone(),
+        status: VerifyStatus::Ok,
+        expected: Some(entry.digest.clone()),
+        actual: None,
+        error: None,
+    };
+
+    match hash_file(&path, algorithm) {
+        Ok(actual) => {
+            // Parsed, so a listing in uppercase hex still matches
+            if Digest::parse(algorithm, &entry.digest).as_ref() != Some(&actual) {
+                result.status = VerifyStatus::Failed;
+            }
+            result.actual = Some(actual.to_string());
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => result.status = VerifyStatus::Missing,
+        Err(e) => {
+            result.status = VerifyStatus::Failed;
+            result.error = Some(e.to_string());
+        }
+    }
+    result
+}
+
+/// Hash a file with a single algorithm
+fn hash_file(path: &Path, algorithm: HashAlgorithm) -> io::Result<Digest> {
+    let mut hashes = calculate_file_hashes(path, &[algorithm])?;
+    hashes.remove(&algorithm).ok_or_else(|| io::Error::other(format!("no {} digest calculated", algorithm)))
+}
+
+/// Manifest and tag files sitting in the base directory are not payload
+fn is_manifest_file(path: &Path, base: &Path) -> bool {
+    path.parent() == Some(normalize(base).as_path())
+        && path.file_name()
+            .map(|name| ManifestFormat::detect(Path::new(name)).is_some())
+            .unwrap_or(false)
+}
+
+/// Remove `.` components so joined and scanned paths compare equal
+fn normalize(path: &Path) -> PathBuf {
+    path.components().filter(|c| *c != Component::CurDir).collect()
+}
+
+/// Render a relative path with forward slashes, as manifests expect
+fn manifest_path_string(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Undo the backslash escaping used by coreutils for unusual file names
+fn unescape_coreutils(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_manifest_round_trip_and_verify() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("a.txt"), b"alpha").unwrap();
+        fs::write(dir.path().join("sub/b.txt"), b"beta").unwrap();
+        fs::write(dir.path().join("gone.txt"), b"gone").unwrap();
+
+        let scanner = DirectoryScanner::new().skip_virtual_filesystems(false);
+        let files = scanner.scan_detailed(dir.path()).unwrap();
+
+        let mut manifest = Vec::new();
+        write_manifest(&files, dir.path(), HashAlgorithm::Sha256, ManifestFormat::Coreutils, &mut manifest).unwrap();
+        let text = String::from_utf8(manifest).unwrap();
+        assert!(text.contains("  sub/b.txt\n"));
+        fs::write(dir.path().join("SHA256SUMS"), &text).unwrap();
+
+        fs::write(dir.path().join("a.txt"), b"tampered").unwrap();
+        fs::remove_file(dir.path().join("gone.txt")).unwrap();
+        fs::write(dir.path().join("new.txt"), b"new").unwrap();
+
+        let entries = read_manifest(text.as_bytes(), ManifestFormat::Coreutils).unwrap();
+        assert_eq!(entries.len(), 3);
+        let report = verify_manifest(&entries, dir.path(), HashAlgorithm::Sha256, ManifestFormat::Coreutils, &scanner, None).unwrap();
+
+        let statuses: Vec<_> = report.results.iter()
+            .map(|result| (result.path.to_string_lossy().to_string(), result.status))
+            .collect();
+        assert_eq!(statuses, vec![
+            ("a.txt".to_string(), VerifyStatus::Failed),
+            ("gone.txt".to_string(), VerifyStatus::Missing),
+            ("new.txt".to_string(), VerifyStatus::Extra),
+            ("sub/b.txt".to_string(), VerifyStatus::Ok),
+        ]);
+    }
+
+    #[test]
+    fn test_detect_manifest_format() {
+        assert_eq!(
+            ManifestFormat::detect(Path::new("bag/manifest-sha512.txt")),
+            Some((ManifestFormat::BagIt, HashAlgorithm::Sha512))
+        );
+        assert_eq!(
+            ManifestFormat::detect(Path::new("MD5SUMS")),
+            Some((ManifestFormat::Coreutils, HashAlgorithm::Md5))
+        );
+        assert_eq!(
+            ManifestFormat::detect(Path::new("files.sha256")),
+            Some((ManifestFormat::Coreutils, HashAlgorithm::Sha256))
+        );
+        assert_eq!(ManifestFormat::detect(Path::new("notes.txt")), None);
+    }
+}
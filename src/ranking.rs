@@ -0,0 +1,216 @@
+//! Largest files and directories
+//!
+//! Ranks the entries of a scan's [`TreeNode`] hierarchy three ways: the
+//! largest files, the directories taking the most space with everything
+//! below them (as `du` counts it), and the directories holding the most
+//! files directly. Only the requested number of entries is kept while the
+//! tree is walked, so ranking a scan of millions of files stays cheap.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::scanner::format_file_size;
+use crate::TreeNode;
+
+/// Entries each ranking lists unless asked otherwise
+pub const DEFAULT_TOP_COUNT: usize = 10;
+
+/// One entry of a ranking
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RankedEntry {
+    pub path: PathBuf,
+    /// Bytes taken by the files counted in `file_count`
+    pub size: u64,
+    pub file_count: usize,
+}
+
+/// The largest entries of a scan, each list largest first, with ties in
+/// path order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Rankings {
+    /// Files by size
+    pub largest_files: Vec<RankedEntry>,
+    /// Directories below the scanned one by the total size of every file
+    /// below them
+    pub largest_directories: Vec<RankedEntry>,
+    /// Directories by the number of files directly inside them; files in
+    /// their subdirectories are left out of the count and the size
+    pub most_files: Vec<RankedEntry>,
+}
+
+impl Rankings {
+    /// Rank the entries below `tree`, keeping `count` in each list
+    pub fn from_tree(tree: &TreeNode, count: usize) -> Self {
+        let mut ranker = Ranker {
+            files: Top::new(count),
+            directories: Top::new(count),
+            most_files: Top::new(count),
+        };
+        ranker.visit(tree, true);
+        Self {
+            largest_files: ranker.files.into_entries(),
+            largest_directories: ranker.directories.into_entries(),
+            most_files: ranker.most_files.into_entries(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.largest_files.is_empty() && self.largest_directories.is_empty() && self.most_files.is_empty()
+    }
+
+    /// The rankings as numbered lists for the analysis summary
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        push_ranking(&mut summary, "Largest files", &self.largest_files, |entry| {
+            (format_file_size(entry.size), String::new())
+        });
+        push_ranking(&mut summary, "Largest directories", &self.largest_directories, |entry| {
+            (format_file_size(entry.size), format!(" ({})", file_count(entry.file_count)))
+        });
+        push_ranking(&mut summary, "Most files", &self.most_files, |entry| {
+            (file_count(entry.file_count), format!(" ({})", format_file_size(entry.size)))
+        });
+        summary
+    }
+}
+
+/// "1 file" or "N files"
+fn file_count(count: usize) -> String {
+    if count == 1 {
+        "1 file".to_string()
+    } else {
+        format!("{} files", count)
+    }
+}
+
+/// Append a titled, numbered list, with `describe` giving each entry's
+/// value and a note after its path; nothing for an empty list
+fn push_ranking(summary: &mut String, title: &str, entries: &[RankedEntry], describe: impl Fn(&RankedEntry) -> (String, String)) {
+    if entries.is_empty() {
+        return;
+    }
+    if !summary.is_empty() {
+        summary.push('\n');
+    }
+    summary.push_str(&format!("{}:\n", title));
+    for (rank, entry) in entries.iter().enumerate() {
+        let (value, note) = describe(entry);
+        summary.push_str(&format!("  {:>2}. {:>10}  {}{}\n", rank + 1, value, entry.path.display(), note));
+    }
+}
+
+struct Ranker<'a> {
+    files: Top<'a>,
+    directories: Top<'a>,
+    most_files: Top<'a>,
+}
+
+impl<'a> Ranker<'a> {
+    fn visit(&mut self, node: &'a TreeNode, is_root: bool) {
+        if !node.is_directory {
+            self.files.offer(node.size, &node.path, node.size, 1);
+            return;
+        }
+        // The scanned directory would always top the list
+        if !is_root {
+            self.directories.offer(node.size, &node.path, node.size, node.file_count);
+        }
+        let (count, bytes) = node.children
+            .iter()
+            .filter(|child| !child.is_directory)
+            .fold((0, 0), |(count, bytes), file| (count + 1, bytes + file.size));
+        if count > 0 {
+            self.most_files.offer(count as u64, &node.path, bytes, count);
+        }
+        for child in &node.children {
+            self.visit(child, false);
+        }
+    }
+}
+
+/// Key, path (reversed so earlier paths win ties), size and file count
+type Candidate<'a> = (u64, Reverse<&'a Path>, u64, usize);
+
+/// The `count` entries with the largest keys seen so far
+struct Top<'a> {
+    count: usize,
+    /// The entry to drop next is on top
+    heap: BinaryHeap<Reverse<Candidate<'a>>>,
+}
+
+impl<'a> Top<'a> {
+    fn new(count: usize) -> Self {
+        Self { count, heap: BinaryHeap::with_capacity(count.min(1024) + 1) }
+    }
+
+    fn offer(&mut self, key: u64, path: &'a Path, size: u64, file_count: usize) {
+        let entry = Reverse((key, Reverse(path), size, file_count));
+        if self.heap.len() < self.count {
+            self.heap.push(entry);
+        } else if let Some(mut smallest) = self.heap.peek_mut() {
+            if entry < *smallest {
+                *smallest = entry;
+            }
+        }
+    }
+
+    fn into_entries(self) -> Vec<RankedEntry> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((_, Reverse(path), size, file_count))| RankedEntry { path: path.to_path_buf(), size, file_count })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rankings() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("logs/old")).unwrap();
+        std::fs::create_dir(root.join("media")).unwrap();
+        for (path, size) in [
+            ("logs/a.log", 10),
+            ("logs/b.log", 10),
+            ("logs/c.log", 30),
+            ("logs/old/d.log", 5),
+            ("media/clip.mp4", 500),
+            ("notes.txt", 20),
+        ] {
+            std::fs::write(root.join(path), vec![0u8; size]).unwrap();
+        }
+        let tree = crate::DirectoryScanner::new().skip_virtual_filesystems(false).scan_tree(root).unwrap();
+
+        let rankings = Rankings::from_tree(&tree, 2);
+        let entry = |path: &str, size, file_count| RankedEntry { path: root.join(path), size, file_count };
+        assert_eq!(rankings.largest_files, [entry("media/clip.mp4", 500, 1), entry("logs/c.log", 30, 1)]);
+        assert_eq!(rankings.largest_directories, [entry("media", 500, 1), entry("logs", 55, 4)]);
+        assert_eq!(rankings.most_files, [entry("logs", 50, 3), entry("", 20, 1)]);
+
+        // Equal sizes are listed in path order
+        let files: Vec<RankedEntry> = Rankings::from_tree(&tree, 10).largest_files;
+        assert_eq!(files.len(), 6);
+        assert_eq!(files[3..5], [entry("logs/a.log", 10, 1), entry("logs/b.log", 10, 1)]);
+        assert!(Rankings::from_tree(&tree, 0).is_empty());
+
+        let summary = rankings.summary();
+        assert!(summary.starts_with("Largest files:\n   1.      500 B  "));
+        assert!(summary.contains("\n\nMost files:\n   1.    3 files  "));
+        assert!(summary.ends_with("(20 B)\n"));
+
+        // The analysis summary lists as many entries as the scan asked for
+        let scanner = crate::DirectoryScanner::new().skip_virtual_filesystems(false);
+        let (_, analysis) = crate::scan_and_analyze_with_progress(root, &scanner, 2, std::sync::Arc::new(|_, _| {})).unwrap();
+        assert_eq!(analysis.top_count, 2);
+        assert!(analysis.summary().ends_with(&summary));
+        let analysis = crate::scan_and_analyze_each(root, &scanner, 0, std::sync::Arc::new(|_, _| {}), |_| {}).unwrap();
+        assert!(!analysis.summary().contains("Largest files:"));
+    }
+}
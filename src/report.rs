@@ -0,0 +1,170 @@
+//! Structured reporting of entries that could not be scanned
+//!
+//! Scans never abort because of a single unreadable file or directory.
+//! Instead, each skipped path is recorded in a [`ScanReport`] together with
+//! the kind of I/O error and the phase of the scan in which it occurred, so
+//! that the gaps in an inventory can be reviewed and exported.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Stage of the scan in which a problem occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ScanPhase {
+    /// Listing a directory or walking to an entry
+    Traversal,
+    /// Reading file metadata (size, timestamps)
+    Metadata,
+    /// Reading file contents for hashing
+    Hashing,
+}
+
+impl ScanPhase {
+    /// Returns a human-readable name for the phase
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScanPhase::Traversal => "Traversal",
+            ScanPhase::Metadata => "Metadata",
+            ScanPhase::Hashing => "Hashing",
+        }
+    }
+}
+
+impl std::fmt::Display for ScanPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A single path that was skipped during a scan
+#[derive(Debug, Clone)]
+pub struct ScanIssue {
+    pub path: PathBuf,
+    pub kind: io::ErrorKind,
+    pub phase: ScanPhase,
+    pub message: String,
+}
+
+impl ScanIssue {
+    pub fn new(path: &Path, phase: ScanPhase, error: &io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            kind: error.kind(),
+            phase,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Collection of all paths skipped during a scan
+#[derive(Debug, Clone, Default)]
+pub struct ScanReport {
+    pub issues: Vec<ScanIssue>,
+}
+
+impl ScanReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    pub fn record(&mut self, issue: ScanIssue) {
+        self.issues.push(issue);
+    }
+
+    /// Sort issues by path so reports are stable between runs
+    pub fn sort(&mut self) {
+        self.issues.sort_by(|a, b| a.path.cmp(&b.path).then(a.phase.cmp(&b.phase)));
+    }
+
+    /// Number of issues recorded in a given phase
+    pub fn count_in_phase(&self, phase: ScanPhase) -> usize {
+        self.issues.iter().filter(|issue| issue.phase == phase).count()
+    }
+
+    /// Get a one-line summary of the problems encountered
+    pub fn summary(&self) -> String {
+        if self.issues.is_empty() {
+            return "No problems encountered".to_string();
+        }
+
+        let parts: Vec<String> = [ScanPhase::Traversal, ScanPhase::Metadata, ScanPhase::Hashing]
+            .iter()
+            .map(|phase| (phase, self.count_in_phase(*phase)))
+            .filter(|(_, count)| *count > 0)
+            .map(|(phase, count)| format!("{} {}", count, phase.name().to_lowercase()))
+            .collect();
+
+        format!("{} entries could not be scanned ({})", self.issues.len(), parts.join(", "))
+    }
+
+    /// Write the report as CSV with a header row
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "Path,Phase,Error Kind,Message")?;
+        for issue in &self.issues {
+            writeln!(
+                writer,
+                "\"{}\",{},{:?},\"{}\"",
+                issue.path.to_string_lossy().replace('"', "\"\""),
+                issue.phase,
+                issue.kind,
+                issue.message.replace('"', "\"\""),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Thread-safe collector used while a scan is in progress
+#[derive(Default)]
+pub(crate) struct IssueCollector {
+    report: Mutex<ScanReport>,
+}
+
+impl IssueCollector {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(&self, issue: ScanIssue) {
+        if let Ok(mut report) = self.report.lock() {
+            report.record(issue);
+        }
+    }
+
+    pub(crate) fn into_report(self) -> ScanReport {
+        let mut report = self.report.into_inner().unwrap_or_default();
+        report.sort();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_csv_escapes_quotes() {
+        let mut report = ScanReport::new();
+        let error = io::Error::new(io::ErrorKind::PermissionDenied, "denied \"here\"");
+        report.record(ScanIssue::new(Path::new("/data/a \"b\".txt"), ScanPhase::Hashing, &error));
+
+        let mut output = Vec::new();
+        report.write_csv(&mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+
+        assert_eq!(
+            csv,
+            "Path,Phase,Error Kind,Message\n\"/data/a \"\"b\"\".txt\",Hashing,PermissionDenied,\"denied \"\"here\"\"\"\n"
+        );
+        assert_eq!(report.summary(), "1 entries could not be scanned (1 hashing)");
+    }
+}
@@ -1,19 +1,31 @@
 use std::fs;
-use std::path::Path;
-use std::io::{self, Read};
-use std::time::SystemTime;
-use sha2::{Sha256, Sha512, Digest};
-use walkdir::WalkDir;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::io;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering};
-use rayon::prelude::*;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
 
 use crate::{FileInfo, TreeNode, ScanError};
+use crate::tree::{FileType, get_file_color};
+use crate::report::{IssueCollector, ScanIssue, ScanPhase, ScanReport};
+use crate::cache::{CacheEntry, CacheScope, FileKey, ScanCache};
+use crate::hashing::{calculate_file_hashes, Digest, FileHashes, HashAlgorithm};
+use crate::timestamps::TimeFormat;
+use crate::unix::UnixMetadata;
+use crate::filter::{CompiledFilter, PathFilter};
+use crate::ignores::IgnoreFiles;
+use crate::criteria::MetadataFilter;
+use crate::walk::Walker;
+use crate::stream::FileStream;
 
 /// Progress callback type for reporting scan progress
 pub type ProgressCallback = Arc<dyn Fn(f32, String) + Send + Sync>;
 
+/// Callback receiving each file as soon as it has been processed
+pub type FileCallback = Arc<dyn Fn(&FileInfo) + Send + Sync>;
+
 /// Virtual/pseudo filesystem types that should typically be skipped
 const VIRTUAL_FS_TYPES: &[&str] = &[
     "proc", "sysfs", "devfs", "devtmpfs", "tmpfs", "cgroup", "cgroup2",
@@ -41,9 +53,8 @@ pub struct DirectoryScanner {
     pub include_dotfiles: bool,
     pub max_depth: Option<usize>,
     pub follow_symlinks: bool,
-    pub calculate_sha256: bool,
-    pub calculate_sha512: bool,
-    pub calculate_md5: bool,
+    /// Digests calculated for every file, all in one read
+    pub hash_algorithms: BTreeSet<HashAlgorithm>,
     pub calculate_format: bool,
     pub calculate_mime: bool,
     pub cancellation_flag: Option<Arc<AtomicBool>>,
@@ -51,6 +62,18 @@ pub struct DirectoryScanner {
     pub skip_virtual_filesystems: bool,
     /// Stay on the same filesystem (don't cross mount boundaries)
     pub stay_on_filesystem: bool,
+    /// Cache of results from earlier scans, reused for unchanged files
+    pub cache: Option<Arc<ScanCache>>,
+    /// Include and exclude patterns; excluded directories are not descended
+    pub path_filter: PathFilter,
+    /// Skip entries matched by `.gitignore`, `.ignore` and global git excludes
+    pub respect_ignore_files: bool,
+    /// Size, date and type conditions checked before a file is processed
+    pub metadata_filter: MetadataFilter,
+    /// Called with every processed file while the scan runs, from worker threads
+    pub file_observer: Option<FileCallback>,
+    /// Zone and style for timestamps in text output made from this scan's results
+    pub time_format: TimeFormat,
 }
 
 impl std::fmt::Debug for DirectoryScanner {
@@ -59,14 +82,18 @@ impl std::fmt::Debug for DirectoryScanner {
             .field("include_dotfiles", &self.include_dotfiles)
             .field("max_depth", &self.max_depth)
             .field("follow_symlinks", &self.follow_symlinks)
-            .field("calculate_sha256", &self.calculate_sha256)
-            .field("calculate_sha512", &self.calculate_sha512)
-            .field("calculate_md5", &self.calculate_md5)
+            .field("hash_algorithms", &self.hash_algorithms)
             .field("calculate_format", &self.calculate_format)
             .field("calculate_mime", &self.calculate_mime)
             .field("skip_virtual_filesystems", &self.skip_virtual_filesystems)
             .field("stay_on_filesystem", &self.stay_on_filesystem)
             .field("cancellation_flag", &"<Arc<AtomicBool>>")
+            .field("cache", &self.cache.as_ref().map(|cache| cache.len()))
+            .field("path_filter", &self.path_filter)
+            .field("respect_ignore_files", &self.respect_ignore_files)
+            .field("metadata_filter", &self.metadata_filter)
+            .field("file_observer", &self.file_observer.as_ref().map(|_| "<FileCallback>"))
+            .field("time_format", &self.time_format)
             .finish()
     }
 }
@@ -77,14 +104,18 @@ impl Default for DirectoryScanner {
             include_dotfiles: false,
             max_depth: None,
             follow_symlinks: false,
-            calculate_sha256: true,
-            calculate_sha512: false,
-            calculate_md5: false,
+            hash_algorithms: BTreeSet::from([HashAlgorithm::Sha256]),
             calculate_format: false,
             calculate_mime: false,
             cancellation_flag: None,
             skip_virtual_filesystems: true,  // Safe default
             stay_on_filesystem: false,
+            cache: None,
+            path_filter: PathFilter::default(),
+            respect_ignore_files: false,
+            metadata_filter: MetadataFilter::default(),
+            file_observer: None,
+            time_format: TimeFormat::default(),
         }
     }
 }
@@ -109,19 +140,32 @@ impl DirectoryScanner {
         self
     }
     
-    pub fn calculate_sha256(mut self, calculate: bool) -> Self {
-        self.calculate_sha256 = calculate;
+    /// Enable or disable a single hash algorithm
+    pub fn calculate_hash(mut self, algorithm: HashAlgorithm, calculate: bool) -> Self {
+        if calculate {
+            self.hash_algorithms.insert(algorithm);
+        } else {
+            self.hash_algorithms.remove(&algorithm);
+        }
         self
     }
     
-    pub fn calculate_sha512(mut self, calculate: bool) -> Self {
-        self.calculate_sha512 = calculate;
+    /// Replace the set of hash algorithms
+    pub fn hash_algorithms<I: IntoIterator<Item = HashAlgorithm>>(mut self, algorithms: I) -> Self {
+        self.hash_algorithms = algorithms.into_iter().collect();
         self
     }
     
-    pub fn calculate_md5(mut self, calculate: bool) -> Self {
-        self.calculate_md5 = calculate;
-        self
+    pub fn calculate_sha256(self, calculate: bool) -> Self {
+        self.calculate_hash(HashAlgorithm::Sha256, calculate)
+    }
+    
+    pub fn calculate_sha512(self, calculate: bool) -> Self {
+        self.calculate_hash(HashAlgorithm::Sha512, calculate)
+    }
+    
+    pub fn calculate_md5(self, calculate: bool) -> Self {
+        self.calculate_hash(HashAlgorithm::Md5, calculate)
     }
     
     pub fn calculate_format(mut self, calculate: bool) -> Self {
@@ -149,6 +193,38 @@ impl DirectoryScanner {
         self
     }
     
+    /// Reuse hashes, formats and MIME types from a cache for unchanged files
+    pub fn cache(mut self, cache: Arc<ScanCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+    
+    /// Only include entries that pass the given patterns
+    pub fn path_filter(mut self, filter: PathFilter) -> Self {
+        self.path_filter = filter;
+        self
+    }
+    
+    pub fn respect_ignore_files(mut self, respect: bool) -> Self {
+        self.respect_ignore_files = respect;
+        self
+    }
+    
+    pub fn metadata_filter(mut self, filter: MetadataFilter) -> Self {
+        self.metadata_filter = filter;
+        self
+    }
+    
+    pub fn file_observer(mut self, observer: FileCallback) -> Self {
+        self.file_observer = Some(observer);
+        self
+    }
+    
+    pub fn time_format(mut self, format: TimeFormat) -> Self {
+        self.time_format = format;
+        self
+    }
+    
     /// Scan directory and return detailed file information
     pub fn scan_detailed(&self, path: &Path) -> Result<Vec<FileInfo>, ScanError> {
         self.scan_detailed_with_progress(path, None)
@@ -160,128 +236,269 @@ impl DirectoryScanner {
         path: &Path, 
         progress_callback: Option<ProgressCallback>
     ) -> Result<Vec<FileInfo>, ScanError> {
+        self.scan_detailed_with_report(path, progress_callback)
+            .map(|(files, _)| files)
+    }
+    
+    /// Scan directory with progress reporting, also returning a report of
+    /// every path that could not be scanned
+    ///
+    /// Files are processed in parallel as soon as their directory has been
+    /// listed. Progress is the share of the files found so far that have
+    /// been processed.
+    pub fn scan_detailed_with_report(
+        &self, 
+        path: &Path, 
+        progress_callback: Option<ProgressCallback>
+    ) -> Result<(Vec<FileInfo>, ScanReport), ScanError> {
+        let files = Mutex::new(Vec::new());
+        let report = self.scan_detailed_each(path, progress_callback.clone(), |info| {
+            files.lock().unwrap().push(info);
+        })?;
+        
+        let files = files.into_inner().unwrap();
+        Ok((finish_processing(files, &progress_callback), report))
+    }
+    
+    /// Scan directory, handing each file to `on_file` as soon as it has been
+    /// processed instead of collecting the list
+    ///
+    /// Files arrive in the order they finish processing, from worker
+    /// threads, so memory use does not grow with the number of files.
+    pub fn scan_detailed_each<F>(
+        &self,
+        path: &Path,
+        progress_callback: Option<ProgressCallback>,
+        on_file: F,
+    ) -> Result<ScanReport, ScanError>
+    where
+        F: Fn(FileInfo) + Sync,
+    {
         validate_path(path)?;
+        let issues = IssueCollector::new();
         
-        // Build mount info for virtual filesystem detection
-        let mount_info = if self.skip_virtual_filesystems || self.stay_on_filesystem {
-            Some(MountInfo::new(path)?)
-        } else {
-            None
-        };
+        let entry_filter = self.entry_filter(path)?;
+        let walker = self.walker(&issues);
+        let cache = self.cache_scope(path);
+        let processed = AtomicUsize::new(0);
         
-        let mut walker = WalkDir::new(path).follow_links(self.follow_symlinks);
+        // Excluded directories are never descended into
+        walker.walk_files(
+            path,
+            |entry, metadata| self.should_include_entry(entry, metadata, &entry_filter),
+            |file, metadata| {
+                if let Some(info) = self.process_found_file(file, metadata, cache.as_ref(), &issues) {
+                    on_file(info);
+                }
+                report_processing(&progress_callback, &processed, &walker);
+            },
+        )?;
         
-        if let Some(depth) = self.max_depth {
-            walker = walker.max_depth(depth);
+        if let Some(cache) = cache {
+            cache.finish();
         }
+        Ok(issues.into_report())
+    }
+    
+    /// Scan directory on a background thread, delivering files in batches
+    /// as they are processed
+    ///
+    /// The report (or the error that stopped the scan) is returned by
+    /// [`FileStream::finish`] once every batch has been read.
+    pub fn scan_detailed_streaming(
+        &self,
+        path: &Path,
+        progress_callback: Option<ProgressCallback>
+    ) -> FileStream {
+        FileStream::spawn(self.clone(), path.to_path_buf(), progress_callback)
+    }
+    
+    /// Scan directory once and build the detailed file list, tree structure,
+    /// statistics and file type counts from the same traversal
+    pub fn scan_all(&self, path: &Path) -> Result<CompleteScan, ScanError> {
+        self.scan_all_with_progress(path, None)
+    }
+    
+    /// Single-pass scan with progress reporting
+    ///
+    /// Files are processed (hashes, format and media type) while the tree is
+    /// still being walked; progress is the share of the files found so far
+    /// that have been processed.
+    pub fn scan_all_with_progress(
+        &self,
+        path: &Path,
+        progress_callback: Option<ProgressCallback>
+    ) -> Result<CompleteScan, ScanError> {
+        let issues = IssueCollector::new();
+        let walk = self.walk_structure(path, &progress_callback, &issues, FileHandling::Collect)?;
         
-        // Collect all file paths first (sequential traversal)
-        let files: Vec<_> = walker
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| {
-                // Check cancellation before processing each entry
-                if let Some(ref flag) = self.cancellation_flag {
-                    if flag.load(Ordering::Relaxed) {
-                        return false;
-                    }
-                }
-                e.file_type().is_file()
-            })
-            .filter(|e| self.should_include_entry(e.path(), &mount_info))
-            .collect();
+        Ok(CompleteScan {
+            files: walk.files,
+            tree: walk.tree,
+            stats: walk.stats,
+            file_type_counts: walk.file_type_counts,
+            report: issues.into_report(),
+        })
+    }
+    
+    /// Single-pass scan that hands each file to `on_file` as soon as it has
+    /// been processed instead of collecting the list, so that callers can
+    /// store the results in a compact form such as a
+    /// [`FileTable`](crate::FileTable). The returned `files` is empty.
+    pub fn scan_all_each<F>(
+        &self,
+        path: &Path,
+        progress_callback: Option<ProgressCallback>,
+        on_file: F,
+    ) -> Result<CompleteScan, ScanError>
+    where
+        F: Fn(FileInfo) + Sync,
+    {
+        let issues = IssueCollector::new();
+        let walk = self.walk_structure(path, &progress_callback, &issues, FileHandling::Each(&on_file))?;
         
-        // Check cancellation after collection
-        if let Some(ref flag) = self.cancellation_flag {
-            if flag.load(Ordering::Relaxed) {
-                return Err(ScanError::Cancelled);
-            }
+        if let Some(ref callback) = progress_callback {
+            callback(1.0, "Scan completed".to_string());
         }
         
-        let total_files = files.len();
+        Ok(CompleteScan {
+            files: Vec::new(),
+            tree: walk.tree,
+            stats: walk.stats,
+            file_type_counts: walk.file_type_counts,
+            report: issues.into_report(),
+        })
+    }
+    
+    /// Build the tree, statistics and file type counts in one traversal
+    /// without processing file contents
+    pub(crate) fn scan_structure_with_progress(
+        &self,
+        path: &Path,
+        progress_callback: Option<ProgressCallback>
+    ) -> Result<(TreeNode, DirectoryStats, HashMap<FileType, usize>, ScanReport), ScanError> {
+        let issues = IssueCollector::new();
+        let walk = self.walk_structure(path, &progress_callback, &issues, FileHandling::Stat)?;
         
-        if total_files == 0 {
-            if let Some(ref callback) = progress_callback {
-                callback(1.0, "No files found".to_string());
-            }
-            return Ok(Vec::new());
+        if let Some(ref callback) = progress_callback {
+            callback(1.0, "Analysis completed".to_string());
         }
         
-        // Sort files by depth (directory level) first, then by path
-        // This helps with disk locality
-        let mut file_paths: Vec<_> = files.iter()
-            .map(|e| e.path().to_path_buf())
-            .collect();
-        
-        file_paths.sort_by(|a, b| {
-            let depth_a = a.components().count();
-            let depth_b = b.components().count();
-            
-            match depth_a.cmp(&depth_b) {
-                std::cmp::Ordering::Equal => a.cmp(b),
-                other => other
-            }
-        });
+        Ok((walk.tree, walk.stats, walk.file_type_counts, issues.into_report()))
+    }
+    
+    /// Walk the directory once, assembling the tree and statistics and,
+    /// unless only statistics are wanted, processing each file as soon as it
+    /// is found
+    fn walk_structure(
+        &self,
+        path: &Path,
+        progress_callback: &Option<ProgressCallback>,
+        issues: &IssueCollector,
+        handling: FileHandling<'_>,
+    ) -> Result<WalkOutput, ScanError> {
+        validate_path(path)?;
         
-        // Atomic counter for progress tracking
-        let processed = Arc::new(AtomicUsize::new(0));
-        let processed_clone = processed.clone();
+        let entry_filter = self.entry_filter(path)?;
+        let walker = self.walker(issues);
+        // Only scans that process file contents use the cache
+        let cache = match handling {
+            FileHandling::Stat => None,
+            FileHandling::Collect | FileHandling::Each(_) => self.cache_scope(path),
+        };
+        let visited = AtomicUsize::new(0);
         
-        // Process files in parallel
-        let calculate_sha256 = self.calculate_sha256;
-        let calculate_sha512 = self.calculate_sha512;
-        let calculate_md5 = self.calculate_md5;
-        let calculate_format = self.calculate_format;
-        let calculate_mime = self.calculate_mime;
-        let cancellation_flag = self.cancellation_flag.clone();
+        if let Some(ref callback) = progress_callback {
+            callback(0.0, format!("Scanning: {}", path.display()));
+        }
         
-        let file_infos: Vec<FileInfo> = file_paths
-            .par_iter()
-            .filter_map(|path| {
-                // Check cancellation before processing each file
-                if let Some(ref flag) = cancellation_flag {
-                    if flag.load(Ordering::Relaxed) {
-                        return None;
+        let mut root = walker.walk(
+            path,
+            |entry, metadata| self.should_include_entry(entry, metadata, &entry_filter),
+            |file, metadata| {
+                match handling {
+                    FileHandling::Stat => {}
+                    FileHandling::Collect => {
+                        let info = self.process_found_file(file, metadata, cache.as_ref(), issues);
+                        report_processing(progress_callback, &visited, &walker);
+                        return Some(FoundFile {
+                            size: info.as_ref().map(|info| info.size),
+                            modified: info.as_ref().and_then(|info| info.last_modified),
+                            unix: info.as_ref().and_then(|info| info.unix),
+                            info,
+                        });
                     }
-                }
-                
-                // Process the file
-                let result = process_file_with_hash_options(path, calculate_sha256, calculate_sha512, calculate_md5, calculate_format, calculate_mime);
-                
-                // Update progress (with throttling to avoid callback spam)
-                if let Some(ref callback) = progress_callback {
-                    let current = processed_clone.fetch_add(1, Ordering::Relaxed) + 1;
-                    
-                    // Only update progress every 10 files or on last file to reduce overhead
-                    if current % 10 == 0 || current == total_files {
-                        let progress = current as f32 / total_files as f32;
-                        let status = format!("Processing: {} of {} files", current, total_files);
-                        callback(progress, status);
+                    FileHandling::Each(on_file) => {
+                        let info = self.process_found_file(file, metadata, cache.as_ref(), issues);
+                        report_processing(progress_callback, &visited, &walker);
+                        let (size, modified, unix) = match info {
+                            Some(info) => {
+                                let found = (Some(info.size), info.last_modified, info.unix);
+                                on_file(info);
+                                found
+                            }
+                            None => (None, None, None),
+                        };
+                        return Some(FoundFile { size, modified, unix, info: None });
                     }
                 }
-                
-                match result {
-                    Ok(info) => Some(info),
-                    Err(e) => {
-                        eprintln!("Error processing file '{}': {}", path.display(), e);
-                        None
+                report_discovery(progress_callback, &visited, &walker);
+                Some(FoundFile {
+                    size: Some(metadata.len()),
+                    modified: metadata.modified().ok().map(DateTime::from),
+                    unix: UnixMetadata::from_metadata(metadata),
+                    info: None,
+                })
+            },
+        )?;
+        if let Some(cache) = cache {
+            cache.finish();
+        }
+        
+        let mut stats = DirectoryStats::default();
+        let mut file_type_counts = HashMap::new();
+        root.for_each_mut(&mut |node| {
+            let (_, file_type) = get_file_color(&node.path, &node.name, node.is_dir);
+            *file_type_counts.entry(file_type).or_insert(0) += 1;
+            
+            if node.depth > 0 && node.is_dir {
+                stats.directory_count += 1;
+            } else if node.is_file {
+                stats.file_count += 1;
+                if let Some(found) = &node.data {
+                    if let Some(size) = found.size {
+                        stats.total_size += size;
+                        stats.size_distribution.add_file(size);
+                        node.size = size;
                     }
+                    node.modified = found.modified;
+                    node.unix = found.unix;
+                    stats.add_modified(found.modified);
                 }
-            })
-            .collect();
-        
-        // Check cancellation after processing
-        if let Some(ref flag) = self.cancellation_flag {
-            if flag.load(Ordering::Relaxed) {
-                return Err(ScanError::Cancelled);
             }
+        });
+        // The root is always the base of the tree, but only counted in the
+        // statistics when the filters would include it
+        if fs::metadata(path).is_ok_and(|metadata| self.should_include_entry(path, &metadata, &entry_filter)) {
+            stats.directory_count += 1;
         }
+        stats.ignored_count = entry_filter.ignored_count();
         
-        if let Some(ref callback) = progress_callback {
-            callback(1.0, format!("Scan completed: {} files processed", total_files));
-        }
+        let mut found = Vec::new();
+        root.take_data(&mut found);
+        let files = match handling {
+            FileHandling::Collect => {
+                finish_processing(found.into_iter().filter_map(|file| file.info).collect(), progress_callback)
+            }
+            FileHandling::Stat | FileHandling::Each(_) => Vec::new(),
+        };
         
-        Ok(file_infos)
+        Ok(WalkOutput {
+            tree: root.into_tree(),
+            stats,
+            file_type_counts,
+            files,
+        })
     }
     
     /// Scan directory and return tree structure
@@ -295,26 +512,49 @@ impl DirectoryScanner {
         path: &Path,
         progress_callback: Option<ProgressCallback>
     ) -> Result<TreeNode, ScanError> {
+        self.scan_tree_with_report(path, progress_callback)
+            .map(|(tree, _)| tree)
+    }
+    
+    /// Scan directory tree with progress reporting, also returning a report
+    /// of every directory that could not be read
+    pub fn scan_tree_with_report(
+        &self, 
+        path: &Path,
+        progress_callback: Option<ProgressCallback>
+    ) -> Result<(TreeNode, ScanReport), ScanError> {
         validate_path(path)?;
+        let issues = IssueCollector::new();
         
-        // Build mount info for virtual filesystem detection
-        let mount_info = if self.skip_virtual_filesystems || self.stay_on_filesystem {
-            Some(MountInfo::new(path)?)
-        } else {
-            None
-        };
+        let entry_filter = self.entry_filter(path)?;
+        let walker = self.walker(&issues);
+        let visited = AtomicUsize::new(0);
         
         if let Some(ref callback) = progress_callback {
             callback(0.0, format!("Scanning: {}", path.display()));
         }
         
-        let result = self.build_tree_node(path, 0, &progress_callback, &mount_info);
+        let mut root = walker.walk(
+            path,
+            |entry, metadata| self.should_include_entry(entry, metadata, &entry_filter),
+            |_, metadata| {
+                report_discovery(&progress_callback, &visited, &walker);
+                Some(metadata.clone())
+            },
+        )?;
+        root.for_each_mut(&mut |node| {
+            if let Some(metadata) = node.data.take() {
+                node.size = metadata.len();
+                node.modified = metadata.modified().ok().map(DateTime::from);
+                node.unix = UnixMetadata::from_metadata(&metadata);
+            }
+        });
         
         if let Some(ref callback) = progress_callback {
             callback(1.0, "Tree scan completed".to_string());
         }
         
-        result
+        Ok((root.into_tree(), issues.into_report()))
     }
     
     /// Get directory statistics without building full structures
@@ -328,68 +568,70 @@ impl DirectoryScanner {
         path: &Path,
         progress_callback: Option<ProgressCallback>
     ) -> Result<DirectoryStats, ScanError> {
-        validate_path(path)?;
+        let walk = self.walk_structure(path, &progress_callback, &IssueCollector::new(), FileHandling::Stat)?;
         
-        // Build mount info for virtual filesystem detection
-        let mount_info = if self.skip_virtual_filesystems || self.stay_on_filesystem {
-            Some(MountInfo::new(path)?)
-        } else {
-            None
-        };
-        
-        let mut stats = DirectoryStats::default();
-        let mut walker = WalkDir::new(path).follow_links(self.follow_symlinks);
-        
-        if let Some(depth) = self.max_depth {
-            walker = walker.max_depth(depth);
+        if let Some(ref callback) = progress_callback {
+            callback(1.0, "Analysis completed".to_string());
         }
         
-        let entries: Vec<_> = walker.into_iter().filter_map(|e| e.ok()).collect();
-        let total = entries.len();
-        
-        for (i, entry) in entries.iter().enumerate() {
-            // Check cancellation
-            if let Some(ref flag) = self.cancellation_flag {
-                if flag.load(Ordering::Relaxed) {
-                    return Err(ScanError::Cancelled);
+        Ok(walk.stats)
+    }
+    
+    /// Parallel walker with this scanner's traversal settings
+    fn walker<'a>(&'a self, issues: &'a IssueCollector) -> Walker<'a> {
+        Walker::new(self.follow_symlinks, self.max_depth, self.cancellation_flag.as_deref(), issues)
+    }
+    
+    /// This scanner's cache, if any, set up for a scan rooted at `path`
+    fn cache_scope<'a>(&'a self, path: &'a Path) -> Option<CacheScope<'a>> {
+        self.cache.as_deref().map(|cache| CacheScope::new(cache, path))
+    }
+    
+    /// Process a file found by the walker, recording it if it cannot be read
+    fn process_found_file(&self, path: &Path, metadata: &fs::Metadata, cache: Option<&CacheScope>, issues: &IssueCollector) -> Option<FileInfo> {
+        match self.process_file_with_options(path, metadata, cache) {
+            Ok(info) => {
+                if let Some(ref observer) = self.file_observer {
+                    observer(&info);
                 }
+                Some(info)
             }
-            
-            if let Some(ref callback) = progress_callback {
-                let progress = (i + 1) as f32 / total as f32;
-                callback(progress, format!("Analyzing: {}", entry.path().display()));
-            }
-            
-            if !self.should_include_entry(entry.path(), &mount_info) {
-                continue;
-            }
-            
-            if entry.file_type().is_dir() {
-                stats.directory_count += 1;
-            } else if entry.file_type().is_file() {
-                stats.file_count += 1;
-                if let Ok(metadata) = entry.metadata() {
-                    let size = metadata.len();
-                    stats.total_size += size;
-                    stats.size_distribution.add_file(size);
-                }
+            Err((phase, e)) => {
+                issues.record(ScanIssue::new(path, phase, &e));
+                None
             }
         }
-        
-        if let Some(ref callback) = progress_callback {
-            callback(1.0, "Analysis completed".to_string());
-        }
-        
-        Ok(stats)
     }
     
     /// Process a file with scanner options
-    fn process_file_with_options(&self, path: &Path) -> io::Result<FileInfo> {
-        process_file_with_hash_options(path, self.calculate_sha256, self.calculate_sha512, self.calculate_md5, self.calculate_format, self.calculate_mime)
+    fn process_file_with_options(&self, path: &Path, metadata: &fs::Metadata, cache: Option<&CacheScope>) -> Result<FileInfo, (ScanPhase, io::Error)> {
+        let algorithms: Vec<HashAlgorithm> = self.hash_algorithms.iter().copied().collect();
+        process_file_checked(path, metadata, &algorithms, self.calculate_format, self.calculate_mime, cache)
     }
     
-    /// Check if a file/directory should be included based on scanner settings
-    fn should_include_entry(&self, path: &Path, mount_info: &Option<MountInfo>) -> bool {
+    /// Set up the mount and pattern checks for a scan rooted at `path`
+    fn entry_filter(&self, path: &Path) -> Result<EntryFilter, ScanError> {
+        // Build mount info for virtual filesystem detection
+        let mount_info = if self.skip_virtual_filesystems || self.stay_on_filesystem {
+            Some(MountInfo::new(path)?)
+        } else {
+            None
+        };
+        let patterns = if self.path_filter.is_empty() {
+            None
+        } else {
+            Some(self.path_filter.compile(path).map_err(|e| ScanError::InvalidFilter(e.to_string()))?)
+        };
+        let ignore_files = self.respect_ignore_files.then(|| IgnoreFiles::new(path));
+        let metadata = (!self.metadata_filter.is_empty()).then(|| self.metadata_filter.clone());
+        Ok(EntryFilter { mount_info, patterns, ignore_files, metadata })
+    }
+    
+    /// Check if a file/directory should be included based on scanner settings,
+    /// given the metadata the walker read for it
+    fn should_include_entry(&self, path: &Path, metadata: &fs::Metadata, entry_filter: &EntryFilter) -> bool {
+        let is_dir = metadata.is_dir();
+        
         // Check dotfiles filter
         if !self.include_dotfiles {
             // Check all components in the path for dotfiles/directories (starting with '.')
@@ -403,93 +645,133 @@ impl DirectoryScanner {
         }
         
         // Check virtual filesystem and mount boundary filters
-        if let Some(ref info) = mount_info {
+        if let Some(ref info) = entry_filter.mount_info {
             if !info.should_include_path(path, self.skip_virtual_filesystems, self.stay_on_filesystem) {
                 return false;
             }
         }
         
-        true
-    }
-    
-    /// Recursively build tree structure
-    fn build_tree_node(
-        &self, 
-        path: &Path, 
-        current_depth: usize,
-        progress_callback: &Option<ProgressCallback>,
-        mount_info: &Option<MountInfo>,
-    ) -> Result<TreeNode, ScanError> {
-        // Check cancellation at the start of each node
-        if let Some(ref flag) = self.cancellation_flag {
-            if flag.load(Ordering::Relaxed) {
-                return Err(ScanError::Cancelled);
+        // Check include and exclude patterns
+        if let Some(ref patterns) = entry_filter.patterns {
+            if !patterns.is_included(path, is_dir) {
+                return false;
             }
         }
         
-        let name = path.file_name()
-            .unwrap_or(path.as_os_str())
-            .to_string_lossy()
-            .to_string();
-        
-        if let Some(ref callback) = progress_callback {
-            callback(0.0, format!("Processing: {}", path.display()));
+        // Check .gitignore and .ignore files
+        if let Some(ref ignore_files) = entry_filter.ignore_files {
+            if ignore_files.is_ignored(path, is_dir) {
+                return false;
+            }
         }
         
-        let mut children = Vec::new();
-        
-        if path.is_dir() && (self.max_depth.is_none() || current_depth < self.max_depth.unwrap()) {
-            let entries = fs::read_dir(path)?;
-            let mut child_paths = Vec::new();
-            
-            for entry in entries {
-                // Check cancellation in directory read loop
-                if let Some(ref flag) = self.cancellation_flag {
-                    if flag.load(Ordering::Relaxed) {
-                        return Err(ScanError::Cancelled);
-                    }
-                }
-                
-                let entry = entry?;
-                let child_path = entry.path();
-                
-                if self.should_include_entry(&child_path, mount_info) {
-                    child_paths.push(child_path);
-                }
-            }
-            
-            // Sort children alphabetically (case-insensitive)
-            child_paths.sort_by(|a, b| {
-                let name_a = a.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
-                let name_b = b.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
-                name_a.cmp(&name_b)
-            });
-            
-            for child_path in child_paths {
-                // Check cancellation before processing each child
-                if let Some(ref flag) = self.cancellation_flag {
-                    if flag.load(Ordering::Relaxed) {
-                        return Err(ScanError::Cancelled);
-                    }
-                }
-                
-                match self.build_tree_node(&child_path, current_depth + 1, progress_callback, mount_info) {
-                    Ok(child_node) => children.push(child_node),
-                    Err(ScanError::Cancelled) => return Err(ScanError::Cancelled),
-                    Err(e) => eprintln!("Error building tree for '{}': {}", child_path.display(), e),
-                }
+        // Check size, date and type conditions
+        if let Some(ref criteria) = entry_filter.metadata {
+            if !is_dir && !criteria.matches(path, metadata) {
+                return false;
             }
         }
         
-        Ok(TreeNode {
-            name,
-            path: path.to_path_buf(),
-            is_directory: path.is_dir(),
-            children,
-        })
+        true
+    }
+}
+
+/// Per-scan state deciding which entries are included
+struct EntryFilter {
+    mount_info: Option<MountInfo>,
+    patterns: Option<CompiledFilter>,
+    ignore_files: Option<IgnoreFiles>,
+    metadata: Option<MetadataFilter>,
+}
+
+impl EntryFilter {
+    /// Number of entries skipped because of ignore files
+    fn ignored_count(&self) -> usize {
+        self.ignore_files.as_ref().map_or(0, IgnoreFiles::ignored_count)
     }
 }
 
+/// Results of a single-pass scan, covering every view of the directory
+#[derive(Debug, Clone)]
+pub struct CompleteScan {
+    /// Detailed information for every included file
+    pub files: Vec<FileInfo>,
+    /// Directory tree rooted at the scanned path
+    pub tree: TreeNode,
+    /// Aggregate counts and sizes
+    pub stats: DirectoryStats,
+    /// Number of tree nodes of each file type (including directories)
+    pub file_type_counts: HashMap<FileType, usize>,
+    /// Paths that could not be scanned
+    pub report: ScanReport,
+}
+
+/// Output of the structure walk
+struct WalkOutput {
+    tree: TreeNode,
+    stats: DirectoryStats,
+    file_type_counts: HashMap<FileType, usize>,
+    /// Processed files in depth-then-path order; empty unless requested
+    files: Vec<FileInfo>,
+}
+
+/// What the structure walk does with each file it finds
+#[derive(Clone, Copy)]
+enum FileHandling<'a> {
+    /// Only read its size for the statistics
+    Stat,
+    /// Process it and return the results in depth-then-path order
+    Collect,
+    /// Process it and hand the result to a callback
+    Each(&'a (dyn Fn(FileInfo) + Sync)),
+}
+
+/// What the structure walk keeps for each file
+struct FoundFile {
+    size: Option<u64>,
+    modified: Option<DateTime<Utc>>,
+    unix: Option<UnixMetadata>,
+    info: Option<FileInfo>,
+}
+
+/// Report file processing progress, throttled to every 10 files
+fn report_processing(progress_callback: &Option<ProgressCallback>, processed: &AtomicUsize, walker: &Walker) {
+    if let Some(ref callback) = progress_callback {
+        let current = processed.fetch_add(1, Ordering::Relaxed) + 1;
+        if current.is_multiple_of(10) {
+            let found = walker.files_found().max(current);
+            callback(current as f32 / found as f32, format!("Processing: {} of {} files found", current, found));
+        }
+    }
+}
+
+/// Report how many files the walk has found, every 1000 files
+fn report_discovery(progress_callback: &Option<ProgressCallback>, visited: &AtomicUsize, walker: &Walker) {
+    if let Some(ref callback) = progress_callback {
+        if (visited.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(1000) {
+            callback(0.0, format!("Scanning: {} files found", walker.files_found()));
+        }
+    }
+}
+
+/// Put processed files in depth-then-path order, so the output is the same
+/// whichever threads finished first
+fn finish_processing(mut files: Vec<FileInfo>, progress_callback: &Option<ProgressCallback>) -> Vec<FileInfo> {
+    files.sort_by_cached_key(|info| {
+        let path = PathBuf::from(&info.full_path);
+        (path.components().count(), path)
+    });
+    
+    if let Some(ref callback) = progress_callback {
+        if files.is_empty() {
+            callback(1.0, "No files found".to_string());
+        } else {
+            callback(1.0, format!("Scan completed: {} files processed", files.len()));
+        }
+    }
+    files
+}
+
 // ============================================================================
 // Mount information for virtual filesystem detection
 // ============================================================================
@@ -726,10 +1008,15 @@ pub struct DirectoryStats {
     pub total_size: u64,
     /// File size distribution counts
     pub size_distribution: FileSizeDistribution,
+    /// Entries skipped because of `.gitignore` or `.ignore` rules
+    pub ignored_count: usize,
+    /// Earliest and latest modification times among the files
+    pub oldest_modified: Option<DateTime<Utc>>,
+    pub newest_modified: Option<DateTime<Utc>>,
 }
 
 /// Distribution of files by size ranges
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, serde::Serialize)]
 pub struct FileSizeDistribution {
     /// Empty files (0 bytes)
     pub empty: usize,
@@ -760,55 +1047,94 @@ pub struct FileSizeDistribution {
 }
 
 impl FileSizeDistribution {
-    /// Categorize a file size and increment the appropriate counter
-    pub fn add_file(&mut self, size: u64) {
+    /// Labels of the size ranges, smallest first
+    pub const LABELS: [&'static str; 13] = [
+        "Empty (0 bytes)",
+        "1 B – 9 B",
+        "10 B – 99 B",
+        "100 B – 999 B",
+        "1 KB – 9.99 KB",
+        "10 KB – 99.99 KB",
+        "100 KB – 999.99 KB",
+        "1 MB – 9.99 MB",
+        "10 MB – 99.99 MB",
+        "100 MB – 999.99 MB",
+        "1 GB – 9.99 GB",
+        "10 GB – 99.99 GB",
+        "100 GB+",
+    ];
+    
+    /// Index into [`FileSizeDistribution::LABELS`] of the range a file size falls in
+    pub fn bucket(size: u64) -> usize {
         const KB: u64 = 1024;
         const MB: u64 = 1024 * KB;
         const GB: u64 = 1024 * MB;
         
         match size {
-            0 => self.empty += 1,
-            1..=9 => self.tiny += 1,
-            10..=99 => self.very_small += 1,
-            100..=999 => self.small += 1,
-            s if s < 10 * KB => self.small_kb += 1,
-            s if s < 100 * KB => self.medium_kb += 1,
-            s if s < MB => self.large_kb += 1,
-            s if s < 10 * MB => self.small_mb += 1,
-            s if s < 100 * MB => self.medium_mb += 1,
-            s if s < GB => self.large_mb += 1,
-            s if s < 10 * GB => self.small_gb += 1,
-            s if s < 100 * GB => self.medium_gb += 1,
-            _ => self.huge += 1,
+            0 => 0,
+            1..=9 => 1,
+            10..=99 => 2,
+            100..=999 => 3,
+            s if s < 10 * KB => 4,
+            s if s < 100 * KB => 5,
+            s if s < MB => 6,
+            s if s < 10 * MB => 7,
+            s if s < 100 * MB => 8,
+            s if s < GB => 9,
+            s if s < 10 * GB => 10,
+            s if s < 100 * GB => 11,
+            _ => 12,
         }
     }
     
+    /// Categorize a file size and increment the appropriate counter
+    pub fn add_file(&mut self, size: u64) {
+        let counter = match Self::bucket(size) {
+            0 => &mut self.empty,
+            1 => &mut self.tiny,
+            2 => &mut self.very_small,
+            3 => &mut self.small,
+            4 => &mut self.small_kb,
+            5 => &mut self.medium_kb,
+            6 => &mut self.large_kb,
+            7 => &mut self.small_mb,
+            8 => &mut self.medium_mb,
+            9 => &mut self.large_mb,
+            10 => &mut self.small_gb,
+            11 => &mut self.medium_gb,
+            _ => &mut self.huge,
+        };
+        *counter += 1;
+    }
+    
+    /// File counts in the order of [`FileSizeDistribution::LABELS`]
+    pub fn counts(&self) -> [usize; 13] {
+        [
+            self.empty,
+            self.tiny,
+            self.very_small,
+            self.small,
+            self.small_kb,
+            self.medium_kb,
+            self.large_kb,
+            self.small_mb,
+            self.medium_mb,
+            self.large_mb,
+            self.small_gb,
+            self.medium_gb,
+            self.huge,
+        ]
+    }
+    
     /// Get a formatted summary of the distribution
     pub fn summary(&self) -> String {
-        let mut lines = Vec::new();
-        
         // Only include non-zero buckets for cleaner output
-        let buckets = [
-            ("Empty (0 bytes)", self.empty),
-            ("1 B – 9 B", self.tiny),
-            ("10 B – 99 B", self.very_small),
-            ("100 B – 999 B", self.small),
-            ("1 KB – 9.99 KB", self.small_kb),
-            ("10 KB – 99.99 KB", self.medium_kb),
-            ("100 KB – 999.99 KB", self.large_kb),
-            ("1 MB – 9.99 MB", self.small_mb),
-            ("10 MB – 99.99 MB", self.medium_mb),
-            ("100 MB – 999.99 MB", self.large_mb),
-            ("1 GB – 9.99 GB", self.small_gb),
-            ("10 GB – 99.99 GB", self.medium_gb),
-            ("100 GB+", self.huge),
-        ];
-        
-        for (label, count) in buckets {
-            if count > 0 {
-                lines.push(format!("  {}: {}", label, count));
-            }
-        }
+        let lines: Vec<String> = Self::LABELS
+            .iter()
+            .zip(self.counts())
+            .filter(|&(_, count)| count > 0)
+            .map(|(label, count)| format!("  {}: {}", label, count))
+            .collect();
         
         if lines.is_empty() {
             "  No files".to_string()
@@ -826,6 +1152,13 @@ impl DirectoryStats {
     pub fn format_size(&self) -> String {
         format_file_size(self.total_size)
     }
+    
+    fn add_modified(&mut self, time: Option<DateTime<Utc>>) {
+        if let Some(time) = time {
+            self.oldest_modified = Some(self.oldest_modified.map_or(time, |oldest| oldest.min(time)));
+            self.newest_modified = Some(self.newest_modified.map_or(time, |newest| newest.max(time)));
+        }
+    }
 }
 
 /// Validate that the path exists and is a directory
@@ -843,17 +1176,27 @@ pub fn validate_path(path: &Path) -> Result<(), ScanError> {
 
 /// Process a single file and extract its information (with SHA256)
 pub fn process_file(path: &Path) -> io::Result<FileInfo> {
-    process_file_with_hash_options(path, true, false, false, false, false)
+    process_file_with_hash_options(path, &[HashAlgorithm::Sha256], false, false)
 }
 
 /// Process a single file without calculating SHA256 (faster)
 pub fn process_file_no_hash(path: &Path) -> io::Result<FileInfo> {
-    process_file_with_hash_options(path, false, false, false, false, false)
+    process_file_with_hash_options(path, &[], false, false)
 }
 
 /// Process a file with configurable hash options
-pub fn process_file_with_hash_options(path: &Path, calculate_sha256: bool, calculate_sha512: bool, calculate_md5: bool, calculate_format: bool, calculate_mime: bool) -> io::Result<FileInfo> {
+pub fn process_file_with_hash_options(path: &Path, algorithms: &[HashAlgorithm], calculate_format: bool, calculate_mime: bool) -> io::Result<FileInfo> {
     let metadata = fs::metadata(path)?;
+    process_file_checked(path, &metadata, algorithms, calculate_format, calculate_mime, None)
+        .map_err(|(_, e)| e)
+}
+
+/// Process a file, given its metadata, reporting which phase failed
+/// alongside any error
+///
+/// When a cache is given, values it holds for an unchanged file are reused and
+/// only the missing ones are computed.
+fn process_file_checked(path: &Path, metadata: &fs::Metadata, algorithms: &[HashAlgorithm], calculate_format: bool, calculate_mime: bool, cache: Option<&CacheScope>) -> Result<FileInfo, (ScanPhase, io::Error)> {
     
     let name = path.file_name()
         .unwrap_or_default()
@@ -865,39 +1208,56 @@ pub fn process_file_with_hash_options(path: &Path, calculate_sha256: bool, calcu
     // Get directory path (without filename)
     let directory_path = path.parent()
         .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_else(|| String::new());
+        .unwrap_or_default();
     
     let size = metadata.len();
     
-    let created = metadata.created()
-        .ok()
-        .and_then(|time| format_time_optional(time))
-        .unwrap_or_else(|| "N/A".to_string());
+    let created = metadata.created().ok().map(DateTime::<Utc>::from);
+    let last_modified = Some(DateTime::<Utc>::from(metadata.modified().map_err(|e| (ScanPhase::Metadata, e))?));
+    let last_accessed = metadata.accessed().ok().map(DateTime::<Utc>::from);
+    let unix = UnixMetadata::from_metadata(metadata);
     
-    let last_modified = format_modified_time(metadata.modified()?);
+    let key = FileKey::from_metadata(metadata);
+    let cached = cache.and_then(|cache| cache.lookup(path, &key));
+    let mut entry = cached.clone().unwrap_or(CacheEntry {
+        key,
+        hashes: FileHashes::new(),
+        format: None,
+        mime_type: None,
+    });
     
-    let last_accessed = metadata.accessed()
-        .ok()
-        .and_then(|time| format_time_optional(time))
-        .unwrap_or_else(|| "N/A".to_string());
+    let missing: Vec<HashAlgorithm> = algorithms.iter()
+        .copied()
+        .filter(|algorithm| !entry.hashes.contains_key(algorithm))
+        .collect();
+    if !missing.is_empty() {
+        let hashes = calculate_file_hashes(path, &missing).map_err(|e| (ScanPhase::Hashing, e))?;
+        entry.hashes.extend(hashes);
+    }
     
-    let (md5, sha256, sha512) = if calculate_sha256 || calculate_sha512 || calculate_md5 {
-        calculate_file_hashes(path, calculate_sha256, calculate_sha512, calculate_md5)?
-    } else {
-        (String::from("Not calculated"), String::from("Not calculated"), String::from("Not calculated"))
-    };
+    if calculate_format && entry.format.is_none() {
+        entry.format = Some(identify_format(path).unwrap_or_else(|| "Unknown".to_string()));
+    }
     
-    let format = if calculate_format {
-        identify_format(path).unwrap_or_else(|| "Unknown".to_string())
-    } else {
-        String::from("Not calculated")
-    };
+    if calculate_mime && entry.mime_type.is_none() {
+        entry.mime_type = Some(identify_mime_type(path).unwrap_or_else(|| "application/octet-stream".to_string()));
+    }
     
-    let mime_type = if calculate_mime {
-        identify_mime_type(path).unwrap_or_else(|| "application/octet-stream".to_string())
-    } else {
-        String::from("Not calculated")
+    if let Some(cache) = cache {
+        if cached.as_ref() != Some(&entry) {
+            cache.store(path, entry.clone());
+        }
+    }
+    
+    let requested = |calculate: bool, value: Option<String>| match value {
+        Some(value) if calculate => value,
+        _ => String::from("Not calculated"),
     };
+    let hashes = entry.hashes.into_iter()
+        .filter(|(algorithm, _)| algorithms.contains(algorithm))
+        .collect();
+    let format = requested(calculate_format, entry.format);
+    let mime_type = requested(calculate_mime, entry.mime_type);
     
     Ok(FileInfo {
         name,
@@ -907,119 +1267,26 @@ pub fn process_file_with_hash_options(path: &Path, calculate_sha256: bool, calcu
         created,
         last_modified,
         last_accessed,
-        md5,
-        sha256,
-        sha512,
+        hashes,
         format,
         mime_type,
+        unix,
     })
 }
 
 /// Calculate SHA256 hash of a file
 pub fn calculate_sha256(path: &Path) -> io::Result<String> {
-    let mut file = fs::File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0; 8192];
-    
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        hasher.update(&buffer[..bytes_read]);
-    }
-    
-    Ok(format!("{:x}", hasher.finalize()))
+    calculate_single_hash(path, HashAlgorithm::Sha256)
 }
 
 /// Calculate MD5 hash of a file
 pub fn calculate_md5(path: &Path) -> io::Result<String> {
-    let mut file = fs::File::open(path)?;
-    let mut buffer = [0; 8192];
-    let mut context = md5::Context::new();
-    
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        context.consume(&buffer[..bytes_read]);
-    }
-    
-    Ok(format!("{:x}", context.compute()))
-}
-
-/// Calculate MD5, SHA256, and SHA512 hashes efficiently in a single pass
-pub fn calculate_file_hashes(path: &Path, calc_sha256: bool, calc_sha512: bool, calc_md5: bool) -> io::Result<(String, String, String)> {
-    if !calc_sha256 && !calc_sha512 && !calc_md5 {
-        return Ok((String::from("Not calculated"), String::from("Not calculated"), String::from("Not calculated")));
-    }
-    
-    let mut file = fs::File::open(path)?;
-    let mut sha256_hasher = if calc_sha256 { Some(Sha256::new()) } else { None };
-    let mut sha512_hasher = if calc_sha512 { Some(Sha512::new()) } else { None };
-    let mut md5_context = if calc_md5 { Some(md5::Context::new()) } else { None };
-    let mut buffer = [0; 8192];
-    
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        
-        if let Some(ref mut hasher) = sha256_hasher {
-            Digest::update(hasher, &buffer[..bytes_read]);
-        }
-        
-        if let Some(ref mut hasher) = sha512_hasher {
-            Digest::update(hasher, &buffer[..bytes_read]);
-        }
-        
-        if let Some(ref mut context) = md5_context {
-            context.consume(&buffer[..bytes_read]);
-        }
-    }
-    
-    let md5 = if let Some(context) = md5_context {
-        format!("{:x}", context.compute())
-    } else {
-        String::from("Not calculated")
-    };
-    
-    let sha256 = if let Some(hasher) = sha256_hasher {
-        format!("{:x}", hasher.finalize())
-    } else {
-        String::from("Not calculated")
-    };
-    
-    let sha512 = if let Some(hasher) = sha512_hasher {
-        format!("{:x}", hasher.finalize())
-    } else {
-        String::from("Not calculated")
-    };
-    
-    Ok((md5, sha256, sha512))
+    calculate_single_hash(path, HashAlgorithm::Md5)
 }
 
-/// Format a SystemTime as a readable string
-fn format_modified_time(time: SystemTime) -> String {
-    time.duration_since(SystemTime::UNIX_EPOCH)
-        .map(|d| {
-            let datetime = chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                .unwrap_or_default();
-            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-        })
-        .unwrap_or_else(|_| "Unknown".to_string())
-}
-
-/// Format a SystemTime as Option<String> (returns None on error)
-fn format_time_optional(time: SystemTime) -> Option<String> {
-    time.duration_since(SystemTime::UNIX_EPOCH)
-        .ok()
-        .and_then(|d| {
-            chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                .map(|datetime| datetime.format("%Y-%m-%d %H:%M:%S").to_string())
-        })
+fn calculate_single_hash(path: &Path, algorithm: HashAlgorithm) -> io::Result<String> {
+    let hashes = calculate_file_hashes(path, &[algorithm])?;
+    Ok(hashes.get(&algorithm).map(Digest::to_string).unwrap_or_default())
 }
 
 /// Format file size in human-readable form
@@ -1067,7 +1334,7 @@ fn identify_format(path: &Path) -> Option<String> {
 }
 
 /// Identify MIME type using file-format crate with fallback to mime_guess
-fn identify_mime_type(path: &Path) -> Option<String> {
+pub(crate) fn identify_mime_type(path: &Path) -> Option<String> {
     // Try content-based detection first using file-format's built-in media_type
     if let Ok(format) = file_format::FileFormat::from_file(path) {
         return Some(format.media_type().to_string());
@@ -1078,3 +1345,113 @@ fn identify_mime_type(path: &Path) -> Option<String> {
         .first_or_octet_stream()
         .to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Temporary directory without the leading dot that would exclude it as a dotfile
+    fn scan_dir() -> tempfile::TempDir {
+        tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap()
+    }
+    
+    fn collect_names(node: &TreeNode, names: &mut Vec<String>) {
+        names.push(node.name.clone());
+        for child in &node.children {
+            collect_names(child, names);
+        }
+    }
+    
+    #[test]
+    fn test_path_filter_prunes_excluded_directories() {
+        let dir = scan_dir();
+        fs::create_dir_all(dir.path().join("node_modules/dep")).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("node_modules/dep/lib.rs"), b"pub fn dep() {}").unwrap();
+        fs::write(dir.path().join("src/main.rs"), b"fn main() {}").unwrap();
+        fs::write(dir.path().join("src/notes.txt"), b"notes").unwrap();
+        
+        let scanner = DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .path_filter(PathFilter::new().exclude_glob("node_modules/").include_extension("rs"));
+        
+        let detailed = scanner.scan_detailed(dir.path()).unwrap();
+        let names: Vec<_> = detailed.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["main.rs"]);
+        
+        let scan = scanner.scan_all(dir.path()).unwrap();
+        assert_eq!(scan.stats.file_count, 1);
+        let mut tree_names = Vec::new();
+        collect_names(&scanner.scan_tree(dir.path()).unwrap(), &mut tree_names);
+        assert!(!tree_names.iter().any(|name| name == "node_modules" || name == "notes.txt"));
+        
+        let invalid = DirectoryScanner::new().path_filter(PathFilter::new().include_regex("["));
+        assert!(matches!(invalid.scan_detailed(dir.path()), Err(ScanError::InvalidFilter(_))));
+    }
+    
+    #[test]
+    fn test_metadata_filter_checks_listed_entries() {
+        let dir = scan_dir();
+        fs::create_dir_all(dir.path().join("small")).unwrap();
+        fs::write(dir.path().join("small/a.txt"), b"abc").unwrap();
+        fs::write(dir.path().join("big.bin"), vec![0u8; 4096]).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("big.bin"), dir.path().join("link.bin")).unwrap();
+        
+        // Directories are entered whatever their size; a followed link is
+        // judged by its target
+        let scanner = DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .follow_symlinks(true)
+            .metadata_filter(MetadataFilter::new().min_size(1000));
+        let detailed = scanner.scan_detailed(dir.path()).unwrap();
+        let names: Vec<_> = detailed.iter().map(|f| f.name.as_str()).collect();
+        #[cfg(unix)]
+        assert_eq!(names, vec!["big.bin", "link.bin"]);
+        #[cfg(not(unix))]
+        assert_eq!(names, vec!["big.bin"]);
+        
+        let scan = scanner.scan_all(dir.path()).unwrap();
+        assert_eq!(scan.stats.file_count, names.len());
+        assert_eq!(scan.stats.directory_count, 2);
+        
+        let small = DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .metadata_filter(MetadataFilter::new().max_size(10));
+        let mut tree_names = Vec::new();
+        collect_names(&small.scan_tree(dir.path()).unwrap(), &mut tree_names);
+        assert_eq!(tree_names[1..], ["small", "a.txt"]);
+    }
+    
+    #[test]
+    fn test_scan_all_matches_separate_scans() {
+        let dir = scan_dir();
+        fs::create_dir_all(dir.path().join("b/nested")).unwrap();
+        fs::create_dir_all(dir.path().join("A")).unwrap();
+        fs::write(dir.path().join("root.txt"), b"root").unwrap();
+        fs::write(dir.path().join("A/one.rs"), b"fn main() {}").unwrap();
+        fs::write(dir.path().join("b/nested/two.png"), b"12345678").unwrap();
+        fs::write(dir.path().join(".hidden"), b"x").unwrap();
+        
+        let scanner = DirectoryScanner::new().skip_virtual_filesystems(false);
+        let scan = scanner.scan_all(dir.path()).unwrap();
+        assert_eq!(scan.files.len(), 3);
+        
+        let detailed = scanner.scan_detailed(dir.path()).unwrap();
+        let detailed_paths: Vec<_> = detailed.iter().map(|f| &f.full_path).collect();
+        let scan_paths: Vec<_> = scan.files.iter().map(|f| &f.full_path).collect();
+        assert_eq!(scan_paths, detailed_paths);
+        
+        let mut tree_names = Vec::new();
+        collect_names(&scanner.scan_tree(dir.path()).unwrap(), &mut tree_names);
+        let mut scan_names = Vec::new();
+        collect_names(&scan.tree, &mut scan_names);
+        assert_eq!(scan_names, tree_names);
+        
+        let stats = scanner.scan_stats(dir.path()).unwrap();
+        assert_eq!(scan.stats.file_count, stats.file_count);
+        assert_eq!(scan.stats.directory_count, stats.directory_count);
+        assert_eq!(scan.stats.total_size, stats.total_size);
+        assert_eq!(scan.file_type_counts, crate::tree::count_files_by_type(&scan.tree));
+    }
+}
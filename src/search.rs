@@ -0,0 +1,327 @@
+//! Searching scan results
+//!
+//! A [`SearchQuery`] finds files by a substring, a glob or a regular
+//! expression, and reports where each match falls so views can highlight
+//! it. Like ripgrep's smart case, a search ignores case unless the pattern
+//! contains an uppercase letter.
+//!
+//! Substring and regex searches look at the full path, so `src/main` finds
+//! `/repo/src/main.rs`. Given the scanned directory with
+//! [`SearchQuery::relative_to`], they look only at the part of the path below
+//! it, so a pattern that appears in the directory's own path does not match
+//! everything. A glob without a slash is matched against the name
+//! alone (`*.rs`), and one with a slash against the end of the path
+//! (`src/*.rs`), as in include patterns. Globs use the same syntax as the
+//! scan filters.
+
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobMatcher};
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+
+use crate::filter::FilterError;
+use crate::table::FileTable;
+use crate::TreeNode;
+
+/// How a search pattern is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    #[default]
+    Substring,
+    Glob,
+    Regex,
+}
+
+impl SearchMode {
+    pub const ALL: [SearchMode; 3] = [SearchMode::Substring, SearchMode::Glob, SearchMode::Regex];
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SearchMode::Substring => "Substring",
+            SearchMode::Glob => "Glob",
+            SearchMode::Regex => "Regex",
+        })
+    }
+}
+
+/// A compiled search over file names and paths
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    matcher: Matcher,
+    /// Whether the pattern applies to the full path rather than the name
+    whole_path: bool,
+    /// Scanned directory, left out of the paths searched
+    root: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Regex(Regex),
+    /// Matches a name, or for a path glob a trailing part of the path that
+    /// starts at a component
+    Glob(GlobMatcher),
+}
+
+impl SearchQuery {
+    pub fn new(mode: SearchMode, pattern: &str) -> Result<Self, FilterError> {
+        let case_insensitive = !pattern.chars().any(char::is_uppercase);
+        let invalid = |message: String| FilterError {
+            pattern: pattern.to_string(),
+            message,
+        };
+        let regex = |source: &str| {
+            RegexBuilder::new(source)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| invalid(e.to_string()))
+        };
+        let (matcher, whole_path) = match mode {
+            SearchMode::Substring => (regex(&regex::escape(pattern))?, true),
+            SearchMode::Regex => (regex(pattern)?, true),
+            SearchMode::Glob => {
+                let glob = GlobBuilder::new(pattern.trim_start_matches('/'))
+                    .case_insensitive(case_insensitive)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| invalid(e.kind().to_string()))?;
+                (Matcher::Glob(glob.compile_matcher()), pattern.contains('/'))
+            }
+        };
+        Ok(Self { matcher, whole_path, root: None })
+    }
+
+    /// Search paths only below the scanned directory `root`
+    pub fn relative_to(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Whether a file or directory with this name and full path matches
+    pub fn is_match(&self, name: &str, path: &str) -> bool {
+        if self.whole_path {
+            self.matches(self.relative(path).0)
+        } else {
+            self.matches(name)
+        }
+    }
+
+    /// The part of `path` below the root, and the byte offset it starts
+    /// at; the whole path for paths outside the root
+    fn relative<'a>(&self, path: &'a str) -> (&'a str, usize) {
+        let below = self.root.as_deref().and_then(|root| Path::new(path).strip_prefix(root).ok());
+        match below.and_then(Path::to_str) {
+            Some(relative) => (relative, path.len() - relative.len()),
+            None => (path, 0),
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match &self.matcher {
+            Matcher::Regex(regex) => regex.is_match(text),
+            Matcher::Glob(glob) if self.whole_path => {
+                component_starts(text).any(|start| glob.is_match(&text[start..]))
+            }
+            Matcher::Glob(glob) => glob.is_match(text),
+        }
+    }
+
+    /// Byte ranges of the matches within a displayed name or path, for highlighting
+    ///
+    /// A glob highlights the longest matching end of a path, or for a name
+    /// glob the last component. The root is never highlighted.
+    pub fn find_matches(&self, text: &str) -> Vec<Range<usize>> {
+        let (text, offset) = self.relative(text);
+        let ranges: Vec<Range<usize>> = match &self.matcher {
+            Matcher::Regex(regex) => regex
+                .find_iter(text)
+                .filter(|found| !found.is_empty())
+                .map(|found| found.range())
+                .collect(),
+            Matcher::Glob(glob) => {
+                let mut starts = component_starts(text);
+                let found = if self.whole_path {
+                    starts.find(|&start| glob.is_match(&text[start..]))
+                } else {
+                    starts.last().filter(|&start| glob.is_match(&text[start..]))
+                };
+                found.map(|start| start..text.len()).into_iter().collect()
+            }
+        };
+        ranges.into_iter().map(|range| range.start + offset..range.end + offset).collect()
+    }
+
+    /// The given table rows whose files match, in the same order. Rows are
+    /// checked in parallel, so this stays quick on very large scans.
+    pub fn matching_rows(&self, files: &FileTable, rows: &[u32]) -> Vec<u32> {
+        rows.par_iter()
+            .copied()
+            .filter(|&row| {
+                // Only path searches need the full path built
+                if self.whole_path {
+                    self.matches(self.relative(&files.full_path(row as usize)).0)
+                } else {
+                    self.matches(files.name(row as usize))
+                }
+            })
+            .collect()
+    }
+
+    /// The part of a tree that matches: every matching entry, with the
+    /// directories leading to it. A matching directory keeps everything
+    /// below it. Directory sizes and file counts cover only what is kept.
+    /// `None` when nothing below the root matches.
+    pub fn filter_tree(&self, tree: &TreeNode) -> Option<TreeNode> {
+        let children: Vec<TreeNode> = tree.children.par_iter().filter_map(|child| self.filter_node(child)).collect();
+        if children.is_empty() {
+            return None;
+        }
+        Some(with_children(tree, children))
+    }
+
+    fn filter_node(&self, node: &TreeNode) -> Option<TreeNode> {
+        if self.is_match(&node.name, &node.path.to_string_lossy()) {
+            return Some(node.clone());
+        }
+        if !node.is_directory {
+            return None;
+        }
+        let children: Vec<TreeNode> = node.children.iter().filter_map(|child| self.filter_node(child)).collect();
+        if children.is_empty() {
+            return None;
+        }
+        Some(with_children(node, children))
+    }
+}
+
+fn with_children(node: &TreeNode, children: Vec<TreeNode>) -> TreeNode {
+    TreeNode {
+        name: node.name.clone(),
+        path: node.path.clone(),
+        is_directory: node.is_directory,
+        size: children.iter().map(|child| child.size).sum(),
+        file_count: children.iter().map(|child| child.file_count).sum(),
+        modified: node.modified,
+        unix: node.unix,
+        children,
+    }
+}
+
+/// Byte offsets where the components of a path start, first to last
+fn component_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+    std::iter::once(0)
+        .chain(text.char_indices().filter(|&(_, c)| std::path::is_separator(c)).map(|(i, _)| i + 1))
+        .filter(move |&start| start < text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileInfo;
+
+    fn query(mode: SearchMode, pattern: &str) -> SearchQuery {
+        SearchQuery::new(mode, pattern).unwrap()
+    }
+
+    #[test]
+    fn test_substring_search() {
+        let substring = query(SearchMode::Substring, "src/main");
+        assert!(substring.is_match("main.rs", "/repo/SRC/main.rs"));
+        assert_eq!(substring.find_matches("/repo/src/main.rs"), vec![6..14]);
+    }
+
+    #[test]
+    fn test_smart_case() {
+        let exact_case = query(SearchMode::Substring, "README");
+        assert!(!exact_case.is_match("readme.md", "/repo/readme.md"));
+        assert!(query(SearchMode::Glob, "readme.*").is_match("README.md", "/repo/README.md"));
+    }
+
+    #[test]
+    fn test_name_glob() {
+        let glob = query(SearchMode::Glob, "*.[ch]");
+        assert!(glob.is_match("lib.c", "/repo/lib.c"));
+        assert!(!glob.is_match("lib.cc", "/repo/lib.c"));
+        assert_eq!(glob.find_matches("/repo/lib.c"), vec![6..11]);
+
+        let alternatives = query(SearchMode::Glob, "*.{rs,toml}");
+        assert!(alternatives.is_match("Cargo.toml", "/repo/Cargo.toml"));
+        assert!(SearchQuery::new(SearchMode::Glob, "[unclosed").is_err());
+    }
+
+    #[test]
+    fn test_path_glob() {
+        let path_glob = query(SearchMode::Glob, "src/**/*.rs");
+        assert!(path_glob.is_match("walk.rs", "/repo/src/deep/walk.rs"));
+        assert!(path_glob.is_match("lib.rs", "/repo/src/lib.rs"));
+        assert!(!path_glob.is_match("walk.rs", "/repo/source/walk.rs"));
+        assert_eq!(path_glob.find_matches("/repo/src/deep/walk.rs"), vec![6..22]);
+    }
+
+    #[test]
+    fn test_regex_search() {
+        let regex = query(SearchMode::Regex, r"\d{4}");
+        assert_eq!(regex.find_matches("scan-2024-03.jsonl"), vec![5..9]);
+        assert!(SearchQuery::new(SearchMode::Regex, "(unclosed").is_err());
+    }
+
+    #[test]
+    fn test_root_is_not_searched() {
+        let search = query(SearchMode::Substring, "alice").relative_to("/home/alice/projects");
+        assert!(!search.is_match("main.rs", "/home/alice/projects/src/main.rs"));
+        assert!(search.find_matches("/home/alice/projects/src/main.rs").is_empty());
+        assert!(search.is_match("alice.txt", "/home/alice/projects/alice.txt"));
+        assert_eq!(search.find_matches("/home/alice/projects/alice.txt"), vec![21..26]);
+
+        let mut files = FileTable::new();
+        files.push(&FileInfo::for_test("/home/alice/projects/src/main.rs", 0));
+        assert!(search.matching_rows(&files, &[0]).is_empty());
+
+        let regex = query(SearchMode::Regex, "^src/").relative_to("/home/alice/projects");
+        assert!(regex.is_match("main.rs", "/home/alice/projects/src/main.rs"));
+    }
+
+    #[test]
+    fn test_matching_rows_keep_order() {
+        let mut files = FileTable::new();
+        for path in ["/data/logs/app.log", "/data/notes.txt", "/data/logs/old.log"] {
+            files.push(&FileInfo::for_test(path, 0));
+        }
+        let logs = query(SearchMode::Substring, "logs");
+        assert_eq!(logs.matching_rows(&files, &[2, 1, 0]), [2, 0]);
+    }
+
+    #[test]
+    fn test_filter_tree() {
+        let leaf = |name: &str, size| TreeNode {
+            name: name.to_string(),
+            path: format!("/data/logs/{}", name).into(),
+            is_directory: false,
+            size,
+            file_count: 1,
+            modified: None,
+            unix: None,
+            children: Vec::new(),
+        };
+        let tree = TreeNode {
+            name: "data".to_string(),
+            path: "/data".into(),
+            is_directory: true,
+            size: 30,
+            file_count: 2,
+            modified: None,
+            unix: None,
+            children: vec![leaf("app.log", 10), leaf("notes.txt", 20)],
+        };
+        let filtered = query(SearchMode::Glob, "*.log").filter_tree(&tree).unwrap();
+        assert_eq!((filtered.children.len(), filtered.size, filtered.file_count), (1, 10, 1));
+        assert!(query(SearchMode::Glob, "*.png").filter_tree(&tree).is_none());
+        // The scanned directory's own name is not searched
+        assert!(query(SearchMode::Substring, "data").relative_to("/data").filter_tree(&tree).is_none());
+    }
+}
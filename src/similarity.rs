@@ -0,0 +1,430 @@
+//! Similar file detection with fuzzy hashes
+//!
+//! Files are compared by their ssdeep or TLSH digests and linked when the
+//! similarity passes a threshold; each connected group of linked files forms
+//! a cluster. Digests already calculated during the scan are reused.
+//!
+//! ssdeep signatures can only match when they share a seven character run at
+//! a compatible block size, so only files sharing such a run are compared.
+//! TLSH has no such shortcut and every pair is compared, which grows
+//! quadratically with the number of files.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::fuzzy::{self, ssdeep_compare, tlsh_distance};
+use crate::hashing::TLSH_NULL;
+use crate::report::{IssueCollector, ScanIssue, ScanPhase, ScanReport};
+use crate::table::FileList;
+use crate::{HashAlgorithm, ProgressCallback, ScanError};
+
+/// Indices of two linked digests and their score
+type Link = (usize, usize, u32);
+
+/// A file that belongs to a cluster
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// The file's fuzzy digest
+    pub digest: String,
+    /// Score (ssdeep) or distance (TLSH) to the closest other file in the cluster
+    pub best_match: u32,
+}
+
+/// Two files in a cluster that passed the threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarPair {
+    /// Index into the cluster's files
+    pub first: usize,
+    /// Index into the cluster's files
+    pub second: usize,
+    /// ssdeep score or TLSH distance
+    pub score: u32,
+}
+
+/// A group of files linked by similarity
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarityCluster {
+    /// Members sorted by path
+    pub files: Vec<SimilarFile>,
+    /// Every pair of members that passed the threshold
+    pub pairs: Vec<SimilarPair>,
+}
+
+/// Result of a similarity search
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarityReport {
+    pub algorithm: HashAlgorithm,
+    pub threshold: u32,
+    /// Clusters, largest first
+    pub clusters: Vec<SimilarityCluster>,
+    /// Number of files with a usable digest
+    pub files_compared: usize,
+    /// Number of files whose digest had to be calculated
+    pub files_hashed: usize,
+    /// Files that could not be read
+    #[serde(skip)]
+    pub report: ScanReport,
+}
+
+impl SimilarityReport {
+    /// Number of files that belong to some cluster
+    pub fn similar_count(&self) -> usize {
+        self.clusters.iter().map(|cluster| cluster.files.len()).sum()
+    }
+
+    /// Get a one-line summary of the clusters found
+    pub fn summary(&self) -> String {
+        format!(
+            "{} clusters of similar files, {} files of {} compared ({} {} {})",
+            self.clusters.len(),
+            self.similar_count(),
+            self.files_compared,
+            self.algorithm.label(),
+            if self.algorithm == HashAlgorithm::Tlsh { "distance <=" } else { "score >=" },
+            self.threshold,
+        )
+    }
+
+    /// Write clusters as CSV, one row per file
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let measure = if self.algorithm == HashAlgorithm::Tlsh { "Closest Distance" } else { "Best Score" };
+        writeln!(writer, "Cluster,{},Size (bytes),{},Path", measure, self.algorithm.label())?;
+        for (index, cluster) in self.clusters.iter().enumerate() {
+            for file in &cluster.files {
+                writeln!(
+                    writer,
+                    "{},{},{},\"{}\",\"{}\"",
+                    index + 1,
+                    file.best_match,
+                    file.size,
+                    file.digest.replace('"', "\"\""),
+                    file.path.to_string_lossy().replace('"', "\"\""),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the clusters as JSON
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self).map_err(io::Error::from)?;
+        writeln!(writer)
+    }
+}
+
+/// Finds clusters of similar files among the results of a scan
+#[derive(Clone)]
+pub struct SimilarityFinder {
+    /// [`HashAlgorithm::Ssdeep`] or [`HashAlgorithm::Tlsh`]
+    pub algorithm: HashAlgorithm,
+    /// Minimum ssdeep score or maximum TLSH distance for two files to be linked
+    pub threshold: u32,
+    /// Ignore files smaller than this (empty files are ignored by default)
+    pub min_size: u64,
+    pub cancellation_flag: Option<Arc<AtomicBool>>,
+}
+
+impl Default for SimilarityFinder {
+    fn default() -> Self {
+        Self {
+            algorithm: HashAlgorithm::Ssdeep,
+            threshold: Self::default_threshold(HashAlgorithm::Ssdeep),
+            min_size: 1,
+            cancellation_flag: None,
+        }
+    }
+}
+
+impl SimilarityFinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A reasonable threshold for each fuzzy algorithm
+    pub fn default_threshold(algorithm: HashAlgorithm) -> u32 {
+        match algorithm {
+            HashAlgorithm::Tlsh => 70,
+            _ => 50,
+        }
+    }
+
+    /// Use the given fuzzy algorithm with its default threshold
+    pub fn algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self.threshold = Self::default_threshold(algorithm);
+        self
+    }
+
+    pub fn threshold(mut self, threshold: u32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn min_size(mut self, size: u64) -> Self {
+        self.min_size = size;
+        self
+    }
+
+    pub fn cancellation_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancellation_flag = Some(flag);
+        self
+    }
+
+    /// Find clusters of similar files among scanned files, given as a list
+    /// of `FileInfo`s or rows of a [`FileTable`](crate::FileTable)
+    pub fn find<F: FileList + ?Sized>(&self, files: &F, progress_callback: Option<ProgressCallback>) -> Result<SimilarityReport, ScanError> {
+        if !self.algorithm.is_fuzzy() {
+            let message = format!("{} is not a similarity hash", self.algorithm.label());
+            return Err(ScanError::Io(io::Error::new(io::ErrorKind::InvalidInput, message)));
+        }
+
+        let issues = IssueCollector::new();
+        let candidates: Vec<usize> = (0..files.len()).filter(|&index| files.size(index) >= self.min_size).collect();
+
+        let total = candidates.len();
+        let processed = AtomicUsize::new(0);
+        let hashed = AtomicUsize::new(0);
+        let digests: Vec<(usize, String)> = candidates
+            .par_iter()
+            .filter_map(|&index| {
+                if self.is_cancelled() {
+                    return None;
+                }
+                let digest = if let Some(digest) = files.hash(index, self.algorithm) {
+                    Some(digest.to_string())
+                } else {
+                    hashed.fetch_add(1, Ordering::Relaxed);
+                    let path = PathBuf::from(files.full_path(index).as_ref());
+                    match crate::calculate_file_hashes(&path, &[self.algorithm]) {
+                        Ok(mut hashes) => hashes.remove(&self.algorithm).map(|digest| digest.to_string()),
+                        Err(e) => {
+                            issues.record(ScanIssue::new(&path, ScanPhase::Hashing, &e));
+                            None
+                        }
+                    }
+                };
+
+                let count = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(ref callback) = progress_callback {
+                    if count.is_multiple_of(10) || count == total {
+                        let progress = 0.5 * count as f32 / total.max(1) as f32;
+                        callback(progress, format!("Fuzzy hashing {}/{} files", count, total));
+                    }
+                }
+
+                digest.filter(|digest| digest != TLSH_NULL).map(|digest| (index, digest))
+            })
+            .collect();
+
+        if self.is_cancelled() {
+            return Err(ScanError::Cancelled);
+        }
+
+        let links = self.compare(&digests, &progress_callback);
+
+        if self.is_cancelled() {
+            return Err(ScanError::Cancelled);
+        }
+
+        Ok(SimilarityReport {
+            algorithm: self.algorithm,
+            threshold: self.threshold,
+            clusters: build_clusters(self.algorithm, files, &digests, links),
+            files_compared: digests.len(),
+            files_hashed: hashed.into_inner(),
+            report: issues.into_report(),
+        })
+    }
+
+    /// Find every pair of files that passes the threshold
+    fn compare(&self, digests: &[(usize, String)], progress_callback: &Option<ProgressCallback>) -> Vec<Link> {
+        let pairs: Vec<(usize, usize)> = match self.algorithm {
+            HashAlgorithm::Ssdeep => ssdeep_candidates(digests),
+            _ => (0..digests.len()).flat_map(|i| (i + 1..digests.len()).map(move |j| (i, j))).collect(),
+        };
+
+        let total = pairs.len();
+        let processed = AtomicUsize::new(0);
+        pairs
+            .par_iter()
+            .filter_map(|&(i, j)| {
+                if self.is_cancelled() {
+                    return None;
+                }
+
+                let count = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(ref callback) = progress_callback {
+                    if count.is_multiple_of(1000) || count == total {
+                        let progress = 0.5 + 0.5 * count as f32 / total.max(1) as f32;
+                        callback(progress, format!("Comparing {}/{} pairs", count, total));
+                    }
+                }
+
+                let (first, second) = (&digests[i].1, &digests[j].1);
+                let linked = match self.algorithm {
+                    HashAlgorithm::Tlsh => tlsh_distance(first, second).filter(|&distance| distance <= self.threshold),
+                    _ => ssdeep_compare(first, second).filter(|&score| score > 0 && score >= self.threshold),
+                };
+                linked.map(|score| (i, j, score))
+            })
+            .collect()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation_flag
+            .as_ref()
+            .map(|flag| flag.load(Ordering::Relaxed))
+            .unwrap_or(false)
+    }
+}
+
+/// Pairs of ssdeep signatures that share a seven character run at a comparable block size
+fn ssdeep_candidates(digests: &[(usize, String)]) -> Vec<(usize, usize)> {
+    let mut index: HashMap<(u64, Vec<u8>), Vec<usize>> = HashMap::new();
+    for (position, (_, digest)) in digests.iter().enumerate() {
+        let Some((size, first, second)) = fuzzy::parse_ssdeep(digest) else {
+            continue;
+        };
+        // The second part is compared against signatures at twice the block size
+        for (block_size, part) in [(size, first), (size * 2, second)] {
+            let part = fuzzy::eliminate_sequences(part);
+            let runs: BTreeSet<&[u8]> = part.windows(fuzzy::ROLLING_WINDOW).collect();
+            for run in runs {
+                index.entry((block_size, run.to_vec())).or_default().push(position);
+            }
+        }
+    }
+
+    let mut pairs = BTreeSet::new();
+    for positions in index.into_values() {
+        for (n, &i) in positions.iter().enumerate() {
+            for &j in &positions[n + 1..] {
+                if i != j {
+                    pairs.insert((i.min(j), i.max(j)));
+                }
+            }
+        }
+    }
+    pairs.into_iter().collect()
+}
+
+/// Group linked files into connected clusters
+fn build_clusters<F: FileList + ?Sized>(
+    algorithm: HashAlgorithm,
+    files: &F,
+    digests: &[(usize, String)],
+    links: Vec<Link>,
+) -> Vec<SimilarityCluster> {
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut parent: Vec<usize> = (0..digests.len()).collect();
+    for &(i, j, _) in &links {
+        let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+        parent[a.max(b)] = a.min(b);
+    }
+
+    let mut grouped: HashMap<usize, (BTreeSet<usize>, Vec<Link>)> = HashMap::new();
+    for link in links {
+        let (members, pairs) = grouped.entry(root(&mut parent, link.0)).or_default();
+        members.extend([link.0, link.1]);
+        pairs.push(link);
+    }
+
+    let mut clusters: Vec<SimilarityCluster> = grouped
+        .into_values()
+        .map(|(members, links)| {
+            let mut members: Vec<usize> = members.into_iter().collect();
+            members.sort_by(|&a, &b| files.full_path(digests[a].0).cmp(&files.full_path(digests[b].0)));
+            let position = |i: usize| members.iter().position(|&member| member == i).unwrap_or_default();
+
+            let mut pairs: Vec<SimilarPair> = links
+                .into_iter()
+                .map(|(i, j, score)| {
+                    let (first, second) = (position(i), position(j));
+                    SimilarPair { first: first.min(second), second: first.max(second), score }
+                })
+                .collect();
+            pairs.sort_by_key(|pair| (pair.first, pair.second));
+
+            let similar = members
+                .iter()
+                .enumerate()
+                .map(|(n, &i)| {
+                    let scores = pairs.iter().filter(|pair| pair.first == n || pair.second == n).map(|pair| pair.score);
+                    let (index, digest) = &digests[i];
+                    SimilarFile {
+                        path: PathBuf::from(files.full_path(*index).as_ref()),
+                        size: files.size(*index),
+                        digest: digest.clone(),
+                        best_match: if algorithm == HashAlgorithm::Tlsh { scores.min() } else { scores.max() }.unwrap_or_default(),
+                    }
+                })
+                .collect();
+
+            SimilarityCluster { files: similar, pairs }
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| b.files.len().cmp(&a.files.len()).then_with(|| a.files[0].path.cmp(&b.files[0].path)));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DirectoryScanner;
+    use std::fs;
+
+    #[test]
+    fn test_clusters_similar_files() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        let mut state = 7u32;
+        let mut text = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    b"abcdefghij klmnopqrst\n"[(state >> 16) as usize % 22]
+                })
+                .collect()
+        };
+
+        let original = text(16_384);
+        let mut revised = original.clone();
+        revised[8_000..8_040].copy_from_slice(&text(40));
+        fs::write(dir.path().join("report-v1.txt"), &original).unwrap();
+        fs::write(dir.path().join("report-v2.txt"), &revised).unwrap();
+        fs::write(dir.path().join("other.txt"), text(16_384)).unwrap();
+
+        let files = DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .hash_algorithms([HashAlgorithm::Ssdeep])
+            .scan_detailed(dir.path())
+            .unwrap();
+
+        for algorithm in HashAlgorithm::FUZZY {
+            let report = SimilarityFinder::new().algorithm(algorithm).find(&files, None).unwrap();
+            assert_eq!(report.clusters.len(), 1, "{}", algorithm);
+            let names: Vec<_> = report.clusters[0].files.iter()
+                .map(|file| file.path.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            assert_eq!(names, vec!["report-v1.txt", "report-v2.txt"]);
+            // TLSH was not part of the scan and had to be calculated
+            let expected_hashed = if algorithm == HashAlgorithm::Tlsh { 3 } else { 0 };
+            assert_eq!(report.files_hashed, expected_hashed);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Streaming scan results
+//!
+//! [`FileStream`] runs a detailed scan on a background thread and hands the
+//! processed files over in batches, so results can be shown or written out
+//! while the scan is still running and a listing never has to be held in
+//! memory in full. The channel between the scan and the reader is bounded:
+//! when the reader falls behind, the scan waits for it.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::report::ScanReport;
+use crate::scanner::{DirectoryScanner, ProgressCallback};
+use crate::{FileInfo, ScanError};
+
+/// Files buffered between the scan and the reader
+const CHANNEL_CAPACITY: usize = 4096;
+
+/// Most files returned by one call to `next`
+const DEFAULT_BATCH_SIZE: usize = 256;
+
+/// Batches of files from a scan running in the background
+///
+/// Each batch holds the files processed since the previous one, in the
+/// order they finished; sort them if a stable order is needed. Dropping the
+/// stream before the end stops the scan.
+pub struct FileStream {
+    receiver: Receiver<FileInfo>,
+    batch_size: usize,
+    handle: JoinHandle<Result<ScanReport, ScanError>>,
+}
+
+impl FileStream {
+    pub(crate) fn spawn(
+        mut scanner: DirectoryScanner,
+        path: PathBuf,
+        progress_callback: Option<ProgressCallback>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        // Stop the scan when the reader goes away
+        let flag = scanner.cancellation_flag.get_or_insert_with(|| Arc::new(AtomicBool::new(false))).clone();
+
+        let handle = thread::spawn(move || {
+            scanner.scan_detailed_each(&path, progress_callback, |info| {
+                if sender.send(info).is_err() {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            })
+        });
+
+        Self {
+            receiver,
+            batch_size: DEFAULT_BATCH_SIZE,
+            handle,
+        }
+    }
+
+    /// Set the largest number of files returned per batch
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Wait for the scan to end and return its report. Files not yet read
+    /// are discarded, which stops a scan that is still running.
+    pub fn finish(self) -> Result<ScanReport, ScanError> {
+        drop(self.receiver);
+        self.handle
+            .join()
+            .unwrap_or_else(|_| Err(ScanError::Io(std::io::Error::other("scan thread panicked"))))
+    }
+}
+
+impl Iterator for FileStream {
+    type Item = Vec<FileInfo>;
+
+    /// Block until at least one file is ready, then take whatever else is
+    /// already waiting, up to the batch size
+    fn next(&mut self) -> Option<Vec<FileInfo>> {
+        let first = self.receiver.recv().ok()?;
+        let mut batch = Vec::with_capacity(self.batch_size.min(CHANNEL_CAPACITY));
+        batch.push(first);
+        while batch.len() < self.batch_size {
+            match self.receiver.try_recv() {
+                Ok(info) => batch.push(info),
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+            }
+        }
+        Some(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_stream_delivers_every_file() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        for i in 0..50 {
+            fs::write(dir.path().join(format!("file{:02}.txt", i)), i.to_string()).unwrap();
+        }
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/nested.txt"), b"nested").unwrap();
+
+        let scanner = DirectoryScanner::new().skip_virtual_filesystems(false);
+        let mut stream = scanner.scan_detailed_streaming(dir.path(), None).batch_size(8);
+        let mut names = Vec::new();
+        for batch in &mut stream {
+            assert!(!batch.is_empty() && batch.len() <= 8);
+            names.extend(batch.into_iter().map(|info| info.name));
+        }
+        assert!(stream.finish().unwrap().is_empty());
+
+        names.sort();
+        assert_eq!(names.len(), 51);
+        assert_eq!(names[0], "file00.txt");
+        assert_eq!(names[50], "nested.txt");
+    }
+}
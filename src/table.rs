@@ -0,0 +1,749 @@
+//! Compact storage for large scan results
+//!
+//! A `FileInfo` owns a dozen strings, so a list of ten million of them needs
+//! several gigabytes. [`FileTable`] keeps the same information column by
+//! column: file names share one buffer, directory paths, formats and media
+//! types are stored once per distinct value, timestamps are kept as seconds
+//! and nanoseconds and digests as raw bytes. Rows are addressed by index, so a view can sort
+//! or filter a list of row numbers instead of copying records.
+//!
+//! With a spill threshold set, names and digests are moved to an unlinked
+//! temporary file once they outgrow it, and read back through a memory map.
+//! Only the fixed-size columns and the interned strings then stay in memory.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use memmap2::Mmap;
+
+use crate::hashing::{Digest, FileHashes, HashAlgorithm};
+use crate::unix::UnixMetadata;
+use crate::FileInfo;
+
+/// Stored in a timestamp column when the time is not available
+const NO_TIME: i64 = i64::MIN;
+
+/// Scan results stored column by column
+///
+/// Rows keep the order they were pushed in. [`get`](FileTable::get) rebuilds
+/// a `FileInfo` for one row; the other accessors read a single column
+/// without building one.
+#[derive(Debug, Clone, Default)]
+pub struct FileTable {
+    names: Bytes,
+    name_ends: Vec<u64>,
+    directories: Interner,
+    directory_ids: Vec<u32>,
+    /// Full paths that are not the directory joined with the name
+    full_paths: HashMap<u32, String>,
+    sizes: Vec<u64>,
+    created: Times,
+    modified: Times,
+    accessed: Times,
+    formats: Interner,
+    format_ids: Vec<u32>,
+    media_types: Interner,
+    media_type_ids: Vec<u32>,
+    digests: Vec<(HashAlgorithm, Digests)>,
+    unix: UnixColumns,
+    spill_threshold: Option<usize>,
+}
+
+impl FileTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move names and digests to a temporary file whenever more than
+    /// `bytes` of them are held in memory
+    pub fn spill_threshold(mut self, bytes: usize) -> Self {
+        self.spill_threshold = Some(bytes);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sizes.is_empty()
+    }
+
+    /// Append a file as the last row
+    pub fn push(&mut self, info: &FileInfo) {
+        let row = self.len();
+        assert!(row < u32::MAX as usize, "a file table holds at most {} rows", u32::MAX);
+
+        self.names.extend(info.name.as_bytes());
+        self.name_ends.push(self.names.len() as u64);
+        self.directory_ids.push(self.directories.intern(&info.directory_path));
+        if join_path(&info.directory_path, &info.name) != info.full_path {
+            self.full_paths.insert(row as u32, info.full_path.clone());
+        }
+        self.sizes.push(info.size);
+        self.created.push(info.created);
+        self.modified.push(info.last_modified);
+        self.accessed.push(info.last_accessed);
+        self.format_ids.push(self.formats.intern(&info.format));
+        self.media_type_ids.push(self.media_types.intern(&info.mime_type));
+        self.unix.push(row, info.unix);
+
+        for algorithm in info.hashes.keys() {
+            if !self.digests.iter().any(|(existing, _)| existing == algorithm) {
+                self.digests.push((*algorithm, Digests::new(*algorithm, row)));
+            }
+        }
+        for (algorithm, digests) in &mut self.digests {
+            digests.push(info.hash(*algorithm));
+        }
+
+        if self.spill_threshold.is_some_and(|threshold| self.bytes_in_memory() > threshold) && self.spill().is_err() {
+            // Keep going in memory rather than losing the scan
+            self.spill_threshold = None;
+        }
+    }
+
+    /// Rebuild the `FileInfo` for a row
+    pub fn get(&self, row: usize) -> FileInfo {
+        let hashes: FileHashes = self.digests
+            .iter()
+            .filter_map(|(algorithm, digests)| digests.get(*algorithm, row).map(|digest| (*algorithm, digest)))
+            .collect();
+        FileInfo {
+            name: self.name(row).to_string(),
+            full_path: self.full_path(row).into_owned(),
+            directory_path: self.directory(row).to_string(),
+            size: self.sizes[row],
+            created: self.created.get(row),
+            last_modified: self.modified.get(row),
+            last_accessed: self.accessed.get(row),
+            hashes,
+            format: self.format(row).to_string(),
+            mime_type: self.mime_type(row).to_string(),
+            unix: self.unix(row),
+        }
+    }
+
+    pub fn name(&self, row: usize) -> &str {
+        let start = if row == 0 { 0 } else { self.name_ends[row - 1] as usize };
+        let bytes = self.names.get(start..self.name_ends[row] as usize);
+        // Names are only ever pushed from `&str`
+        std::str::from_utf8(bytes).unwrap_or_default()
+    }
+
+    pub fn directory(&self, row: usize) -> &str {
+        self.directories.get(self.directory_ids[row])
+    }
+
+    pub fn full_path(&self, row: usize) -> Cow<'_, str> {
+        match self.full_paths.get(&(row as u32)) {
+            Some(path) => Cow::Borrowed(path),
+            None => Cow::Owned(join_path(self.directory(row), self.name(row))),
+        }
+    }
+
+    pub fn size(&self, row: usize) -> u64 {
+        self.sizes[row]
+    }
+
+    pub fn created(&self, row: usize) -> Option<DateTime<Utc>> {
+        self.created.get(row)
+    }
+
+    pub fn modified(&self, row: usize) -> Option<DateTime<Utc>> {
+        self.modified.get(row)
+    }
+
+    pub fn accessed(&self, row: usize) -> Option<DateTime<Utc>> {
+        self.accessed.get(row)
+    }
+
+    pub fn format(&self, row: usize) -> &str {
+        self.formats.get(self.format_ids[row])
+    }
+
+    pub fn mime_type(&self, row: usize) -> &str {
+        self.media_types.get(self.media_type_ids[row])
+    }
+
+    pub fn unix(&self, row: usize) -> Option<UnixMetadata> {
+        self.unix.get(row)
+    }
+
+    /// The digest calculated for a row with an algorithm, if there is one
+    pub fn hash(&self, row: usize, algorithm: HashAlgorithm) -> Option<Digest> {
+        self.digests
+            .iter()
+            .find(|(existing, _)| *existing == algorithm)
+            .and_then(|(_, digests)| digests.get(algorithm, row))
+    }
+
+    /// Every row as a `FileInfo`, in table order
+    pub fn iter(&self) -> impl Iterator<Item = FileInfo> + '_ {
+        (0..self.len()).map(|row| self.get(row))
+    }
+
+    /// A view of some of the rows, in the given order
+    pub fn rows<'a>(&'a self, rows: &'a [u32]) -> TableRows<'a> {
+        TableRows { table: self, rows }
+    }
+
+    /// Row numbers ordered by depth, then path, the order in which scans
+    /// return their detailed file lists
+    pub fn rows_in_path_order(&self) -> Vec<u32> {
+        let mut rows: Vec<u32> = (0..self.len() as u32).collect();
+        if !self.full_paths.is_empty() {
+            rows.sort_by_cached_key(|&row| {
+                let path = PathBuf::from(self.full_path(row as usize).as_ref());
+                (path.components().count(), path)
+            });
+            return rows;
+        }
+
+        // Rows at the same depth have directories of the same depth, so
+        // ranking each distinct directory once leaves only the names to compare
+        let directories = &self.directories.values;
+        let mut by_path: Vec<usize> = (0..directories.len()).collect();
+        by_path.sort_by(|&a, &b| Path::new(directories[a].as_ref()).cmp(Path::new(directories[b].as_ref())));
+        let mut ranks = vec![0; directories.len()];
+        for (rank, &id) in by_path.iter().enumerate() {
+            ranks[id] = rank;
+        }
+        let depths: Vec<usize> = directories
+            .iter()
+            .map(|directory| Path::new(directory.as_ref()).components().count() + 1)
+            .collect();
+
+        rows.sort_by(|&a, &b| {
+            let (a, b) = (a as usize, b as usize);
+            let (directory_a, directory_b) = (self.directory_ids[a] as usize, self.directory_ids[b] as usize);
+            (depths[directory_a], ranks[directory_a])
+                .cmp(&(depths[directory_b], ranks[directory_b]))
+                .then_with(|| self.name(a).cmp(self.name(b)))
+        });
+        rows
+    }
+
+    /// Move the names and digests held in memory to a temporary file
+    pub fn spill(&mut self) -> io::Result<()> {
+        self.names.spill()?;
+        for (_, digests) in &mut self.digests {
+            digests.bytes_mut().spill()?;
+        }
+        Ok(())
+    }
+
+    /// Approximate heap memory used by the table, excluding spilled data
+    pub fn memory_usage(&self) -> usize {
+        let rows = self.len();
+        // Name end, directory, size, three timestamps, format and media type,
+        // then the Unix mode, owner, group, inode, links and change time
+        let fixed = rows * (8 + 4 + 8 + 3 * (8 + 4) + 4 + 4) + self.unix.present.len() * (1 + 4 + 4 + 4 + 8 + 8 + 8 + 4);
+        let digests: usize = self.digests.iter().map(|(_, digests)| digests.memory_usage()).sum();
+        let full_paths: usize = self.full_paths.values().map(|path| path.len() + 32).sum();
+        fixed
+            + self.names.memory_usage()
+            + digests
+            + full_paths
+            + self.directories.memory_usage()
+            + self.formats.memory_usage()
+            + self.media_types.memory_usage()
+    }
+
+    fn bytes_in_memory(&self) -> usize {
+        self.names.tail.len() + self.digests.iter().map(|(_, digests)| digests.bytes().tail.len()).sum::<usize>()
+    }
+}
+
+impl Extend<FileInfo> for FileTable {
+    fn extend<I: IntoIterator<Item = FileInfo>>(&mut self, files: I) {
+        for info in files {
+            self.push(&info);
+        }
+    }
+}
+
+impl FromIterator<FileInfo> for FileTable {
+    fn from_iter<I: IntoIterator<Item = FileInfo>>(files: I) -> Self {
+        let mut table = Self::new();
+        table.extend(files);
+        table
+    }
+}
+
+/// Read access to a list of scanned files by index
+///
+/// Implemented for plain `FileInfo` lists and for [`FileTable`]s and their
+/// [`TableRows`], so duplicate and similarity searches and scan comparisons
+/// can work on a table without rebuilding every row.
+pub trait FileList: Sync {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn full_path(&self, index: usize) -> Cow<'_, str>;
+
+    fn size(&self, index: usize) -> u64;
+
+    fn modified(&self, index: usize) -> Option<DateTime<Utc>>;
+
+    fn hash(&self, index: usize, algorithm: HashAlgorithm) -> Option<Cow<'_, Digest>>;
+}
+
+impl FileList for [FileInfo] {
+    fn len(&self) -> usize {
+        <[FileInfo]>::len(self)
+    }
+
+    fn full_path(&self, index: usize) -> Cow<'_, str> {
+        Cow::Borrowed(&self[index].full_path)
+    }
+
+    fn size(&self, index: usize) -> u64 {
+        self[index].size
+    }
+
+    fn modified(&self, index: usize) -> Option<DateTime<Utc>> {
+        self[index].last_modified
+    }
+
+    fn hash(&self, index: usize, algorithm: HashAlgorithm) -> Option<Cow<'_, Digest>> {
+        self[index].hash(algorithm).map(Cow::Borrowed)
+    }
+}
+
+impl FileList for Vec<FileInfo> {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn full_path(&self, index: usize) -> Cow<'_, str> {
+        self.as_slice().full_path(index)
+    }
+
+    fn size(&self, index: usize) -> u64 {
+        self.as_slice().size(index)
+    }
+
+    fn modified(&self, index: usize) -> Option<DateTime<Utc>> {
+        self.as_slice().modified(index)
+    }
+
+    fn hash(&self, index: usize, algorithm: HashAlgorithm) -> Option<Cow<'_, Digest>> {
+        self.as_slice().hash(index, algorithm)
+    }
+}
+
+impl FileList for FileTable {
+    fn len(&self) -> usize {
+        FileTable::len(self)
+    }
+
+    fn full_path(&self, index: usize) -> Cow<'_, str> {
+        FileTable::full_path(self, index)
+    }
+
+    fn size(&self, index: usize) -> u64 {
+        FileTable::size(self, index)
+    }
+
+    fn modified(&self, index: usize) -> Option<DateTime<Utc>> {
+        FileTable::modified(self, index)
+    }
+
+    fn hash(&self, index: usize, algorithm: HashAlgorithm) -> Option<Cow<'_, Digest>> {
+        FileTable::hash(self, index, algorithm).map(Cow::Owned)
+    }
+}
+
+/// Some rows of a [`FileTable`], read through [`FileList`] with indexes
+/// into the row list
+#[derive(Debug, Clone, Copy)]
+pub struct TableRows<'a> {
+    table: &'a FileTable,
+    rows: &'a [u32],
+}
+
+impl FileList for TableRows<'_> {
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn full_path(&self, index: usize) -> Cow<'_, str> {
+        self.table.full_path(self.rows[index] as usize)
+    }
+
+    fn size(&self, index: usize) -> u64 {
+        self.table.size(self.rows[index] as usize)
+    }
+
+    fn modified(&self, index: usize) -> Option<DateTime<Utc>> {
+        self.table.modified(self.rows[index] as usize)
+    }
+
+    fn hash(&self, index: usize, algorithm: HashAlgorithm) -> Option<Cow<'_, Digest>> {
+        self.table.hash(self.rows[index] as usize, algorithm).map(Cow::Owned)
+    }
+}
+
+/// One timestamp per row, split so a missing time costs no more than a known one
+#[derive(Debug, Clone, Default)]
+struct Times {
+    seconds: Vec<i64>,
+    nanos: Vec<u32>,
+}
+
+impl Times {
+    fn push(&mut self, time: Option<DateTime<Utc>>) {
+        self.seconds.push(time.map_or(NO_TIME, |time| time.timestamp()));
+        self.nanos.push(time.map_or(0, |time| time.timestamp_subsec_nanos()));
+    }
+
+    fn get(&self, row: usize) -> Option<DateTime<Utc>> {
+        let seconds = self.seconds[row];
+        if seconds == NO_TIME {
+            return None;
+        }
+        DateTime::from_timestamp(seconds, self.nanos[row])
+    }
+}
+
+/// Unix metadata, one entry per row from the first that has some; rows
+/// without any are zero-filled
+#[derive(Debug, Clone, Default)]
+struct UnixColumns {
+    present: Vec<bool>,
+    modes: Vec<u32>,
+    uids: Vec<u32>,
+    gids: Vec<u32>,
+    inodes: Vec<u64>,
+    links: Vec<u64>,
+    changed: Times,
+}
+
+impl UnixColumns {
+    /// Store the metadata of `row`. Columns only start once a row has some,
+    /// so tables scanned on other platforms hold nothing here.
+    fn push(&mut self, row: usize, unix: Option<UnixMetadata>) {
+        if unix.is_none() && self.present.is_empty() {
+            return;
+        }
+        while self.present.len() < row {
+            self.push_row(None);
+        }
+        self.push_row(unix);
+    }
+
+    fn push_row(&mut self, unix: Option<UnixMetadata>) {
+        self.present.push(unix.is_some());
+        let unix = unix.unwrap_or(UnixMetadata { mode: 0, uid: 0, gid: 0, inode: 0, links: 0, changed: None });
+        self.modes.push(unix.mode);
+        self.uids.push(unix.uid);
+        self.gids.push(unix.gid);
+        self.inodes.push(unix.inode);
+        self.links.push(unix.links);
+        self.changed.push(unix.changed);
+    }
+
+    fn get(&self, row: usize) -> Option<UnixMetadata> {
+        self.present.get(row).copied()?.then(|| UnixMetadata {
+            mode: self.modes[row],
+            uid: self.uids[row],
+            gid: self.gids[row],
+            inode: self.inodes[row],
+            links: self.links[row],
+            changed: self.changed.get(row),
+        })
+    }
+}
+
+/// The digests of one algorithm, one entry per row
+#[derive(Debug, Clone)]
+enum Digests {
+    /// Digests of a fixed length; rows without one are zero-filled
+    Binary { width: usize, bytes: Bytes, present: Vec<bool> },
+    /// Fuzzy hashes, whose length varies
+    Text { bytes: Bytes, ends: Vec<u64> },
+}
+
+impl Digests {
+    /// A column for an algorithm first seen after `rows` rows, which are left empty
+    fn new(algorithm: HashAlgorithm, rows: usize) -> Self {
+        let mut digests = if algorithm.is_fuzzy() {
+            Digests::Text { bytes: Bytes::default(), ends: Vec::new() }
+        } else {
+            Digests::Binary { width: algorithm.digest_len() / 2, bytes: Bytes::default(), present: Vec::new() }
+        };
+        for _ in 0..rows {
+            digests.push(None);
+        }
+        digests
+    }
+
+    fn push(&mut self, digest: Option<&Digest>) {
+        match self {
+            Digests::Binary { width, bytes, present } => {
+                let digest = digest.map(Digest::as_bytes).filter(|digest| digest.len() == *width);
+                present.push(digest.is_some());
+                match digest {
+                    Some(digest) => bytes.extend(digest),
+                    None => bytes.extend(&vec![0; *width]),
+                }
+            }
+            Digests::Text { bytes, ends } => {
+                bytes.extend(digest.map(Digest::as_bytes).unwrap_or_default());
+                ends.push(bytes.len() as u64);
+            }
+        }
+    }
+
+    fn get(&self, algorithm: HashAlgorithm, row: usize) -> Option<Digest> {
+        let range = match self {
+            Digests::Binary { width, present, .. } => present[row].then_some(row * width..(row + 1) * width)?,
+            Digests::Text { ends, .. } => {
+                let start = if row == 0 { 0 } else { ends[row - 1] as usize };
+                (ends[row] as usize > start).then_some(start..ends[row] as usize)?
+            }
+        };
+        Digest::from_bytes(algorithm, self.bytes().get(range))
+    }
+
+    fn bytes(&self) -> &Bytes {
+        match self {
+            Digests::Binary { bytes, .. } | Digests::Text { bytes, .. } => bytes,
+        }
+    }
+
+    fn bytes_mut(&mut self) -> &mut Bytes {
+        match self {
+            Digests::Binary { bytes, .. } | Digests::Text { bytes, .. } => bytes,
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        let index = match self {
+            Digests::Binary { present, .. } => present.len(),
+            Digests::Text { ends, .. } => ends.len() * 8,
+        };
+        index + self.bytes().memory_usage()
+    }
+}
+
+/// A growable byte buffer whose older contents may have been moved to a
+/// memory-mapped temporary file
+///
+/// Each spill moves the whole in-memory tail, so a range pushed in one piece
+/// is always entirely in the file or entirely in memory.
+#[derive(Debug, Clone, Default)]
+struct Bytes {
+    spilled: Option<Arc<Spill>>,
+    tail: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Spill {
+    file: File,
+    map: Mmap,
+}
+
+impl Bytes {
+    fn spilled_len(&self) -> usize {
+        self.spilled.as_ref().map_or(0, |spill| spill.map.len())
+    }
+
+    fn len(&self) -> usize {
+        self.spilled_len() + self.tail.len()
+    }
+
+    fn extend(&mut self, data: &[u8]) {
+        self.tail.extend_from_slice(data);
+    }
+
+    fn get(&self, range: Range<usize>) -> &[u8] {
+        let spilled = self.spilled_len();
+        if range.start >= spilled {
+            &self.tail[range.start - spilled..range.end - spilled]
+        } else {
+            &self.spilled.as_ref().expect("spilled range without a spill file").map[range]
+        }
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        if self.tail.is_empty() {
+            return Ok(());
+        }
+        // Append to our own file; a file shared with a clone of the table is
+        // copied first so the clone's view of it never changes
+        let mut file = match self.spilled.take().map(Arc::try_unwrap) {
+            Some(Ok(spill)) => spill.file,
+            Some(Err(shared)) => {
+                let mut file = tempfile::tempfile()?;
+                file.write_all(&shared.map)?;
+                file
+            }
+            None => tempfile::tempfile()?,
+        };
+        file.write_all(&self.tail)?;
+        file.flush()?;
+        // SAFETY: the file is unlinked and private to this buffer, and it is
+        // only ever appended to after the region mapped here
+        let map = unsafe { Mmap::map(&file)? };
+        self.spilled = Some(Arc::new(Spill { file, map }));
+        self.tail = Vec::new();
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.tail.capacity()
+    }
+}
+
+/// Strings stored once per distinct value
+#[derive(Debug, Clone, Default)]
+struct Interner {
+    values: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, u32>,
+}
+
+impl Interner {
+    fn intern(&mut self, value: &str) -> u32 {
+        if let Some(&id) = self.ids.get(value) {
+            return id;
+        }
+        let id = self.values.len() as u32;
+        let value: Arc<str> = Arc::from(value);
+        self.values.push(Arc::clone(&value));
+        self.ids.insert(value, id);
+        id
+    }
+
+    fn get(&self, id: u32) -> &str {
+        &self.values[id as usize]
+    }
+
+    fn memory_usage(&self) -> usize {
+        // Each value plus its two references and the map entry
+        self.values.iter().map(|value| value.len() + 48).sum()
+    }
+}
+
+/// Join a directory and a file name the way the scanner builds full paths
+fn join_path(directory: &str, name: &str) -> String {
+    Path::new(directory).join(name).to_string_lossy().into_owned()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_file(index: usize) -> FileInfo {
+        let mut hashes = FileHashes::from([(HashAlgorithm::Sha256, Digest::Sha256([index as u8; 32]))]);
+        if index.is_multiple_of(2) {
+            hashes.insert(HashAlgorithm::Ssdeep, Digest::Ssdeep(format!("3:{}:abc", index)));
+        }
+        FileInfo {
+            last_modified: DateTime::from_timestamp(1_709_296_245, index as u32 * 1_000),
+            last_accessed: DateTime::from_timestamp(1_709_366_400, 0),
+            hashes,
+            format: "Plain Text".to_string(),
+            // The first row has none, so the Unix columns start part way
+            unix: (index > 0).then(|| UnixMetadata {
+                mode: 0o100644,
+                uid: 1000,
+                gid: 1000,
+                inode: index as u64,
+                links: 1,
+                changed: DateTime::from_timestamp(1_709_296_245, 500),
+            }),
+            ..FileInfo::for_test(&format!("/data/dir{}/file{}.txt", index % 3, index), index as u64 * 100)
+        }
+    }
+
+    fn sample_table(count: usize) -> (Vec<FileInfo>, FileTable) {
+        let files: Vec<FileInfo> = (0..count).map(sample_file).collect();
+        let mut table = FileTable::new().spill_threshold(1024);
+        table.extend(files.iter().cloned());
+        (files, table)
+    }
+
+    fn assert_restored(table: &FileTable, row: usize, original: &FileInfo) {
+        let restored = table.get(row);
+        assert_eq!(restored.name, original.name);
+        assert_eq!(restored.full_path, original.full_path);
+        assert_eq!(restored.directory_path, original.directory_path);
+        assert_eq!(restored.size, original.size);
+        assert_eq!(restored.last_modified, original.last_modified);
+        assert_eq!(restored.created, None);
+        assert_eq!(restored.hashes, original.hashes);
+        assert_eq!(restored.unix, original.unix);
+    }
+
+    #[test]
+    fn test_table_round_trips_files() {
+        let (files, table) = sample_table(100);
+        assert_eq!(table.len(), 100);
+        for (row, original) in files.iter().enumerate() {
+            assert_restored(&table, row, original);
+        }
+        assert_eq!(table.directories.values.len(), 3);
+    }
+
+    #[test]
+    fn test_table_spills_to_disk() {
+        let (_, table) = sample_table(100);
+        assert!(table.memory_usage() < 100 * 200);
+    }
+
+    #[test]
+    fn test_table_adds_late_digests() {
+        let (files, mut table) = sample_table(10);
+        // A digest first seen after other rows leaves the earlier ones empty
+        let mut late = sample_file(10);
+        late.hashes.insert(HashAlgorithm::Md5, Digest::Md5([0xd4; 16]));
+        table.push(&late);
+
+        assert_restored(&table, 10, &late);
+        assert_restored(&table, 0, &files[0]);
+    }
+
+    #[test]
+    fn test_table_path_order() {
+        let (_, table) = sample_table(100);
+        assert_eq!(table.rows_in_path_order()[..2], [0, 12]);
+    }
+
+    #[test]
+    fn test_table_path_order_matches_paths() {
+        let files: Vec<FileInfo> = ["/data/b/z.txt", "/data/a.b/c.txt", "/data/a/c.txt", "/data/y.txt", "/data/a/b/c.txt", "/data/a/a.txt"]
+            .into_iter()
+            .map(|path| FileInfo::for_test(path, 1))
+            .collect();
+        let table: FileTable = files.iter().cloned().collect();
+        let mut expected: Vec<&str> = files.iter().map(|file| file.full_path.as_str()).collect();
+        expected.sort_by_key(|&path| (Path::new(path).components().count(), Path::new(path)));
+
+        let ordered: Vec<String> = table.rows_in_path_order().into_iter().map(|row| table.full_path(row as usize).into_owned()).collect();
+        assert_eq!(ordered, expected);
+    }
+
+    #[test]
+    fn test_table_rows_as_file_list() {
+        let (files, table) = sample_table(10);
+        let rows = [7, 2];
+        let view = table.rows(&rows);
+        assert_eq!(FileList::len(&view), 2);
+        assert_eq!(view.full_path(0), files[7].full_path);
+        assert_eq!(view.size(1), files[2].size);
+        assert_eq!(view.hash(1, HashAlgorithm::Ssdeep).as_deref(), files[2].hash(HashAlgorithm::Ssdeep));
+        assert_eq!(view.hash(0, HashAlgorithm::Ssdeep), None);
+    }
+}
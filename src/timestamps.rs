@@ -0,0 +1,185 @@
+//! Timestamp display
+//!
+//! File times are kept as instants in UTC and only turned into text when
+//! they are shown or written out. A [`TimeFormat`] decides how: in which
+//! zone (UTC, the zone this machine is set to, or a named IANA zone) and in
+//! which style (date and time, ISO 8601 with an offset, or seconds or
+//! nanoseconds since the Unix epoch). Listings, CSV files and the text tree
+//! and analysis outputs follow it; JSON and DFXML exports are always written
+//! as ISO 8601 in UTC so they can be read back.
+
+use std::fmt;
+
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Layout of the date and time style
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Shown in place of a time the filesystem did not provide
+const NO_TIME: &str = "N/A";
+
+/// Time zone that timestamps are shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayZone {
+    #[default]
+    Utc,
+    /// The zone configured on this machine
+    Local,
+    /// A zone from the IANA database, such as `Europe/Berlin`
+    Named(Tz),
+}
+
+impl DisplayZone {
+    /// Parse `UTC`, `local` (in any case) or an IANA zone name
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("utc") || value.is_empty() {
+            Ok(DisplayZone::Utc)
+        } else if value.eq_ignore_ascii_case("local") {
+            Ok(DisplayZone::Local)
+        } else {
+            value.parse::<Tz>().map(DisplayZone::Named).map_err(|_| {
+                format!("unknown time zone '{}' (expected UTC, local or an IANA name like Europe/Berlin)", value)
+            })
+        }
+    }
+}
+
+impl fmt::Display for DisplayZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayZone::Utc => f.write_str("UTC"),
+            DisplayZone::Local => f.write_str("Local"),
+            DisplayZone::Named(zone) => f.write_str(zone.name()),
+        }
+    }
+}
+
+/// How a timestamp is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampStyle {
+    /// `2024-03-01 12:30:45`, to the second
+    #[default]
+    DateTime,
+    /// `2024-03-01T13:30:45.123456789+01:00`, with as many fractional
+    /// digits as the time has
+    Iso8601,
+    /// Whole seconds since the Unix epoch
+    EpochSeconds,
+    /// Nanoseconds since the Unix epoch
+    EpochNanos,
+}
+
+impl TimestampStyle {
+    pub const ALL: [TimestampStyle; 4] = [
+        TimestampStyle::DateTime,
+        TimestampStyle::Iso8601,
+        TimestampStyle::EpochSeconds,
+        TimestampStyle::EpochNanos,
+    ];
+
+    /// Name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimestampStyle::DateTime => "datetime",
+            TimestampStyle::Iso8601 => "iso",
+            TimestampStyle::EpochSeconds => "epoch",
+            TimestampStyle::EpochNanos => "epoch-ns",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+impl fmt::Display for TimestampStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TimestampStyle::DateTime => "Date and time",
+            TimestampStyle::Iso8601 => "ISO 8601",
+            TimestampStyle::EpochSeconds => "Epoch seconds",
+            TimestampStyle::EpochNanos => "Epoch nanoseconds",
+        })
+    }
+}
+
+/// Zone and style for displayed timestamps; the default is date and time in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeFormat {
+    pub zone: DisplayZone,
+    pub style: TimestampStyle,
+}
+
+impl TimeFormat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn zone(mut self, zone: DisplayZone) -> Self {
+        self.zone = zone;
+        self
+    }
+
+    pub fn style(mut self, style: TimestampStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Format a time that may be unavailable, as "N/A" when it is
+    pub fn format(&self, time: Option<DateTime<Utc>>) -> String {
+        time.map_or_else(|| NO_TIME.to_string(), |time| self.format_time(time))
+    }
+
+    /// Format a time. The epoch styles count from the same instant in every
+    /// zone, so they ignore it.
+    pub fn format_time(&self, time: DateTime<Utc>) -> String {
+        match self.zone {
+            DisplayZone::Utc => self.render(time),
+            DisplayZone::Local => self.render(time.with_timezone(&Local)),
+            DisplayZone::Named(zone) => self.render(time.with_timezone(&zone)),
+        }
+    }
+
+    fn render<Z: TimeZone>(&self, time: DateTime<Z>) -> String
+    where
+        Z::Offset: fmt::Display,
+    {
+        match self.style {
+            TimestampStyle::DateTime => time.format(DATE_TIME_FORMAT).to_string(),
+            TimestampStyle::Iso8601 => time.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+            TimestampStyle::EpochSeconds => time.timestamp().to_string(),
+            // Computed wide, since nanoseconds only fit an i64 between 1677 and 2262
+            TimestampStyle::EpochNanos => {
+                (i128::from(time.timestamp()) * 1_000_000_000 + i128::from(time.timestamp_subsec_nanos())).to_string()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_formats() {
+        let time = DateTime::from_timestamp(1_709_296_245, 125_000_000);
+        let berlin = TimeFormat::new().zone(DisplayZone::parse("Europe/Berlin").unwrap());
+
+        assert_eq!(TimeFormat::new().format(time), "2024-03-01 12:30:45");
+        assert_eq!(TimeFormat::new().format(None), "N/A");
+        assert_eq!(berlin.format(time), "2024-03-01 13:30:45");
+        assert_eq!(berlin.style(TimestampStyle::Iso8601).format(time), "2024-03-01T13:30:45.125+01:00");
+        assert_eq!(berlin.style(TimestampStyle::EpochSeconds).format(time), "1709296245");
+        assert_eq!(berlin.style(TimestampStyle::EpochNanos).format(time), "1709296245125000000");
+        assert_eq!(
+            TimeFormat::new().style(TimestampStyle::EpochNanos).format(DateTime::from_timestamp(-1, 0)),
+            "-1000000000"
+        );
+
+        assert_eq!(DisplayZone::parse("LOCAL"), Ok(DisplayZone::Local));
+        assert!(DisplayZone::parse("Mars/Olympus_Mons").is_err());
+        assert_eq!(TimestampStyle::from_name("epoch-ns"), Some(TimestampStyle::EpochNanos));
+    }
+}
@@ -1,4 +1,7 @@
 use std::path::Path;
+use chrono::{DateTime, Utc};
+use crate::timestamps::TimeFormat;
+use crate::unix::UnixMetadata;
 use crate::TreeNode;
 
 /// Tree formatting options
@@ -7,8 +10,17 @@ pub struct TreeFormatOptions {
     pub colorize: bool,
     pub show_dotfiles: bool,
     pub use_unicode: bool,
+    /// Show each entry's size before its name, with directories totalled,
+    /// like `tree -h --du`
     pub show_file_sizes: bool,
+    /// Show each entry's permissions, owner and group before its name, like
+    /// `tree -p`, `-u` and `-g`
     pub show_permissions: bool,
+    pub show_owner: bool,
+    pub show_group: bool,
+    /// Show each entry's modification time before its name, like `tree -D`
+    pub show_modified: bool,
+    pub time_format: TimeFormat,
 }
 
 impl Default for TreeFormatOptions {
@@ -19,6 +31,10 @@ impl Default for TreeFormatOptions {
             use_unicode: true,
             show_file_sizes: false,
             show_permissions: false,
+            show_owner: false,
+            show_group: false,
+            show_modified: false,
+            time_format: TimeFormat::default(),
         }
     }
 }
@@ -52,6 +68,53 @@ impl TreeFormatOptions {
         self.show_permissions = show;
         self
     }
+    
+    pub fn show_owner(mut self, show: bool) -> Self {
+        self.show_owner = show;
+        self
+    }
+    
+    pub fn show_group(mut self, show: bool) -> Self {
+        self.show_group = show;
+        self
+    }
+    
+    pub fn show_modified(mut self, show: bool) -> Self {
+        self.show_modified = show;
+        self
+    }
+    
+    pub fn time_format(mut self, format: TimeFormat) -> Self {
+        self.time_format = format;
+        self
+    }
+    
+    /// The fields shown in brackets before an entry's name, in the order
+    /// `tree -pughD` prints them, or nothing when none are enabled. Owners and
+    /// groups are padded to eight characters so names line up.
+    pub fn details(&self, size: u64, modified: Option<DateTime<Utc>>, unix: Option<&UnixMetadata>) -> String {
+        let mut fields = Vec::new();
+        if self.show_permissions {
+            fields.push(unix.map_or_else(|| "?".to_string(), UnixMetadata::permissions));
+        }
+        if self.show_owner {
+            fields.push(format!("{:<8}", unix.map_or_else(|| "?".to_string(), UnixMetadata::owner)));
+        }
+        if self.show_group {
+            fields.push(format!("{:<8}", unix.map_or_else(|| "?".to_string(), UnixMetadata::group)));
+        }
+        if self.show_file_sizes {
+            fields.push(human_size(size));
+        }
+        if self.show_modified {
+            fields.push(self.time_format.format(modified));
+        }
+        if fields.is_empty() {
+            String::new()
+        } else {
+            format!("[{}]  ", fields.join(" "))
+        }
+    }
 }
 
 /// Tree character sets for different display modes
@@ -148,18 +211,20 @@ impl TreeFormatter {
             if self.options.colorize {
                 let (colored_name, _) = get_file_color(&child.path, &child.name, child.is_directory);
                 output.push_str(&format!(
-                    "\x1b[37m{}{}\x1b[0m{} {}\n", 
+                    "\x1b[37m{}{}\x1b[0m{} {}{}\n", 
                     prefix, 
                     connector, 
                     self.chars.horizontal,
+                    self.details(child),
                     colored_name
                 ));
             } else {
                 output.push_str(&format!(
-                    "{}{}{} {}\n", 
+                    "{}{}{} {}{}\n", 
                     prefix, 
                     connector, 
                     self.chars.horizontal,
+                    self.details(child),
                     child.name
                 ));
             }
@@ -195,7 +260,7 @@ impl TreeFormatter {
             let line_prefix = format!("{}{}{} ", prefix, connector, self.chars.horizontal);
             
             lines.push(TreeLine {
-                content: child.name.clone(),
+                content: format!("{}{}", self.details(child), child.name),
                 depth,
                 is_directory: child.is_directory,
                 path: child.path.clone(),
@@ -213,6 +278,11 @@ impl TreeFormatter {
             }
         }
     }
+    
+    /// Fields shown in brackets before an entry's name
+    fn details(&self, node: &TreeNode) -> String {
+        self.options.details(node.size, node.modified, node.unix.as_ref())
+    }
 }
 
 /// Represents a single line in a tree display (useful for GUIs)
@@ -264,6 +334,29 @@ pub enum FileType {
 }
 
 impl FileType {
+    pub const ALL: [FileType; 10] = [
+        FileType::Directory,
+        FileType::Executable,
+        FileType::Archive,
+        FileType::Image,
+        FileType::Document,
+        FileType::SourceCode,
+        FileType::Config,
+        FileType::Audio,
+        FileType::Video,
+        FileType::Other,
+    ];
+    
+    /// Look up a type by its description or variant name, ignoring case and spaces
+    /// (`image`, `source code`, `sourcecode`, `config`, `configuration`)
+    pub fn from_name(name: &str) -> Option<FileType> {
+        let normalize = |value: &str| value.replace(' ', "").to_lowercase();
+        let name = normalize(name);
+        FileType::ALL.into_iter().find(|file_type| {
+            normalize(file_type.description()) == name || normalize(&format!("{:?}", file_type)) == name
+        })
+    }
+    
     /// Get a human-readable description of the file type
     pub fn description(&self) -> &'static str {
         match self {
@@ -295,9 +388,45 @@ impl FileType {
             FileType::Other => "\x1b[37m",           // White
         }
     }
+    
+    /// Get the RGB color used for this file type in charts
+    pub fn chart_color(&self) -> [u8; 3] {
+        match self {
+            FileType::Directory => [77, 115, 204],
+            FileType::Executable => [89, 191, 89],
+            FileType::Archive => [217, 77, 77],
+            FileType::Image => [191, 102, 204],
+            FileType::Document => [77, 191, 204],
+            FileType::SourceCode => [204, 179, 64],
+            FileType::Config => [242, 217, 115],
+            FileType::Audio => [242, 128, 179],
+            FileType::Video => [115, 217, 230],
+            FileType::Other => [140, 140, 140],
+        }
+    }
 }
 
-/// Utility functions for tree operations
+// Utility functions for tree operations
+
+/// Format a size the way `tree -h` does: bytes below 1 KiB, otherwise one
+/// decimal below ten units and none above, right-aligned in four characters
+fn human_size(bytes: u64) -> String {
+    const UNITS: [char; 6] = ['K', 'M', 'G', 'T', 'P', 'E'];
+    if bytes < 1024 {
+        return format!("{:>4}", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value >= 10.0 {
+        format!("{:>3.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:>3.1}{}", value, UNITS[unit])
+    }
+}
 
 /// Filter tree nodes based on criteria
 pub fn filter_tree_by_type(tree: &TreeNode, allowed_types: &[FileType]) -> TreeNode {
@@ -320,6 +449,18 @@ pub fn filter_tree_by_type(tree: &TreeNode, allowed_types: &[FileType]) -> TreeN
         name: tree.name.clone(),
         path: tree.path.clone(),
         is_directory: tree.is_directory,
+        size: if tree.is_directory {
+            filtered_children.iter().map(|child| child.size).sum()
+        } else {
+            tree.size
+        },
+        file_count: if tree.is_directory {
+            filtered_children.iter().map(|child| child.file_count).sum()
+        } else {
+            1
+        },
+        modified: tree.modified,
+        unix: tree.unix,
         children: filtered_children,
     }
 }
@@ -339,3 +480,54 @@ fn count_files_recursive(node: &TreeNode, counts: &mut std::collections::HashMap
         count_files_recursive(child, counts);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_details_like_tree_pug() {
+        let node = |name: &str, mode: u32, children: Vec<TreeNode>| TreeNode {
+            name: name.to_string(),
+            path: name.into(),
+            is_directory: !children.is_empty(),
+            size: 0,
+            file_count: usize::from(children.is_empty()),
+            modified: DateTime::from_timestamp(1_709_296_245, 0),
+            unix: Some(UnixMetadata { mode, uid: u32::MAX - 1, gid: u32::MAX - 1, inode: 1, links: 1, changed: None }),
+            children,
+        };
+        let tree = node("root", 0o040755, vec![node("run.sh", 0o100750, Vec::new()), node("notes", 0o100644, Vec::new())]);
+        let id = (u32::MAX - 1).to_string();
+
+        let options = TreeFormatOptions::new().show_permissions(true).show_owner(true).show_group(true);
+        let output = TreeFormatter::new(options.clone()).format_tree(&tree);
+        assert_eq!(output.lines().nth(1), Some(format!("├─── [-rwxr-x--- {} {}]  run.sh", id, id).as_str()));
+
+        let dated = options.show_owner(false).show_group(false).show_modified(true);
+        assert_eq!(dated.details(0, tree.modified, None), "[? 2024-03-01 12:30:45]  ");
+        assert_eq!(TreeFormatOptions::new().details(0, tree.modified, tree.unix.as_ref()), "");
+    }
+
+    #[test]
+    fn test_tree_sizes_like_tree_du() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/big.bin"), vec![0u8; 3 * 1024 * 1024]).unwrap();
+        std::fs::write(dir.path().join("sub/small.txt"), b"hello").unwrap();
+        std::fs::write(dir.path().join("top.txt"), vec![b'x'; 20 * 1024]).unwrap();
+
+        let tree = crate::DirectoryScanner::new()
+            .skip_virtual_filesystems(false)
+            .scan_tree(dir.path())
+            .unwrap();
+        assert_eq!(tree.size, 3 * 1024 * 1024 + 5 + 20 * 1024);
+        assert_eq!((tree.file_count, tree.children[0].file_count), (3, 2));
+
+        let output = TreeFormatter::new(TreeFormatOptions::new().show_file_sizes(true)).format_tree(&tree);
+        let lines: Vec<&str> = output.lines().skip(1).collect();
+        assert_eq!(lines, ["├─── [3.0M]  sub", "│   ├─── [3.0M]  big.bin", "│   └─── [   5]  small.txt", "└─── [ 20K]  top.txt"]);
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1536), "1.5K");
+    }
+}
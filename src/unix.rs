@@ -0,0 +1,152 @@
+//! Unix file metadata
+//!
+//! Permission bits, ownership, inode number, hard link count and status
+//! change time, read through `std::os::unix::fs::MetadataExt`. Other
+//! platforms have no equivalent, so their files carry no [`UnixMetadata`].
+//! Owner and group names are looked up once per id and remembered for the
+//! rest of the process, since a scan usually sees only a handful of them.
+
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Names found for user or group ids; `None` for ids without one
+type NameCache = OnceLock<Mutex<HashMap<u32, Option<String>>>>;
+
+static USER_NAMES: NameCache = OnceLock::new();
+static GROUP_NAMES: NameCache = OnceLock::new();
+
+/// Ownership, permissions and inode details of a file or directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnixMetadata {
+    /// File type and permission bits, as in `st_mode`
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub inode: u64,
+    /// Number of hard links
+    pub links: u64,
+    /// Last status change (ctime)
+    pub changed: Option<DateTime<Utc>>,
+}
+
+impl UnixMetadata {
+    /// Read the Unix fields of a file's metadata; `None` on other platforms
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        Some(Self {
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            inode: metadata.ino(),
+            links: metadata.nlink(),
+            changed: DateTime::from_timestamp(metadata.ctime(), metadata.ctime_nsec() as u32),
+        })
+    }
+
+    /// Read the Unix fields of a file's metadata; `None` on other platforms
+    #[cfg(not(unix))]
+    pub fn from_metadata(_metadata: &Metadata) -> Option<Self> {
+        None
+    }
+
+    /// Permissions as `ls -l` shows them, such as `-rwxr-xr-x`
+    pub fn permissions(&self) -> String {
+        format_mode(self.mode)
+    }
+
+    /// Name of the owning user, or its id when it has none
+    pub fn owner(&self) -> String {
+        user_name(self.uid)
+    }
+
+    /// Name of the owning group, or its id when it has none
+    pub fn group(&self) -> String {
+        group_name(self.gid)
+    }
+}
+
+/// Format `st_mode` bits the way `ls -l` does: the file type, then read,
+/// write and execute for the owner, group and others, with setuid, setgid
+/// and sticky bits shown in the execute positions
+pub fn format_mode(mode: u32) -> String {
+    let file_type = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o010000 => 'p',
+        0o140000 => 's',
+        _ => '-',
+    };
+    let mut text = String::with_capacity(10);
+    text.push(file_type);
+    // Shift of each class's bits, and the special bit shown in its execute position
+    for (shift, special, set, unset) in [(6, 0o4000, 's', 'S'), (3, 0o2000, 's', 'S'), (0, 0o1000, 't', 'T')] {
+        let bits = (mode >> shift) & 0o7;
+        text.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        text.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => set,
+            (false, true) => unset,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+/// Name of a user id, or the id itself when it has no name
+pub fn user_name(uid: u32) -> String {
+    #[cfg(unix)]
+    let lookup = |uid| uzers::get_user_by_uid(uid).map(|user| user.name().to_string_lossy().into_owned());
+    #[cfg(not(unix))]
+    let lookup = |_| None;
+    cached_name(&USER_NAMES, uid, lookup)
+}
+
+/// Name of a group id, or the id itself when it has no name
+pub fn group_name(gid: u32) -> String {
+    #[cfg(unix)]
+    let lookup = |gid| uzers::get_group_by_gid(gid).map(|group| group.name().to_string_lossy().into_owned());
+    #[cfg(not(unix))]
+    let lookup = |_| None;
+    cached_name(&GROUP_NAMES, gid, lookup)
+}
+
+fn cached_name(cache: &NameCache, id: u32, lookup: impl FnOnce(u32) -> Option<String>) -> String {
+    let mut names = cache.get_or_init(Default::default).lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    names.entry(id).or_insert_with(|| lookup(id)).clone().unwrap_or_else(|| id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_mode() {
+        assert_eq!(format_mode(0o100644), "-rw-r--r--");
+        assert_eq!(format_mode(0o040755), "drwxr-xr-x");
+        assert_eq!(format_mode(0o104755), "-rwsr-xr-x");
+        assert_eq!(format_mode(0o041777), "drwxrwxrwt");
+        assert_eq!(format_mode(0o102640), "-rw-r-S---");
+        assert_eq!(format_mode(0o120777), "lrwxrwxrwx");
+        assert_eq!(user_name(u32::MAX - 1), (u32::MAX - 1).to_string());
+
+        #[cfg(unix)]
+        {
+            let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+            let path = dir.path().join("file.txt");
+            std::fs::write(&path, b"data").unwrap();
+            std::fs::hard_link(&path, dir.path().join("link.txt")).unwrap();
+            let unix = UnixMetadata::from_metadata(&std::fs::metadata(&path).unwrap()).unwrap();
+            assert_eq!(unix.links, 2);
+            assert!(unix.permissions().starts_with("-rw"));
+            assert!(unix.changed.is_some());
+        }
+    }
+}
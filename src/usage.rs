@@ -0,0 +1,283 @@
+//! Disk usage charts
+//!
+//! Lays out a [`TreeNode`] hierarchy as a squarified treemap or a sunburst,
+//! with every entry weighted by its size (the total below it, for a
+//! directory). The layouts are plain geometry so any front end can draw
+//! them; the GUI paints them on a canvas.
+//!
+//! The treemap follows Bruls, Huizing and van Wijk's squarified algorithm:
+//! entries are placed largest first in rows along the shorter side of the
+//! space left, and a row grows only while that keeps its tiles closer to
+//! square. Directories get their own tile with their entries laid out
+//! inside it, as in WinDirStat.
+
+use std::f32::consts::TAU;
+use std::path::Path;
+
+use crate::TreeNode;
+
+/// Space kept around the entries inside a directory's treemap tile
+const TILE_PADDING: f32 = 2.0;
+/// Directories whose tile is narrower or shorter than this are not
+/// subdivided
+const MIN_NESTED_SIZE: f32 = 8.0;
+
+/// A rectangle in chart coordinates, which are pixels in the GUI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width * self.height
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// The rectangle shrunk by `amount` on every side
+    pub fn inset(&self, amount: f32) -> Rect {
+        Rect::new(
+            self.x + amount,
+            self.y + amount,
+            (self.width - 2.0 * amount).max(0.0),
+            (self.height - 2.0 * amount).max(0.0),
+        )
+    }
+}
+
+/// One entry of a treemap
+#[derive(Debug, Clone, Copy)]
+pub struct TreemapTile<'a> {
+    pub node: &'a TreeNode,
+    pub rect: Rect,
+    /// 1 for the entries of the chart's root directory
+    pub depth: usize,
+}
+
+/// Lay out the entries below `root` as a squarified treemap filling
+/// `bounds`, going at most `max_depth` directories deep. Tiles come
+/// parents first, so the last one containing a point is the deepest.
+/// Empty entries and tiles smaller than a unit square are left out.
+pub fn treemap(root: &TreeNode, bounds: Rect, max_depth: usize) -> Vec<TreemapTile<'_>> {
+    let mut tiles = Vec::new();
+    treemap_children(root, bounds, 1, max_depth, &mut tiles);
+    tiles
+}
+
+fn treemap_children<'a>(node: &'a TreeNode, bounds: Rect, depth: usize, max_depth: usize, tiles: &mut Vec<TreemapTile<'a>>) {
+    let children = largest_first(node);
+    let weights: Vec<f64> = children.iter().map(|child| child.size as f64).collect();
+    for (child, rect) in children.into_iter().zip(squarify(&weights, bounds)) {
+        if rect.area() < 1.0 {
+            continue;
+        }
+        tiles.push(TreemapTile { node: child, rect, depth });
+        let inner = rect.inset(TILE_PADDING);
+        if child.is_directory && depth < max_depth && inner.width >= MIN_NESTED_SIZE && inner.height >= MIN_NESTED_SIZE {
+            treemap_children(child, inner, depth + 1, max_depth, tiles);
+        }
+    }
+}
+
+/// The deepest treemap tile at a point
+pub fn tile_at<'t, 'a>(tiles: &'t [TreemapTile<'a>], x: f32, y: f32) -> Option<&'t TreemapTile<'a>> {
+    tiles.iter().rev().find(|tile| tile.rect.contains(x, y))
+}
+
+/// Split `bounds` into one rectangle per weight, in the same order, with
+/// areas proportional to the weights. Weights should be positive and
+/// sorted largest first, which gives the squarest tiles.
+pub fn squarify(weights: &[f64], bounds: Rect) -> Vec<Rect> {
+    let total: f64 = weights.iter().sum();
+    let (mut x, mut y) = (bounds.x as f64, bounds.y as f64);
+    let (mut width, mut height) = (bounds.width as f64, bounds.height as f64);
+    if total <= 0.0 || width <= 0.0 || height <= 0.0 {
+        return vec![Rect::new(bounds.x, bounds.y, 0.0, 0.0); weights.len()];
+    }
+    let scale = width * height / total;
+    let areas: Vec<f64> = weights.iter().map(|weight| weight * scale).collect();
+
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut start = 0;
+    while start < areas.len() {
+        // Grow the row along the shorter side while its worst aspect ratio improves
+        let side = width.min(height);
+        let mut end = start + 1;
+        let mut row_area = areas[start];
+        while end < areas.len() {
+            let grown = row_area + areas[end];
+            if worst_ratio(&areas[start..=end], grown, side) > worst_ratio(&areas[start..end], row_area, side) {
+                break;
+            }
+            row_area = grown;
+            end += 1;
+        }
+
+        let thickness = if side > 0.0 { row_area / side } else { 0.0 };
+        let along = |area: f64| if thickness > 0.0 { area / thickness } else { 0.0 };
+        if width >= height {
+            // A column at the left of the space left
+            let mut offset = y;
+            for &area in &areas[start..end] {
+                rects.push(Rect::new(x as f32, offset as f32, thickness as f32, along(area) as f32));
+                offset += along(area);
+            }
+            x += thickness;
+            width -= thickness;
+        } else {
+            // A row along the top of the space left
+            let mut offset = x;
+            for &area in &areas[start..end] {
+                rects.push(Rect::new(offset as f32, y as f32, along(area) as f32, thickness as f32));
+                offset += along(area);
+            }
+            y += thickness;
+            height -= thickness;
+        }
+        start = end;
+    }
+    rects
+}
+
+/// The largest aspect ratio among tiles of these areas laid in a row of total
+/// `row_area` along a side of length `side`
+fn worst_ratio(areas: &[f64], row_area: f64, side: f64) -> f64 {
+    let largest = areas.iter().cloned().fold(f64::MIN, f64::max);
+    let smallest = areas.iter().cloned().fold(f64::MAX, f64::min);
+    let (side2, row2) = (side * side, row_area * row_area);
+    (side2 * largest / row2).max(row2 / (side2 * smallest))
+}
+
+/// One entry of a sunburst: a slice of the ring for its depth
+#[derive(Debug, Clone, Copy)]
+pub struct SunburstSegment<'a> {
+    pub node: &'a TreeNode,
+    /// Ring, counting from 1 for the entries of the chart's root directory
+    pub depth: usize,
+    /// Angles in radians, clockwise from the top
+    pub start: f32,
+    pub end: f32,
+}
+
+/// Lay out the entries below `root` as the rings of a sunburst, with
+/// `max_depth` rings. Each entry spans the part of its parent's angle that
+/// its size takes; slices narrower than `min_angle` are left out, along
+/// with everything below them.
+pub fn sunburst(root: &TreeNode, max_depth: usize, min_angle: f32) -> Vec<SunburstSegment<'_>> {
+    let mut segments = Vec::new();
+    sunburst_children(root, 0.0, TAU, 1, max_depth, min_angle, &mut segments);
+    segments
+}
+
+fn sunburst_children<'a>(
+    node: &'a TreeNode,
+    start: f32,
+    sweep: f32,
+    depth: usize,
+    max_depth: usize,
+    min_angle: f32,
+    segments: &mut Vec<SunburstSegment<'a>>,
+) {
+    if node.size == 0 || depth > max_depth {
+        return;
+    }
+    let mut angle = start;
+    for child in largest_first(node) {
+        let child_sweep = (sweep as f64 * child.size as f64 / node.size as f64) as f32;
+        // Entries are largest first, so the rest are narrower still
+        if child_sweep < min_angle {
+            break;
+        }
+        segments.push(SunburstSegment { node: child, depth, start: angle, end: angle + child_sweep });
+        if child.is_directory {
+            sunburst_children(child, angle, child_sweep, depth + 1, max_depth, min_angle, segments);
+        }
+        angle += child_sweep;
+    }
+}
+
+/// The sunburst segment in ring `depth` at `angle` (radians clockwise from the top)
+pub fn segment_at<'s, 'a>(segments: &'s [SunburstSegment<'a>], depth: usize, angle: f32) -> Option<&'s SunburstSegment<'a>> {
+    let angle = angle.rem_euclid(TAU);
+    segments.iter().find(|segment| segment.depth == depth && angle >= segment.start && angle < segment.end)
+}
+
+/// The entry at `path` within `root`, found by following the directories
+/// leading to it
+pub fn find_node<'a>(root: &'a TreeNode, path: &Path) -> Option<&'a TreeNode> {
+    let mut node = root;
+    while node.path != path {
+        node = node.children.iter().find(|child| path.starts_with(&child.path))?;
+    }
+    Some(node)
+}
+
+/// Non-empty entries of a directory, largest first
+fn largest_first(node: &TreeNode) -> Vec<&TreeNode> {
+    let mut children: Vec<&TreeNode> = node.children.iter().filter(|child| child.size > 0).collect();
+    children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(path: &str, size: u64, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.into(),
+            is_directory: !children.is_empty(),
+            size,
+            file_count: 1,
+            modified: None,
+            unix: None,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_usage_layouts() {
+        // The example from the squarified treemap paper: a 6 by 4 rectangle
+        let rects = squarify(&[6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0], Rect::new(0.0, 0.0, 6.0, 4.0));
+        assert_eq!(rects[0], Rect::new(0.0, 0.0, 3.0, 2.0));
+        assert_eq!(rects[1], Rect::new(0.0, 2.0, 3.0, 2.0));
+        for (rect, weight) in rects.iter().zip([6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0]) {
+            assert!((rect.area() - weight).abs() < 1e-4);
+            assert!(rect.x >= 0.0 && rect.x + rect.width <= 6.0 + 1e-4);
+        }
+
+        let tree = node("/data", 100, vec![
+            node("/data/a.bin", 25, vec![]),
+            node("/data/docs", 75, vec![node("/data/docs/big.pdf", 50, vec![]), node("/data/docs/small.txt", 25, vec![])]),
+            node("/data/empty.txt", 0, vec![]),
+        ]);
+        let tiles = treemap(&tree, Rect::new(0.0, 0.0, 400.0, 100.0), 2);
+        let names: Vec<&str> = tiles.iter().map(|tile| tile.node.name.as_str()).collect();
+        assert_eq!(names, ["docs", "big.pdf", "small.txt", "a.bin"]);
+        assert_eq!(tiles[0].rect, Rect::new(0.0, 0.0, 300.0, 100.0));
+        assert_eq!(tile_at(&tiles, 10.0, 50.0).unwrap().node.name, "big.pdf");
+        assert_eq!(tile_at(&tiles, 350.0, 50.0).unwrap().node.name, "a.bin");
+        assert_eq!(treemap(&tree, Rect::new(0.0, 0.0, 400.0, 100.0), 1).len(), 2);
+
+        let segments = sunburst(&tree, 2, 0.0);
+        assert_eq!(segments.len(), 4);
+        assert!((segments[0].end - 0.75 * TAU).abs() < 1e-4);
+        assert_eq!(segment_at(&segments, 2, 0.1).unwrap().node.name, "big.pdf");
+        assert_eq!(segment_at(&segments, 1, -0.1).unwrap().node.name, "a.bin");
+        assert_eq!(sunburst(&tree, 2, 2.0).len(), 2);
+
+        assert_eq!(find_node(&tree, Path::new("/data/docs/small.txt")).unwrap().size, 25);
+        assert!(find_node(&tree, Path::new("/elsewhere")).is_none());
+    }
+}
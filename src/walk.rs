@@ -0,0 +1,552 @@
+//! Parallel directory traversal
+//!
+//! Every directory is listed by its own job on the rayon pool. A job filters
+//! and sorts the entries, queues a job for each subdirectory, then visits
+//! its files (hashed, identified) in parallel, so files are processed as
+//! soon as their directory has been listed rather than after the whole walk.
+//! Jobs are queued rather than nested, so deep trees need no more stack
+//! than shallow ones. The listings are put together into a tree once every
+//! job has finished, ordered by name at every level, so the output does not
+//! depend on how the work was scheduled.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+
+use crate::report::{IssueCollector, ScanIssue, ScanPhase};
+use crate::unix::UnixMetadata;
+use crate::{ScanError, TreeNode};
+
+/// Traversal settings shared by every scan mode
+pub(crate) struct Walker<'a> {
+    pub follow_symlinks: bool,
+    pub max_depth: Option<usize>,
+    pub cancellation_flag: Option<&'a AtomicBool>,
+    pub issues: &'a IssueCollector,
+    files_found: AtomicUsize,
+}
+
+/// An entry found by the walker, holding what the visitor returned for it
+/// when it is a regular file
+pub(crate) struct WalkNode<T> {
+    pub name: String,
+    pub path: PathBuf,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub is_file: bool,
+    is_symlink: bool,
+    /// Size of a file, filled in by the caller; directories are totalled
+    /// when the tree is built
+    pub size: u64,
+    /// Modification time and Unix metadata; the walker fills them in for
+    /// directories, and the caller for files, from what its visitor found
+    pub modified: Option<DateTime<Utc>>,
+    pub unix: Option<UnixMetadata>,
+    pub data: Option<T>,
+    pub children: Vec<WalkNode<T>>,
+    /// Metadata read when the entry was listed, held until the file is
+    /// visited or the directory is queued
+    metadata: Option<fs::Metadata>,
+}
+
+/// A directory on the path from the root, as resolved on disk, used to
+/// detect symlink loops when following links
+struct Ancestor {
+    path: PathBuf,
+    parent: Option<Arc<Ancestor>>,
+}
+
+impl Ancestor {
+    fn contains(&self, path: &Path) -> bool {
+        let mut ancestor = Some(self);
+        while let Some(current) = ancestor {
+            if current.path == path {
+                return true;
+            }
+            ancestor = current.parent.as_deref();
+        }
+        false
+    }
+}
+
+/// A directory waiting to be listed
+struct Pending {
+    id: usize,
+    path: PathBuf,
+    depth: usize,
+    metadata: Option<fs::Metadata>,
+    /// Where the directory is on disk, when following links
+    ancestor: Option<Arc<Ancestor>>,
+}
+
+/// A listed directory, waiting to be put into the tree
+struct Listing<T> {
+    id: usize,
+    depth: usize,
+    modified: Option<DateTime<Utc>>,
+    unix: Option<UnixMetadata>,
+    /// Entries in name order, each with the id of its own listing when it
+    /// is a directory that was queued
+    children: Vec<(Option<usize>, WalkNode<T>)>,
+}
+
+/// State shared by the jobs of one walk
+struct Walk<'a, T, I, V> {
+    include: &'a I,
+    visit: &'a V,
+    /// Whether listings are kept to build the tree
+    keep: bool,
+    next_id: AtomicUsize,
+    listings: Mutex<Vec<Listing<T>>>,
+    /// Set when the root cannot be listed
+    failure: Mutex<Option<ScanError>>,
+}
+
+impl<'a> Walker<'a> {
+    pub fn new(
+        follow_symlinks: bool,
+        max_depth: Option<usize>,
+        cancellation_flag: Option<&'a AtomicBool>,
+        issues: &'a IssueCollector,
+    ) -> Self {
+        Self {
+            follow_symlinks,
+            max_depth,
+            cancellation_flag,
+            issues,
+            files_found: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of regular files listed so far, including ones not yet visited
+    pub fn files_found(&self) -> usize {
+        self.files_found.load(Ordering::Relaxed)
+    }
+
+    /// Walk the tree under `root`. Entries for which `include` returns false
+    /// are left out, and excluded directories are not entered. `visit` is
+    /// called for every included regular file, in parallel, while the rest
+    /// of the tree is still being listed. Both are given the metadata read
+    /// when the entry was listed (of the target, for followed links).
+    pub fn walk<T, I, V>(&self, root: &Path, include: I, visit: V) -> Result<WalkNode<T>, ScanError>
+    where
+        T: Send,
+        I: Fn(&Path, &fs::Metadata) -> bool + Sync,
+        V: Fn(&Path, &fs::Metadata) -> Option<T> + Sync,
+    {
+        let listings = self.run(root, &include, &visit, true)?;
+        let root = WalkNode {
+            name: root.file_name().unwrap_or(root.as_os_str()).to_string_lossy().to_string(),
+            path: root.to_path_buf(),
+            depth: 0,
+            is_dir: true,
+            is_file: false,
+            is_symlink: false,
+            size: 0,
+            modified: None,
+            unix: None,
+            data: None,
+            children: Vec::new(),
+            metadata: None,
+        };
+        Ok(assemble(root, listings))
+    }
+
+    /// Visit every included regular file under `root` like [`walk`](Self::walk),
+    /// without keeping the entries to build a tree
+    pub fn walk_files<I, V>(&self, root: &Path, include: I, visit: V) -> Result<(), ScanError>
+    where
+        I: Fn(&Path, &fs::Metadata) -> bool + Sync,
+        V: Fn(&Path, &fs::Metadata) + Sync,
+    {
+        let visit = |path: &Path, metadata: &fs::Metadata| {
+            visit(path, metadata);
+            None::<()>
+        };
+        self.run(root, &include, &visit, false).map(|_| ())
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation_flag.is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// List `root` and every directory below it, returning the listings
+    /// when `keep` is set
+    fn run<T, I, V>(&self, root: &Path, include: &I, visit: &V, keep: bool) -> Result<Vec<Listing<T>>, ScanError>
+    where
+        T: Send,
+        I: Fn(&Path, &fs::Metadata) -> bool + Sync,
+        V: Fn(&Path, &fs::Metadata) -> Option<T> + Sync,
+    {
+        let ancestor = if self.follow_symlinks {
+            Some(Arc::new(Ancestor { path: fs::canonicalize(root)?, parent: None }))
+        } else {
+            None
+        };
+        let walk = Walk {
+            include,
+            visit,
+            keep,
+            next_id: AtomicUsize::new(1),
+            listings: Mutex::new(Vec::new()),
+            failure: Mutex::new(None),
+        };
+        let pending = Pending { id: 0, path: root.to_path_buf(), depth: 0, metadata: fs::metadata(root).ok(), ancestor };
+        rayon::scope(|scope| self.list(scope, &walk, pending));
+
+        if let Some(error) = walk.failure.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            return Err(error);
+        }
+        if self.is_cancelled() {
+            return Err(ScanError::Cancelled);
+        }
+        Ok(walk.listings.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()))
+    }
+
+    /// List a directory, queue its subdirectories and visit its files
+    fn list<'s, T, I, V>(&'s self, scope: &rayon::Scope<'s>, walk: &'s Walk<'s, T, I, V>, directory: Pending)
+    where
+        T: Send,
+        I: Fn(&Path, &fs::Metadata) -> bool + Sync,
+        V: Fn(&Path, &fs::Metadata) -> Option<T> + Sync,
+    {
+        if self.is_cancelled() {
+            return;
+        }
+        let mut listing = Listing { id: directory.id, depth: directory.depth, modified: None, unix: None, children: Vec::new() };
+        if let Some(ref metadata) = directory.metadata {
+            listing.modified = metadata.modified().ok().map(DateTime::from);
+            listing.unix = UnixMetadata::from_metadata(metadata);
+        }
+
+        if self.max_depth.is_none_or(|max| directory.depth < max) {
+            // An unreadable root is an error; an unreadable subdirectory is
+            // recorded and kept in the tree without children
+            match fs::read_dir(&directory.path) {
+                Ok(entries) => listing.children = self.read_entries(entries, &directory, walk.include),
+                Err(e) if directory.depth > 0 => {
+                    self.issues.record(ScanIssue::new(&directory.path, ScanPhase::Traversal, &e));
+                }
+                Err(e) => {
+                    *walk.failure.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(e.into());
+                    return;
+                }
+            }
+        }
+
+        // Subdirectories are queued before the files are visited, so idle
+        // threads can take them meanwhile
+        for (id, child) in &mut listing.children {
+            if !child.is_dir {
+                continue;
+            }
+            let ancestor = match directory.ancestor {
+                Some(ref parent) => match self.resolve(parent, child) {
+                    Some(path) => Some(Arc::new(Ancestor { path, parent: Some(Arc::clone(parent)) })),
+                    None => continue,
+                },
+                None => None,
+            };
+            let pending = Pending {
+                id: walk.next_id.fetch_add(1, Ordering::Relaxed),
+                path: child.path.clone(),
+                depth: child.depth,
+                metadata: child.metadata.take(),
+                ancestor,
+            };
+            *id = Some(pending.id);
+            scope.spawn(move |scope| self.list(scope, walk, pending));
+        }
+
+        listing.children.par_iter_mut().filter(|(_, child)| child.is_file).for_each(|(_, child)| {
+            if let Some(metadata) = child.metadata.take() {
+                if !self.is_cancelled() {
+                    child.data = (walk.visit)(&child.path, &metadata);
+                }
+            }
+        });
+
+        if walk.keep {
+            walk.listings.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(listing);
+        }
+    }
+
+    /// The included entries of a directory, sorted by name
+    fn read_entries<T, I>(&self, entries: fs::ReadDir, directory: &Pending, include: &I) -> Vec<(Option<usize>, WalkNode<T>)>
+    where
+        I: Fn(&Path, &fs::Metadata) -> bool + Sync,
+    {
+        let mut children = Vec::new();
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.issues.record(ScanIssue::new(&directory.path, ScanPhase::Traversal, &e));
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(e) => {
+                    self.issues.record(ScanIssue::new(&path, ScanPhase::Traversal, &e));
+                    continue;
+                }
+            };
+            let metadata = if file_type.is_symlink() && self.follow_symlinks {
+                match fs::metadata(&path) {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        // A dangling link cannot be followed
+                        self.issues.record(ScanIssue::new(&path, ScanPhase::Traversal, &e));
+                        continue;
+                    }
+                }
+            } else {
+                match entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        self.issues.record(ScanIssue::new(&path, ScanPhase::Metadata, &e));
+                        continue;
+                    }
+                }
+            };
+            let (is_dir, is_file) = (metadata.is_dir(), metadata.is_file());
+
+            if !include(&path, &metadata) {
+                continue;
+            }
+            if is_file {
+                self.files_found.fetch_add(1, Ordering::Relaxed);
+            }
+            children.push((None, WalkNode {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path,
+                depth: directory.depth + 1,
+                is_dir,
+                is_file,
+                is_symlink: file_type.is_symlink(),
+                size: 0,
+                modified: None,
+                unix: None,
+                data: None,
+                children: Vec::new(),
+                metadata: Some(metadata),
+            }));
+        }
+
+        // Sort children alphabetically (case-insensitive)
+        children.sort_by_cached_key(|(_, child)| child.name.to_lowercase());
+        children
+    }
+
+    /// Where a subdirectory is on disk, or None (after recording the problem)
+    /// when it is a link back to one of its own ancestors
+    fn resolve<T>(&self, parent: &Ancestor, child: &WalkNode<T>) -> Option<PathBuf> {
+        if !child.is_symlink {
+            return Some(parent.path.join(&child.name));
+        }
+        let target = match fs::canonicalize(&child.path) {
+            Ok(target) => target,
+            Err(e) => {
+                self.issues.record(ScanIssue::new(&child.path, ScanPhase::Traversal, &e));
+                return None;
+            }
+        };
+        if parent.contains(&target) {
+            let error = io::Error::other(format!(
+                "File system loop found: {} points to an ancestor {}",
+                child.path.display(),
+                target.display()
+            ));
+            self.issues.record(ScanIssue::new(&child.path, ScanPhase::Traversal, &error));
+            return None;
+        }
+        Some(target)
+    }
+}
+
+/// Put the listings together under the root, deepest first so each
+/// directory is complete before it is placed in its parent
+fn assemble<T>(mut root: WalkNode<T>, mut listings: Vec<Listing<T>>) -> WalkNode<T> {
+    listings.sort_by_key(|listing| Reverse(listing.depth));
+    let mut complete: HashMap<usize, Listing<T>> = HashMap::new();
+    for mut listing in listings {
+        for (id, child) in &mut listing.children {
+            if let Some(below) = id.and_then(|id| complete.remove(&id)) {
+                fill_directory(child, below);
+            }
+        }
+        complete.insert(listing.id, listing);
+    }
+    if let Some(listing) = complete.remove(&0) {
+        fill_directory(&mut root, listing);
+    }
+    root
+}
+
+fn fill_directory<T>(node: &mut WalkNode<T>, listing: Listing<T>) {
+    node.modified = listing.modified;
+    node.unix = listing.unix;
+    node.children = listing.children.into_iter().map(|(_, child)| child).collect();
+}
+
+impl<T> WalkNode<T> {
+    /// Move the visitor results out of the tree, in tree order
+    pub fn take_data(&mut self, data: &mut Vec<T>) {
+        self.for_each_mut(&mut |node| data.extend(node.data.take()));
+    }
+
+    /// Call `f` on this node and every node below it, parents first
+    pub fn for_each_mut(&mut self, f: &mut impl FnMut(&mut WalkNode<T>)) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            f(node);
+            stack.extend(node.children.iter_mut().rev());
+        }
+    }
+
+    pub fn into_tree(self) -> TreeNode {
+        let children: Vec<TreeNode> = self.children.into_iter().map(WalkNode::into_tree).collect();
+        let (size, file_count) = if self.is_dir {
+            (children.iter().map(|child| child.size).sum(), children.iter().map(|child| child.file_count).sum())
+        } else {
+            (self.size, 1)
+        };
+        TreeNode {
+            name: self.name,
+            path: self.path,
+            is_directory: self.is_dir,
+            size,
+            file_count,
+            modified: self.modified,
+            unix: self.unix,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_walk_is_ordered() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        for sub in ["b", "A", "c/deep"] {
+            fs::create_dir_all(dir.path().join(sub)).unwrap();
+            for name in ["z.txt", "a.txt", "M.txt"] {
+                fs::write(dir.path().join(sub).join(name), name).unwrap();
+            }
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("c/deep/loop")).unwrap();
+
+        let issues = IssueCollector::new();
+        let walker = Walker::new(true, None, None, &issues);
+        let mut root = walker
+            .walk(dir.path(), |_, _| true, |file, _| Some(file.strip_prefix(dir.path()).unwrap().to_path_buf()))
+            .unwrap();
+        let mut files = Vec::new();
+        root.take_data(&mut files);
+
+        let names: Vec<&str> = root.children.iter().map(|child| child.name.as_str()).collect();
+        assert_eq!(names, ["A", "b", "c"]);
+        assert_eq!(files.len(), 9);
+        assert_eq!(files[..3], [PathBuf::from("A/a.txt"), PathBuf::from("A/M.txt"), PathBuf::from("A/z.txt")]);
+        assert_eq!(walker.files_found(), 9);
+        #[cfg(unix)]
+        assert_eq!(issues.into_report().len(), 1);
+    }
+
+    #[test]
+    fn test_walk_order_does_not_depend_on_scheduling() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        for outer in 0..8 {
+            for inner in 0..8 {
+                let sub = dir.path().join(format!("dir{}", outer)).join(format!("Sub{}", 7 - inner));
+                fs::create_dir_all(&sub).unwrap();
+                for file in 0..4 {
+                    fs::write(sub.join(format!("file{}.txt", file)), "x").unwrap();
+                }
+            }
+        }
+
+        let walk = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let issues = IssueCollector::new();
+            let walker = Walker::new(false, None, None, &issues);
+            let mut root = pool.install(|| walker.walk(dir.path(), |_, _| true, |file, _| Some(file.to_path_buf()))).unwrap();
+            let mut files = Vec::new();
+            root.take_data(&mut files);
+            files
+        };
+
+        let expected = walk(1);
+        assert_eq!(expected.len(), 8 * 8 * 4);
+        assert!(expected.windows(2).all(|pair| pair[0].to_string_lossy().to_lowercase() < pair[1].to_string_lossy().to_lowercase()));
+        for _ in 0..10 {
+            assert_eq!(walk(8), expected);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loops_are_not_followed() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        let outside = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/b/file.txt"), "x").unwrap();
+        fs::write(outside.path().join("linked.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("a"), dir.path().join("a/b/up")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("a/b/out")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("a/b/out/again")).unwrap();
+
+        let issues = IssueCollector::new();
+        let walker = Walker::new(true, None, None, &issues);
+        let mut root = walker
+            .walk(dir.path(), |_, _| true, |file, _| Some(file.strip_prefix(dir.path()).unwrap().to_path_buf()))
+            .unwrap();
+        let mut files = Vec::new();
+        root.take_data(&mut files);
+
+        // A link to a directory outside the walk is followed; links back up
+        // to an ancestor are reported and left empty
+        assert_eq!(files, [PathBuf::from("a/b/file.txt"), PathBuf::from("a/b/out/linked.txt")]);
+        assert_eq!(issues.into_report().len(), 2);
+
+        let issues = IssueCollector::new();
+        let walker = Walker::new(false, None, None, &issues);
+        let mut root = walker.walk(dir.path(), |_, _| true, |file, _| Some(file.to_path_buf())).unwrap();
+        let mut files = Vec::new();
+        root.take_data(&mut files);
+        assert_eq!(files.len(), 1);
+        assert!(issues.into_report().is_empty());
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let dir = tempfile::Builder::new().prefix("splendir-test").tempdir().unwrap();
+        let mut deepest = dir.path().to_path_buf();
+        for _ in 0..1000 {
+            deepest.push("d");
+        }
+        fs::create_dir_all(&deepest).unwrap();
+        fs::write(deepest.join("bottom.txt"), "x").unwrap();
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).stack_size(512 * 1024).build().unwrap();
+        let issues = IssueCollector::new();
+        let walker = Walker::new(false, None, None, &issues);
+        let mut root = pool.install(|| walker.walk(dir.path(), |_, _| true, |file, _| Some(file.to_path_buf()))).unwrap();
+        let mut files = Vec::new();
+        root.take_data(&mut files);
+        assert_eq!(files, [deepest.join("bottom.txt")]);
+    }
+}
@@ -0,0 +1,157 @@
+//! Argument handling of the command-line scanner
+
+use std::fs;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+fn cli() -> Command {
+    cargo_bin_cmd!("splendir-cli")
+}
+
+/// Scratch directory for a scan, kept under the build directory because the
+/// CLI skips /tmp as a virtual filesystem
+fn scan_dir() -> TempDir {
+    let dir = tempfile::Builder::new().prefix("splendir-test").tempdir_in(env!("CARGO_TARGET_TMPDIR")).unwrap();
+    fs::write(dir.path().join("hello.txt"), "hello").unwrap();
+    dir
+}
+
+/// Run the CLI on a scratch directory and expect it to fail with `message`
+fn assert_rejected(args: &[&str], message: &str) {
+    let dir = scan_dir();
+    cli()
+        .args(args)
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(message));
+}
+
+#[test]
+fn test_no_arguments_prints_usage() {
+    cli().assert().failure().code(1).stderr(predicate::str::contains("Usage:"));
+}
+
+#[test]
+fn test_help() {
+    cli().arg("--help").assert().success().stdout(predicate::str::contains("USAGE:"));
+}
+
+#[test]
+fn test_detailed_listing() {
+    let dir = scan_dir();
+    cli().arg(dir.path()).assert().success().stdout(predicate::str::contains("hello.txt"));
+}
+
+#[test]
+fn test_unknown_flag() {
+    assert_rejected(&["--frobnicate"], "Unknown flag: --frobnicate");
+}
+
+#[test]
+fn test_missing_directory() {
+    cli().arg("--tree").assert().failure().stderr(predicate::str::contains("No directory path specified"));
+}
+
+#[test]
+fn test_multiple_directories() {
+    let dir = scan_dir();
+    cli()
+        .arg(dir.path())
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Multiple directory paths specified"));
+}
+
+#[test]
+fn test_mode_conflicts() {
+    assert_rejected(&["--tree", "--analyze"], "Cannot use --tree and --analyze together");
+    assert_rejected(&["--tree", "--jsonl"], "--jsonl is only available for the detailed file listing");
+    assert_rejected(&["--analyze", "--dfxml"], "--dfxml is only available for the detailed file listing");
+    assert_rejected(&["--duplicates", "--stream"], "--stream is only available for the detailed file listing");
+    assert_rejected(&["--duplicates", "--tree"], "--duplicates can only be combined with");
+    assert_rejected(&["--similar", "tlsh", "--analyze"], "--similar can only be combined with");
+    assert_rejected(&["--compare", "old.json", "--tree"], "--compare can only be combined with");
+    assert_rejected(&["--manifest", "SHA256SUMS", "--json"], "--manifest can only be combined with");
+    assert_rejected(&["--verify", "SHA256SUMS", "--tree"], "--verify can only be combined with --json");
+}
+
+#[test]
+fn test_manifest_rejects_filters() {
+    for flags in [
+        &["--include", "*.txt"][..],
+        &["--exclude", "*.log"],
+        &["--ext", "txt"],
+        &["--min-size", "1K"],
+        &["--modified-from", "30d"],
+        &["--type", "image"],
+        &["--gitignore"],
+        &["--fast"],
+    ] {
+        let dir = scan_dir();
+        let manifest = dir.path().join("SHA256SUMS");
+        cli()
+            .arg("--manifest")
+            .arg(&manifest)
+            .args(flags)
+            .arg(dir.path())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--manifest can only be combined with --cache and --problems"));
+        assert!(!manifest.exists());
+    }
+}
+
+#[test]
+fn test_manifest_lists_every_file() {
+    let dir = scan_dir();
+    let manifest = dir.path().join("SHA256SUMS");
+    cli().arg("--manifest").arg(&manifest).arg(dir.path()).assert().success();
+    let written = fs::read_to_string(&manifest).unwrap();
+    assert!(written.contains("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  hello.txt"));
+}
+
+#[test]
+fn test_flags_outside_their_mode() {
+    assert_rejected(&["--du"], "--du, --dates, -p, -u and -g are only available with --tree");
+    assert_rejected(&["--top", "5"], "--top is only available with --analyze");
+    assert_rejected(&["--tree", "--hash", "md5"], "--hash is only available for the detailed file listing and --compare");
+    assert_rejected(&["--threshold", "50"], "--threshold is only used with --similar");
+}
+
+#[test]
+fn test_missing_values() {
+    for (flag, message) in [
+        ("--top", "--top requires a whole number"),
+        ("--hash", "--hash requires a comma-separated list of algorithms"),
+        ("--cache", "--cache requires a file path"),
+        ("--similar", "--similar requires a fuzzy hash algorithm"),
+        ("--min-size", "--min-size requires a size"),
+        ("--time-zone", "--time-zone requires UTC, local or a zone name"),
+    ] {
+        cli()
+            .args(["--analyze", flag])
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains(message));
+    }
+}
+
+#[test]
+fn test_invalid_values() {
+    assert_rejected(&["--analyze", "--top", "many"], "--top requires a whole number");
+    assert_rejected(&["--hash", "sha256,crc64"], "unknown hash algorithm 'crc64'");
+    assert_rejected(&["--similar", "md5"], "--similar requires a fuzzy hash algorithm");
+    assert_rejected(&["--time-format", "julian"], "unknown time format 'julian'");
+    assert_rejected(&["--type", "directory"], "unknown file type 'directory'");
+    assert_rejected(&["--min-size", "lots"], "Error:");
+    assert_rejected(&["--modified-from", "yesterday-ish"], "Error:");
+    assert_rejected(&["--include-regex", "("], "Error:");
+}